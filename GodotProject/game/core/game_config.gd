@@ -5,8 +5,6 @@ extends RefCounted
 ## 默认配置
 const DEFAULT_MIN_PLAYERS: int = 5
 const DEFAULT_MAX_PLAYERS: int = 10
-const DEFAULT_TEAM_SIZE: Array = [2, 3, 2, 3, 3]  # 5轮任务人数
-const DEFAULT_FAIL_THRESHOLD: Array = [1, 1, 1, 2, 1]  # 失败票阈值
 
 ## 任务配置
 class TaskConfig:
@@ -54,23 +52,20 @@ static func get_role_config(player_count: int) -> Dictionary:
 			# 默认5人配置
 			return get_role_config(5)
 
-## 获取每轮任务配置
-static func get_task_configs(player_count: int) -> Array[TaskConfig]:
-	var sizes: Array = [2, 3, 2, 3, 3]
-	var thresholds: Array = [1, 1, 1, 2, 1]
+## 任务人数和失败票数只来自服务器（RustCoreServer.get_quest_rules() 或 /rules），客户端不再保存副本
 
-	# 根据人数调整部分任务人数
-	match player_count:
-		6:
-			sizes = [2, 3, 4, 3, 4]
-		7:
-			sizes = [2, 3, 3, 4, 4]
-		8, 9, 10:
-			sizes = [3, 4, 4, 5, 5]
+## 从 /rules 返回的规则表中取出某个人数的任务规则（[{team_size, fails_required}]，没有时为空数组）
+static func quests_from_rules_table(table: Dictionary, player_count: int) -> Array:
+	for game in table.get("games", []):
+		if int(game["players"]) == player_count:
+			return game["quests"]
+	return []
 
+## 从服务器下发的规则（[{team_size, fails_required}]）创建任务配置
+static func task_configs_from_rules(quests: Array) -> Array[TaskConfig]:
 	var configs: Array[TaskConfig] = []
-	for i in range(5):
-		configs.append(TaskConfig.new(sizes[i], thresholds[i]))
+	for quest in quests:
+		configs.append(TaskConfig.new(int(quest["team_size"]), int(quest["fails_required"])))
 	return configs
 
 ## 获取好人/坏人数量
@@ -43,7 +43,7 @@ enum VoteResult {
 enum TaskResult {
 	SUCCESS,         # 成功
 	FAIL,            # 失败（1张失败票即可）
-	FAIL_2           # 失败（需2张失败票，仅4人局第4任务）
+	FAIL_2           # 失败（需2张失败票，仅7人及以上第4任务）
 }
 
 ## 游戏结果
@@ -11,6 +11,7 @@ signal sig_game_result_announced(result: GameEnums.GameResult, winners: Array)
 signal sig_player_info_updated(player: Player)
 signal sig_assassin_needed(assassin_id: String)
 signal sig_error_occurred(message: String)
+signal sig_quest_rules_loaded(quests: Array)
 
 ## 游戏配置
 var config: GameConfig = GameConfig.new()
@@ -119,8 +120,39 @@ func all_players_ready() -> bool:
 			return false
 	return true
 
-## 开始游戏（服务端调用）
-func start_game() -> bool:
+## 使用服务器下发的任务规则（[{team_size, fails_required}]，来自 RustCoreServer.get_quest_rules() 或 /rules）
+func set_quest_rules(quests: Array) -> bool:
+	if quests.size() != 5:
+		sig_error_occurred.emit("Quest rules must list 5 quests")
+		return false
+	_task_configs = GameConfig.task_configs_from_rules(quests)
+	sig_quest_rules_loaded.emit(quests)
+	return true
+
+## 客户端从主机的 /rules 读取任务规则（base_url 如 https://192.168.1.2:8089），完成后发出 sig_quest_rules_loaded
+func fetch_quest_rules(base_url: String, player_count: int, tls_options: TLSOptions = null) -> int:
+	var request := HTTPRequest.new()
+	add_child(request)
+	request.request_completed.connect(func(result: int, code: int, _headers: PackedStringArray, body: PackedByteArray):
+		request.queue_free()
+		if result != HTTPRequest.RESULT_SUCCESS or code != 200:
+			sig_error_occurred.emit("Could not load quest rules from %s/rules (%d, HTTP %d)" % [base_url, result, code])
+			return
+		var table = JSON.parse_string(body.get_string_from_utf8())
+		if not table is Dictionary:
+			sig_error_occurred.emit("Quest rules from %s/rules are not a JSON object" % base_url)
+			return
+		set_quest_rules(GameConfig.quests_from_rules_table(table, player_count))
+	)
+	if tls_options:
+		request.set_tls_options(tls_options)
+	var err := request.request(base_url + "/rules")
+	if err != OK:
+		request.queue_free()
+	return err
+
+## 开始游戏（服务端调用，quests 为 RustCoreServer.get_quest_rules(人数)）
+func start_game(quests: Array) -> bool:
 	if not is_server:
 		sig_error_occurred.emit("Only server can start the game")
 		return false
@@ -130,13 +162,14 @@ func start_game() -> bool:
 		sig_error_occurred.emit("Player count must be between 5-10")
 		return false
 
+	# 获取任务配置
+	if not set_quest_rules(quests):
+		return false
+
 	# 分配角色
 	if not _assign_roles():
 		return false
 
-	# 获取任务配置
-	_task_configs = GameConfig.get_task_configs(player_count)
-
 	# 设置玩家顺序
 	_update_player_order()
 
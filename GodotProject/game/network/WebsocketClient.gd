@@ -4,10 +4,13 @@ extends Node
 @export var handshake_headers: PackedStringArray
 @export var supported_protocols: PackedStringArray
 @export var verify_certificate: bool = false  # 开发环境设为 false 以支持自签名证书
+## 主机证书的 SHA-256 指纹（"AB:CD:..."）。设置后在非 Web 平台使用 RustCoreClient 固定证书连接
+@export var certificate_fingerprint: String = ""
 
 var tls_options: TLSOptions = null
 var socket := WebSocketPeer.new()
 var last_state := WebSocketPeer.STATE_CLOSED
+var rust_client = null  # RustCoreClient; untyped so the script still loads on the web
 
 signal sig_connected_to_server()
 signal sig_connection_closed()
@@ -24,6 +27,9 @@ func init_tls_options() -> void:
 		print("WebSocketClient: Using TLS WITHOUT certificate verification")
 
 func connect_to_url(url: String) -> int:
+	if _use_rust_client(url):
+		return _connect_rust_client(url)
+
 	# 根据 URL 协议决定是否使用 TLS
 	if url.begins_with("wss://"):
 		init_tls_options()
@@ -42,7 +48,29 @@ func connect_to_url(url: String) -> int:
 	return OK
 
 
+func _use_rust_client(url: String) -> bool:
+	return url.begins_with("wss://") and not certificate_fingerprint.is_empty() \
+		and not OS.has_feature("web") and ClassDB.class_exists("RustCoreClient")
+
+
+func _connect_rust_client(url: String) -> int:
+	rust_client = ClassDB.instantiate("RustCoreClient")
+	rust_client.connected.connect(func(_url: String): sig_connected_to_server.emit())
+	rust_client.disconnected.connect(func(_code: int, _reason: String): sig_connection_closed.emit())
+	rust_client.message_received.connect(func(message: String): sig_message_received.emit(message))
+	rust_client.connect_failed.connect(func(reason: String): push_error("WebSocketClient: " + reason))
+	print("WebSocketClient: Connecting to ", url, " with pinned certificate ", certificate_fingerprint)
+	var err: int = rust_client.connect_to_url(url, certificate_fingerprint)
+	if err != OK:
+		print("WebSocketClient: connect_to_url failed: ", rust_client.get_last_error())
+		rust_client = null
+		return FAILED
+	return OK
+
+
 func send(message: String) -> int:
+	if rust_client:
+		return OK if rust_client.send(message) == 0 else FAILED
 	if typeof(message) == TYPE_STRING:
 		return socket.send_text(message)
 	return socket.send(var_to_bytes(message))
@@ -58,6 +86,11 @@ func get_message() -> Variant:
 
 
 func close(code: int = 1000, reason: String = "") -> void:
+	if rust_client:
+		rust_client.disconnect_from_host()
+		rust_client.poll()
+		rust_client = null
+		return
 	socket.close(code, reason)
 	last_state = socket.get_ready_state()
 
@@ -72,6 +105,9 @@ func get_socket() -> WebSocketPeer:
 
 
 func poll() -> void:
+	if rust_client:
+		rust_client.poll()
+		return
 	socket.poll()
 	var state := socket.get_ready_state()
 	if last_state != state:
@@ -7,18 +7,32 @@ func _ready() -> void:
 	pass # Replace with function body.
 
 	
+func _process(_delta: float) -> void:
+	if rust_server:
+		rust_server.poll()
+
+
 func setup():
 	rust_server = RustCoreServer.new()
-	rust_server.create_mdns()
-	rust_server.create_server()
-	rust_server.start_mdns(
-		"_game._tcp.local.",
-		"GameInstance",
-		"qo-oq",
-		8989)
-	var static_web_resource = ProjectSettings.globalize_path("res://web")
-	rust_server.start_server(
-		"0.0.0.0:8089",
-		static_web_resource,
-		true)
+	rust_server.server_started.connect(func(): print("RustCoreServer: started"))
+	rust_server.server_failed.connect(func(reason: String): push_error("RustCoreServer failed: " + reason))
+	rust_server.client_connected.connect(func(peer_id: int): print("RustCoreServer: peer connected ", peer_id))
+	rust_server.client_disconnected.connect(func(peer_id: int): print("RustCoreServer: peer disconnected ", peer_id))
+	rust_server.mdns_failed.connect(func(reason: String): push_error("RustCoreServer mDNS failed: " + reason))
+	rust_server.join_rejected.connect(func(address: String, reason: String): print("RustCoreServer: rejected ", address, " (", reason, ")"))
+	rust_server.seat_held.connect(func(peer_id: int, seat: int, grace_ms: int): print("RustCoreServer: holding seat ", seat, " of peer ", peer_id, " for ", grace_ms, " ms"))
+	rust_server.seat_released.connect(func(peer_id: int, seat: int): print("RustCoreServer: released seat ", seat, " of peer ", peer_id))
+	rust_server.player_resumed.connect(func(peer_id: int, previous_peer_id: int, seat: int): print("RustCoreServer: peer ", previous_peer_id, " resumed as ", peer_id, " in seat ", seat))
+	rust_server.peer_stale.connect(func(peer_id: int, missed_pongs: int): print("RustCoreServer: peer ", peer_id, " missed ", missed_pongs, " pings"))
+	rust_server.peer_recovered.connect(func(peer_id: int, rtt_ms: int): print("RustCoreServer: peer ", peer_id, " recovered (", rtt_ms, " ms)"))
+	rust_server.connection_refused.connect(func(address: String, reason: String): print("RustCoreServer: refused ", address, " (", reason, ")"))
+	rust_server.peer_limited.connect(func(peer_id: int, reason: String): print("RustCoreServer: peer ", peer_id, " closed for ", reason))
+	rust_server.game_event.connect(func(message: String): print("RustCoreServer: game ", message))
+	var config := RustCoreServerConfig.new()
+	config.static_dir = "res://web"
+	config.mdns_hostname = "qo-oq"
+	var errors := rust_server.start(config)
+	for field in errors:
+		push_error("RustCoreServer start failed: %s: %s" % [field, errors[field]])
 	print(rust_server.get_status())
+	print("RustCoreServer: room code ", rust_server.get_room_code())
@@ -0,0 +1,37 @@
+//! Build script - generates the C header from the FFI sources.
+//!
+//! cbindgen writes the header for `src/ffi` (configured by `cbindgen.toml`)
+//! to `$OUT_DIR/facingtime_core.h`. The copy hosts use is checked in as
+//! `include/facingtime_core.h`; `tests/ffi_header_test.rs` fails when it
+//! differs from the generated one.
+
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let (Some(crate_dir), Some(out_dir)) = (std::env::var_os("CARGO_MANIFEST_DIR"), std::env::var_os("OUT_DIR")) else {
+        return;
+    };
+    let crate_dir = Path::new(&crate_dir);
+    let config = match cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("cargo:warning=cbindgen.toml is invalid, header not generated: {}", e);
+            return;
+        }
+    };
+
+    // Only the FFI module is parsed; everything it exports is C-compatible
+    match cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/ffi/mod.rs"))
+        .generate()
+    {
+        Ok(bindings) => {
+            bindings.write_to_file(Path::new(&out_dir).join("facingtime_core.h"));
+        }
+        Err(e) => println!("cargo:warning=C header not generated: {}", e),
+    }
+}
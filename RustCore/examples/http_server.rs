@@ -1,6 +1,7 @@
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::time::Duration;
 
+use facingtime_core::ffi::error::ft_last_error_message;
 use facingtime_core::ffi::server::{
     ft_http_server_create,
     ft_http_server_free,
@@ -19,6 +20,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 1)
     };
     println!("\nStart server result {}", result);
+    if result != 0 {
+        let message = unsafe { CStr::from_ptr(ft_last_error_message()) };
+        eprintln!("Failed to start server: {}", message.to_string_lossy());
+        unsafe { ft_http_server_free(server) };
+        return Ok(());
+    }
     // Wait for server to be running
     loop {
         let is_running = unsafe { ft_http_server_is_running(server) };
@@ -1,6 +1,7 @@
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::time::Duration;
 
+use facingtime_core::ffi::error::ft_last_error_message;
 use facingtime_core::ffi::mdns::{
     ft_mdns_server_create,
     ft_mdns_server_free,
@@ -28,6 +29,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
     };
     println!("\nStart mDNS server result: {}", result);
+    if result != 0 {
+        let message = unsafe { CStr::from_ptr(ft_last_error_message()) };
+        eprintln!("Failed to start mDNS server: {}", message.to_string_lossy());
+        unsafe { ft_mdns_server_free(server) };
+        return Ok(());
+    }
 
     // Wait for server to be running
     loop {
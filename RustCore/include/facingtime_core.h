@@ -0,0 +1,603 @@
+/*
+ * facingtime_core.h - C interface of the FacingTime RustCore library.
+ *
+ * Generated by cbindgen from RustCore/src/ffi when the crate is built; do
+ * not edit by hand. tests/ffi_header_test.rs checks that it declares every
+ * exported `ft_*` function.
+ *
+ * Hosts should check ft_core_abi_version() == FT_ABI_VERSION at startup.
+ */
+
+#ifndef FACINGTIME_CORE_H
+#define FACINGTIME_CORE_H
+
+#include <stdint.h>
+/* Opaque handles */
+typedef struct FtHttpServer FtHttpServer;
+typedef struct FtMdnsServer FtMdnsServer;
+typedef struct FtMdnsBrowser FtMdnsBrowser;
+typedef struct FtWsClient FtWsClient;
+typedef struct FtHttpResponse FtHttpResponse;
+
+// ABI version of the C interface declared in `include/facingtime_core.h`
+//
+// Bump this whenever an exported signature or its semantics change; the
+// header's `FT_ABI_VERSION` is generated from this constant.
+#define FT_ABI_VERSION 8
+
+// Success
+#define FT_OK 0
+
+// A required pointer argument was NULL
+#define FT_ERR_NULL_POINTER 1
+
+// A string argument was not valid UTF-8
+#define FT_ERR_INVALID_UTF8 2
+
+// The server is not running
+#define FT_ERR_NOT_RUNNING 3
+
+// The server is already running
+#define FT_ERR_ALREADY_RUNNING 4
+
+// Failed to bind to the requested address
+#define FT_ERR_BIND_FAILED 5
+
+// The address could not be parsed
+#define FT_ERR_INVALID_ADDRESS 6
+
+// The static directory does not exist
+#define FT_ERR_STATIC_DIR_NOT_FOUND 7
+
+// An I/O operation failed
+#define FT_ERR_IO 8
+
+// JSON serialization or deserialization failed
+#define FT_ERR_JSON 9
+
+// A join token could not be encoded or decoded
+#define FT_ERR_INVALID_JOIN_TOKEN 10
+
+// TLS setup failed
+#define FT_ERR_TLS 11
+
+// The async runtime could not be created
+#define FT_ERR_RUNTIME 12
+
+// The mDNS daemon reported an error
+#define FT_ERR_MDNS 13
+
+// A route handler prefix is invalid
+#define FT_ERR_INVALID_ROUTE 14
+
+// No WebSocket peer has the given id
+#define FT_ERR_PEER_NOT_FOUND 15
+
+// Host configuration failed validation
+#define FT_ERR_INVALID_CONFIG 16
+
+// The send queue is full
+#define FT_ERR_QUEUE_FULL 17
+
+// No session holds the seat, or it is taken
+#define FT_ERR_SEAT_NOT_FOUND 18
+
+// Any other error
+#define FT_ERR_UNKNOWN -1
+
+// `server_started` event, payload `{"address"}`
+#define FT_EVENT_SERVER_STARTED 1
+
+// `server_stopped` event, payload `{}`
+#define FT_EVENT_SERVER_STOPPED 2
+
+// `server_failed` event, payload `{"reason"}`
+#define FT_EVENT_SERVER_FAILED 3
+
+// `client_connected` event, payload `{"peer_id", "address"}`
+#define FT_EVENT_CLIENT_CONNECTED 4
+
+// `client_disconnected` event, payload `{"peer_id"}`
+#define FT_EVENT_CLIENT_DISCONNECTED 5
+
+// `message_received` event, payload `{"peer_id", "message"}`
+#define FT_EVENT_MESSAGE_RECEIVED 6
+
+// `room` event, payload `{"name", "data"}`
+#define FT_EVENT_ROOM 7
+
+// `game` event, payload `{"name", "data"}`
+#define FT_EVENT_GAME 8
+
+// `mdns_service_found` event, payload `{"service_type", "fullname"}`
+#define FT_EVENT_MDNS_SERVICE_FOUND 9
+
+// `mdns_service_removed` event, payload `{"service_type", "fullname"}`
+#define FT_EVENT_MDNS_SERVICE_REMOVED 10
+
+// `mdns_failed` event, payload `{"reason"}`
+#define FT_EVENT_MDNS_FAILED 11
+
+// `host_connected` event, payload `{"url"}`
+#define FT_EVENT_HOST_CONNECTED 12
+
+// `host_disconnected` event, payload `{"code", "reason"}`
+#define FT_EVENT_HOST_DISCONNECTED 13
+
+// `host_message` event, payload `{"message"}`
+#define FT_EVENT_HOST_MESSAGE 14
+
+// `host_reconnecting` event, payload `{"attempt", "delay_ms"}`
+#define FT_EVENT_HOST_RECONNECTING 15
+
+// `host_connect_failed` event, payload `{"reason"}`
+#define FT_EVENT_HOST_CONNECT_FAILED 16
+
+// `join_rejected` event, payload `{"address", "reason"}`
+#define FT_EVENT_JOIN_REJECTED 17
+
+// `seat_held` event, payload `{"peer_id", "seat", "grace_ms"}`
+#define FT_EVENT_SEAT_HELD 18
+
+// `seat_released` event, payload `{"peer_id", "seat"}`
+#define FT_EVENT_SEAT_RELEASED 19
+
+// `player_resumed` event, payload `{"peer_id", "previous_peer_id", "seat" (or null)}`
+#define FT_EVENT_PLAYER_RESUMED 20
+
+// `peer_stale` event, payload `{"peer_id", "missed_pongs"}`
+#define FT_EVENT_PEER_STALE 21
+
+// `peer_recovered` event, payload `{"peer_id", "rtt_ms"}`
+#define FT_EVENT_PEER_RECOVERED 22
+
+// `connection_refused` event, payload `{"address", "reason"}`
+#define FT_EVENT_CONNECTION_REFUSED 23
+
+// `peer_limited` event, payload `{"peer_id", "reason"}`
+#define FT_EVENT_PEER_LIMITED 24
+
+// Not connected and not trying to connect
+#define FT_CLIENT_DISCONNECTED 0
+
+// First connection attempt in progress
+#define FT_CLIENT_CONNECTING 1
+
+// Connected to the host
+#define FT_CLIENT_CONNECTED 2
+
+// Waiting to retry, or retrying, after a failure
+#define FT_CLIENT_RECONNECTING 3
+
+// Event callback
+//
+// Receives the user data pointer, the event type (`FT_EVENT_*`) and the
+// JSON-encoded event, which is only valid for the duration of the call.
+typedef void (*FtEventCallback)(void *user_data,
+                                int32_t event_type,
+                                const char *event_json);
+
+// Host route handler callback
+//
+// Receives the user data pointer, the JSON-encoded request and the response
+// to fill in with `ft_http_response_set`.
+typedef void (*FtRouteHandler)(void *user_data,
+                               const char *request_json,
+                               FtHttpResponse *response);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Get the library version string (e.g. "0.1.0")
+//
+// # Returns
+// Pointer to a static NUL-terminated string; must not be freed
+const char *ft_core_version(void);
+
+// Get the ABI version of the C interface
+//
+// Hosts should compare this against the `FT_ABI_VERSION` they were
+// compiled with and refuse to run on mismatch.
+uint32_t ft_core_abi_version(void);
+
+// Get the code of the most recent error on this thread
+//
+// # Returns
+// The error code, or `FT_OK` if the last call succeeded
+int32_t ft_last_error_code(void);
+
+// Get the message of the most recent error on this thread
+//
+// # Returns
+// NUL-terminated message owned by the library, valid until the next `ft_*`
+// call on this thread, or NULL if the last call succeeded
+const char *ft_last_error_message(void);
+
+// Set (or clear, with NULL) the event callback
+//
+// The callback is only ever invoked from inside `ft_core_poll_events`, on
+// the thread calling it. `user_data` is passed through unchanged.
+//
+// # Returns
+// FT_OK (0)
+int32_t ft_core_set_event_callback(FtEventCallback callback,
+                                   void *user_data);
+
+// Deliver queued events to the callback
+//
+// Events are delivered oldest first. Without a callback the queued events
+// are discarded. The queue holds at most 1024 events; older events are
+// dropped if the host polls less often than that.
+//
+// # Returns
+// Number of events delivered
+int32_t ft_core_poll_events(void);
+
+// Create a new HTTP server instance
+//
+// The server's events are delivered through `ft_core_poll_events`.
+//
+// # Safety
+// The returned pointer must be freed with ft_http_server_free
+FtHttpServer *ft_http_server_create(void);
+
+// Free an HTTP server instance
+//
+// # Safety
+// The pointer must be valid and will be consumed.
+// This function drops the server in a separate thread to avoid
+// "Cannot drop a runtime in a context where blocking is not allowed" errors.
+void ft_http_server_free(FtHttpServer *server);
+
+// Start the HTTP/HTTPS server
+//
+// # Arguments
+// * `server` - Server handle
+// * `address` - Address to bind to (e.g., "0.0.0.0:8080" for HTTP, "0.0.0.0:8443" for HTTPS)
+// * `static_dir` - Directory for static files
+// * `use_https` - 1 for HTTPS, 0 for HTTP
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_http_server_start(FtHttpServer *server,
+                             const char *address,
+                             const char *static_dir,
+                             int32_t use_https);
+
+// Stop the HTTP server
+//
+// # Arguments
+// * `server` - Server handle
+void ft_http_server_stop(FtHttpServer *server);
+
+// Check if the HTTP server is running
+//
+// # Arguments
+// * `server` - Server handle
+//
+// # Returns
+// 1 if running, 0 if not
+int32_t ft_http_server_is_running(FtHttpServer *server);
+
+// Get the SHA-256 fingerprint of the served certificate
+//
+// # Arguments
+// * `server` - Server handle
+//
+// # Returns
+// `"AB:CD:..."` string that must be freed with ft_http_server_free_response,
+// or NULL when the server is not serving HTTPS
+char *ft_http_server_certificate_fingerprint(FtHttpServer *server);
+
+// Get the room code players must enter to join
+//
+// # Arguments
+// * `server` - Server handle
+// * `regenerate` - Non-zero to replace the code with a fresh one first
+//
+// # Returns
+// Room code (e.g. `"K7M2QX"`) that must be freed with
+// ft_http_server_free_response, or NULL if `server` is NULL
+char *ft_http_server_room_code(FtHttpServer *server,
+                               int32_t regenerate);
+
+// Configure room access for WebSocket joins
+//
+// Clients pass the code and password as `/ws?code=...&password=...`.
+// Changes apply to the next join attempt, also while running.
+//
+// # Arguments
+// * `server` - Server handle
+// * `require_code` - Non-zero to require the room code
+// * `password` - Room password, or NULL / empty for none
+// * `max_failures` - Failed attempts from one address before a lockout (0 disables lockouts)
+// * `lockout_ms` - Lockout duration in milliseconds
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_http_server_set_room_access(FtHttpServer *server,
+                                       int32_t require_code,
+                                       const char *password,
+                                       uint32_t max_failures,
+                                       uint64_t lockout_ms);
+
+// Keep finished games in a data directory and serve them under `/history`
+//
+// Takes effect the next time the server starts.
+//
+// # Arguments
+// * `server` - Server handle
+// * `data_dir` - Directory for `games.jsonl` (created if missing), or NULL / empty to keep no history
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_http_server_set_data_dir(FtHttpServer *server,
+                                    const char *data_dir);
+
+// List the finished games in the data directory
+//
+// # Arguments
+// * `server` - Server handle
+//
+// # Returns
+// JSON array of game summaries (`id`, `started_at_ms`, `finished_at_ms`,
+// `player_count`, `players`, `winner`, `reason`), oldest first, that must
+// be freed with ft_http_server_free_response, or NULL on error (see
+// `ft_last_error_message`)
+char *ft_http_server_list_games(FtHttpServer *server);
+
+// Get one finished game from the data directory
+//
+// # Arguments
+// * `server` - Server handle
+// * `id` - Game id from ft_http_server_list_games
+//
+// # Returns
+// JSON record of the game (players, roles, proposals and votes, quests,
+// assassination, winner and the full log) that must be freed with
+// ft_http_server_free_response, or NULL if there is no such game (error
+// code FT_OK) or on error
+char *ft_http_server_get_game(FtHttpServer *server,
+                              const char *id);
+
+// Export every finished game in the data directory
+//
+// # Arguments
+// * `server` - Server handle
+//
+// # Returns
+// One JSON record per line (empty without games) that must be freed with
+// ft_http_server_free_response, or NULL on error (see `ft_last_error_message`)
+char *ft_http_server_export_games(FtHttpServer *server);
+
+// Handle an HTTP request through the registered host handlers
+//
+// Runs the handler synchronously on the calling thread, exactly as the
+// router would for a network request. Paths without a handler get a 404.
+//
+// # Arguments
+// * `server` - Server handle
+// * `method` - HTTP method
+// * `path` - Request path, optionally with a query string
+// * `headers` - Request headers (JSON object)
+// * `body` - Request body (optional)
+//
+// # Returns
+// JSON-encoded `HttpResponse` (`status_code`, `body`, `headers`) that must be
+// freed with ft_http_server_free_response, or NULL on invalid input
+// (see `ft_last_error_message`)
+char *ft_http_server_handle_request(FtHttpServer *server,
+                                    const char *method,
+                                    const char *path,
+                                    const char *headers,
+                                    const char *body);
+
+// Register a host handler for a path prefix
+//
+// The handler is called with the JSON-encoded `HttpRequest` and must fill
+// in the response with `ft_http_response_set` before returning. It is
+// invoked on a Tokio blocking-pool thread (or on the caller's thread for
+// `ft_http_server_handle_request`), possibly concurrently, so it must be
+// thread-safe. `user_data` is passed through unchanged and must stay valid
+// until the handler is unregistered or the server is freed.
+//
+// # Arguments
+// * `server` - Server handle
+// * `prefix` - Path prefix starting with '/' (e.g. "/api/room")
+// * `handler` - Callback
+// * `user_data` - Opaque pointer passed to the callback
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code
+int32_t ft_http_server_register_handler(FtHttpServer *server,
+                                        const char *prefix,
+                                        FtRouteHandler handler,
+                                        void *user_data);
+
+// Remove the host handler for a path prefix
+//
+// # Returns
+// FT_OK (0) if a handler was removed, otherwise an error code
+int32_t ft_http_server_unregister_handler(FtHttpServer *server,
+                                          const char *prefix);
+
+// Fill in the response from inside a route handler
+//
+// Strings are copied; the caller keeps ownership of its buffers.
+//
+// # Arguments
+// * `response` - Response passed to the handler
+// * `status_code` - HTTP status code
+// * `headers` - Response headers as a JSON object (NULL for none)
+// * `body` - Response body (NULL for empty)
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code
+int32_t ft_http_response_set(FtHttpResponse *response,
+                             int32_t status_code,
+                             const char *headers,
+                             const char *body);
+
+// Free a response string allocated by Rust
+//
+// # Arguments
+// * `response` - Response string to free
+void ft_http_server_free_response(char *response);
+
+// Create a new mDNS server instance
+//
+// # Safety
+// The returned pointer must be freed with ft_mdns_server_free
+FtMdnsServer *ft_mdns_server_create(void);
+
+// Free an mDNS server instance
+//
+// # Safety
+// The pointer must be valid and will be consumed.
+// This function drops the server in a separate thread to avoid
+// "Cannot drop a runtime in a context where blocking is not allowed" errors.
+void ft_mdns_server_free(FtMdnsServer *server);
+
+// Start the mDNS service registration
+//
+// # Arguments
+// * `server` - Server handle
+// * `service_type` - Service type (e.g., "_game._tcp.local.")
+// * `instance_name` - Instance name (e.g., "MyServer")
+// * `hostname` - Hostname (e.g., "myserver")
+// * `port` - Service port
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_mdns_server_start(FtMdnsServer *server,
+                             const char *service_type,
+                             const char *instance_name,
+                             const char *hostname,
+                             uint16_t port);
+
+// Set a TXT record property published with the service
+//
+// Replaces any previous value for `key`. Takes effect on the next
+// `ft_mdns_server_start`. Publish the host certificate fingerprint under
+// the `"fingerprint"` key so clients can pin it.
+//
+// # Arguments
+// * `server` - Server handle
+// * `key` - Property key
+// * `value` - Property value
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_mdns_server_set_txt_property(FtMdnsServer *server,
+                                        const char *key,
+                                        const char *value);
+
+// Stop the mDNS service
+//
+// # Arguments
+// * `server` - Server handle
+void ft_mdns_server_stop(FtMdnsServer *server);
+
+// Check if the mDNS service is running
+//
+// # Arguments
+// * `server` - Server handle
+//
+// # Returns
+// 1 if running, 0 if not
+int32_t ft_mdns_server_is_running(FtMdnsServer *server);
+
+// Create a new mDNS browser instance
+//
+// Discoveries are delivered through `ft_core_poll_events`.
+//
+// # Safety
+// The returned pointer must be freed with ft_mdns_browser_free
+FtMdnsBrowser *ft_mdns_browser_create(void);
+
+// Free an mDNS browser instance
+//
+// # Safety
+// The pointer must be valid and will be consumed.
+// Dropped in a separate thread, like the other servers.
+void ft_mdns_browser_free(FtMdnsBrowser *browser);
+
+// Start browsing for a service type
+//
+// # Arguments
+// * `browser` - Browser handle
+// * `service_type` - Service type (e.g., "_game._tcp.local.")
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_mdns_browser_start(FtMdnsBrowser *browser,
+                              const char *service_type);
+
+// Stop browsing
+//
+// # Arguments
+// * `browser` - Browser handle
+void ft_mdns_browser_stop(FtMdnsBrowser *browser);
+
+// Create a new WebSocket client instance
+//
+// Connection events and host messages are delivered through
+// `ft_core_poll_events`.
+//
+// # Safety
+// The returned pointer must be freed with ft_ws_client_free
+FtWsClient *ft_ws_client_create(void);
+
+// Free a WebSocket client instance
+//
+// # Safety
+// The pointer must be valid and will be consumed.
+// Dropped in a separate thread, like the servers.
+void ft_ws_client_free(FtWsClient *client);
+
+// Start connecting to a host
+//
+// # Arguments
+// * `client` - Client handle
+// * `url` - Host URL (e.g., "wss://192.168.1.2:8089/ws")
+// * `fingerprint` - SHA-256 certificate fingerprint of the host ("AB:CD:..."); required for wss://, NULL for ws://
+// * `reconnect` - Non-zero to reconnect with backoff after failures
+//
+// # Returns
+// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+int32_t ft_ws_client_connect(FtWsClient *client,
+                             const char *url,
+                             const char *fingerprint,
+                             int32_t reconnect);
+
+// Queue a text message for the host
+//
+// Messages queued while reconnecting are sent once connected.
+//
+// # Returns
+// FT_OK (0) on success, FT_ERR_QUEUE_FULL if the send queue is full,
+// otherwise an error code (see `ft_last_error_message`)
+int32_t ft_ws_client_send(FtWsClient *client,
+                          const char *message);
+
+// Close the connection and stop reconnecting
+//
+// # Arguments
+// * `client` - Client handle
+void ft_ws_client_disconnect(FtWsClient *client);
+
+// Get the connection status
+//
+// # Arguments
+// * `client` - Client handle
+//
+// # Returns
+// One of the FT_CLIENT_* values (FT_CLIENT_DISCONNECTED for NULL)
+int32_t ft_ws_client_status(FtWsClient *client);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FACINGTIME_CORE_H */
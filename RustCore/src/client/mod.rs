@@ -0,0 +1,13 @@
+//! Client module - WebSocket connection to a host.
+//!
+//! Provides a WebSocket client with automatic reconnection, certificate
+//! fingerprint pinning for self-signed hosts, heartbeats and a bounded send
+//! queue. Connection events are published as [`crate::server::CoreEvent`]s.
+//!
+//! This module is only available on native platforms (not wasm32).
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod websocket_client;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use websocket_client::{ClientConfig, ClientStatus, WebSocketClientState};
@@ -0,0 +1,590 @@
+//! WebSocket client.
+//!
+//! [`WebSocketClientState`] keeps one connection to a host alive: it
+//! reconnects with exponential backoff, pings the host to notice dead
+//! connections, and queues outgoing messages while disconnected. `wss://`
+//! hosts are verified against a pinned certificate [`Fingerprint`].
+//! The session token sent by the host is kept and presented on every
+//! reconnect, so the player gets their seat back.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use http::Uri;
+use parking_lot::Mutex;
+use rustls::pki_types::ServerName;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::error::CoreError;
+use crate::server::events::{CoreEvent, EventBus};
+use crate::server::limits::CLOSE_POLICY_VIOLATION;
+use crate::server::room_access::CLOSE_REJECTED;
+use crate::server::websocket::{Payload, CLOSE_KICKED};
+use crate::tls::{Fingerprint, PinnedCertVerifier};
+
+/// Close code reported when the connection dropped without a close frame
+pub const CLOSE_ABNORMAL: u16 = 1006;
+
+/// Close code sent when the client disconnects on purpose
+const CLOSE_NORMAL: u16 = 1000;
+
+/// Default number of messages queued while waiting for a connection
+pub const DEFAULT_SEND_QUEUE_CAPACITY: usize = 256;
+
+/// Connection settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientConfig {
+    /// Host URL (`ws://` or `wss://`), e.g. "wss://192.168.1.2:8089/ws"
+    pub url: String,
+    /// Certificate fingerprint of the host; required for `wss://`
+    pub fingerprint: Option<Fingerprint>,
+    /// Reconnect after the connection drops or an attempt fails
+    pub reconnect: bool,
+    /// Give up after this many failed reconnect attempts in a row (0 = never)
+    pub max_reconnect_attempts: u32,
+    /// Delay before the first reconnect attempt; doubled after each failure
+    pub initial_backoff: Duration,
+    /// Upper bound for the reconnect delay
+    pub max_backoff: Duration,
+    /// Time allowed for TCP, TLS and the WebSocket handshake together
+    pub connect_timeout: Duration,
+    /// Interval between pings (zero disables heartbeats)
+    pub heartbeat_interval: Duration,
+    /// Drop the connection if nothing arrives from the host for this long
+    pub heartbeat_timeout: Duration,
+    /// Messages queued while disconnected before `send` fails
+    pub send_queue_capacity: usize,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            fingerprint: None,
+            reconnect: true,
+            max_reconnect_attempts: 0,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            heartbeat_interval: Duration::from_secs(5),
+            heartbeat_timeout: Duration::from_secs(15),
+            send_queue_capacity: DEFAULT_SEND_QUEUE_CAPACITY,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Default settings for `url`
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Delay before reconnect attempt `attempt` (starting at 1)
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Connection status of the client
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ClientStatus {
+    /// Not connected and not trying to connect
+    #[default]
+    Disconnected,
+    /// First connection attempt in progress
+    Connecting,
+    /// Connected to the host
+    Connected,
+    /// Waiting to retry, or retrying, after a failure
+    Reconnecting,
+}
+
+impl ClientStatus {
+    /// Stable numeric code used across the C interface (`FT_CLIENT_*`)
+    pub fn code(&self) -> i32 {
+        use crate::ffi::client::*;
+        match self {
+            ClientStatus::Disconnected => FT_CLIENT_DISCONNECTED,
+            ClientStatus::Connecting => FT_CLIENT_CONNECTING,
+            ClientStatus::Connected => FT_CLIENT_CONNECTED,
+            ClientStatus::Reconnecting => FT_CLIENT_RECONNECTING,
+        }
+    }
+}
+
+impl std::fmt::Display for ClientStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientStatus::Disconnected => write!(f, "disconnected"),
+            ClientStatus::Connecting => write!(f, "connecting"),
+            ClientStatus::Connected => write!(f, "connected"),
+            ClientStatus::Reconnecting => write!(f, "reconnecting"),
+        }
+    }
+}
+
+/// WebSocket client state for FFI interface
+pub struct WebSocketClientState {
+    /// Tokio runtime for the connection task
+    runtime: Option<Arc<Runtime>>,
+
+    /// Shutdown signal sender (None when not connected)
+    shutdown_tx: Option<watch::Sender<()>>,
+
+    /// Messages waiting to be written to the socket
+    outbound: Option<mpsc::Sender<Payload>>,
+
+    /// Connection status, shared with the connection task
+    status: Arc<Mutex<ClientStatus>>,
+
+    /// Session token issued by the host, shared with the connection task
+    session_token: Arc<Mutex<Option<String>>>,
+
+    /// Connection events
+    events: EventBus,
+}
+
+impl Default for WebSocketClientState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebSocketClientState {
+    /// Create a new, disconnected client
+    pub fn new() -> Self {
+        Self {
+            runtime: None,
+            shutdown_tx: None,
+            outbound: None,
+            status: Arc::new(Mutex::new(ClientStatus::Disconnected)),
+            session_token: Arc::new(Mutex::new(None)),
+            events: EventBus::new(),
+        }
+    }
+
+    /// Event bus for connection events and host messages
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Current connection status
+    pub fn status(&self) -> ClientStatus {
+        *self.status.lock()
+    }
+
+    /// Session token issued by the host for this connection, if any
+    ///
+    /// Reconnects present it automatically. Store it to resume after the
+    /// app restarts by connecting to `.../ws?session=<token>`.
+    pub fn session_token(&self) -> Option<String> {
+        self.session_token.lock().clone()
+    }
+
+    /// Check if the connection task is running (connected or retrying)
+    pub fn is_running(&self) -> bool {
+        // The task holds the only receiver; it is closed once the task ends
+        self.shutdown_tx.as_ref().is_some_and(|tx| !tx.is_closed())
+    }
+
+    /// Start connecting to the host described by `config`
+    ///
+    /// Returns once the connection task is running; the outcome is reported
+    /// through `HostConnected`, `HostReconnecting` and `HostConnectFailed`
+    /// events.
+    ///
+    /// # Returns
+    /// Ok(()) on success, Err(CoreError) if the settings are invalid or the
+    /// client is already running
+    pub fn connect(&mut self, config: ClientConfig) -> Result<(), CoreError> {
+        if self.is_running() {
+            eprintln!("[WSC] Connect failed: client is already running");
+            return Err(CoreError::AlreadyRunning);
+        }
+
+        let target = Target::parse(&config.url)?;
+        if target.secure && config.fingerprint.is_none() {
+            return Err(CoreError::InvalidConfig(
+                "wss:// requires the host certificate fingerprint".to_string(),
+            ));
+        }
+        if !target.secure && config.fingerprint.is_some() {
+            return Err(CoreError::InvalidConfig(
+                "a certificate fingerprint requires a wss:// URL".to_string(),
+            ));
+        }
+        if config.send_queue_capacity == 0 {
+            return Err(CoreError::InvalidConfig("send queue capacity must be at least 1".to_string()));
+        }
+
+        if self.runtime.is_none() {
+            let runtime = Runtime::new()
+                .map_err(|e| {
+                    eprintln!("[WSC] Failed to create Tokio runtime: {}", e);
+                    CoreError::RuntimeError(e.to_string())
+                })?;
+            self.runtime = Some(Arc::new(runtime));
+        }
+        let runtime = self.runtime.as_ref().unwrap().clone();
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let (outbound, outbound_rx) = mpsc::channel(config.send_queue_capacity);
+        *self.status.lock() = ClientStatus::Connecting;
+        *self.session_token.lock() = None;
+        eprintln!("[WSC] Connecting to {}", config.url);
+
+        let task = ConnectionTask {
+            config,
+            target,
+            outbound_rx,
+            shutdown_rx,
+            status: self.status.clone(),
+            session_token: self.session_token.clone(),
+            events: self.events.clone(),
+        };
+        runtime.spawn(task.run());
+
+        self.shutdown_tx = Some(shutdown_tx);
+        self.outbound = Some(outbound);
+        Ok(())
+    }
+
+    /// Queue a message for the host
+    ///
+    /// Messages queued while disconnected are sent once the connection is
+    /// (re-)established.
+    ///
+    /// # Returns
+    /// Ok(()) if queued, `CoreError::QueueFull` if the queue is full, or
+    /// `CoreError::NotRunning` if the client is not running
+    pub fn send(&self, payload: Payload) -> Result<(), CoreError> {
+        let outbound = self.outbound.as_ref().ok_or(CoreError::NotRunning)?;
+        outbound.try_send(payload).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => CoreError::QueueFull,
+            mpsc::error::TrySendError::Closed(_) => CoreError::NotRunning,
+        })
+    }
+
+    /// Close the connection (with code 1000) and stop reconnecting
+    pub fn disconnect(&mut self) {
+        self.outbound = None;
+        match self.shutdown_tx.take() {
+            Some(tx) => {
+                let _ = tx.send(());
+                eprintln!("[WSC] Disconnect requested");
+            }
+            None => eprintln!("[WSC] Disconnect called but client is not running"),
+        }
+    }
+}
+
+impl Drop for WebSocketClientState {
+    fn drop(&mut self) {
+        self.disconnect();
+        self.runtime.take();
+    }
+}
+
+/// Parsed host URL
+struct Target {
+    url: String,
+    host: String,
+    port: u16,
+    secure: bool,
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self, CoreError> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|e| CoreError::InvalidAddress(format!("{}: {}", url, e)))?;
+        let secure = match uri.scheme_str() {
+            Some("ws") => false,
+            Some("wss") => true,
+            _ => return Err(CoreError::InvalidAddress(format!("{}: expected ws:// or wss://", url))),
+        };
+        let host = uri
+            .host()
+            .ok_or_else(|| CoreError::InvalidAddress(format!("{}: missing host", url)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+        Ok(Self {
+            url: url.to_string(),
+            host,
+            port,
+            secure,
+        })
+    }
+}
+
+/// Byte stream under the WebSocket (plain TCP or TLS)
+trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+type Socket = WebSocketStream<Box<dyn Transport>>;
+
+/// Why a connection attempt failed
+enum ConnectError {
+    /// Worth retrying (host unreachable, handshake failed, ...)
+    Retry(String),
+    /// Retrying cannot help (certificate does not match the pin)
+    Fatal(String),
+}
+
+/// How a connected session ended
+struct SessionEnd {
+    code: u16,
+    reason: String,
+    /// Do not reconnect (disconnect requested, kicked or rejected by the host)
+    stop: bool,
+}
+
+impl SessionEnd {
+    fn dropped(reason: impl Into<String>) -> Self {
+        Self {
+            code: CLOSE_ABNORMAL,
+            reason: reason.into(),
+            stop: false,
+        }
+    }
+}
+
+/// Background task owning the connection
+struct ConnectionTask {
+    config: ClientConfig,
+    target: Target,
+    outbound_rx: mpsc::Receiver<Payload>,
+    shutdown_rx: watch::Receiver<()>,
+    status: Arc<Mutex<ClientStatus>>,
+    session_token: Arc<Mutex<Option<String>>>,
+    events: EventBus,
+}
+
+impl ConnectionTask {
+    async fn run(mut self) {
+        // Reconnect attempts since the last successful connection
+        let mut attempt: u32 = 0;
+        loop {
+            // Resume the current session if the host issued one
+            let url = session_url(&self.target.url, self.session_token.lock().clone().as_deref());
+            let result = tokio::select! {
+                result = tokio::time::timeout(self.config.connect_timeout, open(&self.target, &url, &self.config)) => {
+                    result.unwrap_or_else(|_| Err(ConnectError::Retry("connection timed out".to_string())))
+                }
+                _ = self.shutdown_rx.changed() => break,
+            };
+
+            let reason = match result {
+                Ok(socket) => {
+                    attempt = 0;
+                    self.set_status(ClientStatus::Connected);
+                    eprintln!("[WSC] Connected to {}", self.target.url);
+                    self.events.emit(CoreEvent::HostConnected {
+                        url: self.target.url.clone(),
+                    });
+
+                    let end = self.run_session(socket).await;
+                    eprintln!("[WSC] Disconnected ({}): {}", end.code, end.reason);
+                    self.events.emit(CoreEvent::HostDisconnected {
+                        code: end.code,
+                        reason: end.reason.clone(),
+                    });
+                    if end.stop || !self.config.reconnect {
+                        break;
+                    }
+                    end.reason
+                }
+                Err(ConnectError::Fatal(reason)) => {
+                    eprintln!("[WSC] Connection to {} failed: {}", self.target.url, reason);
+                    self.events.emit(CoreEvent::HostConnectFailed { reason });
+                    break;
+                }
+                Err(ConnectError::Retry(reason)) => {
+                    eprintln!("[WSC] Connection to {} failed: {}", self.target.url, reason);
+                    if !self.config.reconnect {
+                        self.events.emit(CoreEvent::HostConnectFailed { reason });
+                        break;
+                    }
+                    if self.config.max_reconnect_attempts > 0 && attempt >= self.config.max_reconnect_attempts {
+                        self.events.emit(CoreEvent::HostConnectFailed {
+                            reason: format!("gave up after {} reconnect attempts: {}", attempt, reason),
+                        });
+                        break;
+                    }
+                    reason
+                }
+            };
+
+            attempt += 1;
+            let delay = self.config.backoff_delay(attempt);
+            self.set_status(ClientStatus::Reconnecting);
+            eprintln!("[WSC] Reconnecting in {:?} (attempt {}, last error: {})", delay, attempt, reason);
+            self.events.emit(CoreEvent::HostReconnecting {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            });
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.shutdown_rx.changed() => break,
+            }
+        }
+
+        self.set_status(ClientStatus::Disconnected);
+        eprintln!("[WSC] Connection task ended");
+    }
+
+    fn set_status(&self, status: ClientStatus) {
+        *self.status.lock() = status;
+    }
+
+    /// Pump messages until the connection ends or a disconnect is requested
+    async fn run_session(&mut self, socket: Socket) -> SessionEnd {
+        let (mut sink, mut stream) = socket.split();
+        let heartbeat_enabled = !self.config.heartbeat_interval.is_zero();
+        let period = if heartbeat_enabled {
+            self.config.heartbeat_interval
+        } else {
+            Duration::from_secs(3600)
+        };
+        let mut heartbeat = tokio::time::interval_at(Instant::now() + period, period);
+        let mut last_seen = Instant::now();
+
+        loop {
+            tokio::select! {
+                _ = self.shutdown_rx.changed() => break,
+                payload = self.outbound_rx.recv() => {
+                    let message = match payload {
+                        Some(Payload::Text(text)) => Message::text(text),
+                        Some(Payload::Binary(bytes)) => Message::binary(bytes),
+                        // The sender is dropped on disconnect, possibly before the shutdown signal is seen
+                        None => break,
+                    };
+                    if let Err(e) = sink.send(message).await {
+                        return SessionEnd::dropped(e.to_string());
+                    }
+                }
+                frame = stream.next() => {
+                    last_seen = Instant::now();
+                    match frame {
+                        Some(Ok(Message::Text(text))) => match session_token(&text) {
+                            Some(token) => *self.session_token.lock() = Some(token),
+                            None => self.events.emit(CoreEvent::HostMessage { message: text.to_string() }),
+                        },
+                        Some(Ok(Message::Close(frame))) => {
+                            let (code, reason) = frame
+                                .map(|f| (u16::from(f.code), f.reason.to_string()))
+                                .unwrap_or((CLOSE_NORMAL, String::new()));
+                            // Retrying a rejected join would only count towards a lockout,
+                            // and a client closed for flooding would flood again
+                            let stop = code == CLOSE_KICKED || code == CLOSE_REJECTED || code == CLOSE_POLICY_VIOLATION;
+                            return SessionEnd { code, reason, stop };
+                        }
+                        // Pings are answered by tungstenite; binary frames are not part of the protocol
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return SessionEnd::dropped(e.to_string()),
+                        None => return SessionEnd::dropped("Connection closed"),
+                    }
+                }
+                _ = heartbeat.tick(), if heartbeat_enabled => {
+                    if last_seen.elapsed() > self.config.heartbeat_timeout {
+                        return SessionEnd::dropped("Heartbeat timed out");
+                    }
+                    if let Err(e) = sink.send(Message::Ping(Vec::new().into())).await {
+                        return SessionEnd::dropped(e.to_string());
+                    }
+                }
+            }
+        }
+
+        let reason = "Client disconnected";
+        let _ = sink
+            .send(Message::Close(Some(CloseFrame {
+                code: CloseCode::from(CLOSE_NORMAL),
+                reason: reason.into(),
+            })))
+            .await;
+        SessionEnd {
+            code: CLOSE_NORMAL,
+            reason: reason.to_string(),
+            stop: true,
+        }
+    }
+}
+
+/// Token from a host `session` message; other messages give None
+fn session_token(text: &str) -> Option<String> {
+    if !text.contains("\"session\"") {
+        return None;
+    }
+    let message: serde_json::Value = serde_json::from_str(text).ok()?;
+    if message["type"] != "session" {
+        return None;
+    }
+    message["token"].as_str().map(str::to_string)
+}
+
+/// `url` with its `session` query parameter set to `token`
+fn session_url(url: &str, token: Option<&str>) -> String {
+    let Some(token) = token else {
+        return url.to_string();
+    };
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let session = format!("session={}", token);
+    let params: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("session="))
+        .chain(std::iter::once(session.as_str()))
+        .collect();
+    format!("{}?{}", base, params.join("&"))
+}
+
+/// Open the TCP connection, TLS session and WebSocket to `url`
+async fn open(target: &Target, url: &str, config: &ClientConfig) -> Result<Socket, ConnectError> {
+    let tcp = TcpStream::connect((target.host.as_str(), target.port))
+        .await
+        .map_err(|e| ConnectError::Retry(e.to_string()))?;
+    let _ = tcp.set_nodelay(true);
+
+    let transport: Box<dyn Transport> = match config.fingerprint {
+        Some(fingerprint) if target.secure => {
+            let connector = TlsConnector::from(Arc::new(PinnedCertVerifier::client_config(fingerprint)));
+            let server_name = ServerName::try_from(target.host.clone())
+                .map_err(|e| ConnectError::Fatal(format!("invalid host name {}: {}", target.host, e)))?;
+            let tls = connector.connect(server_name, tcp).await.map_err(|e| {
+                let mismatch = e
+                    .get_ref()
+                    .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+                    .is_some_and(|inner| matches!(inner, rustls::Error::InvalidCertificate(_)));
+                if mismatch {
+                    ConnectError::Fatal(format!("host certificate does not match fingerprint {}", fingerprint))
+                } else {
+                    ConnectError::Retry(e.to_string())
+                }
+            })?;
+            Box::new(tls)
+        }
+        _ => Box::new(tcp),
+    };
+
+    let (socket, _response) = tokio_tungstenite::client_async(url, transport)
+        .await
+        .map_err(|e| ConnectError::Retry(e.to_string()))?;
+    Ok(socket)
+}
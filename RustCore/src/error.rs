@@ -34,6 +34,46 @@ pub enum CoreError {
     #[error("JSON serialization error: {0}")]
     JsonError(String),
 
+    /// A join token could not be encoded or decoded.
+    #[error("Invalid join token: {0}")]
+    InvalidJoinToken(String),
+
+    /// TLS configuration or certificate generation failed.
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    /// The async runtime could not be created.
+    #[error("Runtime error: {0}")]
+    RuntimeError(String),
+
+    /// The mDNS daemon could not be created or failed to register.
+    #[error("mDNS error: {0}")]
+    MdnsError(String),
+
+    /// A route handler prefix is invalid.
+    #[error("Invalid route: {0}")]
+    InvalidRoute(String),
+
+    /// No WebSocket peer has the given id.
+    #[error("Peer not found: {0}")]
+    PeerNotFound(u32),
+
+    /// Host configuration failed validation.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// The send queue is full; the message was not queued.
+    #[error("Send queue is full")]
+    QueueFull,
+
+    /// No player session holds the given seat, or it is taken by another player.
+    #[error("Seat not available: {0}")]
+    SeatNotFound(u32),
+
+    /// A seat index from the host is negative or too large to be a seat.
+    #[error("Invalid seat: {0}")]
+    InvalidSeat(i64),
+
     /// An unknown error occurred.
     #[error("Unknown error occurred")]
     Unknown,
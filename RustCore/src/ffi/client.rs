@@ -0,0 +1,144 @@
+// WebSocket client FFI implementation - exports C-compatible functions
+
+use std::os::raw::c_char;
+
+use crate::client::ClientConfig;
+use crate::server::Payload;
+
+use super::error::{read_c_string, set_core_error, set_last_error, status_code, FtErrorCode};
+use super::events::ffi_event_queue;
+
+// WebSocket client status returned by ft_ws_client_status, exported to the C header
+
+/// Not connected and not trying to connect
+pub const FT_CLIENT_DISCONNECTED: i32 = 0;
+/// First connection attempt in progress
+pub const FT_CLIENT_CONNECTING: i32 = 1;
+/// Connected to the host
+pub const FT_CLIENT_CONNECTED: i32 = 2;
+/// Waiting to retry, or retrying, after a failure
+pub const FT_CLIENT_RECONNECTING: i32 = 3;
+
+/// Pointer type for WebSocketClientState
+pub type FtWsClient = crate::client::WebSocketClientState;
+
+/// Create a new WebSocket client instance
+///
+/// Connection events and host messages are delivered through
+/// `ft_core_poll_events`.
+///
+/// # Safety
+/// The returned pointer must be freed with ft_ws_client_free
+#[no_mangle]
+pub unsafe extern "C" fn ft_ws_client_create() -> *mut FtWsClient {
+    let client = Box::new(FtWsClient::new());
+    client.events().attach(ffi_event_queue());
+    Box::into_raw(client)
+}
+
+/// Free a WebSocket client instance
+///
+/// # Safety
+/// The pointer must be valid and will be consumed.
+/// Dropped in a separate thread, like the servers.
+#[no_mangle]
+pub unsafe extern "C" fn ft_ws_client_free(client: *mut FtWsClient) {
+    if client.is_null() {
+        return;
+    }
+    let client = Box::from_raw(client);
+    std::thread::spawn(|| {
+        drop(client);
+    });
+}
+
+/// Start connecting to a host
+///
+/// # Arguments
+/// * `client` - Client handle
+/// * `url` - Host URL (e.g., "wss://192.168.1.2:8089/ws")
+/// * `fingerprint` - SHA-256 certificate fingerprint of the host ("AB:CD:..."); required for wss://, NULL for ws://
+/// * `reconnect` - Non-zero to reconnect with backoff after failures
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_ws_client_connect(
+    client: *mut FtWsClient,
+    url: *const c_char,
+    fingerprint: *const c_char,
+    reconnect: i32,
+) -> i32 {
+    if client.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "client is NULL");
+    }
+    let url = match read_c_string(url, "url", None) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    let fingerprint = match read_c_string(fingerprint, "fingerprint", Some("")) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+
+    let mut config = ClientConfig::new(&url);
+    config.reconnect = reconnect != 0;
+    if !fingerprint.is_empty() {
+        match fingerprint.parse() {
+            Ok(fingerprint) => config.fingerprint = Some(fingerprint),
+            Err(e) => return set_core_error(&e),
+        }
+    }
+
+    let client = &mut *client;
+    status_code(client.connect(config))
+}
+
+/// Queue a text message for the host
+///
+/// Messages queued while reconnecting are sent once connected.
+///
+/// # Returns
+/// FT_OK (0) on success, FT_ERR_QUEUE_FULL if the send queue is full,
+/// otherwise an error code (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_ws_client_send(client: *mut FtWsClient, message: *const c_char) -> i32 {
+    if client.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "client is NULL");
+    }
+    let message = match read_c_string(message, "message", None) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    let client = &*client;
+    status_code(client.send(Payload::Text(message)))
+}
+
+/// Close the connection and stop reconnecting
+///
+/// # Arguments
+/// * `client` - Client handle
+#[no_mangle]
+pub unsafe extern "C" fn ft_ws_client_disconnect(client: *mut FtWsClient) {
+    if client.is_null() {
+        return;
+    }
+    let client = &mut *client;
+    client.disconnect();
+}
+
+/// Get the connection status
+///
+/// # Arguments
+/// * `client` - Client handle
+///
+/// # Returns
+/// One of the FT_CLIENT_* values (FT_CLIENT_DISCONNECTED for NULL)
+#[no_mangle]
+pub unsafe extern "C" fn ft_ws_client_status(client: *mut FtWsClient) -> i32 {
+    if client.is_null() {
+        return FT_CLIENT_DISCONNECTED;
+    }
+    let client = &*client;
+    client.status().code()
+}
@@ -0,0 +1,215 @@
+//! FFI error reporting.
+//!
+//! Fallible `ft_*` functions return an [`FtErrorCode`] (`FT_OK` on success).
+//! The full message of the most recent failure on the calling thread is
+//! available from `ft_last_error_message()`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::error::CoreError;
+
+// Error codes returned by fallible functions, exported to the C header.
+// Values are part of the ABI: never renumber, only append.
+
+/// Success
+pub const FT_OK: i32 = 0;
+/// A required pointer argument was NULL
+pub const FT_ERR_NULL_POINTER: i32 = 1;
+/// A string argument was not valid UTF-8
+pub const FT_ERR_INVALID_UTF8: i32 = 2;
+/// The server is not running
+pub const FT_ERR_NOT_RUNNING: i32 = 3;
+/// The server is already running
+pub const FT_ERR_ALREADY_RUNNING: i32 = 4;
+/// Failed to bind to the requested address
+pub const FT_ERR_BIND_FAILED: i32 = 5;
+/// The address could not be parsed
+pub const FT_ERR_INVALID_ADDRESS: i32 = 6;
+/// The static directory does not exist
+pub const FT_ERR_STATIC_DIR_NOT_FOUND: i32 = 7;
+/// An I/O operation failed
+pub const FT_ERR_IO: i32 = 8;
+/// JSON serialization or deserialization failed
+pub const FT_ERR_JSON: i32 = 9;
+/// A join token could not be encoded or decoded
+pub const FT_ERR_INVALID_JOIN_TOKEN: i32 = 10;
+/// TLS setup failed
+pub const FT_ERR_TLS: i32 = 11;
+/// The async runtime could not be created
+pub const FT_ERR_RUNTIME: i32 = 12;
+/// The mDNS daemon reported an error
+pub const FT_ERR_MDNS: i32 = 13;
+/// A route handler prefix is invalid
+pub const FT_ERR_INVALID_ROUTE: i32 = 14;
+/// No WebSocket peer has the given id
+pub const FT_ERR_PEER_NOT_FOUND: i32 = 15;
+/// Host configuration failed validation
+pub const FT_ERR_INVALID_CONFIG: i32 = 16;
+/// The send queue is full
+pub const FT_ERR_QUEUE_FULL: i32 = 17;
+/// No session holds the seat, or it is taken
+pub const FT_ERR_SEAT_NOT_FOUND: i32 = 18;
+/// Any other error
+pub const FT_ERR_UNKNOWN: i32 = -1;
+
+/// Stable numeric error codes returned across the C interface (the `FT_OK` / `FT_ERR_*` constants)
+///
+/// Values are part of the ABI: never renumber, only append.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FtErrorCode {
+    /// Success
+    Ok = FT_OK,
+    /// A required pointer argument was NULL
+    NullPointer = FT_ERR_NULL_POINTER,
+    /// A string argument was not valid UTF-8
+    InvalidUtf8 = FT_ERR_INVALID_UTF8,
+    /// The server is not running
+    NotRunning = FT_ERR_NOT_RUNNING,
+    /// The server is already running
+    AlreadyRunning = FT_ERR_ALREADY_RUNNING,
+    /// Failed to bind to the requested address
+    BindFailed = FT_ERR_BIND_FAILED,
+    /// The address could not be parsed
+    InvalidAddress = FT_ERR_INVALID_ADDRESS,
+    /// The static directory does not exist
+    StaticDirNotFound = FT_ERR_STATIC_DIR_NOT_FOUND,
+    /// An I/O operation failed
+    Io = FT_ERR_IO,
+    /// JSON serialization or deserialization failed
+    Json = FT_ERR_JSON,
+    /// A join token could not be encoded or decoded
+    InvalidJoinToken = FT_ERR_INVALID_JOIN_TOKEN,
+    /// TLS setup failed
+    Tls = FT_ERR_TLS,
+    /// The async runtime could not be created
+    Runtime = FT_ERR_RUNTIME,
+    /// The mDNS daemon reported an error
+    Mdns = FT_ERR_MDNS,
+    /// A route handler prefix is invalid
+    InvalidRoute = FT_ERR_INVALID_ROUTE,
+    /// No WebSocket peer has the given id
+    PeerNotFound = FT_ERR_PEER_NOT_FOUND,
+    /// Host configuration failed validation
+    InvalidConfig = FT_ERR_INVALID_CONFIG,
+    /// The send queue is full
+    QueueFull = FT_ERR_QUEUE_FULL,
+    /// No session holds the seat, or it is taken
+    SeatNotFound = FT_ERR_SEAT_NOT_FOUND,
+    /// Any other error
+    Unknown = FT_ERR_UNKNOWN,
+}
+
+impl From<&CoreError> for FtErrorCode {
+    fn from(error: &CoreError) -> Self {
+        match error {
+            CoreError::NotRunning => FtErrorCode::NotRunning,
+            CoreError::AlreadyRunning => FtErrorCode::AlreadyRunning,
+            CoreError::BindFailed(_) => FtErrorCode::BindFailed,
+            CoreError::InvalidAddress(_) => FtErrorCode::InvalidAddress,
+            CoreError::StaticDirNotFound(_) => FtErrorCode::StaticDirNotFound,
+            CoreError::IoError(_) => FtErrorCode::Io,
+            CoreError::JsonError(_) => FtErrorCode::Json,
+            CoreError::InvalidJoinToken(_) => FtErrorCode::InvalidJoinToken,
+            CoreError::TlsError(_) => FtErrorCode::Tls,
+            CoreError::RuntimeError(_) => FtErrorCode::Runtime,
+            CoreError::MdnsError(_) => FtErrorCode::Mdns,
+            CoreError::InvalidRoute(_) => FtErrorCode::InvalidRoute,
+            CoreError::PeerNotFound(_) => FtErrorCode::PeerNotFound,
+            CoreError::InvalidConfig(_) => FtErrorCode::InvalidConfig,
+            CoreError::QueueFull => FtErrorCode::QueueFull,
+            CoreError::SeatNotFound(_) | CoreError::InvalidSeat(_) => FtErrorCode::SeatNotFound,
+            CoreError::Unknown => FtErrorCode::Unknown,
+        }
+    }
+}
+
+thread_local! {
+    /// Most recent error on this thread
+    static LAST_ERROR: RefCell<Option<(FtErrorCode, CString)>> = const { RefCell::new(None) };
+}
+
+/// Record an error for `ft_last_error_*` and return its code
+pub(crate) fn set_last_error(code: FtErrorCode, message: impl Into<String>) -> i32 {
+    let message = message.into();
+    eprintln!("[FFI] Error {:?}: {}", code, message);
+    // Interior NULs cannot cross the C boundary; replace them
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((code, message)));
+    code as i32
+}
+
+/// Record a `CoreError` and return its code
+pub(crate) fn set_core_error(error: &CoreError) -> i32 {
+    set_last_error(FtErrorCode::from(error), error.to_string())
+}
+
+/// Clear the last error and return `FT_OK`
+pub(crate) fn clear_last_error() -> i32 {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    FtErrorCode::Ok as i32
+}
+
+/// Convert a `Result` into a status code, recording the error if any
+pub(crate) fn status_code(result: Result<(), CoreError>) -> i32 {
+    match result {
+        Ok(()) => clear_last_error(),
+        Err(e) => set_core_error(&e),
+    }
+}
+
+/// Read a C string argument
+///
+/// Returns `default` for NULL (or `FtErrorCode::NullPointer` if there is no
+/// default), and records `FtErrorCode::InvalidUtf8` for invalid strings.
+///
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string.
+pub(crate) unsafe fn read_c_string(
+    ptr: *const c_char,
+    name: &str,
+    default: Option<&str>,
+) -> Result<String, i32> {
+    if ptr.is_null() {
+        return match default {
+            Some(default) => Ok(default.to_string()),
+            None => Err(set_last_error(FtErrorCode::NullPointer, format!("{} is NULL", name))),
+        };
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(|s| s.to_string())
+        .map_err(|e| set_last_error(FtErrorCode::InvalidUtf8, format!("{} is not valid UTF-8: {}", name, e)))
+}
+
+/// Get the code of the most recent error on this thread
+///
+/// # Returns
+/// The error code, or `FT_OK` if the last call succeeded
+#[no_mangle]
+pub extern "C" fn ft_last_error_code() -> i32 {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|(code, _)| *code as i32)
+            .unwrap_or(FtErrorCode::Ok as i32)
+    })
+}
+
+/// Get the message of the most recent error on this thread
+///
+/// # Returns
+/// NUL-terminated message owned by the library, valid until the next `ft_*`
+/// call on this thread, or NULL if the last call succeeded
+#[no_mangle]
+pub extern "C" fn ft_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|(_, message)| message.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
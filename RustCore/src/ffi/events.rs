@@ -0,0 +1,132 @@
+// Event FFI implementation - exports C-compatible functions
+//
+// Every server created through the FFI publishes into one process-wide
+// queue. The host registers a callback and drains the queue with
+// ft_core_poll_events from the thread of its choice (e.g. the main thread),
+// so callbacks never run on Tokio worker threads.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+
+use crate::server::EventQueue;
+
+use super::error::clear_last_error;
+use super::server::UserData;
+
+// Event types passed to FtEventCallback, exported to the C header. Values are
+// part of the ABI: never renumber, only append. The JSON payload always has a
+// "type" field with the snake_case event name.
+
+/// `server_started` event, payload `{"address"}`
+pub const FT_EVENT_SERVER_STARTED: i32 = 1;
+/// `server_stopped` event, payload `{}`
+pub const FT_EVENT_SERVER_STOPPED: i32 = 2;
+/// `server_failed` event, payload `{"reason"}`
+pub const FT_EVENT_SERVER_FAILED: i32 = 3;
+/// `client_connected` event, payload `{"peer_id", "address"}`
+pub const FT_EVENT_CLIENT_CONNECTED: i32 = 4;
+/// `client_disconnected` event, payload `{"peer_id"}`
+pub const FT_EVENT_CLIENT_DISCONNECTED: i32 = 5;
+/// `message_received` event, payload `{"peer_id", "message"}`
+pub const FT_EVENT_MESSAGE_RECEIVED: i32 = 6;
+/// `room` event, payload `{"name", "data"}`
+pub const FT_EVENT_ROOM: i32 = 7;
+/// `game` event, payload `{"name", "data"}`
+pub const FT_EVENT_GAME: i32 = 8;
+/// `mdns_service_found` event, payload `{"service_type", "fullname"}`
+pub const FT_EVENT_MDNS_SERVICE_FOUND: i32 = 9;
+/// `mdns_service_removed` event, payload `{"service_type", "fullname"}`
+pub const FT_EVENT_MDNS_SERVICE_REMOVED: i32 = 10;
+/// `mdns_failed` event, payload `{"reason"}`
+pub const FT_EVENT_MDNS_FAILED: i32 = 11;
+/// `host_connected` event, payload `{"url"}`
+pub const FT_EVENT_HOST_CONNECTED: i32 = 12;
+/// `host_disconnected` event, payload `{"code", "reason"}`
+pub const FT_EVENT_HOST_DISCONNECTED: i32 = 13;
+/// `host_message` event, payload `{"message"}`
+pub const FT_EVENT_HOST_MESSAGE: i32 = 14;
+/// `host_reconnecting` event, payload `{"attempt", "delay_ms"}`
+pub const FT_EVENT_HOST_RECONNECTING: i32 = 15;
+/// `host_connect_failed` event, payload `{"reason"}`
+pub const FT_EVENT_HOST_CONNECT_FAILED: i32 = 16;
+/// `join_rejected` event, payload `{"address", "reason"}`
+pub const FT_EVENT_JOIN_REJECTED: i32 = 17;
+/// `seat_held` event, payload `{"peer_id", "seat", "grace_ms"}`
+pub const FT_EVENT_SEAT_HELD: i32 = 18;
+/// `seat_released` event, payload `{"peer_id", "seat"}`
+pub const FT_EVENT_SEAT_RELEASED: i32 = 19;
+/// `player_resumed` event, payload `{"peer_id", "previous_peer_id", "seat" (or null)}`
+pub const FT_EVENT_PLAYER_RESUMED: i32 = 20;
+/// `peer_stale` event, payload `{"peer_id", "missed_pongs"}`
+pub const FT_EVENT_PEER_STALE: i32 = 21;
+/// `peer_recovered` event, payload `{"peer_id", "rtt_ms"}`
+pub const FT_EVENT_PEER_RECOVERED: i32 = 22;
+/// `connection_refused` event, payload `{"address", "reason"}`
+pub const FT_EVENT_CONNECTION_REFUSED: i32 = 23;
+/// `peer_limited` event, payload `{"peer_id", "reason"}`
+pub const FT_EVENT_PEER_LIMITED: i32 = 24;
+
+/// Event callback
+///
+/// Receives the user data pointer, the event type (`FT_EVENT_*`) and the
+/// JSON-encoded event, which is only valid for the duration of the call.
+pub type FtEventCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, event_type: i32, event_json: *const c_char)>;
+
+/// Queue shared by every FFI-created server
+static EVENT_QUEUE: LazyLock<EventQueue> = LazyLock::new(EventQueue::default);
+
+/// Registered callback and its user data
+static EVENT_CALLBACK: Mutex<Option<(unsafe extern "C" fn(*mut c_void, i32, *const c_char), UserData)>> =
+    Mutex::new(None);
+
+/// Queue that FFI-created servers publish into
+pub(crate) fn ffi_event_queue() -> EventQueue {
+    EVENT_QUEUE.clone()
+}
+
+/// Set (or clear, with NULL) the event callback
+///
+/// The callback is only ever invoked from inside `ft_core_poll_events`, on
+/// the thread calling it. `user_data` is passed through unchanged.
+///
+/// # Returns
+/// FT_OK (0)
+#[no_mangle]
+pub unsafe extern "C" fn ft_core_set_event_callback(callback: FtEventCallback, user_data: *mut c_void) -> i32 {
+    *EVENT_CALLBACK.lock() = callback.map(|callback| (callback, UserData(user_data)));
+    clear_last_error()
+}
+
+/// Deliver queued events to the callback
+///
+/// Events are delivered oldest first. Without a callback the queued events
+/// are discarded. The queue holds at most 1024 events; older events are
+/// dropped if the host polls less often than that.
+///
+/// # Returns
+/// Number of events delivered
+#[no_mangle]
+pub unsafe extern "C" fn ft_core_poll_events() -> i32 {
+    let events = EVENT_QUEUE.drain();
+    // Copy the callback out so it may call ft_core_set_event_callback itself
+    let callback = *EVENT_CALLBACK.lock();
+    let (callback, user_data) = match callback {
+        Some(callback) => callback,
+        None => return 0,
+    };
+
+    let mut delivered = 0;
+    for event in events {
+        let json = match CString::new(event.to_json()) {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+        callback(user_data.get(), event.code(), json.as_ptr());
+        delivered += 1;
+    }
+    delivered
+}
@@ -1,11 +1,16 @@
 // mDNS Server FFI implementation - exports C-compatible functions
 
-use std::ffi::CStr;
 use std::os::raw::c_char;
 
+use super::error::{clear_last_error, read_c_string, set_last_error, status_code, FtErrorCode};
+use super::events::ffi_event_queue;
+
 /// Pointer type for MdnsServerState
 pub type FtMdnsServer = crate::server::MdnsServerState;
 
+/// Pointer type for MdnsBrowserState
+pub type FtMdnsBrowser = crate::server::MdnsBrowserState;
+
 /// Create a new mDNS server instance
 ///
 /// # Safety
@@ -13,6 +18,7 @@ pub type FtMdnsServer = crate::server::MdnsServerState;
 #[no_mangle]
 pub unsafe extern "C" fn ft_mdns_server_create() -> *mut FtMdnsServer {
     let server = Box::new(FtMdnsServer::new());
+    server.events().attach(ffi_event_queue());
     Box::into_raw(server)
 }
 
@@ -45,7 +51,7 @@ pub unsafe extern "C" fn ft_mdns_server_free(server: *mut FtMdnsServer) {
 /// * `port` - Service port
 ///
 /// # Returns
-/// 1 on success, 0 on failure
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
 #[no_mangle]
 pub unsafe extern "C" fn ft_mdns_server_start(
     server: *mut FtMdnsServer,
@@ -55,42 +61,63 @@ pub unsafe extern "C" fn ft_mdns_server_start(
     port: u16,
 ) -> i32 {
     if server.is_null() {
-        return 0;
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
     }
 
-    let service_type = if service_type.is_null() {
-        "_service._tcp.local.".to_string()
-    } else {
-        match CStr::from_ptr(service_type).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return 0,
-        }
+    let service_type = match read_c_string(service_type, "service_type", Some("_service._tcp.local.")) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
-    let instance_name = if instance_name.is_null() {
-        "Instance".to_string()
-    } else {
-        match CStr::from_ptr(instance_name).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return 0,
-        }
+    let instance_name = match read_c_string(instance_name, "instance_name", Some("Instance")) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
-    let hostname = if hostname.is_null() {
-        "server".to_string()
-    } else {
-        match CStr::from_ptr(hostname).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return 0,
-        }
+    let hostname = match read_c_string(hostname, "hostname", Some("server")) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
     // Dereference mutable pointer (safe because we checked for null)
     let server = &mut *server;
-    match server.start(&service_type, &instance_name, &hostname, port) {
-        Ok(()) => 1,
-        Err(_) => 0,
+    status_code(server.start(&service_type, &instance_name, &hostname, port))
+}
+
+/// Set a TXT record property published with the service
+///
+/// Replaces any previous value for `key`. Takes effect on the next
+/// `ft_mdns_server_start`. Publish the host certificate fingerprint under
+/// the `"fingerprint"` key so clients can pin it.
+///
+/// # Arguments
+/// * `server` - Server handle
+/// * `key` - Property key
+/// * `value` - Property value
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_mdns_server_set_txt_property(
+    server: *mut FtMdnsServer,
+    key: *const c_char,
+    value: *const c_char,
+) -> i32 {
+    if server.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
     }
+    let key = match read_c_string(key, "key", None) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    let value = match read_c_string(value, "value", Some("")) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+
+    let server = &mut *server;
+    server.set_txt_property(&key, &value);
+    clear_last_error()
 }
 
 /// Stop the mDNS service
@@ -125,3 +152,66 @@ pub unsafe extern "C" fn ft_mdns_server_is_running(server: *mut FtMdnsServer) ->
         0
     }
 }
+
+/// Create a new mDNS browser instance
+///
+/// Discoveries are delivered through `ft_core_poll_events`.
+///
+/// # Safety
+/// The returned pointer must be freed with ft_mdns_browser_free
+#[no_mangle]
+pub unsafe extern "C" fn ft_mdns_browser_create() -> *mut FtMdnsBrowser {
+    let browser = Box::new(FtMdnsBrowser::new());
+    browser.events().attach(ffi_event_queue());
+    Box::into_raw(browser)
+}
+
+/// Free an mDNS browser instance
+///
+/// # Safety
+/// The pointer must be valid and will be consumed.
+/// Dropped in a separate thread, like the other servers.
+#[no_mangle]
+pub unsafe extern "C" fn ft_mdns_browser_free(browser: *mut FtMdnsBrowser) {
+    if browser.is_null() {
+        return;
+    }
+    let browser = Box::from_raw(browser);
+    std::thread::spawn(|| {
+        drop(browser);
+    });
+}
+
+/// Start browsing for a service type
+///
+/// # Arguments
+/// * `browser` - Browser handle
+/// * `service_type` - Service type (e.g., "_game._tcp.local.")
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_mdns_browser_start(browser: *mut FtMdnsBrowser, service_type: *const c_char) -> i32 {
+    if browser.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "browser is NULL");
+    }
+    let service_type = match read_c_string(service_type, "service_type", None) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    let browser = &mut *browser;
+    status_code(browser.start(&service_type))
+}
+
+/// Stop browsing
+///
+/// # Arguments
+/// * `browser` - Browser handle
+#[no_mangle]
+pub unsafe extern "C" fn ft_mdns_browser_stop(browser: *mut FtMdnsBrowser) {
+    if browser.is_null() {
+        return;
+    }
+    let browser = &mut *browser;
+    browser.stop();
+}
@@ -1,6 +1,12 @@
 //! FFI module - exports C-compatible interfaces.
 //!
 //! Provides C-compatible function exports for integration with Swift/iOS.
+//! `include/facingtime_core.h` is generated from this module with cbindgen (see `build.rs`).
+//! Fallible functions return an error code from [`error::FtErrorCode`].
 
+pub mod version;
+pub mod error;
+pub mod events;
 pub mod server;
 pub mod mdns;
+pub mod client;
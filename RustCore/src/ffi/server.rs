@@ -1,19 +1,60 @@
 // Server FFI implementation - exports C-compatible functions
 
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
 use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::game::GameHistory;
+use crate::server::RouteHandler;
+use crate::types::{HttpRequest, HttpResponse};
+
+use super::error::{clear_last_error, read_c_string, set_core_error, set_last_error, status_code, FtErrorCode};
+use super::events::ffi_event_queue;
 
 /// Pointer type for HttpServerState
 pub type FtHttpServer = crate::server::HttpServerState;
 
+/// Pointer type for a response being filled in by a route handler
+pub type FtHttpResponse = HttpResponse;
+
+/// Host route handler callback
+///
+/// Receives the user data pointer, the JSON-encoded request and the response
+/// to fill in with `ft_http_response_set`.
+pub type FtRouteHandler = Option<
+    unsafe extern "C" fn(user_data: *mut c_void, request_json: *const c_char, response: *mut FtHttpResponse),
+>;
+
+/// User data pointer handed back to host callbacks
+///
+/// The host guarantees the pointer stays valid and is safe to use from any
+/// thread (see `ft_http_server_register_handler`).
+#[derive(Clone, Copy)]
+pub(crate) struct UserData(pub(crate) *mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    /// Get the raw pointer (a method, so closures capture the whole wrapper)
+    pub(crate) fn get(self) -> *mut c_void {
+        self.0
+    }
+}
+
 /// Create a new HTTP server instance
 ///
+/// The server's events are delivered through `ft_core_poll_events`.
+///
 /// # Safety
 /// The returned pointer must be freed with ft_http_server_free
 #[no_mangle]
 pub unsafe extern "C" fn ft_http_server_create() -> *mut FtHttpServer {
     let server = Box::new(FtHttpServer::new());
+    server.events().attach(ffi_event_queue());
     Box::into_raw(server)
 }
 
@@ -45,7 +86,7 @@ pub unsafe extern "C" fn ft_http_server_free(server: *mut FtHttpServer) {
 /// * `use_https` - 1 for HTTPS, 0 for HTTP
 ///
 /// # Returns
-/// 1 on success, 0 on failure
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
 #[no_mangle]
 pub unsafe extern "C" fn ft_http_server_start(
     server: *mut FtHttpServer,
@@ -54,40 +95,26 @@ pub unsafe extern "C" fn ft_http_server_start(
     use_https: i32,
 ) -> i32 {
     if server.is_null() {
-        return 0;
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
     }
 
-    let address = if address.is_null() {
-        "0.0.0.0:8080".to_string()
-    } else {
-        match CStr::from_ptr(address).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return 0,
-        }
+    let address = match read_c_string(address, "address", Some("0.0.0.0:8080")) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
-    let static_dir = if static_dir.is_null() {
-        "/tmp".to_string()
-    } else {
-        match CStr::from_ptr(static_dir).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return 0,
-        }
+    let static_dir = match read_c_string(static_dir, "static_dir", Some("/tmp")) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
     // Dereference mutable pointer (safe because we checked for null)
     let server = &mut *server;
 
     if use_https != 0 {
-        match server.start_https(&address, &static_dir) {
-            Ok(()) => 1,
-            Err(_) => 0,
-        }
+        status_code(server.start_https(&address, &static_dir))
     } else {
-        match server.start(&address, &static_dir) {
-            Ok(()) => 1,
-            Err(_) => 0,
-        }
+        status_code(server.start(&address, &static_dir))
     }
 }
 
@@ -124,17 +151,212 @@ pub unsafe extern "C" fn ft_http_server_is_running(server: *mut FtHttpServer) ->
     }
 }
 
-/// Handle an HTTP request (for custom request handling)
+/// Get the SHA-256 fingerprint of the served certificate
+///
+/// # Arguments
+/// * `server` - Server handle
+///
+/// # Returns
+/// `"AB:CD:..."` string that must be freed with ft_http_server_free_response,
+/// or NULL when the server is not serving HTTPS
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_certificate_fingerprint(server: *mut FtHttpServer) -> *mut c_char {
+    if server.is_null() {
+        set_last_error(FtErrorCode::NullPointer, "server is NULL");
+        return ptr::null_mut();
+    }
+    let server = &*server;
+    clear_last_error();
+    match server.certificate_fingerprint() {
+        Some(fingerprint) if server.is_running() => {
+            CString::new(fingerprint.to_string()).map(CString::into_raw).unwrap_or(ptr::null_mut())
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Get the room code players must enter to join
+///
+/// # Arguments
+/// * `server` - Server handle
+/// * `regenerate` - Non-zero to replace the code with a fresh one first
+///
+/// # Returns
+/// Room code (e.g. `"K7M2QX"`) that must be freed with
+/// ft_http_server_free_response, or NULL if `server` is NULL
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_room_code(server: *mut FtHttpServer, regenerate: i32) -> *mut c_char {
+    if server.is_null() {
+        set_last_error(FtErrorCode::NullPointer, "server is NULL");
+        return ptr::null_mut();
+    }
+    let server = &*server;
+    let code = if regenerate != 0 {
+        server.access().regenerate_room_code()
+    } else {
+        server.access().room_code()
+    };
+    clear_last_error();
+    CString::new(code).map(CString::into_raw).unwrap_or(ptr::null_mut())
+}
+
+/// Configure room access for WebSocket joins
+///
+/// Clients pass the code and password as `/ws?code=...&password=...`.
+/// Changes apply to the next join attempt, also while running.
+///
+/// # Arguments
+/// * `server` - Server handle
+/// * `require_code` - Non-zero to require the room code
+/// * `password` - Room password, or NULL / empty for none
+/// * `max_failures` - Failed attempts from one address before a lockout (0 disables lockouts)
+/// * `lockout_ms` - Lockout duration in milliseconds
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_set_room_access(
+    server: *mut FtHttpServer,
+    require_code: i32,
+    password: *const c_char,
+    max_failures: u32,
+    lockout_ms: u64,
+) -> i32 {
+    if server.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
+    }
+    let password = match read_c_string(password, "password", Some("")) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+
+    let access = (*server).access();
+    access.set_code_required(require_code != 0);
+    access.set_password(Some(&password));
+    access.set_lockout_policy(max_failures, Duration::from_millis(lockout_ms));
+    clear_last_error()
+}
+
+/// Keep finished games in a data directory and serve them under `/history`
+///
+/// Takes effect the next time the server starts.
+///
+/// # Arguments
+/// * `server` - Server handle
+/// * `data_dir` - Directory for `games.jsonl` (created if missing), or NULL / empty to keep no history
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_set_data_dir(server: *mut FtHttpServer, data_dir: *const c_char) -> i32 {
+    if server.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
+    }
+    let data_dir = match read_c_string(data_dir, "data_dir", Some("")) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    status_code((*server).set_data_dir(Some(data_dir.as_str()).filter(|dir| !dir.is_empty())))
+}
+
+/// Run `read` on the server's game history and hand the result to C
+///
+/// # Safety
+/// `server` must be NULL or a valid server handle.
+unsafe fn read_history(
+    server: *mut FtHttpServer,
+    read: impl FnOnce(&GameHistory) -> Result<Option<String>, CoreError>,
+) -> *mut c_char {
+    if server.is_null() {
+        set_last_error(FtErrorCode::NullPointer, "server is NULL");
+        return ptr::null_mut();
+    }
+    let Some(history) = (*server).history() else {
+        set_last_error(FtErrorCode::InvalidConfig, "no data directory is set");
+        return ptr::null_mut();
+    };
+    match read(history) {
+        Ok(Some(text)) => {
+            clear_last_error();
+            CString::new(text).map(CString::into_raw).unwrap_or(ptr::null_mut())
+        }
+        Ok(None) => {
+            clear_last_error();
+            ptr::null_mut()
+        }
+        Err(e) => {
+            set_core_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// List the finished games in the data directory
+///
+/// # Arguments
+/// * `server` - Server handle
+///
+/// # Returns
+/// JSON array of game summaries (`id`, `started_at_ms`, `finished_at_ms`,
+/// `player_count`, `players`, `winner`, `reason`), oldest first, that must
+/// be freed with ft_http_server_free_response, or NULL on error (see
+/// `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_list_games(server: *mut FtHttpServer) -> *mut c_char {
+    read_history(server, |history| {
+        Ok(Some(serde_json::Value::from(history.list()?).to_string()))
+    })
+}
+
+/// Get one finished game from the data directory
+///
+/// # Arguments
+/// * `server` - Server handle
+/// * `id` - Game id from ft_http_server_list_games
+///
+/// # Returns
+/// JSON record of the game (players, roles, proposals and votes, quests,
+/// assassination, winner and the full log) that must be freed with
+/// ft_http_server_free_response, or NULL if there is no such game (error
+/// code FT_OK) or on error
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_get_game(server: *mut FtHttpServer, id: *const c_char) -> *mut c_char {
+    let id = match read_c_string(id, "id", None) {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+    read_history(server, |history| Ok(history.get(&id)?.map(|record| record.to_string())))
+}
+
+/// Export every finished game in the data directory
+///
+/// # Arguments
+/// * `server` - Server handle
+///
+/// # Returns
+/// One JSON record per line (empty without games) that must be freed with
+/// ft_http_server_free_response, or NULL on error (see `ft_last_error_message`)
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_export_games(server: *mut FtHttpServer) -> *mut c_char {
+    read_history(server, |history| history.export().map(Some))
+}
+
+/// Handle an HTTP request through the registered host handlers
+///
+/// Runs the handler synchronously on the calling thread, exactly as the
+/// router would for a network request. Paths without a handler get a 404.
 ///
 /// # Arguments
 /// * `server` - Server handle
 /// * `method` - HTTP method
-/// * `path` - Request path
-/// * `headers` - Request headers (JSON format)
+/// * `path` - Request path, optionally with a query string
+/// * `headers` - Request headers (JSON object)
 /// * `body` - Request body (optional)
 ///
 /// # Returns
-/// JSON response string (must be freed with ft_http_server_free_response)
+/// JSON-encoded `HttpResponse` (`status_code`, `body`, `headers`) that must be
+/// freed with ft_http_server_free_response, or NULL on invalid input
+/// (see `ft_last_error_message`)
 #[no_mangle]
 pub unsafe extern "C" fn ft_http_server_handle_request(
     server: *mut FtHttpServer,
@@ -144,53 +366,181 @@ pub unsafe extern "C" fn ft_http_server_handle_request(
     body: *const c_char,
 ) -> *mut c_char {
     if server.is_null() {
-        let error = CString::new(r#"{"error": "server is null"}"#).unwrap();
-        return error.into_raw();
+        set_last_error(FtErrorCode::NullPointer, "server is NULL");
+        return ptr::null_mut();
     }
 
-    let _method_str = if method.is_null() {
-        "GET".to_string()
+    let method = match read_c_string(method, "method", Some("GET")) {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let path = match read_c_string(path, "path", Some("/")) {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let headers = match read_c_string(headers, "headers", Some("{}")) {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let body = if body.is_null() {
+        None
     } else {
-        match CStr::from_ptr(method).to_str() {
-            Ok(s) => s.to_string(),
+        match read_c_string(body, "body", None) {
+            Ok(s) => Some(s),
             Err(_) => return ptr::null_mut(),
         }
     };
 
-    let _path_str = if path.is_null() {
-        "/".to_string()
-    } else {
-        match CStr::from_ptr(path).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return ptr::null_mut(),
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (path, None),
+    };
+
+    let server = &*server;
+    let response = server.handle_request(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    });
+
+    let response = match serde_json::to_string(&response) {
+        Ok(json) => json,
+        Err(e) => {
+            set_last_error(FtErrorCode::Json, format!("failed to serialize response: {}", e));
+            return ptr::null_mut();
         }
     };
 
-    let headers_str = if headers.is_null() {
-        "{}".to_string()
-    } else {
-        match CStr::from_ptr(headers).to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return ptr::null_mut(),
+    match CString::new(response) {
+        Ok(cstring) => {
+            clear_last_error();
+            cstring.into_raw()
         }
+        Err(e) => {
+            set_last_error(FtErrorCode::InvalidUtf8, format!("response contains NUL: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Register a host handler for a path prefix
+///
+/// The handler is called with the JSON-encoded `HttpRequest` and must fill
+/// in the response with `ft_http_response_set` before returning. It is
+/// invoked on a Tokio blocking-pool thread (or on the caller's thread for
+/// `ft_http_server_handle_request`), possibly concurrently, so it must be
+/// thread-safe. `user_data` is passed through unchanged and must stay valid
+/// until the handler is unregistered or the server is freed.
+///
+/// # Arguments
+/// * `server` - Server handle
+/// * `prefix` - Path prefix starting with '/' (e.g. "/api/room")
+/// * `handler` - Callback
+/// * `user_data` - Opaque pointer passed to the callback
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_register_handler(
+    server: *mut FtHttpServer,
+    prefix: *const c_char,
+    handler: FtRouteHandler,
+    user_data: *mut c_void,
+) -> i32 {
+    if server.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
+    }
+    let handler = match handler {
+        Some(handler) => handler,
+        None => return set_last_error(FtErrorCode::NullPointer, "handler is NULL"),
+    };
+    let prefix = match read_c_string(prefix, "prefix", None) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
-    let _body_str = if body.is_null() {
-        None
-    } else {
-        match CStr::from_ptr(body).to_str() {
-            Ok(s) => Some(s.to_string()),
-            Err(_) => return ptr::null_mut(),
+    let user_data = UserData(user_data);
+    let route_handler: RouteHandler = Arc::new(move |request: HttpRequest| {
+        let mut response = HttpResponse::new(500, "Handler did not set a response");
+        let request_json = serde_json::to_string(&request)
+            .ok()
+            .and_then(|json| CString::new(json).ok());
+        if let Some(request_json) = request_json {
+            handler(user_data.get(), request_json.as_ptr(), &mut response);
         }
+        response
+    });
+
+    let server = &*server;
+    status_code(server.register_route(&prefix, route_handler))
+}
+
+/// Remove the host handler for a path prefix
+///
+/// # Returns
+/// FT_OK (0) if a handler was removed, otherwise an error code
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_server_unregister_handler(
+    server: *mut FtHttpServer,
+    prefix: *const c_char,
+) -> i32 {
+    if server.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "server is NULL");
+    }
+    let prefix = match read_c_string(prefix, "prefix", None) {
+        Ok(s) => s,
+        Err(code) => return code,
     };
 
-    // Build response (simplified - just return success)
-    let response = format!(r#"{{"status_code": 200, "headers": {}}}"#, headers_str);
+    let server = &*server;
+    if server.unregister_route(&prefix) {
+        clear_last_error()
+    } else {
+        set_last_error(FtErrorCode::InvalidRoute, format!("no handler registered for {}", prefix))
+    }
+}
 
-    match CString::new(response) {
-        Ok(cstring) => cstring.into_raw(),
-        Err(_) => ptr::null_mut(),
+/// Fill in the response from inside a route handler
+///
+/// Strings are copied; the caller keeps ownership of its buffers.
+///
+/// # Arguments
+/// * `response` - Response passed to the handler
+/// * `status_code` - HTTP status code
+/// * `headers` - Response headers as a JSON object (NULL for none)
+/// * `body` - Response body (NULL for empty)
+///
+/// # Returns
+/// FT_OK (0) on success, otherwise an error code
+#[no_mangle]
+pub unsafe extern "C" fn ft_http_response_set(
+    response: *mut FtHttpResponse,
+    status_code: i32,
+    headers: *const c_char,
+    body: *const c_char,
+) -> i32 {
+    if response.is_null() {
+        return set_last_error(FtErrorCode::NullPointer, "response is NULL");
     }
+    let headers = match read_c_string(headers, "headers", Some("{}")) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    let body = match read_c_string(body, "body", Some("")) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+
+    let response = &mut *response;
+    response.status_code = status_code;
+    response.headers = headers;
+    response.body = body;
+    clear_last_error()
 }
 
 /// Free a response string allocated by Rust
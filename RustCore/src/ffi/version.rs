@@ -0,0 +1,30 @@
+//! Library-level FFI functions (version and ABI information).
+
+use std::os::raw::c_char;
+
+/// ABI version of the C interface declared in `include/facingtime_core.h`
+///
+/// Bump this whenever an exported signature or its semantics change; the
+/// header's `FT_ABI_VERSION` is generated from this constant.
+pub const FT_ABI_VERSION: u32 = 8;
+
+/// Library version, NUL-terminated for C callers
+static FT_CORE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// Get the library version string (e.g. "0.1.0")
+///
+/// # Returns
+/// Pointer to a static NUL-terminated string; must not be freed
+#[no_mangle]
+pub extern "C" fn ft_core_version() -> *const c_char {
+    FT_CORE_VERSION.as_ptr() as *const c_char
+}
+
+/// Get the ABI version of the C interface
+///
+/// Hosts should compare this against the `FT_ABI_VERSION` they were
+/// compiled with and refuse to run on mismatch.
+#[no_mangle]
+pub extern "C" fn ft_core_abi_version() -> u32 {
+    FT_ABI_VERSION
+}
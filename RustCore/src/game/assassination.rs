@@ -0,0 +1,48 @@
+//! The assassination phase.
+//!
+//! When three quests succeed with Merlin in the game, the spies get one
+//! last chance: the assassin dealt with the roles names a single player,
+//! and the spies win if it is Merlin. While the phase lasts, the spies who
+//! know each other (and the assassin) talk in a private channel. With a
+//! timeout, a game whose assassin never names anyone ends with the
+//! configured default winner.
+
+use std::time::Duration;
+
+use crate::server::Seat;
+
+use super::roles::{Faction, RoleAssignment};
+
+/// Longest spy channel message, in characters
+pub const MAX_SPY_CHAT_CHARS: usize = 500;
+
+/// Variant rules for the assassination
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssassinationRules {
+    /// How long the assassin has to name a target; None waits forever
+    pub timeout: Option<Duration>,
+    /// Side that wins if the time runs out
+    pub default_winner: Faction,
+}
+
+impl Default for AssassinationRules {
+    /// No timeout; an assassin who runs out of time has missed Merlin
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            default_winner: Faction::Resistance,
+        }
+    }
+}
+
+/// Seats in the spies' private channel: the spies who know each other and the assassin, ordered by seat
+pub fn spy_channel(roles: &RoleAssignment) -> Vec<Seat> {
+    let assassin = roles.assassin();
+    roles
+        .roles()
+        .filter(|&(seat, role)| {
+            role.faction() == Faction::Spies && (role.known_to_spies() || Some(seat) == assassin)
+        })
+        .map(|(seat, _)| seat)
+        .collect()
+}
@@ -0,0 +1,216 @@
+//! Player commands and the client messages that carry them.
+//!
+//! Clients send one JSON message per action; the seat comes from the
+//! sender's session, never from the message:
+//! - `{"type": "propose_team", "team": [seat, ...], "grants": {"excalibur": seat}}`
+//!   (leader; `grants` only with quest modules that hand something out)
+//! - `{"type": "vote_team", "approve": bool}`
+//! - `{"type": "play_quest_card", "success": bool}` (team members)
+//! - `{"type": "quest_action", "module": "excalibur", "target": seat | null}`
+//!   (the seat a quest module waits for)
+//! - `{"type": "lady_inspect", "target": seat}` (Lady of the Lake holder)
+//! - `{"type": "spy_chat", "text": "..."}` (spies, during the assassination)
+//! - `{"type": "assassinate", "target": seat}` (assassin)
+//!
+//! [`Command::AssassinationTimedOut`] comes from the host's clock, never from a client.
+//!
+//! [`Command::to_json`] writes a command for the game log: the client message
+//! with the acting `seat` added, or `{"type": "assassination_timed_out"}`.
+
+use std::collections::BTreeMap;
+
+use crate::server::Seat;
+
+use super::state::GameError;
+
+/// An action by the player in `seat`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// The leader proposes a team for the current quest
+    ProposeTeam {
+        /// Leader
+        seat: Seat,
+        /// Proposed team
+        team: Vec<Seat>,
+        /// Seats the leader hands module items to, by module name
+        grants: BTreeMap<String, Seat>,
+    },
+    /// A player approves or rejects the proposed team
+    VoteTeam {
+        /// Voter
+        seat: Seat,
+        /// true approves
+        approve: bool,
+    },
+    /// A team member plays a quest card
+    PlayQuestCard {
+        /// Team member
+        seat: Seat,
+        /// true plays Success, false plays Fail
+        success: bool,
+    },
+    /// The seat a quest module waits for acts on a target, or declines
+    QuestAction {
+        /// Acting player
+        seat: Seat,
+        /// Module name
+        module: String,
+        /// Chosen player, None to decline
+        target: Option<Seat>,
+    },
+    /// The Lady of the Lake holder inspects a player
+    InspectLoyalty {
+        /// Holder
+        seat: Seat,
+        /// Inspected player
+        target: Seat,
+    },
+    /// A spy talks in the spies' channel
+    SpyChat {
+        /// Sender
+        seat: Seat,
+        /// Message text
+        text: String,
+    },
+    /// The assassin names Merlin
+    Assassinate {
+        /// Assassin
+        seat: Seat,
+        /// Named player
+        target: Seat,
+    },
+    /// The assassin's time ran out
+    AssassinationTimedOut,
+}
+
+impl Command {
+    /// Seat of the acting player (None for the host's own commands)
+    pub fn seat(&self) -> Option<Seat> {
+        let seat = match self {
+            Command::ProposeTeam { seat, .. }
+            | Command::VoteTeam { seat, .. }
+            | Command::PlayQuestCard { seat, .. }
+            | Command::QuestAction { seat, .. }
+            | Command::InspectLoyalty { seat, .. }
+            | Command::SpyChat { seat, .. }
+            | Command::Assassinate { seat, .. } => seat,
+            Command::AssassinationTimedOut => return None,
+        };
+        Some(*seat)
+    }
+
+    /// Parse a client message sent by the player in `seat`
+    ///
+    /// # Returns
+    /// None if the text is not a game command (so the host can handle it)
+    ///
+    /// # Errors
+    /// [`GameError::InvalidMessage`] for a game command with missing or malformed fields
+    pub fn parse(seat: Seat, text: &str) -> Result<Option<Command>, GameError> {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(text) else {
+            return Ok(None);
+        };
+        let invalid = |field: &str| GameError::InvalidMessage(format!("`{}` is missing or invalid", field));
+        let flag = |field: &str| message.get(field).and_then(|v| v.as_bool()).ok_or_else(|| invalid(field));
+        let as_seat = |value: &serde_json::Value| value.as_u64().and_then(|v| Seat::try_from(v).ok());
+        let target = || message.get("target").and_then(as_seat).ok_or_else(|| invalid("target"));
+
+        let command = match message.get("type").and_then(|v| v.as_str()) {
+            Some("propose_team") => {
+                let team = message
+                    .get("team")
+                    .and_then(|v| v.as_array())
+                    .and_then(|team| team.iter().map(as_seat).collect::<Option<Vec<Seat>>>())
+                    .ok_or_else(|| invalid("team"))?;
+                let grants = match message.get("grants") {
+                    None | Some(serde_json::Value::Null) => BTreeMap::new(),
+                    Some(grants) => grants
+                        .as_object()
+                        .and_then(|grants| {
+                            grants
+                                .iter()
+                                .map(|(module, seat)| as_seat(seat).map(|seat| (module.clone(), seat)))
+                                .collect::<Option<BTreeMap<String, Seat>>>()
+                        })
+                        .ok_or_else(|| invalid("grants"))?,
+                };
+                Command::ProposeTeam { seat, team, grants }
+            }
+            Some("vote_team") => Command::VoteTeam {
+                seat,
+                approve: flag("approve")?,
+            },
+            Some("play_quest_card") => Command::PlayQuestCard {
+                seat,
+                success: flag("success")?,
+            },
+            Some("quest_action") => Command::QuestAction {
+                seat,
+                module: message
+                    .get("module")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| invalid("module"))?
+                    .to_string(),
+                target: match message.get("target") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(target) => Some(as_seat(target).ok_or_else(|| invalid("target"))?),
+                },
+            },
+            Some("lady_inspect") => Command::InspectLoyalty { seat, target: target()? },
+            Some("spy_chat") => Command::SpyChat {
+                seat,
+                text: message
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| invalid("text"))?
+                    .to_string(),
+            },
+            Some("assassinate") => Command::Assassinate { seat, target: target()? },
+            _ => return Ok(None),
+        };
+        Ok(Some(command))
+    }
+
+    /// The command as written to the game log
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Command::ProposeTeam { seat, team, grants } => {
+                serde_json::json!({"type": "propose_team", "seat": seat, "team": team, "grants": grants})
+            }
+            Command::VoteTeam { seat, approve } => {
+                serde_json::json!({"type": "vote_team", "seat": seat, "approve": approve})
+            }
+            Command::PlayQuestCard { seat, success } => {
+                serde_json::json!({"type": "play_quest_card", "seat": seat, "success": success})
+            }
+            Command::QuestAction { seat, module, target } => {
+                serde_json::json!({"type": "quest_action", "seat": seat, "module": module, "target": target})
+            }
+            Command::InspectLoyalty { seat, target } => {
+                serde_json::json!({"type": "lady_inspect", "seat": seat, "target": target})
+            }
+            Command::SpyChat { seat, text } => serde_json::json!({"type": "spy_chat", "seat": seat, "text": text}),
+            Command::Assassinate { seat, target } => {
+                serde_json::json!({"type": "assassinate", "seat": seat, "target": target})
+            }
+            Command::AssassinationTimedOut => serde_json::json!({"type": "assassination_timed_out"}),
+        }
+    }
+
+    /// Read a command written by [`to_json`](Self::to_json)
+    ///
+    /// # Errors
+    /// [`GameError::InvalidMessage`] if the value is not a logged command
+    pub fn from_json(value: &serde_json::Value) -> Result<Command, GameError> {
+        if value.get("type").and_then(|v| v.as_str()) == Some("assassination_timed_out") {
+            return Ok(Command::AssassinationTimedOut);
+        }
+        let seat = value
+            .get("seat")
+            .and_then(|v| v.as_u64())
+            .and_then(|v| Seat::try_from(v).ok())
+            .ok_or_else(|| GameError::InvalidMessage("`seat` is missing or invalid".to_string()))?;
+        Command::parse(seat, &value.to_string())?
+            .ok_or_else(|| GameError::InvalidMessage("not a game command".to_string()))
+    }
+}
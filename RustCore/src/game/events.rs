@@ -0,0 +1,422 @@
+//! What happens in a game, and who may see it.
+//!
+//! [`GameState::apply`](super::state::GameState::apply) answers every
+//! command with [`GameEvent`]s. Each event has an [`Audience`]: public
+//! events go to every player, private ones only to one seat or a few. Events turn
+//! into the JSON messages of the game protocol with [`GameEvent::to_message`].
+
+use std::collections::BTreeMap;
+
+use crate::server::Seat;
+
+use super::roles::{Faction, Role};
+use super::rules::QuestRule;
+use super::state::Phase;
+
+/// Who may see an event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Audience {
+    /// Every player and spectator
+    Everyone,
+    /// Only the player in this seat
+    Seat(Seat),
+    /// Only the players in these seats (e.g. the spies' channel)
+    Seats(Vec<Seat>),
+}
+
+/// Why the game ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinReason {
+    /// Three quests succeeded (and there was no Merlin to assassinate)
+    QuestsSucceeded,
+    /// Three quests failed
+    QuestsFailed,
+    /// Five teams in a row were rejected
+    VoteTrackExhausted,
+    /// The assassin named Merlin
+    MerlinAssassinated,
+    /// The assassin named someone other than Merlin
+    MerlinSurvived,
+    /// The assassin named nobody in time
+    AssassinationTimedOut,
+}
+
+impl WinReason {
+    /// Stable name sent to clients
+    pub fn name(&self) -> &'static str {
+        match self {
+            WinReason::QuestsSucceeded => "quests_succeeded",
+            WinReason::QuestsFailed => "quests_failed",
+            WinReason::VoteTrackExhausted => "vote_track_exhausted",
+            WinReason::MerlinAssassinated => "merlin_assassinated",
+            WinReason::MerlinSurvived => "merlin_survived",
+            WinReason::AssassinationTimedOut => "assassination_timed_out",
+        }
+    }
+}
+
+/// Something that happened in the game
+///
+/// Quests are numbered from 0 and proposal attempts from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The game began: seating order, first leader, Lady of the Lake holder and quests
+    GameStarted {
+        /// Seats in playing order
+        seats: Vec<Seat>,
+        /// First leader
+        leader: Seat,
+        /// First holder of the Lady of the Lake, if used
+        lady: Option<Seat>,
+        /// The five quests
+        quests: Vec<QuestRule>,
+        /// Names of the quest modules in play
+        modules: Vec<&'static str>,
+    },
+    /// The game moved to a new phase
+    PhaseChanged {
+        /// New phase
+        phase: Phase,
+        /// Current quest
+        quest: usize,
+        /// Current leader
+        leader: Seat,
+        /// Rejected teams in a row on the vote track
+        rejections: usize,
+    },
+    /// The leader proposed a team
+    TeamProposed {
+        /// Quest the team is for
+        quest: usize,
+        /// Proposal attempt for this quest
+        attempt: usize,
+        /// Proposing leader
+        leader: Seat,
+        /// Proposed team
+        team: Vec<Seat>,
+        /// What the leader handed out, by module name (e.g. the Excalibur holder)
+        grants: BTreeMap<String, Seat>,
+    },
+    /// A player voted on the team (the vote itself is revealed with the result)
+    TeamVoteCast {
+        /// Voting seat
+        seat: Seat,
+    },
+    /// Everyone voted on the team
+    TeamVoteResult {
+        /// Quest the team is for
+        quest: usize,
+        /// Proposal attempt for this quest
+        attempt: usize,
+        /// Every seat's vote (true approves), ordered by seat
+        votes: Vec<(Seat, bool)>,
+        /// Whether the team goes on the quest
+        approved: bool,
+    },
+    /// A team member played a quest card (the card stays secret)
+    QuestCardPlayed {
+        /// Seat that played
+        seat: Seat,
+    },
+    /// A quest module waits for a player before the cards are counted
+    QuestActionRequired {
+        /// Module name
+        module: &'static str,
+        /// Seat that has to act
+        seat: Seat,
+    },
+    /// The Excalibur holder switched a card, or declined to
+    ExcaliburUsed {
+        /// Holder
+        holder: Seat,
+        /// Team member whose card was switched
+        target: Option<Seat>,
+    },
+    /// The card Excalibur switched (private to the holder)
+    ExcaliburRevealed {
+        /// Quest the card was played on
+        quest: usize,
+        /// Holder
+        holder: Seat,
+        /// Team member whose card was switched
+        target: Seat,
+        /// The card as played: true for Success
+        success: bool,
+    },
+    /// Every team member played a card; only the count of Fail cards is told, never who played them
+    QuestResult {
+        /// Quest that was resolved
+        quest: usize,
+        /// Team that went on it
+        team: Vec<Seat>,
+        /// Fail cards played
+        fails: usize,
+        /// Whether the quest succeeded
+        succeeded: bool,
+    },
+    /// The Lady of the Lake holder inspected a player, who now holds the token
+    LadyInspected {
+        /// Inspecting holder
+        holder: Seat,
+        /// Inspected player, the new holder
+        target: Seat,
+    },
+    /// The loyalty the Lady of the Lake revealed (private to the holder)
+    LoyaltyRevealed {
+        /// Inspecting holder
+        holder: Seat,
+        /// Inspected player
+        target: Seat,
+        /// The target's side
+        faction: Faction,
+    },
+    /// The assassination began
+    AssassinationStarted {
+        /// Time the assassin has, if limited
+        timeout_ms: Option<u64>,
+    },
+    /// Who the assassin is (private to the spies' channel)
+    AssassinDesignated {
+        /// Assassin
+        assassin: Seat,
+        /// Seats in the spies' channel
+        spies: Vec<Seat>,
+    },
+    /// A message in the spies' channel (private to the channel)
+    SpyMessage {
+        /// Position in the channel, from 0
+        index: usize,
+        /// Sender
+        seat: Seat,
+        /// Message text
+        text: String,
+        /// Seats in the spies' channel
+        spies: Vec<Seat>,
+    },
+    /// The assassin named a target
+    AssassinationAttempted {
+        /// Assassin
+        assassin: Seat,
+        /// Named player
+        target: Seat,
+    },
+    /// The game ended; every role and quest card is revealed
+    GameOver {
+        /// Winning side
+        winner: Faction,
+        /// Why it won
+        reason: WinReason,
+        /// Every seat's role, ordered by seat
+        roles: Vec<(Seat, Role)>,
+        /// Cards counted on each resolved quest (true for Success), ordered by seat
+        quest_cards: Vec<Vec<(Seat, bool)>>,
+    },
+}
+
+impl GameEvent {
+    /// Who may see the event
+    pub fn audience(&self) -> Audience {
+        match self {
+            GameEvent::LoyaltyRevealed { holder, .. } | GameEvent::ExcaliburRevealed { holder, .. } => {
+                Audience::Seat(*holder)
+            }
+            GameEvent::AssassinDesignated { spies, .. } | GameEvent::SpyMessage { spies, .. } => {
+                Audience::Seats(spies.clone())
+            }
+            _ => Audience::Everyone,
+        }
+    }
+
+    /// Key under which a private event is kept for the seat's resumed sessions
+    ///
+    /// None for public events.
+    pub fn private_key(&self) -> Option<String> {
+        match self {
+            GameEvent::LoyaltyRevealed { target, .. } => Some(format!("lady_result_{}", target)),
+            GameEvent::ExcaliburRevealed { quest, .. } => Some(format!("excalibur_result_{}", quest)),
+            GameEvent::AssassinDesignated { .. } => Some("assassin_designated".to_string()),
+            GameEvent::SpyMessage { index, .. } => Some(format!("spy_chat_{}", index)),
+            _ => None,
+        }
+    }
+
+    /// JSON message of the game protocol
+    pub fn to_message(&self) -> String {
+        self.to_json().to_string()
+    }
+
+    /// [`to_message`](Self::to_message) with the event's number as `"seq"`
+    pub fn to_sequenced_message(&self, seq: u64) -> String {
+        let mut message = self.to_json();
+        message["seq"] = seq.into();
+        message.to_string()
+    }
+
+    /// The message of [`to_message`](Self::to_message) as JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            GameEvent::GameStarted {
+                seats,
+                leader,
+                lady,
+                quests,
+                modules,
+            } => {
+                let quests: Vec<serde_json::Value> = quests
+                    .iter()
+                    .map(|rule| serde_json::json!({"team_size": rule.team_size, "fails_required": rule.fails_required}))
+                    .collect();
+                serde_json::json!({
+                    "type": "game_started",
+                    "seats": seats,
+                    "leader": leader,
+                    "lady": lady,
+                    "quests": quests,
+                    "modules": modules,
+                })
+            }
+            GameEvent::PhaseChanged {
+                phase,
+                quest,
+                leader,
+                rejections,
+            } => serde_json::json!({
+                "type": "phase",
+                "phase": phase.name(),
+                "quest": quest,
+                "leader": leader,
+                "rejections": rejections,
+            }),
+            GameEvent::TeamProposed {
+                quest,
+                attempt,
+                leader,
+                team,
+                grants,
+            } => serde_json::json!({
+                "type": "team_proposed",
+                "quest": quest,
+                "attempt": attempt,
+                "leader": leader,
+                "team": team,
+                "grants": grants,
+            }),
+            GameEvent::TeamVoteCast { seat } => serde_json::json!({"type": "team_vote_cast", "seat": seat}),
+            GameEvent::TeamVoteResult {
+                quest,
+                attempt,
+                votes,
+                approved,
+            } => {
+                let votes: Vec<serde_json::Value> = votes
+                    .iter()
+                    .map(|(seat, approve)| serde_json::json!({"seat": seat, "approve": approve}))
+                    .collect();
+                serde_json::json!({
+                    "type": "team_vote_result",
+                    "quest": quest,
+                    "attempt": attempt,
+                    "votes": votes,
+                    "approved": approved,
+                })
+            }
+            GameEvent::QuestCardPlayed { seat } => serde_json::json!({"type": "quest_card_played", "seat": seat}),
+            GameEvent::QuestActionRequired { module, seat } => serde_json::json!({
+                "type": "quest_action_required",
+                "module": module,
+                "seat": seat,
+            }),
+            GameEvent::ExcaliburUsed { holder, target } => serde_json::json!({
+                "type": "excalibur_used",
+                "holder": holder,
+                "target": target,
+            }),
+            GameEvent::ExcaliburRevealed {
+                quest,
+                holder,
+                target,
+                success,
+            } => serde_json::json!({
+                "type": "excalibur_result",
+                "quest": quest,
+                "holder": holder,
+                "target": target,
+                "success": success,
+            }),
+            GameEvent::QuestResult {
+                quest,
+                team,
+                fails,
+                succeeded,
+            } => serde_json::json!({
+                "type": "quest_result",
+                "quest": quest,
+                "team": team,
+                "fails": fails,
+                "succeeded": succeeded,
+            }),
+            GameEvent::LadyInspected { holder, target } => serde_json::json!({
+                "type": "lady_inspected",
+                "holder": holder,
+                "target": target,
+            }),
+            GameEvent::LoyaltyRevealed {
+                holder,
+                target,
+                faction,
+            } => serde_json::json!({
+                "type": "lady_result",
+                "holder": holder,
+                "target": target,
+                "faction": faction.name(),
+            }),
+            GameEvent::AssassinationStarted { timeout_ms } => serde_json::json!({
+                "type": "assassination_started",
+                "timeout_ms": timeout_ms,
+            }),
+            GameEvent::AssassinDesignated { assassin, spies } => serde_json::json!({
+                "type": "assassin_designated",
+                "assassin": assassin,
+                "spies": spies,
+            }),
+            GameEvent::SpyMessage { index, seat, text, .. } => serde_json::json!({
+                "type": "spy_chat",
+                "index": index,
+                "seat": seat,
+                "text": text,
+            }),
+            GameEvent::AssassinationAttempted { assassin, target } => serde_json::json!({
+                "type": "assassination",
+                "assassin": assassin,
+                "target": target,
+            }),
+            GameEvent::GameOver {
+                winner,
+                reason,
+                roles,
+                quest_cards,
+            } => {
+                let roles: Vec<serde_json::Value> = roles
+                    .iter()
+                    .map(|(seat, role)| serde_json::json!({"seat": seat, "role": role.name()}))
+                    .collect();
+                let quest_cards: Vec<Vec<serde_json::Value>> = quest_cards
+                    .iter()
+                    .map(|cards| {
+                        cards
+                            .iter()
+                            .map(|(seat, success)| serde_json::json!({"seat": seat, "success": success}))
+                            .collect()
+                    })
+                    .collect();
+                serde_json::json!({
+                    "type": "game_over",
+                    "winner": winner.name(),
+                    "reason": reason.name(),
+                    "roles": roles,
+                    "quest_cards": quest_cards,
+                })
+            }
+        }
+    }
+}
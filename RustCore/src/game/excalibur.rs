@@ -0,0 +1,181 @@
+//! The Excalibur expansion.
+//!
+//! The leader gives Excalibur to a team member with each proposal. Once
+//! every quest card is in, the holder may use it on another team member's
+//! card, which is turned over to the opposite result before the cards are
+//! counted. Everyone learns whose card was switched; only the holder learns
+//! what it was.
+
+use std::collections::BTreeMap;
+
+use crate::server::Seat;
+
+use super::events::GameEvent;
+use super::modules::{QuestContext, QuestModule};
+use super::state::GameError;
+
+/// Whose cards Excalibur may switch
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExcaliburTargeting {
+    /// Another team member's card (the official rule)
+    #[default]
+    OtherTeamMember,
+    /// Any team member's card, the holder's own included
+    AnyTeamMember,
+}
+
+impl ExcaliburTargeting {
+    /// Stable name used in setups
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExcaliburTargeting::OtherTeamMember => "other_team_member",
+            ExcaliburTargeting::AnyTeamMember => "any_team_member",
+        }
+    }
+
+    /// Parse a name from [`name`](Self::name)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "other_team_member" => Some(ExcaliburTargeting::OtherTeamMember),
+            "any_team_member" => Some(ExcaliburTargeting::AnyTeamMember),
+            _ => None,
+        }
+    }
+}
+
+/// Variant rules for Excalibur
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExcaliburRules {
+    /// Whose cards may be switched
+    pub targeting: ExcaliburTargeting,
+    /// Whether the leader may keep Excalibur (officially it goes to another team member)
+    pub leader_may_hold: bool,
+}
+
+/// Excalibur as a [`QuestModule`]
+#[derive(Clone, Debug)]
+pub struct Excalibur {
+    rules: ExcaliburRules,
+    holder: Option<Seat>,
+    used: bool,
+    /// Switched cards: quest, holder, target and the card as played
+    switched: Vec<(usize, Seat, Seat, bool)>,
+}
+
+impl Excalibur {
+    /// Module name, also the grant key in `propose_team`
+    pub const NAME: &'static str = "excalibur";
+
+    /// Excalibur with the given variant rules
+    pub fn new(rules: ExcaliburRules) -> Self {
+        Self {
+            rules,
+            holder: None,
+            used: false,
+            switched: Vec::new(),
+        }
+    }
+
+    /// Variant rules
+    pub fn rules(&self) -> ExcaliburRules {
+        self.rules
+    }
+
+    /// Team member holding Excalibur for the current proposal
+    pub fn holder(&self) -> Option<Seat> {
+        self.holder
+    }
+}
+
+impl QuestModule for Excalibur {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn clone_box(&self) -> Box<dyn QuestModule> {
+        Box::new(self.clone())
+    }
+
+    fn check_grant(&self, context: &QuestContext<'_>, grant: Option<Seat>) -> Result<(), GameError> {
+        let holder = grant.ok_or_else(|| {
+            GameError::InvalidMessage("the leader must give Excalibur to a team member".to_string())
+        })?;
+        if !context.team.contains(&holder) || (holder == context.leader && !self.rules.leader_may_hold) {
+            return Err(GameError::InvalidTarget(holder));
+        }
+        Ok(())
+    }
+
+    fn team_proposed(&mut self, _context: &QuestContext<'_>, grant: Option<Seat>) {
+        self.holder = grant;
+        self.used = false;
+    }
+
+    fn pending_action(&self, _context: &QuestContext<'_>) -> Option<Seat> {
+        self.holder.filter(|_| !self.used)
+    }
+
+    fn act(
+        &mut self,
+        context: &QuestContext<'_>,
+        seat: Seat,
+        target: Option<Seat>,
+        cards: &mut BTreeMap<Seat, bool>,
+    ) -> Result<Vec<GameEvent>, GameError> {
+        if Some(seat) != self.pending_action(context) {
+            return Err(GameError::NotYourTurn);
+        }
+        let Some(target) = target else {
+            self.used = true;
+            return Ok(vec![GameEvent::ExcaliburUsed { holder: seat, target: None }]);
+        };
+        let own_card = target == seat && self.rules.targeting == ExcaliburTargeting::OtherTeamMember;
+        let Some(card) = cards.get_mut(&target).filter(|_| !own_card) else {
+            return Err(GameError::InvalidTarget(target));
+        };
+
+        let played = *card;
+        *card = !played;
+        self.used = true;
+        self.switched.push((context.quest, seat, target, played));
+        Ok(vec![
+            GameEvent::ExcaliburUsed {
+                holder: seat,
+                target: Some(target),
+            },
+            GameEvent::ExcaliburRevealed {
+                quest: context.quest,
+                holder: seat,
+                target,
+                success: played,
+            },
+        ])
+    }
+
+    fn quest_resolved(&mut self) {
+        self.holder = None;
+        self.used = false;
+    }
+
+    /// `{"holder": seat | null, "used": bool}` for the current quest
+    fn public_view(&self) -> serde_json::Value {
+        serde_json::json!({"holder": self.holder, "used": self.used})
+    }
+
+    /// `{"results": [{"quest", "target", "success"}]}`: the cards `seat` switched
+    fn private_view(&self, seat: Seat) -> serde_json::Value {
+        let results: Vec<serde_json::Value> = self
+            .switched
+            .iter()
+            .filter(|(_, holder, _, _)| *holder == seat)
+            .map(|(quest, _, target, success)| {
+                serde_json::json!({"quest": quest, "target": target, "success": success})
+            })
+            .collect();
+        if results.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::json!({ "results": results })
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Finished games kept on disk.
+//!
+//! When a game is over, [`GameTable`](super::table::GameTable) turns its
+//! [`GameLog`] into a [`GameRecord`] and appends it to the [`GameHistory`]
+//! in the host's data directory: one JSON line per game in
+//! [`HISTORY_FILE`]. A record holds the players, their roles, every
+//! proposal with its votes, the quest outcomes with their cards, the Lady
+//! of the Lake's inspections, the assassination and the winner, plus the
+//! whole log, so a past game can also be replayed.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::error::CoreError;
+use crate::server::Seat;
+
+use super::events::{GameEvent, WinReason};
+use super::log::{GameLog, ReplayError};
+use super::roles::Faction;
+
+/// File in the data directory that holds the finished games
+pub const HISTORY_FILE: &str = "games.jsonl";
+
+/// Fields of a record that [`GameHistory::list`] returns
+const SUMMARY_FIELDS: [&str; 7] = [
+    "id",
+    "started_at_ms",
+    "finished_at_ms",
+    "player_count",
+    "players",
+    "winner",
+    "reason",
+];
+
+/// A finished game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    id: String,
+    names: BTreeMap<Seat, String>,
+    log: GameLog,
+    winner: Faction,
+    reason: WinReason,
+}
+
+impl GameRecord {
+    /// Record a finished game, with the players' names by seat where known
+    ///
+    /// # Returns
+    /// None if the game is not over
+    pub fn new(log: GameLog, names: BTreeMap<Seat, String>) -> Option<Self> {
+        let (winner, reason) = log.entries().last()?.events.iter().find_map(|event| match event {
+            GameEvent::GameOver { winner, reason, .. } => Some((*winner, *reason)),
+            _ => None,
+        })?;
+        let id = format!("{}-{:016x}", log.entries()[0].at_ms, log.seed());
+        Some(Self {
+            id,
+            names,
+            log,
+            winner,
+            reason,
+        })
+    }
+
+    /// Unique id: the start time and the seed
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The game's log
+    pub fn log(&self) -> &GameLog {
+        &self.log
+    }
+
+    /// Winner and reason
+    pub fn result(&self) -> (Faction, WinReason) {
+        (self.winner, self.reason)
+    }
+
+    /// The record as stored
+    ///
+    /// # Returns
+    /// `{"id", "started_at_ms", "finished_at_ms", "player_count", "players":
+    /// [{"seat", "name", "role"}], "winner", "reason", "setup", "proposals":
+    /// [{"quest", "attempt", "leader", "team", "grants", "votes": [{"seat",
+    /// "approve"}], "approved"}], "quests": [{"quest", "team", "fails",
+    /// "succeeded", "cards": [{"seat", "success"}]}], "lady": [{"holder",
+    /// "target", "faction"}], "assassination": {"assassin", "target"} | null,
+    /// "log": {...}}`
+    pub fn to_json(&self) -> serde_json::Value {
+        let entries = self.log.entries();
+        let events = || entries.iter().flat_map(|entry| &entry.events);
+        let (roles, quest_cards) = events()
+            .find_map(|event| match event {
+                GameEvent::GameOver { roles, quest_cards, .. } => Some((roles.clone(), quest_cards.clone())),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let cards = |cards: &[(Seat, bool)]| -> Vec<serde_json::Value> {
+            cards
+                .iter()
+                .map(|(seat, success)| serde_json::json!({"seat": seat, "success": success}))
+                .collect()
+        };
+
+        let players: Vec<serde_json::Value> = roles
+            .iter()
+            .map(|(seat, role)| serde_json::json!({"seat": seat, "name": self.names.get(seat), "role": role.name()}))
+            .collect();
+        let mut proposals: Vec<serde_json::Value> = Vec::new();
+        let mut quests = Vec::new();
+        let mut lady = Vec::new();
+        let mut assassination: Option<serde_json::Value> = None;
+        for event in events() {
+            match event {
+                GameEvent::TeamProposed {
+                    quest,
+                    attempt,
+                    leader,
+                    team,
+                    grants,
+                } => proposals.push(serde_json::json!({
+                    "quest": quest,
+                    "attempt": attempt,
+                    "leader": leader,
+                    "team": team,
+                    "grants": grants,
+                    "votes": null,
+                    "approved": null,
+                })),
+                GameEvent::TeamVoteResult { votes, approved, .. } => {
+                    if let Some(proposal) = proposals.last_mut() {
+                        let votes: Vec<serde_json::Value> = votes
+                            .iter()
+                            .map(|(seat, approve)| serde_json::json!({"seat": seat, "approve": approve}))
+                            .collect();
+                        proposal["votes"] = votes.into();
+                        proposal["approved"] = (*approved).into();
+                    }
+                }
+                GameEvent::QuestResult {
+                    quest,
+                    team,
+                    fails,
+                    succeeded,
+                } => quests.push(serde_json::json!({
+                    "quest": quest,
+                    "team": team,
+                    "fails": fails,
+                    "succeeded": succeeded,
+                    "cards": quest_cards.get(*quest).map(|played| cards(played)),
+                })),
+                GameEvent::LoyaltyRevealed { holder, target, faction } => {
+                    lady.push(serde_json::json!({"holder": holder, "target": target, "faction": faction.name()}));
+                }
+                GameEvent::AssassinDesignated { assassin, .. } => {
+                    assassination = Some(serde_json::json!({"assassin": assassin, "target": null}));
+                }
+                GameEvent::AssassinationAttempted { assassin, target } => {
+                    assassination = Some(serde_json::json!({"assassin": assassin, "target": target}));
+                }
+                _ => {}
+            }
+        }
+
+        serde_json::json!({
+            "id": self.id,
+            "started_at_ms": entries[0].at_ms,
+            "finished_at_ms": entries[entries.len() - 1].at_ms,
+            "player_count": self.log.seats().len(),
+            "players": players,
+            "winner": self.winner.name(),
+            "reason": self.reason.name(),
+            "setup": self.log.setup().to_json(),
+            "proposals": proposals,
+            "quests": quests,
+            "lady": lady,
+            "assassination": assassination,
+            "log": self.log.to_json(),
+        })
+    }
+
+    /// Read a record written by [`to_json`](Self::to_json), replaying its log
+    ///
+    /// # Errors
+    /// A [`ReplayError`] if the record is malformed, its log does not replay
+    /// or the game it logs is not over
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, ReplayError> {
+        let malformed = |field: &str| ReplayError::Malformed(format!("`{}` is missing or invalid", field));
+        let id = value.get("id").and_then(|v| v.as_str()).ok_or_else(|| malformed("id"))?;
+        let players = value.get("players").and_then(|v| v.as_array()).ok_or_else(|| malformed("players"))?;
+        let mut names = BTreeMap::new();
+        for player in players {
+            let seat = player
+                .get("seat")
+                .and_then(|v| v.as_u64())
+                .and_then(|v| Seat::try_from(v).ok())
+                .ok_or_else(|| malformed("players"))?;
+            if let Some(name) = player.get("name").and_then(|v| v.as_str()) {
+                names.insert(seat, name.to_string());
+            }
+        }
+        let log = GameLog::from_json(value.get("log").ok_or_else(|| malformed("log"))?)?;
+        let mut record =
+            Self::new(log, names).ok_or_else(|| ReplayError::Malformed("the game is not over".to_string()))?;
+        record.id = id.to_string();
+        Ok(record)
+    }
+}
+
+/// The finished games in a data directory
+#[derive(Clone, Debug)]
+pub struct GameHistory {
+    data_dir: PathBuf,
+    /// Serializes writes to the history file
+    lock: Arc<Mutex<()>>,
+}
+
+impl GameHistory {
+    /// Open the history in `data_dir`, creating the directory if needed
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the directory cannot be created
+    pub fn open(data_dir: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let data_dir = data_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| CoreError::IoError(format!("Cannot create {}: {}", data_dir.display(), e)))?;
+        Ok(Self {
+            data_dir,
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Directory the history is kept in
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Path of [`HISTORY_FILE`]
+    pub fn path(&self) -> PathBuf {
+        self.data_dir.join(HISTORY_FILE)
+    }
+
+    /// Append a finished game
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the file cannot be written
+    pub fn save(&self, record: &GameRecord) -> Result<(), CoreError> {
+        let mut line = record.to_json().to_string();
+        line.push('\n');
+        let _guard = self.lock.lock();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path())
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| CoreError::IoError(format!("Cannot save game {}: {}", record.id(), e)))
+    }
+
+    /// Every stored game in the order they were saved (lines that are not JSON are skipped)
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the file cannot be read
+    pub fn records(&self) -> Result<Vec<serde_json::Value>, CoreError> {
+        Ok(self
+            .export()?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    eprintln!("[HISTORY] Skipping an unreadable line in {}: {}", HISTORY_FILE, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Summaries of the stored games, oldest first: `[{"id", "started_at_ms",
+    /// "finished_at_ms", "player_count", "players", "winner", "reason"}]`
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the file cannot be read
+    pub fn list(&self) -> Result<Vec<serde_json::Value>, CoreError> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .map(|record| {
+                let summary: serde_json::Map<String, serde_json::Value> = SUMMARY_FIELDS
+                    .iter()
+                    .map(|&field| (field.to_string(), record[field].clone()))
+                    .collect();
+                summary.into()
+            })
+            .collect())
+    }
+
+    /// The stored game with `id`, as written by [`GameRecord::to_json`]
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the file cannot be read
+    pub fn get(&self, id: &str) -> Result<Option<serde_json::Value>, CoreError> {
+        Ok(self.records()?.into_iter().find(|record| record["id"] == id))
+    }
+
+    /// Every stored game as JSON lines, the contents of [`HISTORY_FILE`]
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the file cannot be read
+    pub fn export(&self) -> Result<String, CoreError> {
+        let _guard = self.lock.lock();
+        match fs::read_to_string(self.path()) {
+            Ok(text) => Ok(text),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(CoreError::IoError(format!("Cannot read {}: {}", self.path().display(), e))),
+        }
+    }
+}
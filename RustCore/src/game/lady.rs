@@ -0,0 +1,73 @@
+//! The Lady of the Lake expansion.
+//!
+//! The token starts with the player to the first leader's right. After the
+//! second, third and fourth quests its holder inspects another player's
+//! loyalty, learns it privately, and hands the token to that player.
+//! Nobody who has held the token can be inspected.
+
+use crate::server::Seat;
+
+/// Completed quests (counted from 1) after which the Lady of the Lake is used
+pub const LADY_AFTER_QUESTS: [usize; 3] = [2, 3, 4];
+
+/// Why an inspection is not allowed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LadyTargetError {
+    /// The holder cannot inspect themselves
+    SelfInspection,
+    /// The target has held the token before
+    PreviousHolder,
+}
+
+/// Who holds the Lady of the Lake and who has held it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LadyOfTheLake {
+    holder: Seat,
+    previous_holders: Vec<Seat>,
+}
+
+impl LadyOfTheLake {
+    /// Give the token to its first holder
+    pub fn new(holder: Seat) -> Self {
+        Self {
+            holder,
+            previous_holders: Vec::new(),
+        }
+    }
+
+    /// Current holder
+    pub fn holder(&self) -> Seat {
+        self.holder
+    }
+
+    /// Everyone who has held the token, in order, ending with the current holder
+    pub fn holders(&self) -> Vec<Seat> {
+        let mut holders = self.previous_holders.clone();
+        holders.push(self.holder);
+        holders
+    }
+
+    /// Whether the Lady of the Lake is used once `completed` quests are done
+    pub fn is_due(completed: usize) -> bool {
+        LADY_AFTER_QUESTS.contains(&completed)
+    }
+
+    /// Check that the holder may inspect `target`
+    pub fn check_target(&self, target: Seat) -> Result<(), LadyTargetError> {
+        if target == self.holder {
+            Err(LadyTargetError::SelfInspection)
+        } else if self.previous_holders.contains(&target) {
+            Err(LadyTargetError::PreviousHolder)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Record an inspection of `target`, who receives the token
+    pub fn inspect(&mut self, target: Seat) -> Result<(), LadyTargetError> {
+        self.check_target(target)?;
+        self.previous_holders.push(self.holder);
+        self.holder = target;
+        Ok(())
+    }
+}
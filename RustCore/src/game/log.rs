@@ -0,0 +1,312 @@
+//! The game log.
+//!
+//! [`GameLog`] records a game as it is played: the setup, the seats and the
+//! seed the deal was drawn from, then every accepted command with the events
+//! it produced, numbered like [`GameState::seq`] and stamped with the time.
+//! Refused commands change nothing and are not logged.
+//!
+//! [`GameLog::replay`] rebuilds the [`GameState`] from the commands alone,
+//! dealing again with a [`SeededRng`] on the logged seed, and checks that
+//! every command produces the logged events again. A disputed game can be
+//! re-checked step by step ([`GameLog::replay_to`]) and a bug reproduced
+//! from the log. Only the modules of the setup are replayed, not modules a
+//! host passed to [`GameState::with_modules`].
+//!
+//! The log holds every secret of the game (the seed deals the roles), so
+//! it is for the host, not for the players.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::server::Seat;
+
+use super::commands::Command;
+use super::events::GameEvent;
+use super::rng::SeededRng;
+use super::roles::RoleError;
+use super::setup::GameSetup;
+use super::state::{GameError, GameState};
+
+/// Why a log could not be read or replayed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The JSON is not a game log
+    Malformed(String),
+    /// The setup could not be dealt onto the seats
+    Deal(RoleError),
+    /// The game refused the command of the entry ending at `seq`
+    Refused {
+        /// Number of the entry's last event
+        seq: u64,
+        /// Why the game refused it
+        error: GameError,
+    },
+    /// The entry ending at `seq` did not produce its logged events
+    Diverged {
+        /// Number of the entry's last event
+        seq: u64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Malformed(reason) => write!(f, "Not a game log: {}", reason),
+            ReplayError::Deal(e) => write!(f, "Cannot deal the logged setup: {}", e),
+            ReplayError::Refused { seq, error } => write!(f, "The command before event {} was refused: {}", seq, error),
+            ReplayError::Diverged { seq } => write!(f, "The replay differs from the log at event {}", seq),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// One step of the game: a command and its events
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Number of the latest event once the entry's events happened
+    pub seq: u64,
+    /// When the entry was recorded, in milliseconds since the Unix epoch
+    pub at_ms: u64,
+    /// Command, or None for the start of the game
+    pub command: Option<Command>,
+    /// Resulting events
+    pub events: Vec<GameEvent>,
+}
+
+impl LogEntry {
+    /// The events with their numbers
+    pub fn numbered_events(&self) -> impl Iterator<Item = (u64, &GameEvent)> {
+        let first_seq = self.seq + 1 - self.events.len() as u64;
+        (first_seq..).zip(&self.events)
+    }
+
+    /// `{"seq": n, "at_ms": n, "command": {...} | null, "events": [{..., "seq": n}]}`
+    pub fn to_json(&self) -> serde_json::Value {
+        let events: Vec<serde_json::Value> = self
+            .numbered_events()
+            .map(|(seq, event)| {
+                let mut message = event.to_json();
+                message["seq"] = seq.into();
+                message
+            })
+            .collect();
+        serde_json::json!({
+            "seq": self.seq,
+            "at_ms": self.at_ms,
+            "command": self.command.as_ref().map(Command::to_json),
+            "events": events,
+        })
+    }
+}
+
+/// A game's setup, seats, seed and every accepted command with its events
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameLog {
+    setup: GameSetup,
+    seats: Vec<Seat>,
+    seed: u64,
+    entries: Vec<LogEntry>,
+}
+
+impl GameLog {
+    /// Start a game dealt from `seed` and its log
+    ///
+    /// # Returns
+    /// The log, the game and its opening events (as [`GameState::new`])
+    ///
+    /// # Errors
+    /// The [`RoleError`] of [`GameState::new`]
+    pub fn start(setup: GameSetup, seats: &[Seat], seed: u64) -> Result<(Self, GameState, Vec<GameEvent>), RoleError> {
+        let (state, events) = GameState::new(setup, seats, &mut SeededRng::new(seed))?;
+        let log = Self {
+            setup,
+            seats: seats.to_vec(),
+            seed,
+            entries: vec![LogEntry {
+                seq: state.seq(),
+                at_ms: now_ms(),
+                command: None,
+                events: events.clone(),
+            }],
+        };
+        Ok((log, state, events))
+    }
+
+    /// Append a command `state` accepted and the events it produced
+    pub fn record(&mut self, state: &GameState, command: Command, events: &[GameEvent]) {
+        self.entries.push(LogEntry {
+            seq: state.seq(),
+            at_ms: now_ms(),
+            command: Some(command),
+            events: events.to_vec(),
+        });
+    }
+
+    /// Setup of the game
+    pub fn setup(&self) -> &GameSetup {
+        &self.setup
+    }
+
+    /// Seats the game was dealt onto
+    pub fn seats(&self) -> &[Seat] {
+        &self.seats
+    }
+
+    /// Seed of the deal
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Entries in order, starting with the start of the game
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Rebuild the game from the logged commands
+    ///
+    /// # Errors
+    /// A [`ReplayError`] if a command is refused or produces other events than logged
+    pub fn replay(&self) -> Result<GameState, ReplayError> {
+        self.replay_to(u64::MAX)
+    }
+
+    /// Rebuild the game as it was once event `seq` had happened
+    ///
+    /// Entries are replayed whole: the state is that after the last entry
+    /// whose events are all numbered `seq` or lower (at least the start).
+    ///
+    /// # Errors
+    /// A [`ReplayError`] if a command is refused or produces other events than logged
+    pub fn replay_to(&self, seq: u64) -> Result<GameState, ReplayError> {
+        let count = self.entries.iter().skip(1).take_while(|entry| entry.seq <= seq).count() + 1;
+        let steps = self.entries[..count]
+            .iter()
+            .map(|entry| (entry.seq, entry.command.clone()));
+        let (state, _) = replay_steps(&self.setup, &self.seats, self.seed, steps, |index, events| {
+            self.entries[index].events == events
+        })?;
+        Ok(state)
+    }
+
+    /// `{"setup": {...}, "seats": [...], "seed": "n", "entries": [...]}`
+    ///
+    /// The seed is written as a string, as it may not fit a double.
+    pub fn to_json(&self) -> serde_json::Value {
+        let entries: Vec<serde_json::Value> = self.entries.iter().map(LogEntry::to_json).collect();
+        serde_json::json!({
+            "setup": self.setup.to_json(),
+            "seats": self.seats,
+            "seed": self.seed.to_string(),
+            "entries": entries,
+        })
+    }
+
+    /// Read a log written by [`to_json`](Self::to_json)
+    ///
+    /// The events are not read but replayed, and must match the logged ones.
+    ///
+    /// # Errors
+    /// A [`ReplayError`] if the JSON is not a log or does not replay
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, ReplayError> {
+        let malformed = |field: &str| ReplayError::Malformed(format!("`{}` is missing or invalid", field));
+        let setup = value.get("setup").and_then(GameSetup::from_json).ok_or_else(|| malformed("setup"))?;
+        let seats = value
+            .get("seats")
+            .and_then(|v| v.as_array())
+            .and_then(|seats| {
+                seats
+                    .iter()
+                    .map(|seat| seat.as_u64().and_then(|seat| Seat::try_from(seat).ok()))
+                    .collect::<Option<Vec<Seat>>>()
+            })
+            .ok_or_else(|| malformed("seats"))?;
+        let seed = value
+            .get("seed")
+            .and_then(|v| v.as_str())
+            .and_then(|seed| seed.parse::<u64>().ok())
+            .ok_or_else(|| malformed("seed"))?;
+        let logged = value.get("entries").and_then(|v| v.as_array()).ok_or_else(|| malformed("entries"))?;
+
+        let mut steps = Vec::new();
+        let mut times = Vec::new();
+        for (index, entry) in logged.iter().enumerate() {
+            let seq = entry.get("seq").and_then(|v| v.as_u64()).ok_or_else(|| malformed("seq"))?;
+            let at_ms = entry.get("at_ms").and_then(|v| v.as_u64()).ok_or_else(|| malformed("at_ms"))?;
+            let command = match entry.get("command") {
+                Some(serde_json::Value::Null) if index == 0 => None,
+                Some(command) if index > 0 => {
+                    Some(Command::from_json(command).map_err(|e| ReplayError::Malformed(e.to_string()))?)
+                }
+                _ => return Err(malformed("command")),
+            };
+            steps.push((seq, command));
+            times.push(at_ms);
+        }
+        if steps.is_empty() {
+            return Err(malformed("entries"));
+        }
+
+        let matches = |index: usize, events: &[GameEvent]| {
+            let entry = LogEntry {
+                seq: steps[index].0,
+                at_ms: times[index],
+                command: steps[index].1.clone(),
+                events: events.to_vec(),
+            };
+            entry.to_json() == logged[index]
+        };
+        let (_, events) = replay_steps(&setup, &seats, seed, steps.iter().cloned(), matches)?;
+        let entries = steps
+            .into_iter()
+            .zip(times)
+            .zip(events)
+            .map(|(((seq, command), at_ms), events)| LogEntry {
+                seq,
+                at_ms,
+                command,
+                events,
+            })
+            .collect();
+        Ok(Self {
+            setup,
+            seats,
+            seed,
+            entries,
+        })
+    }
+}
+
+/// Deal from `seed` and apply each step's command, checking each step with `matches`
+///
+/// # Returns
+/// The final state and each step's events
+fn replay_steps(
+    setup: &GameSetup,
+    seats: &[Seat],
+    seed: u64,
+    steps: impl Iterator<Item = (u64, Option<Command>)>,
+    matches: impl Fn(usize, &[GameEvent]) -> bool,
+) -> Result<(GameState, Vec<Vec<GameEvent>>), ReplayError> {
+    let (mut state, opening) = GameState::new(*setup, seats, &mut SeededRng::new(seed)).map_err(ReplayError::Deal)?;
+    let mut replayed = Vec::new();
+    for (index, (seq, command)) in steps.enumerate() {
+        let events = match (index, command) {
+            (0, None) => opening.clone(),
+            (0, Some(_)) | (_, None) => return Err(ReplayError::Malformed("misplaced start of game".to_string())),
+            (_, Some(command)) => state.apply(command).map_err(|error| ReplayError::Refused { seq, error })?,
+        };
+        if state.seq() != seq || !matches(index, &events) {
+            return Err(ReplayError::Diverged { seq });
+        }
+        replayed.push(events);
+    }
+    Ok((state, replayed))
+}
+
+/// Current time in milliseconds since the Unix epoch
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
@@ -0,0 +1,78 @@
+//! Game module - the Avalon rules engine.
+//!
+//! Holds the official quest rules for each player count, validates the
+//! host's choice of roles and variant rules, deals roles
+//! with an injectable random number source and works out each player's
+//! private night information, which is sent only to that player's seat
+//! through the WebSocket peer hub. [`GameState`] runs the game itself
+//! (teams, votes, quests with pluggable modules such as Excalibur, the
+//! Lady of the Lake and the assassination) and [`GameTable`] plays it
+//! with the seated WebSocket peers; [`GameState::view_for`] takes a
+//! snapshot of it for one player, [`GameLog`] records it for replay and
+//! [`GameHistory`] keeps finished games on disk.
+//!
+//! This module is only available on native platforms (not wasm32).
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rng;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod roles;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod setup;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rules;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod night_info;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lady;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod assassination;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod modules;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod excalibur;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod commands;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod state;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod log;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod history;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod table;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod view;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use rng::{GameRng, SeededRng, SystemRng};
+#[cfg(not(target_arch = "wasm32"))]
+pub use roles::{default_lineup, spy_count, Faction, Role, RoleAssignment, RoleError, MAX_PLAYERS, MIN_PLAYERS};
+#[cfg(not(target_arch = "wasm32"))]
+pub use setup::{GameSetup, SetupError};
+#[cfg(not(target_arch = "wasm32"))]
+pub use rules::{quest_rules, QuestRule, QUEST_COUNT};
+#[cfg(not(target_arch = "wasm32"))]
+pub use night_info::{send_night_info, NightInfo, Sighting, NIGHT_INFO_KEY};
+#[cfg(not(target_arch = "wasm32"))]
+pub use lady::LadyOfTheLake;
+#[cfg(not(target_arch = "wasm32"))]
+pub use assassination::{spy_channel, AssassinationRules, MAX_SPY_CHAT_CHARS};
+#[cfg(not(target_arch = "wasm32"))]
+pub use modules::{QuestContext, QuestModule};
+#[cfg(not(target_arch = "wasm32"))]
+pub use excalibur::{Excalibur, ExcaliburRules, ExcaliburTargeting};
+#[cfg(not(target_arch = "wasm32"))]
+pub use commands::Command;
+#[cfg(not(target_arch = "wasm32"))]
+pub use events::{Audience, GameEvent, WinReason};
+#[cfg(not(target_arch = "wasm32"))]
+pub use state::{GameError, GameState, Phase, QuestOutcome};
+#[cfg(not(target_arch = "wasm32"))]
+pub use log::{GameLog, LogEntry, ReplayError};
+#[cfg(not(target_arch = "wasm32"))]
+pub use history::{GameHistory, GameRecord, HISTORY_FILE};
+#[cfg(not(target_arch = "wasm32"))]
+pub use table::GameTable;
@@ -0,0 +1,97 @@
+//! Expansion modules that change how quests resolve.
+//!
+//! A [`QuestModule`] hooks into the quest phase at fixed points: when the
+//! leader proposes a team (with anything the leader hands out, such as
+//! Excalibur), after every quest card is in but before the cards are
+//! counted, and once the quest has resolved. [`GameState`](super::state::GameState)
+//! calls the hooks of every module in the setup in order, so a new module
+//! only implements this trait and is listed in
+//! [`GameSetup::quest_modules`](super::setup::GameSetup::quest_modules).
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::server::Seat;
+
+use super::events::GameEvent;
+use super::roles::RoleAssignment;
+use super::state::GameError;
+
+/// What a module sees of the quest in play
+#[derive(Clone, Copy, Debug)]
+pub struct QuestContext<'a> {
+    /// Current quest (0-based)
+    pub quest: usize,
+    /// Leader who proposed the team
+    pub leader: Seat,
+    /// Proposed or approved team
+    pub team: &'a [Seat],
+    /// Dealt roles
+    pub roles: &'a RoleAssignment,
+}
+
+/// Hooks of an expansion in the quest phase
+///
+/// Every hook has a default that does nothing, so a module only
+/// implements the points it changes.
+pub trait QuestModule: fmt::Debug + Send + Sync {
+    /// Stable name: the key of the module's grant in `propose_team` and the
+    /// `module` of its `quest_action` messages
+    fn name(&self) -> &'static str;
+
+    /// Copy the module with its state
+    fn clone_box(&self) -> Box<dyn QuestModule>;
+
+    /// Check what the leader hands out with a proposal (`grant` is the seat
+    /// named under the module's name, if any)
+    ///
+    /// # Errors
+    /// A [`GameError`] refusing the whole proposal
+    fn check_grant(&self, _context: &QuestContext<'_>, grant: Option<Seat>) -> Result<(), GameError> {
+        match grant {
+            Some(_) => Err(GameError::InvalidMessage(format!("{} takes no grant", self.name()))),
+            None => Ok(()),
+        }
+    }
+
+    /// The leader proposed a team; `grant` has passed [`check_grant`](Self::check_grant)
+    fn team_proposed(&mut self, _context: &QuestContext<'_>, _grant: Option<Seat>) {}
+
+    /// Seat that must act after every card is in and before they are counted, if any
+    fn pending_action(&self, _context: &QuestContext<'_>) -> Option<Seat> {
+        None
+    }
+
+    /// The pending seat acts on `target` (None declines); may change the played cards
+    ///
+    /// # Errors
+    /// A [`GameError`] if the action is not allowed; nothing may change then
+    fn act(
+        &mut self,
+        _context: &QuestContext<'_>,
+        _seat: Seat,
+        _target: Option<Seat>,
+        _cards: &mut BTreeMap<Seat, bool>,
+    ) -> Result<Vec<GameEvent>, GameError> {
+        Err(GameError::NotYourTurn)
+    }
+
+    /// The quest was resolved
+    fn quest_resolved(&mut self) {}
+
+    /// What every player may see of the module, for snapshots (null for nothing)
+    fn public_view(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+
+    /// What `seat` alone knows from the module, for snapshots (null for nothing)
+    fn private_view(&self, _seat: Seat) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+}
+
+impl Clone for Box<dyn QuestModule> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
@@ -0,0 +1,125 @@
+//! Private "night" information.
+//!
+//! Before the first quest each player learns something about the others,
+//! depending on their role:
+//! - Merlin sees every spy except Mordred
+//! - Percival sees Merlin and Morgana, without knowing which is which
+//! - Spies see each other, except Oberon, who also sees nobody
+//!
+//! Each view is sent only to the seat it belongs to, with
+//! [`PeerHub::send_to_seat`], so it is sent again when the player reconnects.
+
+use crate::error::CoreError;
+use crate::server::{PeerHub, Seat};
+
+use super::roles::{Faction, Role, RoleAssignment};
+
+/// Key night information is stored under for resumed sessions
+pub const NIGHT_INFO_KEY: &str = "night_info";
+
+/// What a player learns about another seat
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sighting {
+    /// The seat is a spy
+    Spy,
+    /// The seat is Merlin or Morgana (Percival's view)
+    MerlinOrMorgana,
+}
+
+impl Sighting {
+    /// Stable name sent to clients
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sighting::Spy => "spy",
+            Sighting::MerlinOrMorgana => "merlin_or_morgana",
+        }
+    }
+}
+
+/// One player's private view of the deal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NightInfo {
+    /// Seat the view belongs to
+    pub seat: Seat,
+    /// The player's own role
+    pub role: Role,
+    /// Whether the player is the assassin
+    pub assassin: bool,
+    /// Other seats the player knows something about, ordered by seat
+    pub sees: Vec<(Seat, Sighting)>,
+}
+
+impl NightInfo {
+    /// The view of `seat`, or None if it is not in the game
+    pub(crate) fn for_seat(assignment: &RoleAssignment, seat: Seat) -> Option<Self> {
+        let role = assignment.role_of(seat)?;
+        let sees = assignment
+            .roles()
+            .filter(|&(other, _)| other != seat)
+            .filter_map(|(other, other_role)| sighting(role, other_role).map(|sighting| (other, sighting)))
+            .collect();
+        Some(Self {
+            seat,
+            role,
+            assassin: assignment.assassin() == Some(seat),
+            sees,
+        })
+    }
+
+    /// Side the player is on
+    pub fn faction(&self) -> Faction {
+        self.role.faction()
+    }
+
+    /// JSON message sent to the player
+    ///
+    /// `{"type": "night_info", "seat": n, "role": "...", "faction": "...",
+    /// "assassin": bool, "sees": [{"seat": n, "as": "spy" | "merlin_or_morgana"}]}`
+    pub fn to_message(&self) -> String {
+        let mut message = self.to_json();
+        message["type"] = "night_info".into();
+        message.to_string()
+    }
+
+    /// The fields of [`to_message`](Self::to_message) without the message type
+    pub fn to_json(&self) -> serde_json::Value {
+        let sees: Vec<serde_json::Value> = self
+            .sees
+            .iter()
+            .map(|(seat, sighting)| serde_json::json!({"seat": seat, "as": sighting.name()}))
+            .collect();
+        serde_json::json!({
+            "seat": self.seat,
+            "role": self.role.name(),
+            "faction": self.faction().name(),
+            "assassin": self.assassin,
+            "sees": sees,
+        })
+    }
+}
+
+/// What `viewer` learns about a player holding `other`
+fn sighting(viewer: Role, other: Role) -> Option<Sighting> {
+    match viewer {
+        Role::Merlin if other.known_to_merlin() => Some(Sighting::Spy),
+        Role::Percival if matches!(other, Role::Merlin | Role::Morgana) => Some(Sighting::MerlinOrMorgana),
+        _ if viewer.known_to_spies() && other.known_to_spies() => Some(Sighting::Spy),
+        _ => None,
+    }
+}
+
+/// Send every seat its own night information
+///
+/// Every seat in the assignment must be seated in the hub's sessions.
+/// Views already sent to seats before an error are kept.
+///
+/// # Errors
+/// [`CoreError::SeatNotFound`] for the first seat nobody holds
+pub fn send_night_info(hub: &PeerHub, assignment: &RoleAssignment) -> Result<(), CoreError> {
+    for seat in assignment.seats() {
+        if let Some(info) = assignment.night_info(seat) {
+            hub.send_to_seat(seat, NIGHT_INFO_KEY, &info.to_message())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Random number sources for the game engine.
+//!
+//! Everything random in a game (role deals, the first leader) draws from a
+//! [`GameRng`], so hosts use [`SystemRng`] while tests and replays use a
+//! [`SeededRng`] that always produces the same game.
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// A source of random numbers
+pub trait GameRng {
+    /// A uniformly distributed 32-bit value
+    fn next_u32(&mut self) -> u32;
+
+    /// A uniformly distributed value in `0..bound` (`bound` must not be 0)
+    fn below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "bound must not be 0");
+        // Reject the top of the range that would bias the modulo
+        let zone = u32::MAX - u32::MAX % bound;
+        loop {
+            let value = self.next_u32();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Shuffle `items` in place (Fisher-Yates)
+pub fn shuffle<T>(rng: &mut dyn GameRng, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = rng.below(i as u32 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Operating system randomness, for real games
+pub struct SystemRng {
+    inner: SystemRandom,
+}
+
+impl Default for SystemRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemRng {
+    /// Create a generator backed by the operating system
+    pub fn new() -> Self {
+        Self {
+            inner: SystemRandom::new(),
+        }
+    }
+}
+
+impl GameRng for SystemRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.inner
+            .fill(&mut bytes)
+            .expect("system random number generator failed");
+        u32::from_le_bytes(bytes)
+    }
+}
+
+/// Deterministic generator (SplitMix64): the same seed gives the same game
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl GameRng for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 32) as u32
+    }
+}
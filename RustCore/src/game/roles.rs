@@ -0,0 +1,283 @@
+//! Roles and how they are dealt.
+//!
+//! A lineup (one [`Role`] per player) is shuffled onto the seats with a
+//! [`GameRng`]. When Merlin is in the game, one spy who can act in the open
+//! is picked as the assassin, like `_assign_roles` in `game_manager.gd`: a
+//! Morgana or plain spy when there is one.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::server::Seat;
+
+use super::night_info::NightInfo;
+use super::rng::{shuffle, GameRng};
+use super::setup::{GameSetup, SetupError};
+
+/// Fewest players in a game
+pub const MIN_PLAYERS: usize = 5;
+
+/// Most players in a game
+pub const MAX_PLAYERS: usize = 10;
+
+/// Side a role plays for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Faction {
+    /// The good side (反抗军)
+    Resistance,
+    /// The evil side (间谍)
+    Spies,
+}
+
+impl Faction {
+    /// Stable name sent to clients
+    pub fn name(&self) -> &'static str {
+        match self {
+            Faction::Resistance => "resistance",
+            Faction::Spies => "spies",
+        }
+    }
+
+    /// Parse a name from [`name`](Self::name)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "resistance" => Some(Faction::Resistance),
+            "spies" => Some(Faction::Spies),
+            _ => None,
+        }
+    }
+}
+
+/// A hidden role, matching `GameEnums.Role`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Role {
+    /// Sees every spy except Mordred
+    Merlin,
+    /// Sees Merlin and Morgana without knowing which is which
+    Percival,
+    /// Plain resistance member with no information
+    ResistanceMember,
+    /// Spy hidden from Merlin
+    Mordred,
+    /// Spy who appears as Merlin to Percival
+    Morgana,
+    /// Plain spy
+    Spy,
+    /// Spy unknown to the other spies, who does not know them either
+    Oberon,
+}
+
+impl Role {
+    /// Every role, in `GameEnums.Role` order
+    pub const ALL: [Role; 7] = [
+        Role::Merlin,
+        Role::Percival,
+        Role::ResistanceMember,
+        Role::Mordred,
+        Role::Morgana,
+        Role::Spy,
+        Role::Oberon,
+    ];
+
+    /// Side the role plays for
+    pub fn faction(&self) -> Faction {
+        match self {
+            Role::Merlin | Role::Percival | Role::ResistanceMember => Faction::Resistance,
+            Role::Mordred | Role::Morgana | Role::Spy | Role::Oberon => Faction::Spies,
+        }
+    }
+
+    /// Stable name sent to clients
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Merlin => "merlin",
+            Role::Percival => "percival",
+            Role::ResistanceMember => "resistance_member",
+            Role::Mordred => "mordred",
+            Role::Morgana => "morgana",
+            Role::Spy => "spy",
+            Role::Oberon => "oberon",
+        }
+    }
+
+    /// Parse a name returned by [`Role::name`]
+    pub fn from_name(name: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.name() == name)
+    }
+
+    /// Whether other spies know this player is a spy
+    pub fn known_to_spies(&self) -> bool {
+        self.faction() == Faction::Spies && *self != Role::Oberon
+    }
+
+    /// Whether Merlin sees this player as a spy
+    pub fn known_to_merlin(&self) -> bool {
+        self.faction() == Faction::Spies && *self != Role::Mordred
+    }
+
+    /// Preference when picking the assassin (lower is preferred)
+    fn assassin_rank(&self) -> Option<u8> {
+        match self {
+            Role::Morgana | Role::Spy => Some(0),
+            Role::Mordred => Some(1),
+            Role::Oberon => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// Spies in a game of `player_count` (the rest are resistance), as in `GameConfig.get_faction_counts`
+///
+/// # Returns
+/// None outside [`MIN_PLAYERS`]..=[`MAX_PLAYERS`]
+pub fn spy_count(player_count: usize) -> Option<usize> {
+    match player_count {
+        5 | 6 => Some(2),
+        7..=9 => Some(3),
+        10 => Some(4),
+        _ => None,
+    }
+}
+
+/// The lineup `GameConfig.get_role_config` intends for a player count
+///
+/// Merlin, Percival, Morgana and Mordred, with plain resistance members and
+/// spies making up the faction counts of [`spy_count`] (see
+/// [`GameSetup::new`]). The GDScript lists for 6 or more players hold more
+/// roles than there are players.
+///
+/// # Returns
+/// None outside [`MIN_PLAYERS`]..=[`MAX_PLAYERS`]
+pub fn default_lineup(player_count: usize) -> Option<Vec<Role>> {
+    GameSetup::new(player_count).lineup().ok()
+}
+
+/// Why roles could not be dealt
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RoleError {
+    /// The lineup does not have one role per seat
+    WrongPlayerCount {
+        /// Roles in the lineup
+        roles: usize,
+        /// Seats to deal to
+        seats: usize,
+    },
+    /// A seat was listed twice
+    DuplicateSeat(Seat),
+    /// No lineup exists for this many players
+    UnsupportedPlayerCount(usize),
+    /// The game setup cannot be played
+    InvalidSetup(Vec<SetupError>),
+}
+
+impl fmt::Display for RoleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoleError::WrongPlayerCount { roles, seats } => {
+                write!(f, "{} roles cannot be dealt to {} seats", roles, seats)
+            }
+            RoleError::DuplicateSeat(seat) => write!(f, "Seat {} is listed twice", seat),
+            RoleError::UnsupportedPlayerCount(count) => write!(
+                f,
+                "Games need {} to {} players, not {}",
+                MIN_PLAYERS, MAX_PLAYERS, count
+            ),
+            RoleError::InvalidSetup(errors) => {
+                let explanations: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "{}", explanations.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoleError {}
+
+/// Roles dealt to the seats of one game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoleAssignment {
+    roles: BTreeMap<Seat, Role>,
+    assassin: Option<Seat>,
+}
+
+impl RoleAssignment {
+    /// Deal `lineup` onto `seats` in a random order
+    ///
+    /// # Errors
+    /// [`RoleError::WrongPlayerCount`] unless there is exactly one role per seat,
+    /// [`RoleError::DuplicateSeat`] if a seat is listed twice
+    pub fn deal(seats: &[Seat], lineup: &[Role], rng: &mut dyn GameRng) -> Result<Self, RoleError> {
+        if lineup.len() != seats.len() {
+            return Err(RoleError::WrongPlayerCount {
+                roles: lineup.len(),
+                seats: seats.len(),
+            });
+        }
+        let mut roles = BTreeMap::new();
+        let mut deck = lineup.to_vec();
+        shuffle(rng, &mut deck);
+        for (&seat, role) in seats.iter().zip(deck) {
+            if roles.insert(seat, role).is_some() {
+                return Err(RoleError::DuplicateSeat(seat));
+            }
+        }
+
+        // Without Merlin there is nobody to assassinate
+        let has_merlin = roles.values().any(|&role| role == Role::Merlin);
+        let best = roles.values().filter_map(Role::assassin_rank).min().filter(|_| has_merlin);
+        let candidates: Vec<Seat> = roles
+            .iter()
+            .filter(|(_, role)| role.assassin_rank().is_some() && role.assassin_rank() == best)
+            .map(|(&seat, _)| seat)
+            .collect();
+        let assassin = if candidates.is_empty() {
+            None
+        } else {
+            Some(candidates[rng.below(candidates.len() as u32) as usize])
+        };
+        Ok(Self { roles, assassin })
+    }
+
+    /// Deal the [`default_lineup`] for `seats.len()` players
+    pub fn deal_default(seats: &[Seat], rng: &mut dyn GameRng) -> Result<Self, RoleError> {
+        let lineup = default_lineup(seats.len()).ok_or(RoleError::UnsupportedPlayerCount(seats.len()))?;
+        Self::deal(seats, &lineup, rng)
+    }
+
+    /// Role of a seat
+    pub fn role_of(&self, seat: Seat) -> Option<Role> {
+        self.roles.get(&seat).copied()
+    }
+
+    /// Every seat with its role, ordered by seat
+    pub fn roles(&self) -> impl Iterator<Item = (Seat, Role)> + '_ {
+        self.roles.iter().map(|(&seat, &role)| (seat, role))
+    }
+
+    /// Seats in the game, in order
+    pub fn seats(&self) -> Vec<Seat> {
+        self.roles.keys().copied().collect()
+    }
+
+    /// Seats holding `role`, in order
+    pub fn seats_with(&self, role: Role) -> Vec<Seat> {
+        self.roles().filter(|&(_, r)| r == role).map(|(seat, _)| seat).collect()
+    }
+
+    /// Seats on `faction`, in order
+    pub fn faction_seats(&self, faction: Faction) -> Vec<Seat> {
+        self.roles()
+            .filter(|(_, role)| role.faction() == faction)
+            .map(|(seat, _)| seat)
+            .collect()
+    }
+
+    /// Seat of the spy who may assassinate Merlin (None without Merlin)
+    pub fn assassin(&self) -> Option<Seat> {
+        self.assassin
+    }
+
+    /// What the player in `seat` learns at night
+    pub fn night_info(&self, seat: Seat) -> Option<NightInfo> {
+        NightInfo::for_seat(self, seat)
+    }
+}
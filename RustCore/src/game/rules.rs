@@ -0,0 +1,87 @@
+//! Official quest rules for each player count.
+//!
+//! Every game has five quests. The table gives each quest's team size and
+//! how many fail cards it takes to fail it: one, except the fourth quest of
+//! games with seven or more players, which needs two. The host serves the
+//! table at `/rules` so clients show the same numbers the engine uses.
+
+use super::roles::{spy_count, MAX_PLAYERS, MIN_PLAYERS};
+
+/// Quests in a game
+pub const QUEST_COUNT: usize = 5;
+
+/// Smallest player count whose fourth quest needs two fail cards
+pub const TWO_FAIL_MIN_PLAYERS: usize = 7;
+
+/// Team size and fail threshold of one quest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuestRule {
+    /// Players sent on the quest
+    pub team_size: usize,
+    /// Fail cards needed to fail the quest
+    pub fails_required: usize,
+}
+
+impl QuestRule {
+    const fn new(team_size: usize, fails_required: usize) -> Self {
+        Self {
+            team_size,
+            fails_required,
+        }
+    }
+
+    /// Whether `fails` fail cards fail the quest
+    pub fn fails_with(&self, fails: usize) -> bool {
+        fails >= self.fails_required
+    }
+}
+
+/// Team sizes by player count, from 5 to 10 players
+const TEAM_SIZES: [[usize; QUEST_COUNT]; MAX_PLAYERS - MIN_PLAYERS + 1] = [
+    [2, 3, 2, 3, 3],
+    [2, 3, 4, 3, 4],
+    [2, 3, 3, 4, 4],
+    [3, 4, 4, 5, 5],
+    [3, 4, 4, 5, 5],
+    [3, 4, 4, 5, 5],
+];
+
+/// The five quests of a game with `player_count` players
+///
+/// # Returns
+/// None outside [`MIN_PLAYERS`]..=[`MAX_PLAYERS`]
+pub fn quest_rules(player_count: usize) -> Option<[QuestRule; QUEST_COUNT]> {
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&player_count) {
+        return None;
+    }
+    let sizes = TEAM_SIZES[player_count - MIN_PLAYERS];
+    Some(std::array::from_fn(|quest| {
+        let two_fails = quest == 3 && player_count >= TWO_FAIL_MIN_PLAYERS;
+        QuestRule::new(sizes[quest], if two_fails { 2 } else { 1 })
+    }))
+}
+
+/// The whole table as JSON, as served at `/rules`
+///
+/// `{"min_players": 5, "max_players": 10, "games": [{"players": n, "spies": n,
+/// "quests": [{"team_size": n, "fails_required": n}, ...]}, ...]}`
+pub fn rules_json() -> serde_json::Value {
+    let games: Vec<serde_json::Value> = (MIN_PLAYERS..=MAX_PLAYERS)
+        .filter_map(|players| {
+            let quests: Vec<serde_json::Value> = quest_rules(players)?
+                .iter()
+                .map(|rule| serde_json::json!({"team_size": rule.team_size, "fails_required": rule.fails_required}))
+                .collect();
+            Some(serde_json::json!({
+                "players": players,
+                "spies": spy_count(players),
+                "quests": quests,
+            }))
+        })
+        .collect();
+    serde_json::json!({
+        "min_players": MIN_PLAYERS,
+        "max_players": MAX_PLAYERS,
+        "games": games,
+    })
+}
@@ -0,0 +1,301 @@
+//! Role sets and variant rules chosen by the host.
+//!
+//! A [`GameSetup`] says which optional roles and expansions are in play. Resistance and
+//! spy counts are fixed by the player count ([`spy_count`]); the optional
+//! roles take their places and plain members and spies fill the rest.
+//! [`GameSetup::validate`] reports every impossible combination at once,
+//! each with an explanation the host can show.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::server::Seat;
+
+use super::assassination::AssassinationRules;
+use super::excalibur::{Excalibur, ExcaliburRules, ExcaliburTargeting};
+use super::modules::QuestModule;
+use super::rng::GameRng;
+use super::roles::{spy_count, Faction, Role, RoleAssignment, RoleError, MAX_PLAYERS, MIN_PLAYERS};
+use super::rules::{quest_rules, QuestRule, QUEST_COUNT};
+
+/// Why a setup cannot be played
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SetupError {
+    /// Games need between [`MIN_PLAYERS`] and [`MAX_PLAYERS`] players
+    UnsupportedPlayerCount(usize),
+    /// The role only makes sense with Merlin in the game
+    RequiresMerlin(Role),
+    /// Morgana is only there to fool Percival
+    MorganaWithoutPercival,
+    /// More special spies were chosen than there are spies
+    TooManySpies {
+        /// Players in the game
+        player_count: usize,
+        /// Spies a game of that size has
+        spies: usize,
+        /// Special spy roles chosen
+        chosen: Vec<Role>,
+    },
+}
+
+impl SetupError {
+    /// Setup option the problem is reported against
+    pub fn field(&self) -> &'static str {
+        match self {
+            SetupError::UnsupportedPlayerCount(_) => "player_count",
+            SetupError::RequiresMerlin(role) => role.name(),
+            SetupError::MorganaWithoutPercival => "morgana",
+            SetupError::TooManySpies { .. } => "spies",
+        }
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::UnsupportedPlayerCount(count) => write!(
+                f,
+                "Games need {} to {} players, not {}",
+                MIN_PLAYERS, MAX_PLAYERS, count
+            ),
+            SetupError::RequiresMerlin(role) => write!(f, "{} needs Merlin in the game", display_name(*role)),
+            SetupError::MorganaWithoutPercival => {
+                write!(f, "Morgana only matters to Percival; add Percival or leave Morgana out")
+            }
+            SetupError::TooManySpies {
+                player_count,
+                spies,
+                chosen,
+            } => {
+                let names: Vec<&str> = chosen.iter().map(|role| display_name(*role)).collect();
+                write!(
+                    f,
+                    "{} players have {} spies, but {} need {}",
+                    player_count,
+                    spies,
+                    names.join(", "),
+                    chosen.len()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// Capitalized role name for explanations
+fn display_name(role: Role) -> &'static str {
+    match role {
+        Role::Merlin => "Merlin",
+        Role::Percival => "Percival",
+        Role::ResistanceMember => "Resistance member",
+        Role::Mordred => "Mordred",
+        Role::Morgana => "Morgana",
+        Role::Spy => "Spy",
+        Role::Oberon => "Oberon",
+    }
+}
+
+/// Player count, optional roles and expansions for one game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameSetup {
+    /// Players in the game
+    pub player_count: usize,
+    /// Play with Merlin and the assassination; false for plain Resistance
+    pub merlin: bool,
+    /// Percival, who sees Merlin (and Morgana)
+    pub percival: bool,
+    /// Morgana, who appears as Merlin to Percival
+    pub morgana: bool,
+    /// Mordred, hidden from Merlin
+    pub mordred: bool,
+    /// Oberon, unknown to the other spies
+    pub oberon: bool,
+    /// Use the Lady of the Lake after the second, third and fourth quests
+    pub lady_of_the_lake: bool,
+    /// Play with Excalibur, under these variant rules
+    pub excalibur: Option<ExcaliburRules>,
+    /// Timeout and default winner of the assassination
+    pub assassination: AssassinationRules,
+}
+
+impl GameSetup {
+    /// The lineup `GameConfig.get_role_config` uses: Merlin, Percival, Morgana and Mordred
+    pub fn new(player_count: usize) -> Self {
+        Self {
+            player_count,
+            merlin: true,
+            percival: true,
+            morgana: true,
+            mordred: true,
+            oberon: false,
+            lady_of_the_lake: false,
+            excalibur: None,
+            assassination: AssassinationRules::default(),
+        }
+    }
+
+    /// Plain Resistance: resistance members and spies only
+    pub fn plain(player_count: usize) -> Self {
+        Self {
+            player_count,
+            merlin: false,
+            percival: false,
+            morgana: false,
+            mordred: false,
+            oberon: false,
+            lady_of_the_lake: false,
+            excalibur: None,
+            assassination: AssassinationRules::default(),
+        }
+    }
+
+    /// Special roles chosen for `faction`, in [`Role::ALL`] order
+    pub fn special_roles(&self, faction: Faction) -> Vec<Role> {
+        [
+            (Role::Merlin, self.merlin),
+            (Role::Percival, self.percival),
+            (Role::Mordred, self.mordred),
+            (Role::Morgana, self.morgana),
+            (Role::Oberon, self.oberon),
+        ]
+        .into_iter()
+        .filter(|&(role, chosen)| chosen && role.faction() == faction)
+        .map(|(role, _)| role)
+        .collect()
+    }
+
+    /// Check the setup
+    ///
+    /// # Returns
+    /// Every problem found (empty when the setup is playable)
+    pub fn validate(&self) -> Vec<SetupError> {
+        let mut errors = Vec::new();
+        let Some(spies) = spy_count(self.player_count) else {
+            errors.push(SetupError::UnsupportedPlayerCount(self.player_count));
+            return errors;
+        };
+
+        if !self.merlin {
+            for (role, chosen) in [
+                (Role::Percival, self.percival),
+                (Role::Morgana, self.morgana),
+                (Role::Mordred, self.mordred),
+            ] {
+                if chosen {
+                    errors.push(SetupError::RequiresMerlin(role));
+                }
+            }
+        }
+        if self.morgana && !self.percival {
+            errors.push(SetupError::MorganaWithoutPercival);
+        }
+        let chosen = self.special_roles(Faction::Spies);
+        if chosen.len() > spies {
+            errors.push(SetupError::TooManySpies {
+                player_count: self.player_count,
+                spies,
+                chosen,
+            });
+        }
+        errors
+    }
+
+    /// One role per player: the chosen special roles, then plain members and spies
+    ///
+    /// # Errors
+    /// Every problem [`GameSetup::validate`] finds
+    pub fn lineup(&self) -> Result<Vec<Role>, Vec<SetupError>> {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let spies = spy_count(self.player_count).unwrap_or_default();
+        let good = self.special_roles(Faction::Resistance);
+        let evil = self.special_roles(Faction::Spies);
+
+        let mut lineup = good.clone();
+        lineup.extend(std::iter::repeat_n(
+            Role::ResistanceMember,
+            self.player_count - spies - good.len(),
+        ));
+        lineup.extend(&evil);
+        lineup.extend(std::iter::repeat_n(Role::Spy, spies - evil.len()));
+        Ok(lineup)
+    }
+
+    /// Team sizes and fail thresholds of the five quests
+    ///
+    /// # Returns
+    /// None if the player count is not supported
+    pub fn quests(&self) -> Option<[QuestRule; QUEST_COUNT]> {
+        quest_rules(self.player_count)
+    }
+
+    /// Quest modules of the chosen expansions, in the order their hooks run
+    pub fn quest_modules(&self) -> Vec<Box<dyn QuestModule>> {
+        let mut modules: Vec<Box<dyn QuestModule>> = Vec::new();
+        if let Some(rules) = self.excalibur {
+            modules.push(Box::new(Excalibur::new(rules)));
+        }
+        modules
+    }
+
+    /// Deal this setup onto `seats` (one per player)
+    ///
+    /// # Errors
+    /// [`RoleError::InvalidSetup`] if the setup is not playable, or the
+    /// errors of [`RoleAssignment::deal`]
+    pub fn deal(&self, seats: &[Seat], rng: &mut dyn GameRng) -> Result<RoleAssignment, RoleError> {
+        let lineup = self.lineup().map_err(RoleError::InvalidSetup)?;
+        RoleAssignment::deal(seats, &lineup, rng)
+    }
+
+    /// The setup as JSON, with the option names of Godot's `start_game`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "player_count": self.player_count,
+            "merlin": self.merlin,
+            "percival": self.percival,
+            "morgana": self.morgana,
+            "mordred": self.mordred,
+            "oberon": self.oberon,
+            "lady_of_the_lake": self.lady_of_the_lake,
+            "excalibur": self.excalibur.is_some(),
+            "excalibur_targeting": self.excalibur.unwrap_or_default().targeting.name(),
+            "excalibur_leader_may_hold": self.excalibur.unwrap_or_default().leader_may_hold,
+            "assassination_timeout_ms": self.assassination.timeout.map(|timeout| timeout.as_millis() as u64),
+            "assassination_default_winner": self.assassination.default_winner.name(),
+        })
+    }
+
+    /// Read a setup written by [`to_json`](Self::to_json)
+    ///
+    /// # Returns
+    /// None if a field is missing or invalid
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let flag = |key: &str| value.get(key)?.as_bool();
+        let excalibur = ExcaliburRules {
+            targeting: ExcaliburTargeting::from_name(value.get("excalibur_targeting")?.as_str()?)?,
+            leader_may_hold: flag("excalibur_leader_may_hold")?,
+        };
+        let timeout = match value.get("assassination_timeout_ms")? {
+            serde_json::Value::Null => None,
+            timeout_ms => Some(Duration::from_millis(timeout_ms.as_u64()?)),
+        };
+        Some(Self {
+            player_count: usize::try_from(value.get("player_count")?.as_u64()?).ok()?,
+            merlin: flag("merlin")?,
+            percival: flag("percival")?,
+            morgana: flag("morgana")?,
+            mordred: flag("mordred")?,
+            oberon: flag("oberon")?,
+            lady_of_the_lake: flag("lady_of_the_lake")?,
+            excalibur: flag("excalibur")?.then_some(excalibur),
+            assassination: AssassinationRules {
+                timeout,
+                default_winner: Faction::from_name(value.get("assassination_default_winner")?.as_str()?)?,
+            },
+        })
+    }
+}
@@ -0,0 +1,735 @@
+//! The game state machine.
+//!
+//! [`GameState`] holds one game from the deal to the end. The leader
+//! proposes a team, everyone votes on it, an approved team plays quest
+//! cards, and leadership passes on. Quest modules such as Excalibur may
+//! act on the cards before they are counted. With the Lady of the Lake, her phase
+//! follows the second, third and fourth quests. Three successful quests
+//! lead to the assassination when Merlin is in the game, where the spies
+//! confer in private and the assassin names one player; three failed
+//! quests or five rejected teams in a row win for the spies.
+//!
+//! Quest cards are secret: loyal players can only play Success, a quest
+//! result only tells how many Fail cards there were, and the cards each
+//! player played are kept until the game is over.
+//!
+//! The state only changes through [`GameState::apply`], which checks the
+//! command and returns the resulting [`GameEvent`]s. Events are numbered
+//! from 1 in the order they happen; [`GameState::seq`] is the number of the
+//! latest, so a snapshot from [`GameState::view_for`] says which events it
+//! already includes.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::server::Seat;
+
+use super::assassination::{spy_channel, MAX_SPY_CHAT_CHARS};
+use super::commands::Command;
+use super::events::{GameEvent, WinReason};
+use super::lady::{LadyOfTheLake, LadyTargetError};
+use super::modules::{QuestContext, QuestModule};
+use super::rng::GameRng;
+use super::roles::{Faction, Role, RoleAssignment, RoleError};
+use super::rules::{QuestRule, QUEST_COUNT};
+use super::setup::{GameSetup, SetupError};
+
+/// Quests one side must win
+pub const QUESTS_TO_WIN: usize = 3;
+
+/// Rejected teams in a row that win the game for the spies
+pub const MAX_REJECTIONS: usize = 5;
+
+/// Stage of the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// The leader chooses a team
+    TeamBuilding,
+    /// Everyone votes on the proposed team
+    TeamVote,
+    /// The team plays quest cards
+    Quest,
+    /// A quest module waits for a player before the cards are counted
+    QuestAction,
+    /// The Lady of the Lake holder inspects a player
+    LadyOfTheLake,
+    /// The assassin names Merlin
+    Assassination,
+    /// The game has ended
+    GameOver,
+}
+
+impl Phase {
+    /// Stable name sent to clients
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::TeamBuilding => "team_building",
+            Phase::TeamVote => "team_vote",
+            Phase::Quest => "quest",
+            Phase::QuestAction => "quest_action",
+            Phase::LadyOfTheLake => "lady_of_the_lake",
+            Phase::Assassination => "assassination",
+            Phase::GameOver => "game_over",
+        }
+    }
+}
+
+/// Why a command was refused
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameError {
+    /// The command does not belong to the current phase
+    WrongPhase(Phase),
+    /// Another player has to act
+    NotYourTurn,
+    /// The seat is not in the game
+    NotInGame(Seat),
+    /// The team does not have the quest's size
+    WrongTeamSize {
+        /// Size the quest needs
+        expected: usize,
+        /// Size proposed
+        actual: usize,
+    },
+    /// A seat is on the proposed team twice
+    DuplicateTeamMember(Seat),
+    /// Only team members play quest cards
+    NotOnTeam,
+    /// Loyal players can only play Success
+    LoyalMustSucceed,
+    /// The player has already voted or played
+    AlreadyActed,
+    /// The Lady of the Lake cannot inspect this player
+    InvalidLadyTarget(LadyTargetError),
+    /// The player cannot be named
+    InvalidTarget(Seat),
+    /// Only the spies' channel may talk during the assassination
+    NotInSpyChannel,
+    /// A game message had missing or malformed fields
+    InvalidMessage(String),
+}
+
+impl GameError {
+    /// Stable machine-readable code sent to clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::WrongPhase(_) => "wrong_phase",
+            GameError::NotYourTurn => "not_your_turn",
+            GameError::NotInGame(_) => "not_in_game",
+            GameError::WrongTeamSize { .. } => "wrong_team_size",
+            GameError::DuplicateTeamMember(_) => "duplicate_team_member",
+            GameError::NotOnTeam => "not_on_team",
+            GameError::LoyalMustSucceed => "loyal_must_succeed",
+            GameError::AlreadyActed => "already_acted",
+            GameError::InvalidLadyTarget(_) | GameError::InvalidTarget(_) => "invalid_target",
+            GameError::NotInSpyChannel => "not_in_spy_channel",
+            GameError::InvalidMessage(_) => "invalid_message",
+        }
+    }
+
+    /// JSON error message sent to the player
+    ///
+    /// `{"type": "error", "code": "...", "message": "..."}`, like the limit errors.
+    pub fn to_message(&self) -> String {
+        serde_json::json!({
+            "type": "error",
+            "code": self.code(),
+            "message": self.to_string(),
+        })
+        .to_string()
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::WrongPhase(phase) => write!(f, "Not allowed during {}", phase.name()),
+            GameError::NotYourTurn => write!(f, "It is not your turn"),
+            GameError::NotInGame(seat) => write!(f, "Seat {} is not in the game", seat),
+            GameError::WrongTeamSize { expected, actual } => {
+                write!(f, "This quest needs {} players, not {}", expected, actual)
+            }
+            GameError::DuplicateTeamMember(seat) => write!(f, "Seat {} is on the team twice", seat),
+            GameError::NotOnTeam => write!(f, "Only team members play quest cards"),
+            GameError::LoyalMustSucceed => write!(f, "Loyal players can only play Success"),
+            GameError::AlreadyActed => write!(f, "You have already acted"),
+            GameError::InvalidLadyTarget(LadyTargetError::SelfInspection) => {
+                write!(f, "The Lady of the Lake cannot inspect her holder")
+            }
+            GameError::InvalidLadyTarget(LadyTargetError::PreviousHolder) => {
+                write!(f, "Players who have held the Lady of the Lake cannot be inspected")
+            }
+            GameError::InvalidTarget(seat) => write!(f, "Seat {} cannot be named", seat),
+            GameError::NotInSpyChannel => write!(f, "Only the spies talk during the assassination"),
+            GameError::InvalidMessage(reason) => write!(f, "Invalid message: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// A resolved quest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuestOutcome {
+    /// Team that went on the quest
+    pub team: Vec<Seat>,
+    /// Fail cards played
+    pub fails: usize,
+    /// Whether the quest succeeded
+    pub succeeded: bool,
+}
+
+/// One game, from the deal to the end
+#[derive(Clone, Debug)]
+pub struct GameState {
+    setup: GameSetup,
+    roles: RoleAssignment,
+    /// Seats in playing order
+    order: Vec<Seat>,
+    /// Index of the leader in `order`
+    leader: usize,
+    quests: [QuestRule; QUEST_COUNT],
+    outcomes: Vec<QuestOutcome>,
+    /// Rejected teams in a row for the current quest
+    rejections: usize,
+    team: Vec<Seat>,
+    team_votes: BTreeMap<Seat, bool>,
+    quest_cards: BTreeMap<Seat, bool>,
+    /// Cards on the current quest as their team members played them, before any module switched one
+    played_cards: BTreeMap<Seat, bool>,
+    /// Cards counted on each resolved quest; secret until the game is over
+    counted_cards: Vec<Vec<(Seat, bool)>>,
+    lady: Option<LadyOfTheLake>,
+    modules: Vec<Box<dyn QuestModule>>,
+    /// Messages sent in the spies' channel
+    spy_messages: Vec<(Seat, String)>,
+    /// Number of the latest event
+    seq: u64,
+    phase: Phase,
+    result: Option<(Faction, WinReason)>,
+}
+
+impl GameState {
+    /// Deal `setup` onto `seats` and start the game
+    ///
+    /// Seats play in ascending order. The first leader is drawn from `rng`;
+    /// the Lady of the Lake, if used, starts with the player before them.
+    ///
+    /// # Returns
+    /// The state and the [`GameEvent::GameStarted`] event
+    ///
+    /// # Errors
+    /// The errors of [`GameSetup::deal`]
+    pub fn new(setup: GameSetup, seats: &[Seat], rng: &mut dyn GameRng) -> Result<(Self, Vec<GameEvent>), RoleError> {
+        let modules = setup.quest_modules();
+        Self::with_modules(setup, seats, rng, modules)
+    }
+
+    /// Like [`new`](Self::new), with quest modules of the host's own instead of the setup's
+    ///
+    /// # Errors
+    /// The errors of [`GameSetup::deal`]
+    pub fn with_modules(
+        setup: GameSetup,
+        seats: &[Seat],
+        rng: &mut dyn GameRng,
+        modules: Vec<Box<dyn QuestModule>>,
+    ) -> Result<(Self, Vec<GameEvent>), RoleError> {
+        let quests = setup
+            .quests()
+            .ok_or(RoleError::InvalidSetup(vec![SetupError::UnsupportedPlayerCount(setup.player_count)]))?;
+        let roles = setup.deal(seats, rng)?;
+        let order = roles.seats();
+        let leader = rng.below(order.len() as u32) as usize;
+        let lady = setup
+            .lady_of_the_lake
+            .then(|| LadyOfTheLake::new(order[(leader + order.len() - 1) % order.len()]));
+
+        let mut state = Self {
+            setup,
+            roles,
+            order,
+            leader,
+            quests,
+            outcomes: Vec::new(),
+            rejections: 0,
+            team: Vec::new(),
+            team_votes: BTreeMap::new(),
+            quest_cards: BTreeMap::new(),
+            played_cards: BTreeMap::new(),
+            counted_cards: Vec::new(),
+            lady,
+            modules,
+            spy_messages: Vec::new(),
+            seq: 0,
+            phase: Phase::TeamBuilding,
+            result: None,
+        };
+        let events = vec![
+            GameEvent::GameStarted {
+                seats: state.order.clone(),
+                leader: state.leader(),
+                lady: state.lady_holder(),
+                quests: state.quests.to_vec(),
+                modules: state.modules.iter().map(|module| module.name()).collect(),
+            },
+            state.phase_changed(),
+        ];
+        state.seq = events.len() as u64;
+        Ok((state, events))
+    }
+
+    /// Setup the game was dealt from
+    pub fn setup(&self) -> &GameSetup {
+        &self.setup
+    }
+
+    /// Dealt roles
+    pub fn roles(&self) -> &RoleAssignment {
+        &self.roles
+    }
+
+    /// Seats in playing order
+    pub fn seats(&self) -> &[Seat] {
+        &self.order
+    }
+
+    /// Current phase
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Current leader
+    pub fn leader(&self) -> Seat {
+        self.order[self.leader]
+    }
+
+    /// Current quest (0-based; equal to the quests played once the game is over)
+    pub fn quest(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// Rules of the five quests
+    pub fn quests(&self) -> &[QuestRule; QUEST_COUNT] {
+        &self.quests
+    }
+
+    /// Resolved quests, in order
+    pub fn outcomes(&self) -> &[QuestOutcome] {
+        &self.outcomes
+    }
+
+    /// Rejected teams in a row for the current quest
+    pub fn rejections(&self) -> usize {
+        self.rejections
+    }
+
+    /// Proposed or approved team (empty while the leader chooses)
+    pub fn team(&self) -> &[Seat] {
+        &self.team
+    }
+
+    /// Seats that have voted on the proposed team
+    pub fn voted(&self) -> Vec<Seat> {
+        self.team_votes.keys().copied().collect()
+    }
+
+    /// Team members who have played their quest card
+    pub fn played(&self) -> Vec<Seat> {
+        self.quest_cards.keys().copied().collect()
+    }
+
+    /// Cards counted on each resolved quest (true for Success), once the game is over
+    ///
+    /// None while the game lasts: nobody learns who played which card before the end.
+    pub fn quest_cards(&self) -> Option<&[Vec<(Seat, bool)>]> {
+        (self.phase == Phase::GameOver).then_some(self.counted_cards.as_slice())
+    }
+
+    /// Lady of the Lake state, if used
+    pub fn lady(&self) -> Option<&LadyOfTheLake> {
+        self.lady.as_ref()
+    }
+
+    /// Holder of the Lady of the Lake, if used
+    pub fn lady_holder(&self) -> Option<Seat> {
+        self.lady.as_ref().map(LadyOfTheLake::holder)
+    }
+
+    /// Quest modules in play, in the order their hooks run
+    pub fn modules(&self) -> &[Box<dyn QuestModule>] {
+        &self.modules
+    }
+
+    /// Number of the latest event (events are numbered from 1)
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// A seat's vote on the proposed team, while the vote lasts
+    pub(crate) fn team_vote_of(&self, seat: Seat) -> Option<bool> {
+        self.team_votes.get(&seat).copied()
+    }
+
+    /// A team member's card on the current quest as they played it, until it is counted
+    ///
+    /// A card switched by a module (Excalibur) still reads as played; only
+    /// the module's private reveal tells its holder what was switched.
+    pub(crate) fn quest_card_of(&self, seat: Seat) -> Option<bool> {
+        self.played_cards.get(&seat).copied()
+    }
+
+    /// Messages in the spies' channel: sender and text
+    pub(crate) fn spy_messages(&self) -> &[(Seat, String)] {
+        &self.spy_messages
+    }
+
+    /// Winner and reason, once the game is over
+    pub fn result(&self) -> Option<(Faction, WinReason)> {
+        self.result
+    }
+
+    /// Check and carry out a command
+    ///
+    /// # Errors
+    /// A [`GameError`] if the command is not allowed now; the state is unchanged
+    pub fn apply(&mut self, command: Command) -> Result<Vec<GameEvent>, GameError> {
+        if let Some(seat) = command.seat().filter(|&seat| self.roles.role_of(seat).is_none()) {
+            return Err(GameError::NotInGame(seat));
+        }
+        let events = match command {
+            Command::ProposeTeam { seat, team, grants } => self.propose_team(seat, team, grants),
+            Command::VoteTeam { seat, approve } => self.vote_team(seat, approve),
+            Command::PlayQuestCard { seat, success } => self.play_quest_card(seat, success),
+            Command::QuestAction { seat, module, target } => self.quest_action(seat, &module, target),
+            Command::InspectLoyalty { seat, target } => self.inspect_loyalty(seat, target),
+            Command::SpyChat { seat, text } => self.spy_chat(seat, text),
+            Command::Assassinate { seat, target } => self.assassinate(seat, target),
+            Command::AssassinationTimedOut => self.assassination_timed_out(),
+        }?;
+        self.seq += events.len() as u64;
+        Ok(events)
+    }
+
+    fn expect_phase(&self, phase: Phase) -> Result<(), GameError> {
+        if self.phase == phase {
+            Ok(())
+        } else {
+            Err(GameError::WrongPhase(self.phase))
+        }
+    }
+
+    fn propose_team(
+        &mut self,
+        seat: Seat,
+        team: Vec<Seat>,
+        grants: BTreeMap<String, Seat>,
+    ) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::TeamBuilding)?;
+        if seat != self.leader() {
+            return Err(GameError::NotYourTurn);
+        }
+        let expected = self.quests[self.quest()].team_size;
+        if team.len() != expected {
+            return Err(GameError::WrongTeamSize {
+                expected,
+                actual: team.len(),
+            });
+        }
+        for (i, member) in team.iter().enumerate() {
+            if self.roles.role_of(*member).is_none() {
+                return Err(GameError::NotInGame(*member));
+            }
+            if team[..i].contains(member) {
+                return Err(GameError::DuplicateTeamMember(*member));
+            }
+        }
+        if let Some(module) = grants.keys().find(|name| !self.modules.iter().any(|m| m.name() == name.as_str())) {
+            return Err(GameError::InvalidMessage(format!("`{}` is not in this game", module)));
+        }
+        let context = QuestContext {
+            quest: self.outcomes.len(),
+            leader: seat,
+            team: &team,
+            roles: &self.roles,
+        };
+        for module in &self.modules {
+            module.check_grant(&context, grants.get(module.name()).copied())?;
+        }
+        for module in &mut self.modules {
+            module.team_proposed(&context, grants.get(module.name()).copied());
+        }
+
+        self.team = team;
+        self.team_votes.clear();
+        self.phase = Phase::TeamVote;
+        Ok(vec![
+            GameEvent::TeamProposed {
+                quest: self.quest(),
+                attempt: self.rejections + 1,
+                leader: seat,
+                team: self.team.clone(),
+                grants,
+            },
+            self.phase_changed(),
+        ])
+    }
+
+    fn vote_team(&mut self, seat: Seat, approve: bool) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::TeamVote)?;
+        if self.team_votes.contains_key(&seat) {
+            return Err(GameError::AlreadyActed);
+        }
+        self.team_votes.insert(seat, approve);
+        let mut events = vec![GameEvent::TeamVoteCast { seat }];
+        if self.team_votes.len() < self.order.len() {
+            return Ok(events);
+        }
+
+        // A strict majority approves
+        let approvals = self.team_votes.values().filter(|&&approve| approve).count();
+        let approved = approvals * 2 > self.order.len();
+        events.push(GameEvent::TeamVoteResult {
+            quest: self.quest(),
+            attempt: self.rejections + 1,
+            votes: self.team_votes.iter().map(|(&seat, &approve)| (seat, approve)).collect(),
+            approved,
+        });
+        self.team_votes.clear();
+        if approved {
+            self.rejections = 0;
+            self.quest_cards.clear();
+            self.played_cards.clear();
+            self.phase = Phase::Quest;
+        } else {
+            self.rejections += 1;
+            self.team.clear();
+            if self.rejections >= MAX_REJECTIONS {
+                events.push(self.finish(Faction::Spies, WinReason::VoteTrackExhausted));
+                return Ok(events);
+            }
+            self.pass_leadership();
+            self.phase = Phase::TeamBuilding;
+        }
+        events.push(self.phase_changed());
+        Ok(events)
+    }
+
+    fn play_quest_card(&mut self, seat: Seat, success: bool) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::Quest)?;
+        if !self.team.contains(&seat) {
+            return Err(GameError::NotOnTeam);
+        }
+        if self.quest_cards.contains_key(&seat) {
+            return Err(GameError::AlreadyActed);
+        }
+        if !success && self.roles.role_of(seat).map(|role| role.faction()) == Some(Faction::Resistance) {
+            return Err(GameError::LoyalMustSucceed);
+        }
+        self.quest_cards.insert(seat, success);
+        self.played_cards.insert(seat, success);
+        let mut events = vec![GameEvent::QuestCardPlayed { seat }];
+        if self.quest_cards.len() < self.team.len() {
+            return Ok(events);
+        }
+        self.next_quest_action(&mut events);
+        Ok(events)
+    }
+
+    fn quest_action(&mut self, seat: Seat, module: &str, target: Option<Seat>) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::QuestAction)?;
+        let context = QuestContext {
+            quest: self.outcomes.len(),
+            leader: self.order[self.leader],
+            team: &self.team,
+            roles: &self.roles,
+        };
+        let Some(acting) = self.modules.iter_mut().find(|m| m.pending_action(&context).is_some()) else {
+            return Err(GameError::WrongPhase(self.phase));
+        };
+        if acting.name() != module {
+            return Err(GameError::InvalidMessage(format!("waiting for `{}`", acting.name())));
+        }
+        let mut events = acting.act(&context, seat, target, &mut self.quest_cards)?;
+        self.next_quest_action(&mut events);
+        Ok(events)
+    }
+
+    /// Wait for the next module that needs a player, or count the cards
+    fn next_quest_action(&mut self, events: &mut Vec<GameEvent>) {
+        let context = QuestContext {
+            quest: self.outcomes.len(),
+            leader: self.order[self.leader],
+            team: &self.team,
+            roles: &self.roles,
+        };
+        let pending = self
+            .modules
+            .iter()
+            .find_map(|module| module.pending_action(&context).map(|seat| (module.name(), seat)));
+        match pending {
+            Some((module, seat)) => {
+                self.phase = Phase::QuestAction;
+                events.push(GameEvent::QuestActionRequired { module, seat });
+                events.push(self.phase_changed());
+            }
+            None => self.resolve_quest(events),
+        }
+    }
+
+    /// Count the cards and move on to the next quest, the Lady of the Lake, the assassination or the end
+    fn resolve_quest(&mut self, events: &mut Vec<GameEvent>) {
+        for module in &mut self.modules {
+            module.quest_resolved();
+        }
+        let fails = self.quest_cards.values().filter(|&&success| !success).count();
+        let succeeded = !self.quests[self.quest()].fails_with(fails);
+        events.push(GameEvent::QuestResult {
+            quest: self.quest(),
+            team: self.team.clone(),
+            fails,
+            succeeded,
+        });
+        self.outcomes.push(QuestOutcome {
+            team: std::mem::take(&mut self.team),
+            fails,
+            succeeded,
+        });
+        self.counted_cards.push(std::mem::take(&mut self.quest_cards).into_iter().collect());
+        self.played_cards.clear();
+
+        let successes = self.outcomes.iter().filter(|outcome| outcome.succeeded).count();
+        let failures = self.outcomes.len() - successes;
+        if failures >= QUESTS_TO_WIN {
+            events.push(self.finish(Faction::Spies, WinReason::QuestsFailed));
+            return;
+        }
+        if successes >= QUESTS_TO_WIN {
+            if self.roles.seats_with(Role::Merlin).is_empty() {
+                events.push(self.finish(Faction::Resistance, WinReason::QuestsSucceeded));
+                return;
+            }
+            self.phase = Phase::Assassination;
+            events.push(self.phase_changed());
+            let timeout = self.setup.assassination.timeout;
+            events.push(GameEvent::AssassinationStarted {
+                timeout_ms: timeout.map(|timeout| timeout.as_millis() as u64),
+            });
+            if let Some(assassin) = self.roles.assassin() {
+                events.push(GameEvent::AssassinDesignated {
+                    assassin,
+                    spies: spy_channel(&self.roles),
+                });
+            }
+            return;
+        }
+
+        self.pass_leadership();
+        self.phase = if self.lady.is_some() && LadyOfTheLake::is_due(self.outcomes.len()) {
+            Phase::LadyOfTheLake
+        } else {
+            Phase::TeamBuilding
+        };
+        events.push(self.phase_changed());
+    }
+
+    fn inspect_loyalty(&mut self, seat: Seat, target: Seat) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::LadyOfTheLake)?;
+        let Some(lady) = self.lady.as_mut() else {
+            return Err(GameError::WrongPhase(self.phase));
+        };
+        if seat != lady.holder() {
+            return Err(GameError::NotYourTurn);
+        }
+        let faction = self
+            .roles
+            .role_of(target)
+            .ok_or(GameError::NotInGame(target))?
+            .faction();
+        lady.inspect(target).map_err(GameError::InvalidLadyTarget)?;
+
+        self.phase = Phase::TeamBuilding;
+        Ok(vec![
+            GameEvent::LadyInspected { holder: seat, target },
+            GameEvent::LoyaltyRevealed {
+                holder: seat,
+                target,
+                faction,
+            },
+            self.phase_changed(),
+        ])
+    }
+
+    fn assassinate(&mut self, seat: Seat, target: Seat) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::Assassination)?;
+        if Some(seat) != self.roles.assassin() {
+            return Err(GameError::NotYourTurn);
+        }
+        let role = self.roles.role_of(target).ok_or(GameError::NotInGame(target))?;
+        // The spies know each other; naming one of them is a mistake, not a guess
+        if spy_channel(&self.roles).contains(&target) {
+            return Err(GameError::InvalidTarget(target));
+        }
+
+        let mut events = vec![GameEvent::AssassinationAttempted { assassin: seat, target }];
+        events.push(if role == Role::Merlin {
+            self.finish(Faction::Spies, WinReason::MerlinAssassinated)
+        } else {
+            self.finish(Faction::Resistance, WinReason::MerlinSurvived)
+        });
+        Ok(events)
+    }
+
+    fn spy_chat(&mut self, seat: Seat, text: String) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::Assassination)?;
+        let spies = spy_channel(&self.roles);
+        if !spies.contains(&seat) {
+            return Err(GameError::NotInSpyChannel);
+        }
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_SPY_CHAT_CHARS {
+            return Err(GameError::InvalidMessage(format!(
+                "spy chat messages have 1 to {} characters",
+                MAX_SPY_CHAT_CHARS
+            )));
+        }
+
+        self.spy_messages.push((seat, text.to_string()));
+        Ok(vec![GameEvent::SpyMessage {
+            index: self.spy_messages.len() - 1,
+            seat,
+            text: text.to_string(),
+            spies,
+        }])
+    }
+
+    fn assassination_timed_out(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        self.expect_phase(Phase::Assassination)?;
+        let winner = self.setup.assassination.default_winner;
+        Ok(vec![self.finish(winner, WinReason::AssassinationTimedOut)])
+    }
+
+    fn pass_leadership(&mut self) {
+        self.leader = (self.leader + 1) % self.order.len();
+    }
+
+    /// End the game and reveal every role
+    fn finish(&mut self, winner: Faction, reason: WinReason) -> GameEvent {
+        self.phase = Phase::GameOver;
+        self.result = Some((winner, reason));
+        GameEvent::GameOver {
+            winner,
+            reason,
+            roles: self.roles.roles().collect(),
+            quest_cards: self.counted_cards.clone(),
+        }
+    }
+
+    fn phase_changed(&self) -> GameEvent {
+        GameEvent::PhaseChanged {
+            phase: self.phase,
+            quest: self.quest(),
+            leader: self.leader(),
+            rejections: self.rejections,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! A game played over the WebSocket peer hub.
+//!
+//! [`GameTable`] connects a [`GameState`] to the seated players: it sends
+//! the night information when the game starts, turns client messages into
+//! commands, answers refused commands with an `error` message to the
+//! sender alone, and delivers each event to its audience. Private events
+//! are sent with [`PeerHub::send_to_seat`], so they survive reconnects.
+//! The table also keeps the assassination's clock: [`GameTable::poll`]
+//! ends the phase once its timeout has passed.
+//!
+//! The table keeps a [`GameLog`] of the game, dealt from a seed drawn from
+//! the host's random number source, so the game can be replayed. With a
+//! [`GameHistory`], the finished game is saved there when it ends.
+//!
+//! Every delivered event carries its number as `"seq"`. Any peer may send
+//! `{"type": "get_state"}` to receive a snapshot of the game (its seat's view,
+//! or the observer view when unseated) with the number of the latest event
+//! it includes.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::error::CoreError;
+use crate::server::{Payload, PeerHub, PeerId, Seat};
+
+use super::commands::Command;
+use super::events::{Audience, GameEvent};
+use super::history::{GameHistory, GameRecord};
+use super::log::GameLog;
+use super::night_info::send_night_info;
+use super::rng::GameRng;
+use super::setup::GameSetup;
+use super::state::{GameError, GameState};
+
+/// A running game and the hub its players are connected to
+pub struct GameTable {
+    state: GameState,
+    log: GameLog,
+    hub: PeerHub,
+    /// Where the finished game is saved
+    history: Option<GameHistory>,
+    /// Players' names for the saved game, by seat
+    names: BTreeMap<Seat, String>,
+    /// When the assassin's time runs out, if limited
+    assassination_deadline: Option<Instant>,
+}
+
+impl GameTable {
+    /// Deal `setup` onto `seats`, send every player their night information and announce the start
+    ///
+    /// Every seat must be taken in the hub's sessions.
+    ///
+    /// # Returns
+    /// The table and the events announcing the start (`game_started` and the
+    /// first `phase_changed`), already delivered to the players
+    ///
+    /// # Errors
+    /// [`CoreError::SeatNotFound`] for an empty seat, [`CoreError::InvalidConfig`]
+    /// if the setup cannot be dealt onto the seats
+    pub fn start(
+        hub: PeerHub,
+        setup: GameSetup,
+        seats: &[Seat],
+        rng: &mut dyn GameRng,
+    ) -> Result<(Self, Vec<GameEvent>), CoreError> {
+        if let Some(&seat) = seats.iter().find(|&&seat| hub.sessions().occupant(seat).is_none()) {
+            return Err(CoreError::SeatNotFound(seat));
+        }
+        let seed = (u64::from(rng.next_u32()) << 32) | u64::from(rng.next_u32());
+        let (log, state, events) =
+            GameLog::start(setup, seats, seed).map_err(|e| CoreError::InvalidConfig(e.to_string()))?;
+        let mut table = Self {
+            state,
+            log,
+            hub,
+            history: None,
+            names: BTreeMap::new(),
+            assassination_deadline: None,
+        };
+        send_night_info(&table.hub, table.state.roles())?;
+        table.deliver(&events);
+        Ok((table, events))
+    }
+
+    /// The game
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Whether the game has ended, so a new one may replace it
+    pub fn is_over(&self) -> bool {
+        self.state.result().is_some()
+    }
+
+    /// Log of the game so far
+    pub fn log(&self) -> &GameLog {
+        &self.log
+    }
+
+    /// Save the game to `history` when it ends (None to not save it)
+    pub fn set_history(&mut self, history: Option<GameHistory>) {
+        self.history = history;
+    }
+
+    /// Name the player in `seat` in the saved game
+    pub fn set_player_name(&mut self, seat: Seat, name: &str) {
+        self.names.insert(seat, name.to_string());
+    }
+
+    /// Handle a message from a peer
+    ///
+    /// Refused commands are answered with an `error` message to the peer,
+    /// and `get_state` with a snapshot of the game for the peer's seat.
+    ///
+    /// # Returns
+    /// The events of an accepted command (none for `get_state`), or None if
+    /// the message is not a game command from a seated player (so the host
+    /// can handle it)
+    pub fn handle_message(&mut self, peer_id: PeerId, text: &str) -> Option<Vec<GameEvent>> {
+        let seat = self.hub.sessions().seat_of(peer_id);
+        if is_state_request(text) {
+            let view = match seat {
+                Some(seat) => self.state.view_for(seat),
+                None => self.state.observer_view(),
+            };
+            let _ = self.hub.send(peer_id, Payload::Text(view.to_string()));
+            return Some(Vec::new());
+        }
+        let seat = seat?;
+        let result = match Command::parse(seat, text) {
+            Ok(Some(command)) => self.apply(command),
+            Ok(None) => return None,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(events) => Some(events),
+            Err(e) => {
+                let _ = self.hub.send(peer_id, Payload::Text(e.to_message()));
+                Some(Vec::new())
+            }
+        }
+    }
+
+    /// When the assassin's time runs out, while the assassination lasts with a timeout
+    pub fn assassination_deadline(&self) -> Option<Instant> {
+        self.assassination_deadline
+    }
+
+    /// Run the table's clock; call this regularly (e.g. every frame)
+    ///
+    /// # Returns
+    /// The events of the assassination timing out, or nothing
+    pub fn poll(&mut self) -> Vec<GameEvent> {
+        match self.assassination_deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.assassination_deadline = None;
+                self.apply(Command::AssassinationTimedOut).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Carry out a command on behalf of a seat (e.g. a player on the host)
+    pub fn apply(&mut self, command: Command) -> Result<Vec<GameEvent>, GameError> {
+        let events = self.state.apply(command.clone())?;
+        self.log.record(&self.state, command, &events);
+        self.deliver(&events);
+        if events.iter().any(|event| matches!(event, GameEvent::GameOver { .. })) {
+            self.save();
+        }
+        Ok(events)
+    }
+
+    /// Save the finished game to the history, if any
+    fn save(&self) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let Some(record) = GameRecord::new(self.log.clone(), self.names.clone()) else {
+            return;
+        };
+        if let Err(e) = history.save(&record) {
+            eprintln!("[GAME] Could not save game {}: {}", record.id(), e);
+        }
+    }
+
+    /// Send every event to its audience and start or stop the assassination clock
+    fn deliver(&mut self, events: &[GameEvent]) {
+        let first_seq = self.state.seq() + 1 - events.len() as u64;
+        for (seq, event) in (first_seq..).zip(events) {
+            match event {
+                GameEvent::AssassinationStarted {
+                    timeout_ms: Some(timeout_ms),
+                } => {
+                    self.assassination_deadline = Some(Instant::now() + Duration::from_millis(*timeout_ms));
+                }
+                GameEvent::GameOver { .. } => self.assassination_deadline = None,
+                _ => {}
+            }
+
+            let message = event.to_sequenced_message(seq);
+            let seats = match event.audience() {
+                Audience::Everyone => {
+                    self.hub.broadcast(Payload::Text(message), None);
+                    continue;
+                }
+                Audience::Seat(seat) => vec![seat],
+                Audience::Seats(seats) => seats,
+            };
+            let key = event.private_key().unwrap_or_else(|| "game".to_string());
+            for seat in seats {
+                if let Err(e) = self.hub.send_to_seat(seat, &key, &message) {
+                    eprintln!("[GAME] Could not send {} to seat {}: {}", key, seat, e);
+                }
+            }
+        }
+    }
+}
+
+/// Whether a client message is `{"type": "get_state"}`
+fn is_state_request(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text).is_ok_and(|message| message["type"] == "get_state")
+}
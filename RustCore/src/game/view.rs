@@ -0,0 +1,163 @@
+//! Snapshots of a game for one player or an observer.
+//!
+//! A snapshot holds the public board (quest results, the vote track, the
+//! leader and the team) and, for a player, what that seat alone knows:
+//! its night information, its own vote and card while they are secret,
+//! what the Lady of the Lake showed it, module results and, for the spies,
+//! their channel. The observer view is the public board alone.
+//!
+//! Every snapshot carries the [`seq`](GameState::seq) of the latest event it
+//! includes, and delivered events carry their own `seq`, so a client that
+//! loads a snapshot applies only the events numbered after it.
+
+use crate::server::Seat;
+
+use super::assassination::spy_channel;
+use super::events::WinReason;
+use super::state::{GameState, Phase};
+
+impl GameState {
+    /// Snapshot for the player in `seat`
+    ///
+    /// A seat outside the game gets the observer view.
+    ///
+    /// # Returns
+    /// `{"type": "game_state", "seq", "seat", "phase", "seats", "leader",
+    /// "quest", "rejections", "team", "voted", "played", "quests", "lady",
+    /// "modules", "result", "private"}`
+    pub fn view_for(&self, seat: Seat) -> serde_json::Value {
+        let mut view = self.observer_view();
+        if self.roles().role_of(seat).is_some() {
+            view["seat"] = seat.into();
+            view["private"] = self.private_view(seat);
+        }
+        view
+    }
+
+    /// Snapshot without anything secret, for observers
+    ///
+    /// Roles and quest cards appear under `result` once the game is over.
+    pub fn observer_view(&self) -> serde_json::Value {
+        let quests: Vec<serde_json::Value> = self
+            .quests()
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let result = self.outcomes().get(index).map(|outcome| {
+                    serde_json::json!({
+                        "team": outcome.team,
+                        "fails": outcome.fails,
+                        "succeeded": outcome.succeeded,
+                    })
+                });
+                serde_json::json!({
+                    "team_size": rule.team_size,
+                    "fails_required": rule.fails_required,
+                    "result": result,
+                })
+            })
+            .collect();
+        let lady = self.lady().map(|lady| serde_json::json!({"holder": lady.holder(), "holders": lady.holders()}));
+        let modules: serde_json::Map<String, serde_json::Value> = self
+            .modules()
+            .iter()
+            .map(|module| (module.name().to_string(), module.public_view()))
+            .collect();
+        let result = self.result().map(|(winner, reason)| {
+            let roles: serde_json::Map<String, serde_json::Value> = self
+                .roles()
+                .roles()
+                .map(|(seat, role)| (seat.to_string(), role.name().into()))
+                .collect();
+            let quest_cards: Vec<Vec<serde_json::Value>> = self
+                .quest_cards()
+                .unwrap_or_default()
+                .iter()
+                .map(|cards| {
+                    cards
+                        .iter()
+                        .map(|(seat, success)| serde_json::json!({"seat": seat, "success": success}))
+                        .collect()
+                })
+                .collect();
+            serde_json::json!({
+                "winner": winner.name(),
+                "reason": reason.name(),
+                "roles": roles,
+                "quest_cards": quest_cards,
+            })
+        });
+
+        serde_json::json!({
+            "type": "game_state",
+            "seq": self.seq(),
+            "seat": null,
+            "phase": self.phase().name(),
+            "seats": self.seats(),
+            "leader": self.leader(),
+            "quest": self.quest(),
+            "rejections": self.rejections(),
+            "team": self.team(),
+            "voted": self.voted(),
+            "played": self.played(),
+            "quests": quests,
+            "lady": lady,
+            "modules": modules,
+            "result": result,
+            "private": null,
+        })
+    }
+
+    /// What only `seat` knows
+    fn private_view(&self, seat: Seat) -> serde_json::Value {
+        let night_info = self.roles().night_info(seat).map(|info| info.to_json());
+        let lady_results: Vec<serde_json::Value> = self
+            .lady()
+            .map(|lady| lady.holders())
+            .unwrap_or_default()
+            .windows(2)
+            .filter(|pair| pair[0] == seat)
+            .filter_map(|pair| {
+                let faction = self.roles().role_of(pair[1])?.faction();
+                Some(serde_json::json!({"target": pair[1], "faction": faction.name()}))
+            })
+            .collect();
+        let modules: serde_json::Map<String, serde_json::Value> = self
+            .modules()
+            .iter()
+            .map(|module| (module.name().to_string(), module.private_view(seat)))
+            .collect();
+
+        let channel = spy_channel(self.roles());
+        let assassination_begun = self.phase() == Phase::Assassination
+            || matches!(
+                self.result(),
+                Some((
+                    _,
+                    WinReason::MerlinAssassinated | WinReason::MerlinSurvived | WinReason::AssassinationTimedOut
+                ))
+            );
+        let spies = (assassination_begun && channel.contains(&seat)).then(|| {
+            let messages: Vec<serde_json::Value> = self
+                .spy_messages()
+                .iter()
+                .enumerate()
+                .map(|(index, (seat, text))| serde_json::json!({"index": index, "seat": seat, "text": text}))
+                .collect();
+            serde_json::json!({
+                "assassin": self.roles().assassin(),
+                "spies": channel,
+                "messages": messages,
+            })
+        });
+
+        serde_json::json!({
+            "night_info": night_info,
+            "team_vote": self.team_vote_of(seat),
+            "quest_card": self.quest_card_of(seat),
+            "lady_results": lady_results,
+            "modules": modules,
+            "spy_channel": spies,
+        })
+    }
+}
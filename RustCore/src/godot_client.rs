@@ -0,0 +1,197 @@
+//! Godot integration for the RustCore WebSocket client
+//!
+//! `RustCoreClient` joins a host from desktop and mobile builds, pinning the
+//! host's self-signed certificate, which Godot's `WebSocketPeer` cannot do.
+//! Web exports keep using `WebSocketPeer`. Connection events are queued on
+//! worker threads and emitted as signals from `poll()` on the main thread.
+
+use std::time::Duration;
+
+use godot::prelude::*;
+
+use crate::client::{ClientConfig, ClientStatus, WebSocketClientState};
+use crate::error::CoreError;
+use crate::ffi::error::FtErrorCode;
+use crate::godot_server::variant_to_payload;
+use crate::server::{CoreEvent, EventQueue};
+
+/// Godot class that wraps the Rust WebSocket client
+///
+/// Methods returning `int` return 0 on success or one of the FFI error codes
+/// (`FT_ERR_*`), with details available from `get_last_error()`. Call
+/// `poll()` every frame to receive signals.
+/// - `connect_to_url(url: String, fingerprint: String) -> int` (fingerprint required for wss://)
+/// - `send(message: Variant) -> int` (queued while reconnecting)
+/// - `disconnect_from_host()`
+/// - `is_connected_to_host() -> bool`
+/// - `get_status() -> String` ("disconnected", "connecting", "connected" or "reconnecting")
+/// - `poll()`
+/// - `get_last_error() -> String`
+/// Signals (emitted from `poll()`):
+/// - `connected(url)`, `disconnected(code, reason)`, `message_received(message)`
+/// - `reconnecting(attempt, delay_ms)`, `connect_failed(reason)`
+#[derive(GodotClass)]
+#[class(base=RefCounted)]
+pub struct RustCoreClient {
+    base: Base<RefCounted>,
+    /// Reconnect with exponential backoff after failures
+    #[var]
+    auto_reconnect: bool,
+    /// Give up after this many failed reconnect attempts in a row (0 = never)
+    #[var]
+    max_reconnect_attempts: i64,
+    /// Interval between pings in milliseconds (0 disables heartbeats)
+    #[var]
+    heartbeat_interval_ms: i64,
+    /// Drop the connection after this many milliseconds without traffic
+    #[var]
+    heartbeat_timeout_ms: i64,
+    /// Inner client state
+    client: WebSocketClientState,
+    /// Events from the client, emitted as signals by `poll()`
+    events: EventQueue,
+    /// Message of the most recent failed call
+    last_error: String,
+}
+
+#[godot_api]
+impl IRefCounted for RustCoreClient {
+    fn init(base: Base<RefCounted>) -> Self {
+        let defaults = ClientConfig::default();
+        let client = WebSocketClientState::new();
+        let events = client.events().subscribe();
+        Self {
+            base,
+            auto_reconnect: defaults.reconnect,
+            max_reconnect_attempts: defaults.max_reconnect_attempts as i64,
+            heartbeat_interval_ms: defaults.heartbeat_interval.as_millis() as i64,
+            heartbeat_timeout_ms: defaults.heartbeat_timeout.as_millis() as i64,
+            client,
+            events,
+            last_error: String::new(),
+        }
+    }
+}
+
+#[godot_api]
+impl RustCoreClient {
+    /// Connected to the host
+    #[signal]
+    fn connected(url: GString);
+
+    /// The connection closed or dropped (code 1006 if no close frame arrived)
+    #[signal]
+    fn disconnected(code: i64, reason: GString);
+
+    /// A text message arrived from the host
+    #[signal]
+    fn message_received(message: GString);
+
+    /// The client will retry after `delay_ms`
+    #[signal]
+    fn reconnecting(attempt: i64, delay_ms: i64);
+
+    /// The client gave up (certificate mismatch, retries exhausted, ...)
+    #[signal]
+    fn connect_failed(reason: GString);
+
+    /// Start connecting to `url`; `fingerprint` is the host's SHA-256
+    /// certificate fingerprint, required for wss:// and empty for ws://
+    #[func]
+    fn connect_to_url(&mut self, url: String, fingerprint: String) -> i32 {
+        let mut config = ClientConfig::new(&url);
+        config.reconnect = self.auto_reconnect;
+        config.max_reconnect_attempts = self.max_reconnect_attempts.clamp(0, u32::MAX as i64) as u32;
+        config.heartbeat_interval = Duration::from_millis(self.heartbeat_interval_ms.max(0) as u64);
+        config.heartbeat_timeout = Duration::from_millis(self.heartbeat_timeout_ms.max(0) as u64);
+        if !fingerprint.is_empty() {
+            match fingerprint.parse() {
+                Ok(fingerprint) => config.fingerprint = Some(fingerprint),
+                Err(e) => return self.fail_with(&e),
+            }
+        }
+
+        match self.client.connect(config) {
+            Ok(()) => self.succeed(),
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    /// Queue a message for the host (String as text, anything else as binary)
+    #[func]
+    fn send(&mut self, message: Variant) -> i32 {
+        match self.client.send(variant_to_payload(&message)) {
+            Ok(()) => self.succeed(),
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    /// Close the connection and stop reconnecting
+    #[func]
+    fn disconnect_from_host(&mut self) {
+        self.client.disconnect();
+    }
+
+    #[func]
+    fn is_connected_to_host(&self) -> bool {
+        self.client.status() == ClientStatus::Connected
+    }
+
+    #[func]
+    fn get_status(&self) -> String {
+        self.client.status().to_string()
+    }
+
+    /// Session token issued by the host, used to reclaim the seat on reconnect (empty until joined)
+    #[func]
+    fn get_session_token(&self) -> String {
+        self.client.session_token().unwrap_or_default()
+    }
+
+    /// Emit signals for events received since the last call
+    #[func]
+    fn poll(&mut self) {
+        for event in self.events.drain() {
+            self.emit_event(event);
+        }
+    }
+
+    /// Get the message of the most recent failed call, empty if it succeeded
+    #[func]
+    fn get_last_error(&self) -> String {
+        self.last_error.clone()
+    }
+}
+
+impl RustCoreClient {
+    /// Emit the signal for a client event
+    fn emit_event(&mut self, event: CoreEvent) {
+        let (signal, args) = match event {
+            CoreEvent::HostConnected { url } => ("connected", vec![url.to_variant()]),
+            CoreEvent::HostDisconnected { code, reason } => {
+                ("disconnected", vec![(code as i64).to_variant(), reason.to_variant()])
+            }
+            CoreEvent::HostMessage { message } => ("message_received", vec![message.to_variant()]),
+            CoreEvent::HostReconnecting { attempt, delay_ms } => {
+                ("reconnecting", vec![(attempt as i64).to_variant(), (delay_ms as i64).to_variant()])
+            }
+            CoreEvent::HostConnectFailed { reason } => ("connect_failed", vec![reason.to_variant()]),
+            // Server events never come from the client
+            _ => return,
+        };
+        self.base_mut().emit_signal(signal, &args);
+    }
+
+    /// Clear the last error and return OK
+    fn succeed(&mut self) -> i32 {
+        self.last_error.clear();
+        FtErrorCode::Ok as i32
+    }
+
+    /// Record a `CoreError` and return its code
+    fn fail_with(&mut self, error: &CoreError) -> i32 {
+        godot_error!("[RustCoreClient] {}", error);
+        self.last_error = error.to_string();
+        FtErrorCode::from(error) as i32
+    }
+}
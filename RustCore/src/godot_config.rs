@@ -0,0 +1,251 @@
+//! Godot resource describing how to host a game
+//!
+//! `RustCoreServerConfig` can be created in the inspector and saved as a
+//! `.tres` file, then passed to `RustCoreServer.start(config)`. Defaults
+//! come from [`HostConfig::default`] so both front ends agree.
+
+use godot::classes::{IResource, ProjectSettings, Resource};
+use godot::prelude::*;
+
+use crate::server::{FieldError, HostConfig};
+
+/// Hosting configuration (listener, TLS, headers, mDNS, discovery, room access and limits)
+///
+/// `static_dir`, `data_dir`, `cert_path` and `key_path` may use `res://` or `user://`
+/// paths; they are globalized before the server starts.
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct RustCoreServerConfig {
+    base: Base<Resource>,
+    /// IP address to listen on ("0.0.0.0" for every interface)
+    #[export]
+    bind_address: GString,
+    /// TCP port (0 picks a free port)
+    #[export]
+    port: i64,
+    /// Directory served as static files
+    #[export(dir)]
+    static_dir: GString,
+    /// Directory finished games are saved in (e.g. `user://history`); leave empty to keep no history
+    #[export(dir)]
+    data_dir: GString,
+    /// Serve HTTPS instead of HTTP
+    #[export]
+    use_https: bool,
+    /// Certificate chain (PEM); leave empty for a self-signed certificate
+    #[export(file = "*.pem,*.crt")]
+    cert_path: GString,
+    /// Private key (PEM); required when `cert_path` is set
+    #[export(file = "*.pem,*.key")]
+    key_path: GString,
+    /// Send COOP/COEP headers (needed by threaded web exports)
+    #[export]
+    cross_origin_isolation: bool,
+    /// `Access-Control-Allow-Origin` value; empty to omit it
+    #[export]
+    cors_allow_origin: GString,
+    /// Advertise the server over mDNS
+    #[export]
+    mdns_enabled: bool,
+    /// mDNS service type
+    #[export]
+    mdns_service_type: GString,
+    /// mDNS instance name shown to players
+    #[export]
+    mdns_instance_name: GString,
+    /// mDNS hostname (without ".local.")
+    #[export]
+    mdns_hostname: GString,
+    /// Answer UDP discovery probes for networks without multicast
+    #[export]
+    discovery_enabled: bool,
+    /// Fewest players needed to start a game
+    #[export]
+    min_players: i64,
+    /// Most players a room accepts
+    #[export]
+    max_players: i64,
+    /// Require players to enter the room code shown on the host
+    #[export]
+    require_room_code: bool,
+    /// Room password; leave empty for none
+    #[export]
+    room_password: GString,
+    /// Failed join attempts from one address before it is locked out (0 disables lockouts)
+    #[export]
+    max_join_failures: i64,
+    /// How long a locked-out address must wait, in seconds
+    #[export]
+    lockout_seconds: i64,
+    /// How long a disconnected player's seat is held for them, in seconds (0 frees it at once)
+    #[export]
+    reconnect_grace_seconds: i64,
+    /// Time between WebSocket pings, in milliseconds (0 disables heartbeats)
+    #[export]
+    ping_interval_ms: i64,
+    /// Missed pongs in a row before a peer is reported stale
+    #[export]
+    stale_after_missed_pongs: i64,
+    /// Missed pongs in a row before a peer is disconnected (0 never disconnects)
+    #[export]
+    disconnect_after_missed_pongs: i64,
+    /// Most WebSocket connections at once (0 for no limit)
+    #[export]
+    max_connections: i64,
+    /// Most WebSocket connections from one address (0 for no limit)
+    #[export]
+    max_connections_per_address: i64,
+    /// Largest WebSocket frame, in bytes
+    #[export]
+    max_frame_bytes: i64,
+    /// Largest WebSocket message, in bytes
+    #[export]
+    max_message_bytes: i64,
+    /// Largest HTTP request body, in bytes
+    #[export]
+    max_request_body_bytes: i64,
+    /// Messages per second one connection may send (0 for no limit)
+    #[export]
+    messages_per_second: i64,
+    /// Messages one connection may send in a burst
+    #[export]
+    message_burst: i64,
+    /// Messages per second all connections from one address may send (0 for no limit)
+    #[export]
+    address_messages_per_second: i64,
+    /// Messages all connections from one address may send in a burst
+    #[export]
+    address_message_burst: i64,
+    /// HTTP requests per second one address may make (0 for no limit)
+    #[export]
+    requests_per_second: i64,
+    /// HTTP requests one address may make in a burst
+    #[export]
+    request_burst: i64,
+    /// Rate-limited messages before a connection is closed (0 never closes)
+    #[export]
+    max_rate_violations: i64,
+}
+
+#[godot_api]
+impl IResource for RustCoreServerConfig {
+    fn init(base: Base<Resource>) -> Self {
+        let defaults = HostConfig::default();
+        Self {
+            base,
+            bind_address: defaults.bind_address.into(),
+            port: defaults.port,
+            static_dir: defaults.static_dir.into(),
+            data_dir: defaults.data_dir.into(),
+            use_https: defaults.use_https,
+            cert_path: defaults.cert_path.into(),
+            key_path: defaults.key_path.into(),
+            cross_origin_isolation: defaults.cross_origin_isolation,
+            cors_allow_origin: defaults.cors_allow_origin.into(),
+            mdns_enabled: defaults.mdns_enabled,
+            mdns_service_type: defaults.mdns_service_type.into(),
+            mdns_instance_name: defaults.mdns_instance_name.into(),
+            mdns_hostname: defaults.mdns_hostname.into(),
+            discovery_enabled: defaults.discovery_enabled,
+            min_players: defaults.min_players,
+            max_players: defaults.max_players,
+            require_room_code: defaults.require_room_code,
+            room_password: defaults.room_password.into(),
+            max_join_failures: defaults.max_join_failures,
+            lockout_seconds: defaults.lockout_seconds,
+            reconnect_grace_seconds: defaults.reconnect_grace_seconds,
+            ping_interval_ms: defaults.ping_interval_ms,
+            stale_after_missed_pongs: defaults.stale_after_missed_pongs,
+            disconnect_after_missed_pongs: defaults.disconnect_after_missed_pongs,
+            max_connections: defaults.max_connections,
+            max_connections_per_address: defaults.max_connections_per_address,
+            max_frame_bytes: defaults.max_frame_bytes,
+            max_message_bytes: defaults.max_message_bytes,
+            max_request_body_bytes: defaults.max_request_body_bytes,
+            messages_per_second: defaults.messages_per_second,
+            message_burst: defaults.message_burst,
+            address_messages_per_second: defaults.address_messages_per_second,
+            address_message_burst: defaults.address_message_burst,
+            requests_per_second: defaults.requests_per_second,
+            request_burst: defaults.request_burst,
+            max_rate_violations: defaults.max_rate_violations,
+        }
+    }
+}
+
+#[godot_api]
+impl RustCoreServerConfig {
+    /// Check the configuration without starting anything
+    ///
+    /// Returns a dictionary of field name -> error message; empty if valid.
+    #[func]
+    fn validate(&self) -> Dictionary {
+        field_errors_to_dictionary(&self.to_host_config().validate())
+    }
+}
+
+impl RustCoreServerConfig {
+    /// Convert to the engine-independent configuration
+    pub fn to_host_config(&self) -> HostConfig {
+        HostConfig {
+            bind_address: self.bind_address.to_string(),
+            port: self.port,
+            static_dir: globalize_path(&self.static_dir),
+            data_dir: globalize_path(&self.data_dir),
+            use_https: self.use_https,
+            cert_path: globalize_path(&self.cert_path),
+            key_path: globalize_path(&self.key_path),
+            cross_origin_isolation: self.cross_origin_isolation,
+            cors_allow_origin: self.cors_allow_origin.to_string(),
+            mdns_enabled: self.mdns_enabled,
+            mdns_service_type: self.mdns_service_type.to_string(),
+            mdns_instance_name: self.mdns_instance_name.to_string(),
+            mdns_hostname: self.mdns_hostname.to_string(),
+            discovery_enabled: self.discovery_enabled,
+            min_players: self.min_players,
+            max_players: self.max_players,
+            require_room_code: self.require_room_code,
+            room_password: self.room_password.to_string(),
+            max_join_failures: self.max_join_failures,
+            lockout_seconds: self.lockout_seconds,
+            reconnect_grace_seconds: self.reconnect_grace_seconds,
+            ping_interval_ms: self.ping_interval_ms,
+            stale_after_missed_pongs: self.stale_after_missed_pongs,
+            disconnect_after_missed_pongs: self.disconnect_after_missed_pongs,
+            max_connections: self.max_connections,
+            max_connections_per_address: self.max_connections_per_address,
+            max_frame_bytes: self.max_frame_bytes,
+            max_message_bytes: self.max_message_bytes,
+            max_request_body_bytes: self.max_request_body_bytes,
+            messages_per_second: self.messages_per_second,
+            message_burst: self.message_burst,
+            address_messages_per_second: self.address_messages_per_second,
+            address_message_burst: self.address_message_burst,
+            requests_per_second: self.requests_per_second,
+            request_burst: self.request_burst,
+            max_rate_violations: self.max_rate_violations,
+        }
+    }
+}
+
+/// Convert `res://` and `user://` paths to OS paths; others pass through
+fn globalize_path(path: &GString) -> String {
+    let path_str = path.to_string();
+    if path_str.starts_with("res://") || path_str.starts_with("user://") {
+        ProjectSettings::singleton().globalize_path(path).to_string()
+    } else {
+        path_str
+    }
+}
+
+/// Convert field errors to a field name -> message dictionary
+pub(crate) fn field_errors_to_dictionary(errors: &[FieldError]) -> Dictionary {
+    let mut dict = Dictionary::new();
+    for error in errors {
+        // Keep the first message when a field has several problems
+        if !dict.contains_key(error.field) {
+            dict.set(error.field, error.message.clone());
+        }
+    }
+    dict
+}
@@ -2,41 +2,158 @@
 //!
 //! This module provides a Godot-native class that wraps the HTTP server and mDNS,
 //! allowing GDScript to control the Rust HTTP server and mDNS service.
+//! Server events are queued on worker threads and emitted as signals from
+//! `poll()` on the main thread.
+
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 
 use godot::prelude::*;
-use crate::server::{HttpServerState, MdnsServerState};
+use crate::error::CoreError;
+use crate::ffi::error::FtErrorCode;
+use crate::game::{
+    quest_rules, send_night_info, Audience, ExcaliburTargeting, Faction, GameEvent, GameHistory, GameSetup, GameTable,
+    NightInfo, RoleAssignment, SystemRng,
+};
+use crate::server::discovery::{discover_hosts, DEFAULT_BEACON_INTERVAL, DISCOVERY_PORT};
+use crate::godot_config::{field_errors_to_dictionary, RustCoreServerConfig};
+use crate::server::{
+    seat_from_index, CoreEvent, DiscoveryServerState, EventQueue, HostConfig, HttpServerState, JoinScheme, JoinToken,
+    MdnsBrowserState, MdnsServerState, MdnsStatus, Payload, PeerHub, PeerLatency, Seat, TXT_FINGERPRINT,
+};
+use crate::tls::Fingerprint;
+use crate::types::{HttpRequest, HttpResponse};
+
+/// How long a worker thread waits for `poll()` to answer a routed request
+const ROUTE_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request waiting for its GDScript handler to run on the main thread
+struct PendingRoute {
+    prefix: String,
+    request: HttpRequest,
+    reply: mpsc::Sender<HttpResponse>,
+}
 
 /// Godot class that wraps the Rust HTTP server and mDNS
 ///
-/// Exposes the following methods to GDScript:
+/// Exposes the following methods to GDScript. `start_*` methods return 0 on
+/// success or one of the FFI error codes (`FT_ERR_*`), with details available
+/// from `get_last_error()`. Call `poll()` every frame to receive signals.
+/// Hosting from a `RustCoreServerConfig` resource:
+/// - `start(config: RustCoreServerConfig) -> Dictionary` (field -> error, empty on success)
+/// - `stop()`
 /// HTTP Server:
 /// - `create_server() -> bool`
-/// - `start_server(address: String, static_dir: String, use_https: bool) -> bool`
+/// - `start_server(address: String, static_dir: String, use_https: bool) -> int`
 /// - `stop_server()`
 /// - `is_running() -> bool`
+/// - `get_certificate_fingerprint() -> String` (empty unless serving HTTPS)
 /// - `free_server()`
+/// Room access (clients join with `/ws?code=...&password=...`):
+/// - `get_room_code() -> String`
+/// - `regenerate_room_code() -> String`
+/// - `set_room_password(password: String) -> int`
+/// - `set_room_code_required(required: bool) -> int`
+/// WebSocket peers (same conventions as `WebSocketServer.gd`):
+/// - `send(peer_id: int, message: Variant) -> int` (0 = everyone, negative = everyone but -peer_id)
+/// - `broadcast(message: Variant, exclude_peer: int) -> int`
+/// - `kick(peer_id: int, reason: String) -> int`
+/// - `get_peers() -> Array`
+/// Game (seated players):
+/// - `assign_roles(seats: PackedInt64Array, options: Dictionary) -> Dictionary` (seat -> role, sends each seat its `night_info`)
+/// - `validate_game_setup(player_count: int, options: Dictionary) -> Dictionary` (option -> explanation)
+/// - `get_quest_rules(player_count: int) -> Array` (also served at `/rules`)
+/// - `get_night_info(seat: int) -> Dictionary`
+/// - `get_assassin_seat() -> int`
+/// - `start_game(seats: PackedInt64Array, options: Dictionary) -> Dictionary` (seat -> role; game messages are then handled in Rust)
+/// - `end_game() -> String` (abandons the game unsaved, so a new one can start before it is over; returns its log)
+/// - `get_game_phase() -> String`
+/// - `get_game_view(seat: int) -> String` (JSON snapshot for a seat, the observer view for -1)
+/// - `get_game_log() -> String` (JSON log of every command and event, with every secret; for the host)
+/// Game history (needs `data_dir` in the config):
+/// - `list_game_history() -> String` (JSON array of summaries, oldest first)
+/// - `get_game_history(id: String) -> String` (JSON record, empty if unknown)
+/// - `export_game_history() -> String` (every record as JSON lines)
 /// mDNS:
 /// - `create_mdns() -> bool`
-/// - `start_mdns(service_type: String, instance_name: String, hostname: String, port: i32) -> bool`
+/// - `start_mdns(service_type: String, instance_name: String, hostname: String, port: i32) -> int`
 /// - `stop_mdns()`
 /// - `is_mdns_running() -> bool`
 /// - `free_mdns()`
+/// - `start_mdns_browse(service_type: String) -> int`
+/// - `stop_mdns_browse()`
+/// Discovery fallback (networks without multicast):
+/// - `start_discovery(server_port: i32, use_https: bool) -> int`
+/// - `stop_discovery()`
+/// - `get_join_code() -> String`
+/// - `decode_join_code(code: String) -> Dictionary`
+/// - `discover_hosts(timeout_ms: i32) -> Array`
+/// Host routes (handlers run on the main thread inside `poll()`):
+/// - `register_route(prefix: String, handler: Callable) -> int`
+/// - `unregister_route(prefix: String) -> bool`
+/// - `poll()`
+/// Errors:
+/// - `get_last_error() -> String`
+/// Signals (emitted from `poll()`):
+/// - `server_started()`, `server_stopped()`, `server_failed(reason)`
+/// - `client_connected(peer_id)`, `client_disconnected(peer_id)`
+/// - `message_received(peer_id, message)`
+/// - `mdns_service_found(info)`, `mdns_service_removed(info)`, `mdns_failed(reason)`
+/// - `join_rejected(address, reason)`, `connection_refused(address, reason)`, `peer_limited(peer_id, reason)`
+/// - `seat_held(peer_id, seat, grace_ms)`, `seat_released(peer_id, seat)`
+/// - `player_resumed(peer_id, previous_peer_id, seat)` (seat -1 if unseated)
+/// - `peer_stale(peer_id, missed_pongs)`, `peer_recovered(peer_id, rtt_ms)`
+/// - `game_event(message)` (public game events, instead of `message_received` for game commands)
 #[derive(GodotClass)]
 #[class(base=RefCounted)]
 pub struct RustCoreServer {
+    base: Base<RefCounted>,
     /// Inner HTTP server state
     http_server: Option<HttpServerState>,
     /// Inner mDNS server state
     mdns_server: Option<MdnsServerState>,
+    /// mDNS browser state
+    mdns_browser: Option<MdnsBrowserState>,
+    /// UDP discovery responder state
+    discovery_server: Option<DiscoveryServerState>,
+    /// GDScript handlers by route prefix
+    route_handlers: HashMap<String, Callable>,
+    /// Sender cloned into every registered route
+    route_tx: mpsc::Sender<PendingRoute>,
+    /// Routed requests waiting for `poll()`
+    route_rx: mpsc::Receiver<PendingRoute>,
+    /// Events from every owned server, emitted as signals by `poll()`
+    events: EventQueue,
+    /// Configuration passed to the last successful `start()`
+    config: Option<HostConfig>,
+    /// Message of the most recent failed call
+    last_error: String,
+    /// Roles dealt by the last `assign_roles()` or `start_game()`
+    roles: Option<RoleAssignment>,
+    /// Game started by `start_game()`
+    game: Option<GameTable>,
 }
 
 #[godot_api]
 impl IRefCounted for RustCoreServer {
-    fn init(_base: Base<RefCounted>) -> Self {
+    fn init(base: Base<RefCounted>) -> Self {
         godot_print!("[RustCoreServer] Constructor called");
+        let (route_tx, route_rx) = mpsc::channel();
         Self {
+            base,
             http_server: None,
             mdns_server: None,
+            mdns_browser: None,
+            discovery_server: None,
+            route_handlers: HashMap::new(),
+            route_tx,
+            route_rx,
+            events: EventQueue::default(),
+            config: None,
+            last_error: String::new(),
+            roles: None,
+            game: None,
         }
     }
 }
@@ -45,6 +162,8 @@ impl Drop for RustCoreServer {
     fn drop(&mut self) {
         self.stop_server();
         self.stop_mdns();
+        self.stop_discovery();
+        self.stop_mdns_browse();
         self.free_server();
         self.free_mdns();
         godot_print!("[RustCoreServer] Destructor called - resources cleaned up");
@@ -53,52 +172,201 @@ impl Drop for RustCoreServer {
 
 #[godot_api]
 impl RustCoreServer {
+    // === Signals ===
+
+    /// The HTTP(S) server is accepting connections
+    #[signal]
+    fn server_started();
+
+    /// The HTTP(S) server was stopped
+    #[signal]
+    fn server_stopped();
+
+    /// The HTTP(S) server failed to start or stopped with an error
+    #[signal]
+    fn server_failed(reason: GString);
+
+    /// A WebSocket client connected to `/ws`
+    #[signal]
+    fn client_connected(peer_id: i64);
+
+    /// A WebSocket client disconnected
+    #[signal]
+    fn client_disconnected(peer_id: i64);
+
+    /// A WebSocket client sent a text message
+    #[signal]
+    fn message_received(peer_id: i64, message: GString);
+
+    /// An mDNS service appeared (`{service_type, fullname, name}`)
+    #[signal]
+    fn mdns_service_found(info: Dictionary);
+
+    /// An mDNS service left the network (`{service_type, fullname, name}`)
+    #[signal]
+    fn mdns_service_removed(info: Dictionary);
+
+    /// The mDNS daemon failed; the service is no longer announced
+    #[signal]
+    fn mdns_failed(reason: GString);
+
+    /// A client was refused by the room access check
+    /// (reason: "invalid_code", "wrong_password" or "locked_out")
+    #[signal]
+    fn join_rejected(address: GString, reason: GString);
+
+    /// A seated player disconnected; their seat is held for `grace_ms`
+    #[signal]
+    fn seat_held(peer_id: i64, seat: i64, grace_ms: i64);
+
+    /// A held seat was freed because its player did not come back in time
+    #[signal]
+    fn seat_released(peer_id: i64, seat: i64);
+
+    /// A player reconnected with their session token (`seat` is -1 if unseated)
+    #[signal]
+    fn player_resumed(peer_id: i64, previous_peer_id: i64, seat: i64);
+
+    /// A peer stopped answering pings and may be gone
+    #[signal]
+    fn peer_stale(peer_id: i64, missed_pongs: i64);
+
+    /// A stale peer answered a ping again
+    #[signal]
+    fn peer_recovered(peer_id: i64, rtt_ms: i64);
+
+    /// A WebSocket connection was refused by the connection caps
+    /// (reason: "server_full", "too_many_connections" or "not_accepting")
+    #[signal]
+    fn connection_refused(address: GString, reason: GString);
+
+    /// A peer was disconnected for exceeding its limits (reason: "rate_limited" or "too_large")
+    #[signal]
+    fn peer_limited(peer_id: i64, reason: GString);
+
+    /// A public event of the game started by `start_game()`, as its JSON protocol message
+    #[signal]
+    fn game_event(message: GString);
+
+    // === Configured Hosting ===
+
+    /// Validate `config` and start the HTTP(S) server, mDNS and discovery it enables
+    ///
+    /// Returns an empty dictionary on success. Otherwise nothing is left
+    /// running and the dictionary maps each invalid field to its error, or
+    /// "server" / "data_dir" / "mdns" / "discovery" to the reason that service failed.
+    #[func]
+    fn start(&mut self, config: Gd<RustCoreServerConfig>) -> Dictionary {
+        let config = config.bind().to_host_config();
+        let errors = config.validate();
+        if !errors.is_empty() {
+            self.last_error = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+            godot_error!("[RustCoreServer] Invalid configuration: {}", self.last_error);
+            return field_errors_to_dictionary(&errors);
+        }
+        self.stop();
+
+        self.create_server();
+        let address = config.socket_address();
+        let http_server = self.http_server.as_mut().expect("server was just created");
+        http_server.set_header_policy(config.header_policy());
+        config.configure_access(http_server.access());
+        config.configure_sessions(http_server.hub().sessions());
+        http_server.hub().set_heartbeat_config(config.heartbeat_config());
+        http_server.hub().limiter().set_limits(config.limits());
+        if let Err(e) = http_server.set_data_dir(Some(config.data_dir.as_str()).filter(|dir| !dir.is_empty())) {
+            return self.start_failed("data_dir", &e);
+        }
+        let result = match (config.use_https, config.certificate()) {
+            (false, _) => http_server.start(&address, &config.static_dir),
+            (true, None) => http_server.start_https(&address, &config.static_dir),
+            (true, Some((cert_path, key_path))) => {
+                http_server.start_https_with_certificate(&address, &config.static_dir, cert_path, key_path)
+            }
+        };
+        if let Err(e) = result {
+            return self.start_failed("server", &e);
+        }
+        // Advertise the bound port, which differs from the configured one for port 0
+        let port = http_server.local_addr().map(|addr| addr.port()).unwrap_or(config.port as u16);
+
+        if config.mdns_enabled {
+            self.create_mdns();
+            self.publish_fingerprint_txt();
+            let mdns_server = self.mdns_server.as_mut().expect("mDNS server was just created");
+            if let Err(e) = mdns_server.start(
+                &config.mdns_service_type,
+                &config.mdns_instance_name,
+                &config.mdns_hostname,
+                port,
+            ) {
+                return self.start_failed("mdns", &e);
+            }
+        }
+
+        if config.discovery_enabled {
+            let started = self.start_discovery(port as i32, config.use_https);
+            if started != FtErrorCode::Ok as i32 {
+                let reason = self.last_error.clone();
+                self.stop();
+                self.last_error = reason.clone();
+                let mut errors = Dictionary::new();
+                errors.set("discovery", reason);
+                return errors;
+            }
+        }
+
+        godot_print!("[RustCoreServer] Hosting on {}", self.get_server_address());
+        self.config = Some(config);
+        self.succeed();
+        Dictionary::new()
+    }
+
+    /// Stop everything started by `start()`
+    #[func]
+    fn stop(&mut self) {
+        self.stop_discovery();
+        self.stop_mdns();
+        self.stop_server();
+        self.config = None;
+    }
+
     // === HTTP Server Methods ===
 
     #[func]
     fn create_server(&mut self) -> bool {
         if self.http_server.is_some() {
-            eprintln!("Server already created");
+            godot_print!("[RustCoreServer] Server already created");
             return true;
         }
 
-        self.http_server = Some(HttpServerState::new());
-        eprintln!("RustCoreServer created successfully");
+        let server = HttpServerState::new();
+        server.events().attach(self.events.clone());
+        self.http_server = Some(server);
+        godot_print!("[RustCoreServer] Server created");
         true
     }
 
     #[func]
-    fn start_server(&mut self, address: String, static_dir: String, use_https: bool) -> bool {
+    fn start_server(&mut self, address: String, static_dir: String, use_https: bool) -> i32 {
         let http_server = match self.http_server.as_mut() {
             Some(s) => s,
             None => {
-                eprintln!("Error: Server not created. Call create_server() first.");
-                return false;
+                return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first.");
             }
         };
 
-        if use_https {
-            match http_server.start_https(&address, &static_dir) {
-                Ok(_) => {
-                    eprintln!("HTTPS server started on {}", address);
-                    true
-                }
-                Err(e) => {
-                    eprintln!("Failed to start HTTPS server: {:?}", e);
-                    false
-                }
-            }
+        let result = if use_https {
+            http_server.start_https(&address, &static_dir)
         } else {
-            match http_server.start(&address, &static_dir) {
-                Ok(_) => {
-                    eprintln!("HTTP server started on {}", address);
-                    true
-                }
-                Err(e) => {
-                    eprintln!("Failed to start HTTP server: {:?}", e);
-                    false
-                }
+            http_server.start(&address, &static_dir)
+        };
+        match result {
+            Ok(_) => {
+                godot_print!("[RustCoreServer] {} server started on {}", if use_https { "HTTPS" } else { "HTTP" }, address);
+                self.succeed()
             }
+            Err(e) => self.fail_with(&e),
         }
     }
 
@@ -106,9 +374,6 @@ impl RustCoreServer {
     fn stop_server(&mut self) {
         if let Some(http_server) = self.http_server.as_mut() {
             http_server.stop();
-            eprintln!("Server stopped");
-        } else {
-            eprintln!("Server not running (no http_server instance)");
         }
     }
 
@@ -135,17 +400,17 @@ impl RustCoreServer {
         };
 
         let mdns_status = match self.mdns_server.as_ref() {
-            Some(s) => {
-                if s.is_running() {
-                    "running"
-                } else {
-                    "stopped"
-                }
-            }
-            None => "not created",
+            Some(s) => s.status().to_string(),
+            None => "not created".to_string(),
         };
 
-        format!("HTTP: {}, mDNS: {}", http_status, mdns_status)
+        match self.config.as_ref() {
+            Some(config) => format!(
+                "HTTP: {}, mDNS: {}, players: {}-{}",
+                http_status, mdns_status, config.min_players, config.max_players
+            ),
+            None => format!("HTTP: {}, mDNS: {}", http_status, mdns_status),
+        }
     }
 
     /// Get the HTTP server address if running, empty string otherwise
@@ -157,10 +422,472 @@ impl RustCoreServer {
         }
     }
 
+    /// SHA-256 fingerprint of the served certificate ("AB:CD:..."),
+    /// empty when not serving HTTPS
+    #[func]
+    fn get_certificate_fingerprint(&self) -> String {
+        self.certificate_fingerprint().map(|fp| fp.to_string()).unwrap_or_default()
+    }
+
+    /// Room code players must enter to join (e.g. "K7M2QX"), empty if no server
+    #[func]
+    fn get_room_code(&self) -> String {
+        self.http_server.as_ref().map(|s| s.access().room_code()).unwrap_or_default()
+    }
+
+    /// Replace the room code with a fresh one and return it
+    ///
+    /// Connected players stay; only new joins need the new code.
+    #[func]
+    fn regenerate_room_code(&mut self) -> String {
+        match self.http_server.as_ref() {
+            Some(s) => s.access().regenerate_room_code(),
+            None => {
+                self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first.");
+                String::new()
+            }
+        }
+    }
+
+    /// Set the room password (empty removes it)
+    #[func]
+    fn set_room_password(&mut self, password: String) -> i32 {
+        match self.http_server.as_ref() {
+            Some(s) => {
+                s.access().set_password(Some(&password));
+                self.succeed()
+            }
+            None => self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        }
+    }
+
+    /// Require (or stop requiring) the room code for new joins
+    #[func]
+    fn set_room_code_required(&mut self, required: bool) -> i32 {
+        match self.http_server.as_ref() {
+            Some(s) => {
+                s.access().set_code_required(required);
+                self.succeed()
+            }
+            None => self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        }
+    }
+
     #[func]
     fn free_server(&mut self) {
         self.http_server = None;
-        eprintln!("Server freed");
+    }
+
+    // === WebSocket Peer Methods ===
+
+    /// Send a message to a peer, like `WebSocketServer.send`
+    ///
+    /// `peer_id` 0 sends to every peer and a negative id sends to every peer
+    /// except `-peer_id`. Strings are sent as text frames, `PackedByteArray`
+    /// as raw binary frames and any other value as `var_to_bytes` binary.
+    #[func]
+    fn send(&mut self, peer_id: i64, message: Variant) -> i32 {
+        if peer_id <= 0 {
+            let exclude = if peer_id < 0 { -peer_id } else { 0 };
+            return self.broadcast(message, exclude);
+        }
+
+        let hub = match self.hub() {
+            Some(hub) => hub,
+            None => return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        };
+        match hub.send(peer_id as u32, variant_to_payload(&message)) {
+            Ok(()) => self.succeed(),
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    /// Send a message to every peer except `exclude_peer` (0 excludes nobody)
+    #[func]
+    fn broadcast(&mut self, message: Variant, exclude_peer: i64) -> i32 {
+        let hub = match self.hub() {
+            Some(hub) => hub,
+            None => return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        };
+        let exclude = if exclude_peer > 0 { Some(exclude_peer as u32) } else { None };
+        hub.broadcast(variant_to_payload(&message), exclude);
+        self.succeed()
+    }
+
+    /// Disconnect a peer; the client sees close code 4000 with `reason`
+    #[func]
+    fn kick(&mut self, peer_id: i64, reason: String) -> i32 {
+        let hub = match self.hub() {
+            Some(hub) => hub,
+            None => return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        };
+        match hub.kick(peer_id as u32, &reason) {
+            Ok(()) => self.succeed(),
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    /// Seat a connected peer so it can reconnect into it; -1 unseats it
+    ///
+    /// Fails if the peer is unknown or another player holds the seat.
+    #[func]
+    fn set_seat(&mut self, peer_id: i64, seat: i64) -> i32 {
+        let hub = match self.hub() {
+            Some(hub) => hub,
+            None => return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        };
+        let peer_id = peer_id as u32;
+        if !hub.peers().iter().any(|peer| peer.peer_id == peer_id) {
+            return self.fail_with(&CoreError::PeerNotFound(peer_id));
+        }
+        let seat = if seat < 0 { None } else { Some(seat as u32) };
+        if hub.sessions().set_seat(peer_id, seat) {
+            self.succeed()
+        } else {
+            self.fail(
+                FtErrorCode::InvalidConfig,
+                &format!("Seat {} belongs to another player", seat.unwrap_or_default()),
+            )
+        }
+    }
+
+    /// Seat of a connected peer, or -1
+    #[func]
+    fn get_seat(&self, peer_id: i64) -> i64 {
+        self.hub()
+            .and_then(|hub| hub.sessions().seat_of(peer_id as u32))
+            .map_or(-1, i64::from)
+    }
+
+    /// Send a private message to a seat and keep it for when the player reconnects
+    ///
+    /// A later message with the same `key` replaces the kept one. Fails
+    /// with `FT_ERR_SEAT_NOT_FOUND` for a negative seat, such as `get_seat()`
+    /// of an unseated peer.
+    #[func]
+    fn send_to_seat(&mut self, seat: i64, key: String, message: String) -> i32 {
+        let hub = match self.hub() {
+            Some(hub) => hub,
+            None => return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        };
+        let seat = match seat_from_index(seat) {
+            Ok(seat) => seat,
+            Err(e) => return self.fail_with(&e),
+        };
+        match hub.send_to_seat(seat, &key, &message) {
+            Ok(()) => self.succeed(),
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    /// Free a seat and forget its private messages
+    ///
+    /// Returns the peer id that occupied it, or -1 if it was empty or the
+    /// seat is invalid (see `get_last_error()`).
+    #[func]
+    fn release_seat(&mut self, seat: i64) -> i64 {
+        let seat = match seat_from_index(seat) {
+            Ok(seat) => seat,
+            Err(e) => {
+                self.fail_with(&e);
+                return -1;
+            }
+        };
+        self.succeed();
+        self.hub()
+            .and_then(|hub| hub.sessions().release_seat(seat))
+            .map_or(-1, i64::from)
+    }
+
+    /// Accept (or refuse) new players, like `refuse_new_connections` in `WebsocketServer.gd`
+    ///
+    /// Players resuming their session can always reconnect.
+    #[func]
+    fn set_accepting_new_players(&mut self, accepting: bool) -> i32 {
+        match self.hub() {
+            Some(hub) => {
+                hub.limiter().set_accepting(accepting);
+                self.succeed()
+            }
+            None => self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first."),
+        }
+    }
+
+    #[func]
+    fn is_accepting_new_players(&self) -> bool {
+        self.hub().is_some_and(|hub| hub.limiter().is_accepting())
+    }
+
+    /// Round-trip statistics of a connected peer (empty dictionary if unknown)
+    ///
+    /// `{rtt_ms, smoothed_rtt_ms, jitter_ms, samples, missed_pongs, stale}`;
+    /// the round-trip times are -1 until the first pong arrives.
+    #[func]
+    fn get_peer_latency(&self, peer_id: i64) -> Dictionary {
+        self.hub()
+            .and_then(|hub| hub.latency(peer_id as u32))
+            .map(|latency| latency_to_dictionary(&latency))
+            .unwrap_or_default()
+    }
+
+    /// Latency of every connected, seated player: `{seat: {peer_id, rtt_ms, ...}}`
+    #[func]
+    fn get_seat_latencies(&self) -> Dictionary {
+        let mut result = Dictionary::new();
+        if let Some(hub) = self.hub() {
+            for (seat, peer_id, latency) in hub.seat_latencies() {
+                let mut info = latency_to_dictionary(&latency);
+                info.set("peer_id", peer_id as i64);
+                result.set(seat as i64, info);
+            }
+        }
+        result
+    }
+
+    /// Deal roles to the given seats and send each player only their own night information
+    ///
+    /// `options` picks the optional roles (`merlin`, `percival`, `morgana`,
+    /// `mordred`, `oberon`; missing keys keep the `GameConfig` lineup). Every
+    /// seat must be taken (`set_seat`). Returns `{seat: role}` for the host,
+    /// or an empty dictionary on failure (see `get_last_error()`).
+    #[func]
+    fn assign_roles(&mut self, seats: PackedInt64Array, options: Dictionary) -> Dictionary {
+        let Some(hub) = self.hub() else {
+            self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first.");
+            return Dictionary::new();
+        };
+        let seats = match seats_from_array(&seats) {
+            Ok(seats) => seats,
+            Err(e) => {
+                self.fail_with(&e);
+                return Dictionary::new();
+            }
+        };
+        if let Some(&seat) = seats.iter().find(|&&seat| hub.sessions().occupant(seat).is_none()) {
+            self.fail_with(&CoreError::SeatNotFound(seat));
+            return Dictionary::new();
+        }
+        let setup = game_setup_from_dictionary(seats.len(), &options);
+        let assignment = match setup.deal(&seats, &mut SystemRng::new()) {
+            Ok(assignment) => assignment,
+            Err(e) => {
+                self.fail(FtErrorCode::InvalidConfig, &e.to_string());
+                return Dictionary::new();
+            }
+        };
+        if let Err(e) = send_night_info(&hub, &assignment) {
+            self.fail_with(&e);
+            return Dictionary::new();
+        }
+
+        let mut result = Dictionary::new();
+        for (seat, role) in assignment.roles() {
+            result.set(seat as i64, role.name());
+        }
+        self.roles = Some(assignment);
+        self.succeed();
+        result
+    }
+
+    /// Check a choice of optional roles for a player count, e.g. while players join the lobby
+    ///
+    /// Returns `option -> explanation` (`player_count`, `percival`,
+    /// `morgana`, `mordred` or `spies`), empty when the game can be played.
+    #[func]
+    fn validate_game_setup(&self, player_count: i64, options: Dictionary) -> Dictionary {
+        let setup = game_setup_from_dictionary(player_count.max(0) as usize, &options);
+        let mut errors = Dictionary::new();
+        for error in setup.validate() {
+            if !errors.contains_key(error.field()) {
+                errors.set(error.field(), error.to_string());
+            }
+        }
+        errors
+    }
+
+    /// Deal roles and start a game whose commands are handled in Rust
+    ///
+    /// Takes the same arguments as `assign_roles()`, plus the
+    /// `lady_of_the_lake` and `excalibur` options (with
+    /// `excalibur_targeting`: `other_team_member` or `any_team_member`, and
+    /// `excalibur_leader_may_hold`), `assassination_timeout_ms` and
+    /// `assassination_default_winner` (`resistance` or `spies`). The
+    /// assassination timeout runs in `poll()`. `player_names` (`{seat: name}`)
+    /// names the players in the game history. Game commands from seated players no longer
+    /// reach `message_received`; public events, from `game_started` on, are emitted as `game_event`.
+    /// With a `data_dir`, the game is saved to the history once it is over.
+    /// Fails (see `get_last_error()`) while a game is not over; call
+    /// `end_game()` first to abandon it.
+    /// Returns `{seat: role}`, or an empty dictionary on failure.
+    #[func]
+    fn start_game(&mut self, seats: PackedInt64Array, options: Dictionary) -> Dictionary {
+        let Some(hub) = self.hub() else {
+            self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first.");
+            return Dictionary::new();
+        };
+        if self.game.as_ref().is_some_and(|game| !game.is_over()) {
+            self.fail(FtErrorCode::AlreadyRunning, "A game is in progress. Call end_game() first.");
+            return Dictionary::new();
+        }
+        let seats = match seats_from_array(&seats) {
+            Ok(seats) => seats,
+            Err(e) => {
+                self.fail_with(&e);
+                return Dictionary::new();
+            }
+        };
+        let names = match player_names_from_dictionary(&options) {
+            Ok(names) => names,
+            Err(e) => {
+                self.fail_with(&e);
+                return Dictionary::new();
+            }
+        };
+        let setup = game_setup_from_dictionary(seats.len(), &options);
+        let (mut game, started) = match GameTable::start(hub, setup, &seats, &mut SystemRng::new()) {
+            Ok(started) => started,
+            Err(e) => {
+                self.fail_with(&e);
+                return Dictionary::new();
+            }
+        };
+        game.set_history(self.http_server.as_ref().and_then(|server| server.history().cloned()));
+        for (seat, name) in names {
+            game.set_player_name(seat, &name);
+        }
+
+        let mut result = Dictionary::new();
+        for (seat, role) in game.state().roles().roles() {
+            result.set(seat as i64, role.name());
+        }
+        self.roles = Some(game.state().roles().clone());
+        self.game = Some(game);
+        self.emit_game_events(&started);
+        self.succeed();
+        result
+    }
+
+    /// Abandon the game started by `start_game()`, over or not
+    ///
+    /// An abandoned game is not saved to the history. Seated players'
+    /// game commands reach `message_received` again.
+    /// Returns the game's JSON log (as `get_game_log()`), empty without a game.
+    #[func]
+    fn end_game(&mut self) -> String {
+        self.game
+            .take()
+            .map(|game| game.log().to_json().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Phase of the game started by `start_game()` (e.g. `team_vote`), empty without a game
+    #[func]
+    fn get_game_phase(&self) -> String {
+        self.game
+            .as_ref()
+            .map(|game| game.state().phase().name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// JSON snapshot of the game started by `start_game()` for `seat`, or the
+    /// observer view for -1; empty without a game
+    ///
+    /// The snapshot's `seq` is that of the latest `game_event` it includes.
+    #[func]
+    fn get_game_view(&self, seat: i64) -> String {
+        let Some(game) = self.game.as_ref() else {
+            return String::new();
+        };
+        match u32::try_from(seat) {
+            Ok(seat) => game.state().view_for(seat).to_string(),
+            Err(_) => game.state().observer_view().to_string(),
+        }
+    }
+
+    /// JSON log of the game started by `start_game()`, empty without a game
+    ///
+    /// The log holds every secret of the game; do not send it to players
+    /// before the game is over.
+    #[func]
+    fn get_game_log(&self) -> String {
+        self.game
+            .as_ref()
+            .map(|game| game.log().to_json().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Summaries of the finished games in the `data_dir`, oldest first, as a
+    /// JSON array of `{id, started_at_ms, finished_at_ms, player_count,
+    /// players, winner, reason}`; empty on failure
+    #[func]
+    fn list_game_history(&mut self) -> String {
+        self.read_history(|history| Ok(Some(serde_json::Value::from(history.list()?).to_string())))
+    }
+
+    /// JSON record of the finished game `id` (players, roles, proposals and
+    /// votes, quests, assassination, winner and log); empty if unknown
+    #[func]
+    fn get_game_history(&mut self, id: String) -> String {
+        self.read_history(|history| Ok(history.get(&id)?.map(|record| record.to_string())))
+    }
+
+    /// Every finished game in the `data_dir` as JSON lines; empty on failure
+    #[func]
+    fn export_game_history(&mut self) -> String {
+        self.read_history(|history| history.export().map(Some))
+    }
+
+    /// Official quests for a player count: `[{team_size, fails_required}]` (empty outside 5-10)
+    ///
+    /// The same table is served at `/rules` for clients.
+    #[func]
+    fn get_quest_rules(&self, player_count: i64) -> VariantArray {
+        let mut result = VariantArray::new();
+        for rule in quest_rules(player_count.max(0) as usize).into_iter().flatten() {
+            let mut quest = Dictionary::new();
+            quest.set("team_size", rule.team_size as i64);
+            quest.set("fails_required", rule.fails_required as i64);
+            result.push(&quest.to_variant());
+        }
+        result
+    }
+
+    /// Night information of one seat from the last `assign_roles()` (empty if unknown or negative)
+    ///
+    /// `{seat, role, faction, assassin, sees: [{seat, as}]}`, the same as the
+    /// `night_info` message sent to that seat. Use it for a player on the host.
+    #[func]
+    fn get_night_info(&self, seat: i64) -> Dictionary {
+        self.roles
+            .as_ref()
+            .zip(seat_from_index(seat).ok())
+            .and_then(|(roles, seat)| roles.night_info(seat))
+            .map(|info| night_info_to_dictionary(&info))
+            .unwrap_or_default()
+    }
+
+    /// Seat of the assassin from the last `assign_roles()`, or -1
+    #[func]
+    fn get_assassin_seat(&self) -> i64 {
+        self.roles
+            .as_ref()
+            .and_then(RoleAssignment::assassin)
+            .map_or(-1, i64::from)
+    }
+
+    /// Connected peers as `[{peer_id, address}]`, ordered by id
+    #[func]
+    fn get_peers(&self) -> VariantArray {
+        let mut result = VariantArray::new();
+        if let Some(hub) = self.hub() {
+            for peer in hub.peers() {
+                let mut info = Dictionary::new();
+                info.set("peer_id", peer.peer_id as i64);
+                info.set("address", peer.address.to_string());
+                result.push(&info.to_variant());
+            }
+        }
+        result
     }
 
     // === mDNS Methods ===
@@ -168,53 +895,42 @@ impl RustCoreServer {
     #[func]
     fn create_mdns(&mut self) -> bool {
         if self.mdns_server.is_some() {
-            eprintln!("mDNS server already created");
+            godot_print!("[RustCoreServer] mDNS server already created");
             return true;
         }
 
-        self.mdns_server = Some(MdnsServerState::new());
-        eprintln!("mDNS server created successfully");
+        let server = MdnsServerState::new();
+        server.events().attach(self.events.clone());
+        self.mdns_server = Some(server);
+        godot_print!("[RustCoreServer] mDNS server created");
         true
     }
 
     #[func]
-    fn start_mdns(&mut self, service_type: String, instance_name: String, hostname: String, port: i32) -> bool {
-        eprintln!("Starting mDNS: type={}, instance={}, hostname={}, port={}",
-            service_type, instance_name, hostname, port);
-
+    fn start_mdns(&mut self, service_type: String, instance_name: String, hostname: String, port: i32) -> i32 {
+        self.publish_fingerprint_txt();
         let mdns_server = match self.mdns_server.as_mut() {
             Some(s) => s,
             None => {
-                eprintln!("Error: mDNS not created. Call create_mdns() first.");
-                return false;
+                return self.fail(FtErrorCode::NotRunning, "mDNS not created. Call create_mdns() first.");
             }
         };
 
         match mdns_server.start(&service_type, &instance_name, &hostname, port as u16) {
             Ok(_) => {
-                let fullname = format!("{}.{}.", instance_name, service_type);
-                eprintln!("mDNS service registered: {}", fullname);
-                true
-            }
-            Err(e) => {
-                eprintln!("Failed to start mDNS: {:?}", e);
-                false
+                godot_print!("[RustCoreServer] mDNS service registered: {}.{}", instance_name, service_type);
+                self.succeed()
             }
+            Err(e) => self.fail_with(&e),
         }
     }
 
     #[func]
     fn stop_mdns(&mut self) {
         if let Some(mdns_server) = self.mdns_server.as_mut() {
-            if mdns_server.is_running() {
-                let fullname = mdns_server.service_fullname();
+            if mdns_server.status() != MdnsStatus::Stopped {
                 mdns_server.stop();
-                eprintln!("mDNS service stopped: {}", fullname);
-            } else {
-                eprintln!("mDNS service not running");
             }
-        } else {
-            eprintln!("mDNS not created (no mdns_server instance)");
         }
     }
 
@@ -229,6 +945,543 @@ impl RustCoreServer {
     #[func]
     fn free_mdns(&mut self) {
         self.mdns_server = None;
-        eprintln!("mDNS server freed");
     }
+
+    /// Browse for a service type; results arrive as `mdns_service_found` /
+    /// `mdns_service_removed` signals
+    #[func]
+    fn start_mdns_browse(&mut self, service_type: String) -> i32 {
+        let events = self.events.clone();
+        let browser = self.mdns_browser.get_or_insert_with(|| {
+            let browser = MdnsBrowserState::new();
+            browser.events().attach(events);
+            browser
+        });
+        match browser.start(&service_type) {
+            Ok(_) => self.succeed(),
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    #[func]
+    fn stop_mdns_browse(&mut self) {
+        if let Some(browser) = self.mdns_browser.as_mut() {
+            if browser.is_running() {
+                browser.stop();
+            }
+        }
+    }
+
+    // === Discovery Fallback Methods ===
+
+    /// Advertise this host over UDP broadcast for clients whose network blocks mDNS
+    #[func]
+    fn start_discovery(&mut self, server_port: i32, use_https: bool) -> i32 {
+        let scheme = if use_https { JoinScheme::Https } else { JoinScheme::Http };
+        let token = match JoinToken::for_local_host(server_port as u16, scheme) {
+            Ok(token) => token,
+            Err(e) => return self.fail_with(&e),
+        };
+        let token = match self.certificate_fingerprint() {
+            Some(fingerprint) if use_https => token.with_fingerprint(*fingerprint.as_bytes()),
+            _ => token,
+        };
+
+        let discovery = self.discovery_server.get_or_insert_with(DiscoveryServerState::new);
+        match discovery.start(token, DISCOVERY_PORT, Some(DEFAULT_BEACON_INTERVAL)) {
+            Ok(_) => {
+                godot_print!("[RustCoreServer] Discovery responder started on UDP port {}", DISCOVERY_PORT);
+                self.succeed()
+            }
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    #[func]
+    fn stop_discovery(&mut self) {
+        if let Some(discovery) = self.discovery_server.as_mut() {
+            if discovery.is_running() {
+                discovery.stop();
+            }
+        }
+    }
+
+    /// Get the join code for the running discovery responder, grouped for
+    /// reading out loud. Empty if discovery is not running.
+    #[func]
+    fn get_join_code(&self) -> String {
+        self.discovery_server
+            .as_ref()
+            .and_then(|d| d.token())
+            .and_then(|token| token.encode_short_code().ok())
+            .unwrap_or_default()
+    }
+
+    /// Decode a join code (QR or short form). Returns an empty dictionary
+    /// if the code is invalid (see `get_last_error()`).
+    #[func]
+    fn decode_join_code(&mut self, code: String) -> Dictionary {
+        match JoinToken::decode(&code) {
+            Ok(token) => {
+                self.succeed();
+                join_token_to_dictionary(&token)
+            }
+            Err(e) => {
+                self.fail_with(&e);
+                Dictionary::new()
+            }
+        }
+    }
+
+    /// Broadcast a discovery probe and return the hosts that answered.
+    /// Blocks for `timeout_ms`.
+    #[func]
+    fn discover_hosts(&self, timeout_ms: i32) -> VariantArray {
+        let mut result = VariantArray::new();
+        match discover_hosts(DISCOVERY_PORT, Duration::from_millis(timeout_ms.max(0) as u64)) {
+            Ok(hosts) => {
+                for host in hosts {
+                    let mut info = join_token_to_dictionary(&host.token);
+                    info.set("source", host.source.to_string());
+                    result.push(&info.to_variant());
+                }
+            }
+            Err(e) => godot_warn!("[RustCoreServer] Discovery probe failed: {}", e),
+        }
+        result
+    }
+
+    // === Host Routes ===
+
+    /// Route requests under `prefix` to `handler`
+    ///
+    /// The handler is called from `poll()` with a Dictionary
+    /// `{method, path, query, headers, body}` and returns either a Dictionary
+    /// `{status_code, headers, body}` or a String body (status 200).
+    /// Requests not answered within 5 seconds get a 504.
+    #[func]
+    fn register_route(&mut self, prefix: String, handler: Callable) -> i32 {
+        let http_server = match self.http_server.as_ref() {
+            Some(s) => s,
+            None => {
+                return self.fail(FtErrorCode::NotRunning, "Server not created. Call create_server() first.");
+            }
+        };
+
+        let route_tx = self.route_tx.clone();
+        let route_prefix = prefix.clone();
+        let result = http_server.register_route(
+            &prefix,
+            Arc::new(move |request| {
+                let (reply_tx, reply_rx) = mpsc::channel();
+                let pending = PendingRoute {
+                    prefix: route_prefix.clone(),
+                    request,
+                    reply: reply_tx,
+                };
+                if route_tx.send(pending).is_err() {
+                    return HttpResponse::new(503, "Server is shutting down");
+                }
+                reply_rx
+                    .recv_timeout(ROUTE_REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| HttpResponse::new(504, "Handler did not respond; is poll() being called?"))
+            }),
+        );
+        match result {
+            Ok(()) => {
+                self.route_handlers.insert(prefix, handler);
+                self.succeed()
+            }
+            Err(e) => self.fail_with(&e),
+        }
+    }
+
+    /// Remove the handler for `prefix`. Returns true if one was registered.
+    #[func]
+    fn unregister_route(&mut self, prefix: String) -> bool {
+        self.route_handlers.remove(&prefix);
+        self.http_server
+            .as_ref()
+            .map(|s| s.unregister_route(&prefix))
+            .unwrap_or(false)
+    }
+
+    /// Emit queued events as signals and run GDScript handlers for requests
+    /// received since the last call
+    ///
+    /// Call this every frame (e.g. from `_process`).
+    #[func]
+    fn poll(&mut self) {
+        for event in self.events.drain() {
+            if let CoreEvent::MessageReceived { peer_id, message } = &event {
+                if let Some(events) = self.game.as_mut().and_then(|game| game.handle_message(*peer_id, message)) {
+                    self.emit_game_events(&events);
+                    continue;
+                }
+            }
+            self.emit_event(event);
+        }
+        if let Some(events) = self.game.as_mut().map(GameTable::poll) {
+            self.emit_game_events(&events);
+        }
+
+        while let Ok(pending) = self.route_rx.try_recv() {
+            let response = match self.route_handlers.get(&pending.prefix) {
+                Some(handler) => {
+                    let result = handler.call(&[request_to_dictionary(&pending.request).to_variant()]);
+                    response_from_variant(&result)
+                }
+                None => HttpResponse::new(404, format!("No handler for {}", pending.request.path)),
+            };
+            // The worker may have timed out already
+            let _ = pending.reply.send(response);
+        }
+    }
+
+    // === Error Reporting ===
+
+    /// Get the message of the most recent failed call, empty if it succeeded
+    #[func]
+    fn get_last_error(&self) -> String {
+        self.last_error.clone()
+    }
+}
+
+impl RustCoreServer {
+    /// Peer hub of the HTTP server, if created
+    fn hub(&self) -> Option<PeerHub> {
+        self.http_server.as_ref().map(|s| s.hub().clone())
+    }
+
+    /// Fingerprint of the certificate served by the running HTTPS server
+    fn certificate_fingerprint(&self) -> Option<Fingerprint> {
+        self.http_server
+            .as_ref()
+            .filter(|s| s.is_running())
+            .and_then(|s| s.certificate_fingerprint())
+    }
+
+    /// Publish the certificate fingerprint in the mDNS TXT record so
+    /// browsing clients can pin it
+    fn publish_fingerprint_txt(&mut self) {
+        let fingerprint = self.certificate_fingerprint();
+        if let (Some(fingerprint), Some(mdns_server)) = (fingerprint, self.mdns_server.as_mut()) {
+            mdns_server.set_txt_property(TXT_FINGERPRINT, &fingerprint.to_string());
+        }
+    }
+
+    /// Emit the signal for a server event
+    fn emit_event(&mut self, event: CoreEvent) {
+        let (signal, args) = match event {
+            CoreEvent::ServerStarted { .. } => ("server_started", vec![]),
+            CoreEvent::ServerStopped => ("server_stopped", vec![]),
+            CoreEvent::ServerFailed { reason } => ("server_failed", vec![reason.to_variant()]),
+            CoreEvent::ClientConnected { peer_id, .. } => ("client_connected", vec![(peer_id as i64).to_variant()]),
+            CoreEvent::ClientDisconnected { peer_id } => ("client_disconnected", vec![(peer_id as i64).to_variant()]),
+            CoreEvent::MessageReceived { peer_id, message } => {
+                ("message_received", vec![(peer_id as i64).to_variant(), message.to_variant()])
+            }
+            CoreEvent::MdnsServiceFound { service_type, fullname } => {
+                ("mdns_service_found", vec![mdns_service_to_dictionary(&service_type, &fullname).to_variant()])
+            }
+            CoreEvent::MdnsServiceRemoved { service_type, fullname } => {
+                ("mdns_service_removed", vec![mdns_service_to_dictionary(&service_type, &fullname).to_variant()])
+            }
+            CoreEvent::MdnsFailed { reason } => ("mdns_failed", vec![reason.to_variant()]),
+            CoreEvent::JoinRejected { address, reason } => {
+                ("join_rejected", vec![address.to_variant(), reason.to_variant()])
+            }
+            CoreEvent::SeatHeld { peer_id, seat, grace_ms } => (
+                "seat_held",
+                vec![(peer_id as i64).to_variant(), (seat as i64).to_variant(), (grace_ms as i64).to_variant()],
+            ),
+            CoreEvent::SeatReleased { peer_id, seat } => {
+                ("seat_released", vec![(peer_id as i64).to_variant(), (seat as i64).to_variant()])
+            }
+            CoreEvent::PeerStale { peer_id, missed_pongs } => {
+                ("peer_stale", vec![(peer_id as i64).to_variant(), (missed_pongs as i64).to_variant()])
+            }
+            CoreEvent::PeerRecovered { peer_id, rtt_ms } => {
+                ("peer_recovered", vec![(peer_id as i64).to_variant(), (rtt_ms as i64).to_variant()])
+            }
+            CoreEvent::ConnectionRefused { address, reason } => {
+                ("connection_refused", vec![address.to_variant(), reason.to_variant()])
+            }
+            CoreEvent::PeerLimited { peer_id, reason } => {
+                ("peer_limited", vec![(peer_id as i64).to_variant(), reason.to_variant()])
+            }
+            CoreEvent::PlayerResumed { peer_id, previous_peer_id, seat } => (
+                "player_resumed",
+                vec![
+                    (peer_id as i64).to_variant(),
+                    (previous_peer_id as i64).to_variant(),
+                    seat.map_or(-1, i64::from).to_variant(),
+                ],
+            ),
+            // No signals for these yet; host events come from RustCoreClient
+            CoreEvent::Room { .. }
+            | CoreEvent::Game { .. }
+            | CoreEvent::HostConnected { .. }
+            | CoreEvent::HostDisconnected { .. }
+            | CoreEvent::HostMessage { .. }
+            | CoreEvent::HostReconnecting { .. }
+            | CoreEvent::HostConnectFailed { .. } => return,
+        };
+        self.base_mut().emit_signal(signal, &args);
+    }
+
+    /// Emit `game_event` for every public game event, numbered like the delivered messages
+    fn emit_game_events(&mut self, events: &[GameEvent]) {
+        let last_seq = self.game.as_ref().map_or(0, |game| game.state().seq());
+        let first_seq = (last_seq + 1).saturating_sub(events.len() as u64);
+        for (seq, event) in (first_seq..).zip(events) {
+            if event.audience() != Audience::Everyone {
+                continue;
+            }
+            let message = event.to_sequenced_message(seq);
+            self.base_mut().emit_signal("game_event", &[message.to_variant()]);
+        }
+    }
+
+    /// Clear the last error and return OK
+    fn succeed(&mut self) -> i32 {
+        self.last_error.clear();
+        FtErrorCode::Ok as i32
+    }
+
+    /// Run `read` on the game history, recording why it failed
+    fn read_history(&mut self, read: impl FnOnce(&GameHistory) -> Result<Option<String>, CoreError>) -> String {
+        let Some(history) = self.http_server.as_ref().and_then(|server| server.history().cloned()) else {
+            self.fail(FtErrorCode::InvalidConfig, "No data directory is set. Set data_dir in the config.");
+            return String::new();
+        };
+        match read(&history) {
+            Ok(text) => {
+                self.succeed();
+                text.unwrap_or_default()
+            }
+            Err(e) => {
+                self.fail_with(&e);
+                String::new()
+            }
+        }
+    }
+
+    /// Record an error and return its code
+    fn fail(&mut self, code: FtErrorCode, message: &str) -> i32 {
+        godot_error!("[RustCoreServer] {}", message);
+        self.last_error = message.to_string();
+        code as i32
+    }
+
+    /// Undo a partial `start()` and report which service failed
+    fn start_failed(&mut self, service: &str, error: &CoreError) -> Dictionary {
+        self.stop();
+        self.fail_with(error);
+        let mut errors = Dictionary::new();
+        errors.set(service, error.to_string());
+        errors
+    }
+
+    /// Record a `CoreError` and return its code
+    fn fail_with(&mut self, error: &CoreError) -> i32 {
+        self.fail(FtErrorCode::from(error), &error.to_string())
+    }
+}
+
+/// Convert a routed request to a GDScript dictionary
+fn request_to_dictionary(request: &HttpRequest) -> Dictionary {
+    let headers = serde_json::from_str::<HashMap<String, String>>(&request.headers).unwrap_or_default();
+    let mut header_dict = Dictionary::new();
+    for (name, value) in headers {
+        header_dict.set(name, value);
+    }
+
+    let mut dict = Dictionary::new();
+    dict.set("method", request.method.as_str());
+    dict.set("path", request.path.as_str());
+    dict.set("query", request.query.clone().unwrap_or_default());
+    dict.set("headers", header_dict);
+    dict.set("body", request.body.clone().unwrap_or_default());
+    dict
+}
+
+/// Convert a GDScript handler result to a response
+fn response_from_variant(result: &Variant) -> HttpResponse {
+    if let Ok(body) = result.try_to::<GString>() {
+        return HttpResponse::new(200, body.to_string());
+    }
+    let dict = match result.try_to::<Dictionary>() {
+        Ok(dict) => dict,
+        Err(_) => return HttpResponse::new(500, "Handler returned neither a Dictionary nor a String"),
+    };
+
+    let status_code = dict
+        .get("status_code")
+        .and_then(|v| v.try_to::<i64>().ok())
+        .unwrap_or(200) as i32;
+    let body = dict
+        .get("body")
+        .map(|v| v.stringify().to_string())
+        .unwrap_or_default();
+    let headers = match dict.get("headers").and_then(|v| v.try_to::<Dictionary>().ok()) {
+        Some(headers) => {
+            let map: HashMap<String, String> = headers
+                .iter_shared()
+                .map(|(name, value)| (name.stringify().to_string(), value.stringify().to_string()))
+                .collect();
+            serde_json::to_string(&map).unwrap_or_else(|_| "{}".to_string())
+        }
+        None => "{}".to_string(),
+    };
+
+    HttpResponse {
+        status_code,
+        headers,
+        body,
+    }
+}
+
+/// Convert a GDScript message to a WebSocket frame
+pub(crate) fn variant_to_payload(message: &Variant) -> Payload {
+    if let Ok(text) = message.try_to::<GString>() {
+        return Payload::Text(text.to_string());
+    }
+    if let Ok(bytes) = message.try_to::<PackedByteArray>() {
+        return Payload::Binary(bytes.to_vec());
+    }
+    Payload::Binary(godot::global::var_to_bytes(message).to_vec())
+}
+
+/// Describe an mDNS service for GDScript
+fn mdns_service_to_dictionary(service_type: &str, fullname: &str) -> Dictionary {
+    // "GameInstance._game._tcp.local." -> "GameInstance"
+    let name = fullname
+        .strip_suffix(service_type)
+        .map(|name| name.trim_end_matches('.'))
+        .unwrap_or(fullname);
+
+    let mut dict = Dictionary::new();
+    dict.set("service_type", service_type);
+    dict.set("fullname", fullname);
+    dict.set("name", name);
+    dict
+}
+
+/// Convert a join token to a GDScript dictionary
+fn join_token_to_dictionary(token: &JoinToken) -> Dictionary {
+    let mut addresses = PackedStringArray::new();
+    for address in &token.addresses {
+        addresses.push(&GString::from(address.to_string().as_str()));
+    }
+
+    let fingerprint = token
+        .fingerprint
+        .map(|fp| Fingerprint::from_bytes(fp).to_string())
+        .unwrap_or_default();
+
+    let mut dict = Dictionary::new();
+    dict.set("addresses", addresses);
+    dict.set("port", token.port as i64);
+    dict.set("scheme", token.scheme.as_str());
+    dict.set("url", token.url().unwrap_or_default());
+    dict.set("fingerprint", fingerprint);
+    dict
+}
+
+/// Describe a peer's round-trip statistics for GDScript
+fn latency_to_dictionary(latency: &PeerLatency) -> Dictionary {
+    let millis = |rtt: Option<std::time::Duration>| rtt.map_or(-1.0, |rtt| rtt.as_secs_f64() * 1000.0);
+
+    let mut dict = Dictionary::new();
+    dict.set("rtt_ms", millis(latency.rtt));
+    dict.set("smoothed_rtt_ms", millis(latency.smoothed_rtt));
+    dict.set("jitter_ms", latency.jitter.as_secs_f64() * 1000.0);
+    dict.set("samples", latency.samples as i64);
+    dict.set("missed_pongs", latency.missed_pongs as i64);
+    dict.set("stale", latency.stale);
+    dict
+}
+
+/// Convert seat indexes from GDScript, refusing negative ones rather than clamping them
+fn seats_from_array(seats: &PackedInt64Array) -> Result<Vec<Seat>, CoreError> {
+    seats.as_slice().iter().map(|&seat| seat_from_index(seat)).collect()
+}
+
+/// Convert a player's night information to a GDScript dictionary
+fn night_info_to_dictionary(info: &NightInfo) -> Dictionary {
+    let mut sees = VariantArray::new();
+    for (seat, sighting) in &info.sees {
+        let mut entry = Dictionary::new();
+        entry.set("seat", *seat as i64);
+        entry.set("as", sighting.name());
+        sees.push(&entry.to_variant());
+    }
+
+    let mut dict = Dictionary::new();
+    dict.set("seat", info.seat as i64);
+    dict.set("role", info.role.name());
+    dict.set("faction", info.faction().name());
+    dict.set("assassin", info.assassin);
+    dict.set("sees", sees);
+    dict
+}
+
+/// Read `player_names` (`{seat: name}`) from `start_game()` options
+///
+/// Entries that are not an integer seat and a string name are skipped;
+/// negative seats are refused rather than clamped.
+fn player_names_from_dictionary(options: &Dictionary) -> Result<Vec<(Seat, String)>, CoreError> {
+    let Some(names) = options.get("player_names").and_then(|value| value.try_to::<Dictionary>().ok()) else {
+        return Ok(Vec::new());
+    };
+    let mut result = Vec::new();
+    for (seat, name) in names.iter_shared() {
+        if let (Ok(seat), Ok(name)) = (seat.try_to::<i64>(), name.try_to::<String>()) {
+            result.push((seat_from_index(seat)?, name));
+        }
+    }
+    Ok(result)
+}
+
+/// Read optional roles from a GDScript dictionary, defaulting to [`GameSetup::new`]
+fn game_setup_from_dictionary(player_count: usize, options: &Dictionary) -> GameSetup {
+    let mut setup = GameSetup::new(player_count);
+    let flag = |key: &str, default: bool| {
+        options
+            .get(key)
+            .and_then(|value| value.try_to::<bool>().ok())
+            .unwrap_or(default)
+    };
+    setup.merlin = flag("merlin", setup.merlin);
+    setup.percival = flag("percival", setup.percival);
+    setup.morgana = flag("morgana", setup.morgana);
+    setup.mordred = flag("mordred", setup.mordred);
+    setup.oberon = flag("oberon", setup.oberon);
+    setup.lady_of_the_lake = flag("lady_of_the_lake", setup.lady_of_the_lake);
+    if flag("excalibur", setup.excalibur.is_some()) {
+        let mut rules = setup.excalibur.unwrap_or_default();
+        if let Some(targeting) = options
+            .get("excalibur_targeting")
+            .and_then(|value| value.try_to::<String>().ok())
+            .and_then(|name| ExcaliburTargeting::from_name(&name))
+        {
+            rules.targeting = targeting;
+        }
+        rules.leader_may_hold = flag("excalibur_leader_may_hold", rules.leader_may_hold);
+        setup.excalibur = Some(rules);
+    } else {
+        setup.excalibur = None;
+    }
+    if let Some(timeout_ms) = options.get("assassination_timeout_ms").and_then(|value| value.try_to::<i64>().ok()) {
+        setup.assassination.timeout = (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms as u64));
+    }
+    if let Some(winner) = options
+        .get("assassination_default_winner")
+        .and_then(|value| value.try_to::<String>().ok())
+        .and_then(|name| Faction::from_name(&name))
+    {
+        setup.assassination.default_winner = winner;
+    }
+    setup
 }
@@ -15,9 +15,17 @@ pub mod types;
 pub mod ffi;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod server;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod client;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tls;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod game;
 
 // Godot integration module (always available)
 mod godot_server;
+mod godot_config;
+mod godot_client;
 
 // Re-export commonly used types (native only)
 #[cfg(not(target_arch = "wasm32"))]
@@ -26,6 +34,8 @@ pub use error::CoreError;
 pub use types::{ServerConfig, HttpRequest, HttpResponse, SharedServerState};
 #[cfg(not(target_arch = "wasm32"))]
 pub use server::HttpServerState;
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::WebSocketClientState;
 
 use godot::prelude::*;
 
@@ -0,0 +1,256 @@
+//! UDP broadcast discovery fallback for networks that block multicast.
+//!
+//! The host runs a responder on [`DISCOVERY_PORT`] that answers probe
+//! datagrams with its encoded [`JoinToken`], and periodically broadcasts the
+//! same token as a beacon. Clients call [`discover_hosts`] to broadcast a
+//! probe and collect the replies.
+
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket as StdUdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::runtime::Runtime;
+
+use crate::error::CoreError;
+
+use super::join_token::JoinToken;
+
+/// Fixed UDP port used by the discovery responder
+pub const DISCOVERY_PORT: u16 = 38989;
+
+/// Probe datagram sent by clients looking for hosts
+pub const DISCOVERY_PROBE: &[u8] = b"FT-DISCOVER";
+
+/// Default interval between beacon broadcasts
+pub const DEFAULT_BEACON_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Largest datagram the responder will read
+const MAX_DATAGRAM: usize = 512;
+
+/// A host found through UDP discovery
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveredHost {
+    /// Address the reply came from
+    pub source: SocketAddr,
+    /// Token advertised by the host
+    pub token: JoinToken,
+}
+
+/// Discovery responder state for FFI interface
+pub struct DiscoveryServerState {
+    /// Tokio runtime for the responder tasks
+    runtime: Option<Arc<Runtime>>,
+
+    /// Responder shutdown sender
+    shutdown_tx: Option<tokio::sync::watch::Sender<()>>,
+
+    /// Token currently being advertised
+    token: Option<JoinToken>,
+}
+
+impl Default for DiscoveryServerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiscoveryServerState {
+    /// Create a new discovery responder state
+    pub fn new() -> Self {
+        Self {
+            runtime: None,
+            shutdown_tx: None,
+            token: None,
+        }
+    }
+
+    /// Check if the responder is running
+    pub fn is_running(&self) -> bool {
+        self.shutdown_tx.is_some()
+    }
+
+    /// Get the token being advertised, if running
+    pub fn token(&self) -> Option<&JoinToken> {
+        self.token.as_ref()
+    }
+
+    /// Start answering probes and broadcasting beacons
+    ///
+    /// # Arguments
+    /// * `token` - Token to advertise
+    /// * `port` - UDP port to listen on (normally [`DISCOVERY_PORT`])
+    /// * `beacon_interval` - Interval between beacons, or None to only answer probes
+    ///
+    /// # Returns
+    /// Ok(()) on success, Err(CoreError) on failure
+    pub fn start(
+        &mut self,
+        token: JoinToken,
+        port: u16,
+        beacon_interval: Option<Duration>,
+    ) -> Result<(), CoreError> {
+        if self.is_running() {
+            eprintln!("[Discovery] Start failed: responder already running");
+            return Err(CoreError::AlreadyRunning);
+        }
+
+        let encoded = token.encode()?;
+        eprintln!("[Discovery] Starting responder on UDP port {} (token={})", port, encoded);
+
+        if self.runtime.is_none() {
+            let runtime = Runtime::new()
+                .map_err(|e| {
+                    eprintln!("[Discovery] Failed to create Tokio runtime: {}", e);
+                    CoreError::RuntimeError(e.to_string())
+                })?;
+            self.runtime = Some(Arc::new(runtime));
+        }
+        let runtime = self.runtime.as_ref().unwrap().clone();
+
+        // Bind synchronously so bind errors are reported to the caller
+        let bind_addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+        let std_socket = StdUdpSocket::bind(bind_addr)
+            .map_err(|e| {
+                eprintln!("[Discovery] Failed to bind {}: {}", bind_addr, e);
+                CoreError::BindFailed(format!("{}: {}", bind_addr, e))
+            })?;
+        std_socket
+            .set_broadcast(true)
+            .and_then(|_| std_socket.set_nonblocking(true))
+            .map_err(|e| CoreError::IoError(e.to_string()))?;
+
+        let socket = {
+            let _guard = runtime.enter();
+            UdpSocket::from_std(std_socket).map_err(|e| CoreError::IoError(e.to_string()))?
+        };
+        let socket = Arc::new(socket);
+
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::watch::channel(());
+        let reply = encoded.into_bytes();
+
+        runtime.spawn(async move {
+            let broadcast_addr = SocketAddr::from((Ipv4Addr::BROADCAST, port));
+            let mut beacon = beacon_interval.map(tokio::time::interval);
+            let mut buf = [0u8; MAX_DATAGRAM];
+
+            loop {
+                tokio::select! {
+                    result = socket.recv_from(&mut buf) => {
+                        match result {
+                            Ok((len, peer)) if &buf[..len] == DISCOVERY_PROBE => {
+                                tracing::debug!("[Discovery] Probe from {}", peer);
+                                if let Err(e) = socket.send_to(&reply, peer).await {
+                                    eprintln!("[Discovery] Failed to reply to {}: {}", peer, e);
+                                }
+                            }
+                            // Our own beacons and other hosts' beacons land here too
+                            Ok(_) => {}
+                            Err(e) => {
+                                eprintln!("[Discovery] Receive error: {}", e);
+                            }
+                        }
+                    }
+                    _ = async {
+                        match beacon.as_mut() {
+                            Some(beacon) => { beacon.tick().await; }
+                            None => std::future::pending::<()>().await,
+                        }
+                    } => {
+                        if let Err(e) = socket.send_to(&reply, broadcast_addr).await {
+                            tracing::debug!("[Discovery] Beacon failed: {}", e);
+                        }
+                    }
+                    _ = shutdown_rx.changed() => {
+                        eprintln!("[Discovery] Shutdown signal received");
+                        break;
+                    }
+                }
+            }
+        });
+
+        self.shutdown_tx = Some(shutdown_tx);
+        self.token = Some(token);
+        eprintln!("[Discovery] Responder started on UDP port {}", port);
+        Ok(())
+    }
+
+    /// Stop the responder
+    pub fn stop(&mut self) {
+        match self.shutdown_tx.take() {
+            Some(tx) => {
+                let _ = tx.send(());
+                self.token = None;
+                eprintln!("[Discovery] Responder stopped");
+            }
+            None => {
+                eprintln!("[Discovery] Stop called but responder is not running");
+            }
+        }
+    }
+}
+
+impl Drop for DiscoveryServerState {
+    fn drop(&mut self) {
+        // Same as the other servers: signal shutdown and let the runtime go
+        self.shutdown_tx.take();
+        self.runtime.take();
+    }
+}
+
+/// Broadcast a probe and collect the hosts that answer within `timeout`
+///
+/// Blocks the calling thread for the whole timeout. Replies that do not
+/// decode as join tokens are ignored, and each token is reported once.
+pub fn discover_hosts(port: u16, timeout: Duration) -> Result<Vec<DiscoveredHost>, CoreError> {
+    probe(SocketAddr::from((Ipv4Addr::BROADCAST, port)), timeout)
+}
+
+/// Send a probe to a single known address (for networks that also drop broadcast)
+pub fn probe_host(addr: SocketAddr, timeout: Duration) -> Result<Vec<DiscoveredHost>, CoreError> {
+    probe(addr, timeout)
+}
+
+fn probe(target: SocketAddr, timeout: Duration) -> Result<Vec<DiscoveredHost>, CoreError> {
+    let socket = StdUdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| CoreError::BindFailed(e.to_string()))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| CoreError::IoError(e.to_string()))?;
+    socket
+        .send_to(DISCOVERY_PROBE, target)
+        .map_err(|e| CoreError::IoError(e.to_string()))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut seen = HashSet::new();
+    let mut hosts = Vec::new();
+    let mut buf = [0u8; MAX_DATAGRAM];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket
+            .set_read_timeout(Some(remaining))
+            .map_err(|e| CoreError::IoError(e.to_string()))?;
+
+        let (len, source) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => break,
+            Err(e) => return Err(CoreError::IoError(e.to_string())),
+        };
+
+        let text = match std::str::from_utf8(&buf[..len]) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        if let Ok(token) = JoinToken::decode(text) {
+            if seen.insert(text.to_string()) {
+                hosts.push(DiscoveredHost { source, token });
+            }
+        }
+    }
+
+    Ok(hosts)
+}
@@ -0,0 +1,329 @@
+//! Typed events raised by the servers and the WebSocket client.
+//!
+//! Servers and clients publish [`CoreEvent`]s on an [`EventBus`]. Hosts never get called
+//! from Tokio worker threads: each subscriber owns an [`EventQueue`] that it
+//! drains from its own thread (the FFI layer in `ft_core_poll_events`, Godot
+//! in `RustCoreServer::poll`).
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+/// Default number of events a queue holds before dropping the oldest
+pub const DEFAULT_EVENT_QUEUE_CAPACITY: usize = 1024;
+
+/// WebSocket peer identifier (0 and 1 are reserved, as in Godot's multiplayer API)
+pub type PeerId = u32;
+
+/// An event raised by the HTTP server, the peer hub, mDNS or the WebSocket client
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CoreEvent {
+    /// The HTTP(S) server is accepting connections
+    ServerStarted {
+        /// Address the listener is bound to
+        address: String,
+    },
+    /// The HTTP(S) server was stopped
+    ServerStopped,
+    /// The HTTP(S) server failed to start or stopped with an error
+    ServerFailed {
+        /// Error description
+        reason: String,
+    },
+    /// A WebSocket client connected
+    ClientConnected {
+        /// Assigned peer id
+        peer_id: PeerId,
+        /// Remote address
+        address: String,
+    },
+    /// A WebSocket client disconnected
+    ClientDisconnected {
+        /// Peer id of the client
+        peer_id: PeerId,
+    },
+    /// A text message arrived from a WebSocket client
+    MessageReceived {
+        /// Sender
+        peer_id: PeerId,
+        /// Message text
+        message: String,
+    },
+    /// A room changed (seat taken, player ready, ...)
+    Room {
+        /// Event name
+        name: String,
+        /// Event payload
+        data: serde_json::Value,
+    },
+    /// A game changed (phase, vote result, ...)
+    Game {
+        /// Event name
+        name: String,
+        /// Event payload
+        data: serde_json::Value,
+    },
+    /// An mDNS service appeared on the network
+    MdnsServiceFound {
+        /// Service type (e.g. "_game._tcp.local.")
+        service_type: String,
+        /// Full service name
+        fullname: String,
+    },
+    /// An mDNS service left the network
+    MdnsServiceRemoved {
+        /// Service type
+        service_type: String,
+        /// Full service name
+        fullname: String,
+    },
+    /// The mDNS daemon failed
+    MdnsFailed {
+        /// Error description
+        reason: String,
+    },
+    /// The WebSocket client connected to its host
+    HostConnected {
+        /// URL of the host
+        url: String,
+    },
+    /// The WebSocket client lost or closed its connection to the host
+    HostDisconnected {
+        /// WebSocket close code (1006 if the connection dropped)
+        code: u16,
+        /// Close reason
+        reason: String,
+    },
+    /// A text message arrived from the host
+    HostMessage {
+        /// Message text
+        message: String,
+    },
+    /// The WebSocket client will try to reconnect after a delay
+    HostReconnecting {
+        /// Reconnect attempt, starting at 1
+        attempt: u32,
+        /// Delay before the attempt
+        delay_ms: u64,
+    },
+    /// The WebSocket client gave up connecting to the host
+    HostConnectFailed {
+        /// Error description
+        reason: String,
+    },
+    /// A WebSocket join attempt was refused by the room access check
+    JoinRejected {
+        /// Remote address
+        address: String,
+        /// Reason code ("invalid_code", "wrong_password" or "locked_out")
+        reason: String,
+    },
+    /// A seated player disconnected; their seat is held for the grace period
+    SeatHeld {
+        /// Peer id of the disconnected player
+        peer_id: PeerId,
+        /// Held seat
+        seat: u32,
+        /// How long the seat is held
+        grace_ms: u64,
+    },
+    /// A held seat was freed because its player did not come back in time
+    SeatReleased {
+        /// Last peer id of the player
+        peer_id: PeerId,
+        /// Freed seat
+        seat: u32,
+    },
+    /// A player reconnected with their session token
+    PlayerResumed {
+        /// New peer id
+        peer_id: PeerId,
+        /// Peer id the player had before
+        previous_peer_id: PeerId,
+        /// Seat taken back, if the player was seated
+        seat: Option<u32>,
+    },
+    /// A peer stopped answering pings and may be gone
+    PeerStale {
+        /// Peer id
+        peer_id: PeerId,
+        /// Pings missed in a row
+        missed_pongs: u32,
+    },
+    /// A stale peer answered a ping again
+    PeerRecovered {
+        /// Peer id
+        peer_id: PeerId,
+        /// Round-trip time of the answered ping
+        rtt_ms: u64,
+    },
+    /// A WebSocket connection was refused by the connection caps
+    ConnectionRefused {
+        /// Remote address
+        address: String,
+        /// Reason code ("server_full", "too_many_connections" or "not_accepting")
+        reason: String,
+    },
+    /// A peer was disconnected for exceeding its rate or size limits
+    PeerLimited {
+        /// Peer id
+        peer_id: PeerId,
+        /// Reason code ("rate_limited" or "too_large")
+        reason: String,
+    },
+}
+
+impl CoreEvent {
+    /// Stable numeric code used across the C interface (`FT_EVENT_*`)
+    pub fn code(&self) -> i32 {
+        use crate::ffi::events::*;
+        match self {
+            CoreEvent::ServerStarted { .. } => FT_EVENT_SERVER_STARTED,
+            CoreEvent::ServerStopped => FT_EVENT_SERVER_STOPPED,
+            CoreEvent::ServerFailed { .. } => FT_EVENT_SERVER_FAILED,
+            CoreEvent::ClientConnected { .. } => FT_EVENT_CLIENT_CONNECTED,
+            CoreEvent::ClientDisconnected { .. } => FT_EVENT_CLIENT_DISCONNECTED,
+            CoreEvent::MessageReceived { .. } => FT_EVENT_MESSAGE_RECEIVED,
+            CoreEvent::Room { .. } => FT_EVENT_ROOM,
+            CoreEvent::Game { .. } => FT_EVENT_GAME,
+            CoreEvent::MdnsServiceFound { .. } => FT_EVENT_MDNS_SERVICE_FOUND,
+            CoreEvent::MdnsServiceRemoved { .. } => FT_EVENT_MDNS_SERVICE_REMOVED,
+            CoreEvent::MdnsFailed { .. } => FT_EVENT_MDNS_FAILED,
+            CoreEvent::HostConnected { .. } => FT_EVENT_HOST_CONNECTED,
+            CoreEvent::HostDisconnected { .. } => FT_EVENT_HOST_DISCONNECTED,
+            CoreEvent::HostMessage { .. } => FT_EVENT_HOST_MESSAGE,
+            CoreEvent::HostReconnecting { .. } => FT_EVENT_HOST_RECONNECTING,
+            CoreEvent::HostConnectFailed { .. } => FT_EVENT_HOST_CONNECT_FAILED,
+            CoreEvent::JoinRejected { .. } => FT_EVENT_JOIN_REJECTED,
+            CoreEvent::SeatHeld { .. } => FT_EVENT_SEAT_HELD,
+            CoreEvent::SeatReleased { .. } => FT_EVENT_SEAT_RELEASED,
+            CoreEvent::PlayerResumed { .. } => FT_EVENT_PLAYER_RESUMED,
+            CoreEvent::PeerStale { .. } => FT_EVENT_PEER_STALE,
+            CoreEvent::PeerRecovered { .. } => FT_EVENT_PEER_RECOVERED,
+            CoreEvent::ConnectionRefused { .. } => FT_EVENT_CONNECTION_REFUSED,
+            CoreEvent::PeerLimited { .. } => FT_EVENT_PEER_LIMITED,
+        }
+    }
+
+    /// Serialize as JSON (`{"type": "client_connected", "peer_id": 2, ...}`)
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+struct QueueInner {
+    events: VecDeque<CoreEvent>,
+    capacity: usize,
+    dropped: u64,
+}
+
+/// Bounded queue of events waiting to be drained by one subscriber
+///
+/// When full, the oldest event is dropped so a host that stops polling
+/// cannot grow memory without bound.
+#[derive(Clone)]
+pub struct EventQueue {
+    inner: Arc<Mutex<QueueInner>>,
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_QUEUE_CAPACITY)
+    }
+}
+
+impl EventQueue {
+    /// Create a queue holding at most `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(QueueInner {
+                events: VecDeque::new(),
+                capacity: capacity.max(1),
+                dropped: 0,
+            })),
+        }
+    }
+
+    /// Append an event, dropping the oldest one if the queue is full
+    pub fn push(&self, event: CoreEvent) {
+        let mut inner = self.inner.lock();
+        if inner.events.len() >= inner.capacity {
+            inner.events.pop_front();
+            inner.dropped += 1;
+        }
+        inner.events.push_back(event);
+    }
+
+    /// Remove and return the oldest event
+    pub fn pop(&self) -> Option<CoreEvent> {
+        self.inner.lock().events.pop_front()
+    }
+
+    /// Remove and return all queued events, oldest first
+    pub fn drain(&self) -> Vec<CoreEvent> {
+        self.inner.lock().events.drain(..).collect()
+    }
+
+    /// Number of queued events
+    pub fn len(&self) -> usize {
+        self.inner.lock().events.len()
+    }
+
+    /// Check if no events are queued
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().events.is_empty()
+    }
+
+    /// Number of events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.inner.lock().dropped
+    }
+
+    fn same_queue(&self, other: &EventQueue) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+/// Fan-out of events to every attached queue
+#[derive(Clone, Default)]
+pub struct EventBus {
+    queues: Arc<Mutex<Vec<EventQueue>>>,
+}
+
+impl EventBus {
+    /// Create a bus with no subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new queue that receives every future event
+    pub fn subscribe(&self) -> EventQueue {
+        let queue = EventQueue::default();
+        self.attach(queue.clone());
+        queue
+    }
+
+    /// Deliver future events to an existing queue (shared between buses)
+    pub fn attach(&self, queue: EventQueue) {
+        let mut queues = self.queues.lock();
+        if !queues.iter().any(|q| q.same_queue(&queue)) {
+            queues.push(queue);
+        }
+    }
+
+    /// Stop delivering events to a queue
+    pub fn detach(&self, queue: &EventQueue) {
+        self.queues.lock().retain(|q| !q.same_queue(queue));
+    }
+
+    /// Publish an event to every attached queue
+    pub fn emit(&self, event: CoreEvent) {
+        tracing::debug!("[Events] {:?}", event);
+        for queue in self.queues.lock().iter() {
+            queue.push(event.clone());
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Host-side route handlers.
+//!
+//! Hosts (Swift through FFI, GDScript through `RustCoreServer`) register a
+//! handler for a path prefix such as `/api/room`. Requests under that prefix
+//! are converted to [`HttpRequest`], passed to the handler on a blocking
+//! worker thread, and the returned [`HttpResponse`] is sent to the client.
+//! Paths without a handler fall through to the built-in routes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use parking_lot::RwLock;
+
+use crate::error::CoreError;
+use crate::types::{HttpRequest, HttpResponse};
+
+use super::limits::RequestBodyLimit;
+
+/// Largest request body passed to a host handler
+pub const MAX_HANDLER_BODY_BYTES: usize = 1024 * 1024;
+
+/// A host handler for one path prefix
+pub type RouteHandler = Arc<dyn Fn(HttpRequest) -> HttpResponse + Send + Sync>;
+
+/// Registered host handlers, shared between the server and its router
+///
+/// Handlers can be added or removed while the server is running.
+#[derive(Clone, Default)]
+pub struct RouteRegistry {
+    routes: Arc<RwLock<Vec<(String, RouteHandler)>>>,
+}
+
+impl RouteRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for a path prefix, replacing any existing one
+    ///
+    /// The prefix must start with '/' and matches whole path segments, so
+    /// `/api/room` handles `/api/room` and `/api/room/3` but not `/api/rooms`.
+    pub fn register(&self, prefix: &str, handler: RouteHandler) -> Result<(), CoreError> {
+        let prefix = normalize_prefix(prefix)?;
+        let mut routes = self.routes.write();
+        routes.retain(|(existing, _)| *existing != prefix);
+        routes.push((prefix, handler));
+        Ok(())
+    }
+
+    /// Remove the handler for a prefix. Returns true if one was registered.
+    pub fn unregister(&self, prefix: &str) -> bool {
+        let prefix = match normalize_prefix(prefix) {
+            Ok(prefix) => prefix,
+            Err(_) => return false,
+        };
+        let mut routes = self.routes.write();
+        let before = routes.len();
+        routes.retain(|(existing, _)| *existing != prefix);
+        routes.len() != before
+    }
+
+    /// Registered prefixes
+    pub fn prefixes(&self) -> Vec<String> {
+        self.routes.read().iter().map(|(prefix, _)| prefix.clone()).collect()
+    }
+
+    /// Find the handler with the longest prefix matching `path`
+    pub fn find(&self, path: &str) -> Option<RouteHandler> {
+        self.routes
+            .read()
+            .iter()
+            .filter(|(prefix, _)| prefix_matches(prefix, path))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, handler)| handler.clone())
+    }
+
+    /// Run the matching handler, or None if no prefix matches
+    pub fn dispatch(&self, request: HttpRequest) -> Option<HttpResponse> {
+        let handler = self.find(&request.path)?;
+        Some(handler(request))
+    }
+}
+
+/// Check and normalize a prefix ("/api/room/" -> "/api/room")
+fn normalize_prefix(prefix: &str) -> Result<String, CoreError> {
+    if !prefix.starts_with('/') {
+        return Err(CoreError::InvalidRoute(format!("prefix '{}' must start with '/'", prefix)));
+    }
+    let trimmed = prefix.trim_end_matches('/');
+    Ok(if trimmed.is_empty() { "/".to_string() } else { trimmed.to_string() })
+}
+
+/// Segment-aware prefix match
+fn prefix_matches(prefix: &str, path: &str) -> bool {
+    if prefix == "/" {
+        return true;
+    }
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Serialize request headers as a JSON object (`{"name": "value"}`)
+pub fn headers_to_json(headers: &HeaderMap) -> String {
+    let map: HashMap<&str, &str> = headers
+        .iter()
+        .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.as_str(), value)))
+        .collect();
+    serde_json::to_string(&map).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Parse a JSON header object from a handler response
+fn headers_from_json(headers: &str) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    if headers.trim().is_empty() {
+        return map;
+    }
+    match serde_json::from_str::<HashMap<String, String>>(headers) {
+        Ok(parsed) => {
+            for (name, value) in parsed {
+                match (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+                    (Ok(name), Ok(value)) => {
+                        map.insert(name, value);
+                    }
+                    _ => eprintln!("[ROUTER] Ignoring invalid response header: {}", name),
+                }
+            }
+        }
+        Err(e) => eprintln!("[ROUTER] Ignoring unparsable response headers: {}", e),
+    }
+    map
+}
+
+/// Convert a handler response to an axum response
+pub fn into_axum_response(response: HttpResponse) -> Response {
+    let status = u16::try_from(response.status_code)
+        .ok()
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    let mut builder = Response::builder().status(status);
+    if let Some(headers) = builder.headers_mut() {
+        headers.extend(headers_from_json(&response.headers));
+    }
+    builder
+        .body(Body::from(response.body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Middleware that sends requests under a registered prefix to the host handler
+pub async fn dispatch_host_routes(
+    State(routes): State<RouteRegistry>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let handler = match routes.find(request.uri().path()) {
+        Some(handler) => handler,
+        None => return next.run(request).await,
+    };
+
+    let limit = request
+        .extensions()
+        .get::<RequestBodyLimit>()
+        .map_or(MAX_HANDLER_BODY_BYTES, |limit| limit.0.min(MAX_HANDLER_BODY_BYTES));
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, limit).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("[ROUTER] Failed to read request body: {}", e);
+            return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+        }
+    };
+
+    let http_request = HttpRequest {
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(|q| q.to_string()),
+        headers: headers_to_json(&parts.headers),
+        body: if bytes.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        },
+    };
+
+    // Host handlers may block (e.g. waiting for the Godot main thread)
+    match tokio::task::spawn_blocking(move || handler(http_request)).await {
+        Ok(response) => into_axum_response(response),
+        Err(e) => {
+            eprintln!("[ROUTER] Host handler panicked: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
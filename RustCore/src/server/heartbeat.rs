@@ -0,0 +1,146 @@
+//! WebSocket heartbeats and latency measurement.
+//!
+//! Every peer is sent a protocol-level ping each [`HeartbeatConfig::interval`].
+//! The ping payload carries a sequence number so the matching pong gives a
+//! round-trip time. A ping that is still unanswered when the next one is due
+//! counts as missed: after [`HeartbeatConfig::stale_after`] misses in a row
+//! the peer is reported stale, and after [`HeartbeatConfig::disconnect_after`]
+//! it is disconnected, which cleans up half-open TCP connections.
+
+use std::time::{Duration, Instant};
+
+/// Default time between pings
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default missed pongs before a peer is reported stale
+pub const DEFAULT_STALE_AFTER: u32 = 2;
+
+/// Default missed pongs before a peer is disconnected
+pub const DEFAULT_DISCONNECT_AFTER: u32 = 4;
+
+/// Close code sent to a peer that stopped answering pings (application-defined range)
+pub const CLOSE_TIMED_OUT: u16 = 4002;
+
+/// How often peers are pinged and when they count as stale or gone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeartbeatConfig {
+    /// Time between pings; zero disables heartbeats
+    pub interval: Duration,
+    /// Missed pongs in a row before the peer is reported stale
+    pub stale_after: u32,
+    /// Missed pongs in a row before the peer is disconnected (0 never disconnects)
+    pub disconnect_after: u32,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_PING_INTERVAL,
+            stale_after: DEFAULT_STALE_AFTER,
+            disconnect_after: DEFAULT_DISCONNECT_AFTER,
+        }
+    }
+}
+
+/// Round-trip statistics for one peer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeerLatency {
+    /// Most recent round-trip time, once a pong has arrived
+    pub rtt: Option<Duration>,
+    /// Smoothed round-trip time (moving average weighted 1/8 towards new samples)
+    pub smoothed_rtt: Option<Duration>,
+    /// Mean deviation between consecutive round trips (RFC 3550 style)
+    pub jitter: Duration,
+    /// Pongs received
+    pub samples: u64,
+    /// Pings missed in a row
+    pub missed_pongs: u32,
+    /// Whether the peer has missed enough pongs to be considered stale
+    pub stale: bool,
+}
+
+/// What the writer should do when a ping is due
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Tick {
+    /// Send a ping with this payload
+    Ping(Vec<u8>),
+    /// Send a ping; the peer has just become stale after this many misses
+    PingStale(Vec<u8>, u32),
+    /// The peer missed too many pongs and must be disconnected
+    TimedOut(u32),
+}
+
+/// Outstanding ping and latency statistics of one peer
+#[derive(Default)]
+pub(crate) struct HeartbeatState {
+    next_sequence: u64,
+    pending: Option<(u64, Instant)>,
+    latency: PeerLatency,
+}
+
+impl HeartbeatState {
+    /// Current statistics
+    pub fn latency(&self) -> PeerLatency {
+        self.latency
+    }
+
+    /// Account for an unanswered ping and prepare the next one
+    pub fn tick(&mut self, config: &HeartbeatConfig, now: Instant) -> Tick {
+        let mut became_stale = false;
+        if self.pending.is_some() {
+            self.latency.missed_pongs += 1;
+            let missed = self.latency.missed_pongs;
+            if config.disconnect_after > 0 && missed >= config.disconnect_after {
+                return Tick::TimedOut(missed);
+            }
+            if config.stale_after > 0 && missed >= config.stale_after && !self.latency.stale {
+                self.latency.stale = true;
+                became_stale = true;
+            }
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.pending = Some((sequence, now));
+        let payload = sequence.to_be_bytes().to_vec();
+        if became_stale {
+            Tick::PingStale(payload, self.latency.missed_pongs)
+        } else {
+            Tick::Ping(payload)
+        }
+    }
+
+    /// Record a pong; pongs for anything but the latest ping are ignored
+    ///
+    /// # Returns
+    /// true if the peer was stale and has recovered
+    pub fn pong(&mut self, payload: &[u8], now: Instant) -> bool {
+        let Some((sequence, sent)) = self.pending else {
+            return false;
+        };
+        if payload != sequence.to_be_bytes() {
+            return false;
+        }
+        self.pending = None;
+
+        let rtt = now.saturating_duration_since(sent);
+        let latency = &mut self.latency;
+        if let Some(previous) = latency.rtt {
+            let deviation = rtt.abs_diff(previous);
+            latency.jitter = if deviation > latency.jitter {
+                latency.jitter + (deviation - latency.jitter) / 16
+            } else {
+                latency.jitter - (latency.jitter - deviation) / 16
+            };
+        }
+        latency.smoothed_rtt = Some(match latency.smoothed_rtt {
+            Some(smoothed) if smoothed > rtt => smoothed - (smoothed - rtt) / 8,
+            Some(smoothed) => smoothed + (rtt - smoothed) / 8,
+            None => rtt,
+        });
+        latency.rtt = Some(rtt);
+        latency.samples += 1;
+        latency.missed_pongs = 0;
+        std::mem::take(&mut latency.stale)
+    }
+}
@@ -0,0 +1,400 @@
+//! Host configuration shared by the engine front ends.
+//!
+//! [`HostConfig`] gathers everything needed to start hosting (HTTP(S)
+//! listener, response headers, mDNS advertisement, discovery, room access,
+//! reconnects, heartbeats, traffic limits and room limits). [`HostConfig::validate`] reports every problem at once, keyed by
+//! field name, so editors can highlight the offending fields.
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+
+use super::handlers::MAX_HANDLER_BODY_BYTES;
+use super::heartbeat::{HeartbeatConfig, DEFAULT_DISCONNECT_AFTER, DEFAULT_PING_INTERVAL, DEFAULT_STALE_AFTER};
+use super::limits::{Limits, RateLimit};
+use super::room_access::{RoomAccess, DEFAULT_LOCKOUT, DEFAULT_MAX_FAILURES};
+use super::router::HeaderPolicy;
+use super::sessions::{SessionRegistry, DEFAULT_RECONNECT_GRACE};
+
+/// Fewest players an Avalon game supports
+pub const MIN_PLAYERS: i64 = 5;
+
+/// Most players an Avalon game supports
+pub const MAX_PLAYERS: i64 = 10;
+
+/// Longest accepted room password, in characters
+pub const MAX_ROOM_PASSWORD_LENGTH: usize = 64;
+
+/// Smallest accepted frame, message and request body limit, in bytes
+pub const MIN_SIZE_LIMIT_BYTES: i64 = 1024;
+
+/// A validation problem with one configuration field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// Field name (matches the `HostConfig` field)
+    pub field: &'static str,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Everything needed to start hosting a game
+///
+/// Integer fields are `i64` so that values coming from GDScript can be
+/// range-checked instead of silently truncated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostConfig {
+    /// IP address to listen on ("0.0.0.0" for every interface)
+    pub bind_address: String,
+    /// TCP port (0 picks a free port)
+    pub port: i64,
+    /// Directory served as static files
+    pub static_dir: String,
+    /// Directory finished games are saved in (created if missing); empty to keep no history
+    pub data_dir: String,
+    /// Serve HTTPS instead of HTTP
+    pub use_https: bool,
+    /// Certificate chain (PEM); empty for a generated self-signed certificate
+    pub cert_path: String,
+    /// Private key (PEM); required when `cert_path` is set
+    pub key_path: String,
+    /// Send the cross-origin isolation headers
+    pub cross_origin_isolation: bool,
+    /// `Access-Control-Allow-Origin` value; empty to omit it
+    pub cors_allow_origin: String,
+    /// Advertise the server over mDNS
+    pub mdns_enabled: bool,
+    /// mDNS service type (e.g. "_game._tcp.local.")
+    pub mdns_service_type: String,
+    /// mDNS instance name shown to players
+    pub mdns_instance_name: String,
+    /// mDNS hostname (without ".local.")
+    pub mdns_hostname: String,
+    /// Answer UDP discovery probes for networks without multicast
+    pub discovery_enabled: bool,
+    /// Fewest players needed to start a game
+    pub min_players: i64,
+    /// Most players a room accepts
+    pub max_players: i64,
+    /// Require players to enter the room code shown on the host
+    pub require_room_code: bool,
+    /// Room password; empty for none
+    pub room_password: String,
+    /// Failed join attempts from one address before it is locked out (0 disables lockouts)
+    pub max_join_failures: i64,
+    /// How long a locked-out address must wait, in seconds
+    pub lockout_seconds: i64,
+    /// How long a disconnected player's seat is held for them, in seconds (0 frees it at once)
+    pub reconnect_grace_seconds: i64,
+    /// Time between WebSocket pings, in milliseconds (0 disables heartbeats)
+    pub ping_interval_ms: i64,
+    /// Missed pongs in a row before a peer is reported stale
+    pub stale_after_missed_pongs: i64,
+    /// Missed pongs in a row before a peer is disconnected (0 never disconnects)
+    pub disconnect_after_missed_pongs: i64,
+    /// Most WebSocket connections at once (0 for no limit)
+    pub max_connections: i64,
+    /// Most WebSocket connections from one address (0 for no limit)
+    pub max_connections_per_address: i64,
+    /// Largest WebSocket frame, in bytes
+    pub max_frame_bytes: i64,
+    /// Largest WebSocket message, in bytes
+    pub max_message_bytes: i64,
+    /// Largest HTTP request body, in bytes
+    pub max_request_body_bytes: i64,
+    /// Messages per second one connection may send (0 for no limit)
+    pub messages_per_second: i64,
+    /// Messages one connection may send in a burst
+    pub message_burst: i64,
+    /// Messages per second all connections from one address may send (0 for no limit)
+    pub address_messages_per_second: i64,
+    /// Messages all connections from one address may send in a burst
+    pub address_message_burst: i64,
+    /// HTTP requests per second one address may make (0 for no limit)
+    pub requests_per_second: i64,
+    /// HTTP requests one address may make in a burst
+    pub request_burst: i64,
+    /// Rate-limited messages before a connection is closed (0 never closes)
+    pub max_rate_violations: i64,
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        let limits = Limits::default();
+        Self {
+            bind_address: "0.0.0.0".to_string(),
+            port: 8089,
+            static_dir: String::new(),
+            data_dir: String::new(),
+            use_https: true,
+            cert_path: String::new(),
+            key_path: String::new(),
+            cross_origin_isolation: true,
+            cors_allow_origin: String::new(),
+            mdns_enabled: true,
+            mdns_service_type: "_game._tcp.local.".to_string(),
+            mdns_instance_name: "GameInstance".to_string(),
+            mdns_hostname: "facingtime".to_string(),
+            discovery_enabled: false,
+            min_players: MIN_PLAYERS,
+            max_players: MAX_PLAYERS,
+            require_room_code: true,
+            room_password: String::new(),
+            max_join_failures: i64::from(DEFAULT_MAX_FAILURES),
+            lockout_seconds: DEFAULT_LOCKOUT.as_secs() as i64,
+            reconnect_grace_seconds: DEFAULT_RECONNECT_GRACE.as_secs() as i64,
+            ping_interval_ms: DEFAULT_PING_INTERVAL.as_millis() as i64,
+            stale_after_missed_pongs: i64::from(DEFAULT_STALE_AFTER),
+            disconnect_after_missed_pongs: i64::from(DEFAULT_DISCONNECT_AFTER),
+            max_connections: limits.max_connections as i64,
+            max_connections_per_address: limits.max_connections_per_address as i64,
+            max_frame_bytes: limits.max_frame_bytes as i64,
+            max_message_bytes: limits.max_message_bytes as i64,
+            max_request_body_bytes: limits.max_request_body_bytes as i64,
+            messages_per_second: i64::from(limits.connection_message_rate.per_second),
+            message_burst: i64::from(limits.connection_message_rate.burst),
+            address_messages_per_second: i64::from(limits.address_message_rate.per_second),
+            address_message_burst: i64::from(limits.address_message_rate.burst),
+            requests_per_second: i64::from(limits.address_request_rate.per_second),
+            request_burst: i64::from(limits.address_request_rate.burst),
+            max_rate_violations: i64::from(limits.max_violations),
+        }
+    }
+}
+
+impl HostConfig {
+    /// Check every field, returning all problems found (empty if valid)
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        if self.bind_address.parse::<IpAddr>().is_err() {
+            errors.push(FieldError::new("bind_address", format!("'{}' is not an IP address", self.bind_address)));
+        }
+        if !(0..=i64::from(u16::MAX)).contains(&self.port) {
+            errors.push(FieldError::new("port", "must be between 0 and 65535"));
+        }
+
+        if self.static_dir.is_empty() {
+            errors.push(FieldError::new("static_dir", "is required"));
+        } else if !Path::new(&self.static_dir).is_dir() {
+            errors.push(FieldError::new("static_dir", format!("'{}' is not a directory", self.static_dir)));
+        }
+        let data_dir = Path::new(&self.data_dir);
+        if !self.data_dir.is_empty() && data_dir.exists() && !data_dir.is_dir() {
+            errors.push(FieldError::new("data_dir", format!("'{}' is not a directory", self.data_dir)));
+        }
+
+        if self.use_https {
+            match (self.cert_path.is_empty(), self.key_path.is_empty()) {
+                (true, true) => {}
+                (false, true) => errors.push(FieldError::new("key_path", "is required when cert_path is set")),
+                (true, false) => errors.push(FieldError::new("cert_path", "is required when key_path is set")),
+                (false, false) => {
+                    for (field, path) in [("cert_path", &self.cert_path), ("key_path", &self.key_path)] {
+                        if !Path::new(path).is_file() {
+                            errors.push(FieldError::new(field, format!("'{}' does not exist", path)));
+                        }
+                    }
+                }
+            }
+        }
+
+        let origin = self.cors_allow_origin.as_str();
+        if !(origin.is_empty()
+            || origin == "*"
+            || origin.starts_with("http://")
+            || origin.starts_with("https://"))
+        {
+            errors.push(FieldError::new("cors_allow_origin", "must be empty, '*' or an http(s) origin"));
+        }
+
+        if self.mdns_enabled {
+            let service_type = self.mdns_service_type.as_str();
+            if !(service_type.starts_with('_')
+                && (service_type.ends_with("._tcp.local.") || service_type.ends_with("._udp.local.")))
+            {
+                errors.push(FieldError::new(
+                    "mdns_service_type",
+                    "must look like '_name._tcp.local.' or '_name._udp.local.'",
+                ));
+            }
+            if self.mdns_instance_name.trim().is_empty() {
+                errors.push(FieldError::new("mdns_instance_name", "is required when mDNS is enabled"));
+            }
+            let hostname = self.mdns_hostname.as_str();
+            if hostname.is_empty()
+                || hostname.starts_with('-')
+                || !hostname.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            {
+                errors.push(FieldError::new("mdns_hostname", "must be letters, digits and '-' only"));
+            }
+        }
+
+        let player_range = MIN_PLAYERS..=MAX_PLAYERS;
+        if !player_range.contains(&self.min_players) {
+            errors.push(FieldError::new(
+                "min_players",
+                format!("must be between {} and {}", MIN_PLAYERS, MAX_PLAYERS),
+            ));
+        }
+        if !player_range.contains(&self.max_players) {
+            errors.push(FieldError::new(
+                "max_players",
+                format!("must be between {} and {}", MIN_PLAYERS, MAX_PLAYERS),
+            ));
+        } else if self.max_players < self.min_players {
+            errors.push(FieldError::new("max_players", "must not be less than min_players"));
+        }
+
+        if self.room_password.chars().count() > MAX_ROOM_PASSWORD_LENGTH {
+            errors.push(FieldError::new(
+                "room_password",
+                format!("must be at most {} characters", MAX_ROOM_PASSWORD_LENGTH),
+            ));
+        }
+        if self.max_join_failures < 0 {
+            errors.push(FieldError::new("max_join_failures", "must not be negative"));
+        }
+        if self.lockout_seconds < 0 {
+            errors.push(FieldError::new("lockout_seconds", "must not be negative"));
+        }
+        if self.reconnect_grace_seconds < 0 {
+            errors.push(FieldError::new("reconnect_grace_seconds", "must not be negative"));
+        }
+        if self.ping_interval_ms < 0 {
+            errors.push(FieldError::new("ping_interval_ms", "must not be negative"));
+        }
+        if !(1..=i64::from(u32::MAX)).contains(&self.stale_after_missed_pongs) {
+            errors.push(FieldError::new("stale_after_missed_pongs", "must be at least 1"));
+        }
+        if !(0..=i64::from(u32::MAX)).contains(&self.disconnect_after_missed_pongs) {
+            errors.push(FieldError::new("disconnect_after_missed_pongs", "must not be negative"));
+        } else if self.disconnect_after_missed_pongs != 0
+            && self.disconnect_after_missed_pongs < self.stale_after_missed_pongs
+        {
+            errors.push(FieldError::new(
+                "disconnect_after_missed_pongs",
+                "must not be less than stale_after_missed_pongs",
+            ));
+        }
+
+        for (field, value) in [
+            ("max_connections", self.max_connections),
+            ("max_connections_per_address", self.max_connections_per_address),
+            ("messages_per_second", self.messages_per_second),
+            ("message_burst", self.message_burst),
+            ("address_messages_per_second", self.address_messages_per_second),
+            ("address_message_burst", self.address_message_burst),
+            ("requests_per_second", self.requests_per_second),
+            ("request_burst", self.request_burst),
+            ("max_rate_violations", self.max_rate_violations),
+        ] {
+            if !(0..=i64::from(u32::MAX)).contains(&value) {
+                errors.push(FieldError::new(field, "must be between 0 and 4294967295"));
+            }
+        }
+        for (field, value, max) in [
+            ("max_frame_bytes", self.max_frame_bytes, i64::from(u32::MAX)),
+            ("max_message_bytes", self.max_message_bytes, i64::from(u32::MAX)),
+            ("max_request_body_bytes", self.max_request_body_bytes, MAX_HANDLER_BODY_BYTES as i64),
+        ] {
+            if !(MIN_SIZE_LIMIT_BYTES..=max).contains(&value) {
+                errors.push(FieldError::new(
+                    field,
+                    format!("must be between {} and {}", MIN_SIZE_LIMIT_BYTES, max),
+                ));
+            }
+        }
+
+        errors
+    }
+
+    /// Socket address to bind (only meaningful once validated)
+    pub fn socket_address(&self) -> String {
+        match self.bind_address.parse::<IpAddr>() {
+            Ok(ip) => SocketAddr::new(ip, self.port as u16).to_string(),
+            Err(_) => format!("{}:{}", self.bind_address, self.port),
+        }
+    }
+
+    /// Certificate and key paths, if a certificate was configured
+    pub fn certificate(&self) -> Option<(&str, &str)> {
+        if self.cert_path.is_empty() || self.key_path.is_empty() {
+            None
+        } else {
+            Some((&self.cert_path, &self.key_path))
+        }
+    }
+
+    /// Headers added to every response
+    pub fn header_policy(&self) -> HeaderPolicy {
+        HeaderPolicy {
+            cross_origin_isolation: self.cross_origin_isolation,
+            cors_allow_origin: if self.cors_allow_origin.is_empty() {
+                None
+            } else {
+                Some(self.cors_allow_origin.clone())
+            },
+        }
+    }
+
+    /// Apply the room code, password and lockout settings
+    ///
+    /// Call on a validated configuration; out-of-range numbers are clamped.
+    pub fn configure_access(&self, access: &RoomAccess) {
+        access.set_code_required(self.require_room_code);
+        access.set_password(Some(&self.room_password));
+        access.set_lockout_policy(
+            self.max_join_failures.clamp(0, i64::from(u32::MAX)) as u32,
+            Duration::from_secs(self.lockout_seconds.max(0) as u64),
+        );
+    }
+
+    /// Apply the reconnect grace period
+    pub fn configure_sessions(&self, sessions: &SessionRegistry) {
+        sessions.set_grace_period(Duration::from_secs(self.reconnect_grace_seconds.max(0) as u64));
+    }
+
+    /// Connection caps, size limits and rate limits (out-of-range numbers are clamped)
+    pub fn limits(&self) -> Limits {
+        let count = |value: i64| value.clamp(0, i64::from(u32::MAX)) as u32;
+        let size = |value: i64| value.clamp(MIN_SIZE_LIMIT_BYTES, i64::from(u32::MAX)) as usize;
+        Limits {
+            max_connections: count(self.max_connections) as usize,
+            max_connections_per_address: count(self.max_connections_per_address) as usize,
+            max_frame_bytes: size(self.max_frame_bytes),
+            max_message_bytes: size(self.max_message_bytes),
+            max_request_body_bytes: size(self.max_request_body_bytes),
+            connection_message_rate: RateLimit::new(count(self.messages_per_second), count(self.message_burst)),
+            address_message_rate: RateLimit::new(
+                count(self.address_messages_per_second),
+                count(self.address_message_burst),
+            ),
+            address_request_rate: RateLimit::new(count(self.requests_per_second), count(self.request_burst)),
+            max_violations: count(self.max_rate_violations),
+        }
+    }
+
+    /// Ping settings for the WebSocket peers (out-of-range numbers are clamped)
+    pub fn heartbeat_config(&self) -> HeartbeatConfig {
+        HeartbeatConfig {
+            interval: Duration::from_millis(self.ping_interval_ms.max(0) as u64),
+            stale_after: self.stale_after_missed_pongs.clamp(0, i64::from(u32::MAX)) as u32,
+            disconnect_after: self.disconnect_after_missed_pongs.clamp(0, i64::from(u32::MAX)) as u32,
+        }
+    }
+}
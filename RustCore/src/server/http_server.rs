@@ -6,13 +6,19 @@ use std::sync::Arc;
 use tokio::runtime::Runtime;
 use tokio_rustls::TlsAcceptor;
 use rustls::ServerConfig as RustlsServerConfig;
-use rustls_pemfile::{certs, pkcs8_private_keys};
+use rustls_pemfile::{certs, private_key};
 
 use crate::error::CoreError;
-use crate::types::{ServerConfig, ServerState, SharedServerState};
+use crate::game::history::GameHistory;
+use crate::tls::Fingerprint;
+use crate::types::{HttpRequest, HttpResponse, ServerConfig, ServerState, SharedServerState};
 use parking_lot::Mutex;
 
-use super::router::create_router;
+use super::events::{CoreEvent, EventBus};
+use super::handlers::{RouteHandler, RouteRegistry};
+use super::room_access::RoomAccess;
+use super::router::{create_router, HeaderPolicy};
+use super::websocket::PeerHub;
 
 /// HTTP Server state for FFI interface
 #[derive(Clone)]
@@ -25,20 +31,102 @@ pub struct HttpServerState {
 
     /// Server shutdown sender
     shutdown_tx: Option<tokio::sync::watch::Sender<()>>,
+
+    /// Host route handlers
+    routes: RouteRegistry,
+
+    /// Server and client events
+    events: EventBus,
+
+    /// Connected WebSocket peers
+    hub: PeerHub,
+
+    /// Room code, password and lockouts checked on WebSocket joins
+    access: RoomAccess,
+
+    /// Extra headers added to every response
+    header_policy: HeaderPolicy,
+
+    /// SHA-256 fingerprint of the certificate served by the last HTTPS start
+    fingerprint: Option<Fingerprint>,
+
+    /// Finished games served under `/history`
+    history: Option<GameHistory>,
 }
 
 impl HttpServerState {
     /// Create a new HTTP server state
     pub fn new() -> Self {
+        let inner = Arc::new(Mutex::new(ServerState::default()));
+        let events = EventBus::new();
         Self {
-            inner: Arc::new(Mutex::new(ServerState::default())),
+            hub: PeerHub::new(events.clone(), inner.clone()),
+            inner,
             runtime: None,
             shutdown_tx: None,
+            routes: RouteRegistry::new(),
+            events,
+            header_policy: HeaderPolicy::default(),
+            fingerprint: None,
+            access: RoomAccess::new(),
+            history: None,
         }
     }
 
+    /// Set the headers added to every response
+    ///
+    /// Takes effect the next time the server starts.
+    pub fn set_header_policy(&mut self, policy: HeaderPolicy) {
+        self.header_policy = policy;
+    }
+
+    /// Keep finished games in `data_dir` and serve them under `/history`
+    ///
+    /// None stops serving them. Takes effect the next time the server starts.
+    ///
+    /// # Errors
+    /// [`CoreError::IoError`] if the directory cannot be created
+    pub fn set_data_dir(&mut self, data_dir: Option<&str>) -> Result<(), CoreError> {
+        self.history = data_dir.map(GameHistory::open).transpose()?;
+        Ok(())
+    }
+
+    /// Finished games in the data directory, if one is set
+    pub fn history(&self) -> Option<&GameHistory> {
+        self.history.as_ref()
+    }
+
+    /// SHA-256 fingerprint of the served certificate
+    ///
+    /// Set once HTTPS has started (None for plain HTTP). A generated
+    /// self-signed certificate changes on every start, so clients must pin
+    /// the value published for the current session (mDNS TXT record, join
+    /// token or `/health`).
+    pub fn certificate_fingerprint(&self) -> Option<Fingerprint> {
+        self.fingerprint
+    }
+
+    /// Event bus for server lifecycle, client and message events
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Connected WebSocket peers, used to send, broadcast and kick
+    pub fn hub(&self) -> &PeerHub {
+        &self.hub
+    }
+
+    /// Room code, password and lockout policy for WebSocket joins
+    ///
+    /// Changes apply to the next join attempt, also while running.
+    pub fn access(&self) -> &RoomAccess {
+        &self.access
+    }
+
     /// Start the HTTP server
     ///
+    /// Emits `ServerStarted` or `ServerFailed`.
+    ///
     /// # Arguments
     /// * `address` - Server address to bind to (e.g., "0.0.0.0:8080")
     /// * `static_dir` - Directory for static file serving
@@ -46,6 +134,12 @@ impl HttpServerState {
     /// # Returns
     /// Ok(()) on success, Err(CoreError) on failure
     pub fn start(&mut self, address: &str, static_dir: &str) -> Result<(), CoreError> {
+        let result = self.start_http(address, static_dir);
+        self.emit_start_result(&result);
+        result
+    }
+
+    fn start_http(&mut self, address: &str, static_dir: &str) -> Result<(), CoreError> {
         eprintln!("[HTTP] Starting HTTP server on {} with static directory: {}", address, static_dir);
 
         // Step 1: Check if already running
@@ -75,7 +169,7 @@ impl HttpServerState {
             let runtime = Runtime::new()
                 .map_err(|e| {
                     eprintln!("[HTTP] Failed to create Tokio runtime: {}", e);
-                    CoreError::Unknown
+                    CoreError::RuntimeError(e.to_string())
                 })?;
             self.runtime = Some(Arc::new(runtime));
         }
@@ -83,11 +177,17 @@ impl HttpServerState {
 
         let runtime = self.runtime.as_ref().unwrap().clone();
 
+        // Bind synchronously so bind errors are reported to the caller
+        let std_listener = bind_listener(addr)?;
+        let local_addr = std_listener.local_addr().ok();
+        eprintln!("[HTTP]   TCP listener bound successfully to {:?}", local_addr);
+
         // Step 4: Update server state
         eprintln!("[HTTP] Step 4/6: Updating server state...");
         {
             let mut state = self.inner.lock();
             state.is_running = true;
+            state.local_addr = local_addr;
             state.config = Some(ServerConfig {
                 address: address.to_string(),
                 static_dir: static_dir.to_string(),
@@ -97,6 +197,7 @@ impl HttpServerState {
             });
         }
         eprintln!("[HTTP] Step 4/6: Server state updated: running=true");
+        self.fingerprint = None;
 
         // Step 5: Create shutdown channel and router
         eprintln!("[HTTP] Step 5/6: Creating shutdown channel and router...");
@@ -105,42 +206,53 @@ impl HttpServerState {
         self.shutdown_tx = Some(shutdown_tx);
         eprintln!("[HTTP]   Shutdown channel created");
 
-        let router = create_router(static_dir);
+        let router = create_router(
+            static_dir,
+            self.routes.clone(),
+            self.hub.clone(),
+            self.header_policy.clone(),
+            None,
+            self.access.clone(),
+            self.history.clone(),
+        );
         eprintln!("[HTTP]   Router created with static directory");
 
         // Store the state for later reference
         let inner = self.inner.clone();
-        let addr_clone = addr.to_string();
+        let events = self.events.clone();
 
         // Step 6: Spawn async server task
         eprintln!("[HTTP] Step 6/6: Spawning async server task...");
         runtime.spawn(async move {
-            eprintln!("[HTTP] Async task started, binding to {}", addr_clone);
+            eprintln!("[HTTP] Async task started on {}", addr);
 
             // Use axum's serve directly
-            let listener = match tokio::net::TcpListener::bind(&addr_clone).await {
-                Ok(l) => {
-                    eprintln!("[HTTP] TCP listener bound successfully to {}", addr_clone);
-                    l
-                }
+            let listener = match tokio::net::TcpListener::from_std(std_listener) {
+                Ok(l) => l,
                 Err(e) => {
-                    eprintln!("[HTTP] Failed to bind TCP listener: {}", e);
+                    eprintln!("[HTTP] Failed to register TCP listener: {}", e);
+                    inner.lock().is_running = false;
+                    events.emit(CoreEvent::ServerFailed { reason: e.to_string() });
                     return;
                 }
             };
 
             eprintln!("[HTTP] Starting axum serve...");
 
-            let server = axum::serve(listener, router)
+            let service = router.into_make_service_with_connect_info::<SocketAddr>();
+            let server = axum::serve(listener, service)
                 .with_graceful_shutdown(watch_shutdown(shutdown_rx));
 
             eprintln!("[HTTP] HTTP server is now accepting connections");
 
             if let Err(e) = server.await {
                 eprintln!("[HTTP] Server error: {}", e);
-                let mut state = inner.lock();
-                state.is_running = false;
+                {
+                    let mut state = inner.lock();
+                    state.is_running = false;
+                }
                 eprintln!("[HTTP] Server state updated: running=false (error)");
+                events.emit(CoreEvent::ServerFailed { reason: e.to_string() });
             }
         });
 
@@ -150,6 +262,8 @@ impl HttpServerState {
 
     /// Start the HTTPS server with auto-generated self-signed certificate
     ///
+    /// Emits `ServerStarted` or `ServerFailed`.
+    ///
     /// # Arguments
     /// * `address` - Server address to bind to (e.g., "0.0.0.0:8443")
     /// * `static_dir` - Directory for static file serving
@@ -157,6 +271,55 @@ impl HttpServerState {
     /// # Returns
     /// Ok(()) on success, Err(CoreError) on failure
     pub fn start_https(&mut self, address: &str, static_dir: &str) -> Result<(), CoreError> {
+        let result = self.start_tls(address, static_dir, None);
+        self.emit_start_result(&result);
+        result
+    }
+
+    /// Start the HTTPS server with a certificate and private key loaded from
+    /// PEM files
+    ///
+    /// Emits `ServerStarted` or `ServerFailed`.
+    ///
+    /// # Arguments
+    /// * `address` - Server address to bind to (e.g., "0.0.0.0:8443")
+    /// * `static_dir` - Directory for static file serving
+    /// * `cert_path` - Certificate chain (PEM)
+    /// * `key_path` - Private key (PEM, PKCS#8, PKCS#1 or SEC1)
+    ///
+    /// # Returns
+    /// Ok(()) on success, Err(CoreError) on failure
+    pub fn start_https_with_certificate(
+        &mut self,
+        address: &str,
+        static_dir: &str,
+        cert_path: &str,
+        key_path: &str,
+    ) -> Result<(), CoreError> {
+        let result = self.start_tls(address, static_dir, Some((cert_path, key_path)));
+        self.emit_start_result(&result);
+        result
+    }
+
+    /// Publish the outcome of a start attempt
+    fn emit_start_result(&self, result: &Result<(), CoreError>) {
+        let event = match result {
+            Ok(()) => CoreEvent::ServerStarted {
+                address: self.local_addr().map(|a| a.to_string()).unwrap_or_else(|| self.get_address()),
+            },
+            // A second start does not change the running server
+            Err(CoreError::AlreadyRunning) => return,
+            Err(e) => CoreEvent::ServerFailed { reason: e.to_string() },
+        };
+        self.events.emit(event);
+    }
+
+    fn start_tls(
+        &mut self,
+        address: &str,
+        static_dir: &str,
+        certificate: Option<(&str, &str)>,
+    ) -> Result<(), CoreError> {
         eprintln!("[HTTPS] Starting HTTPS server on {} with static directory: {}", address, static_dir);
 
         // Step 1: Check if already running
@@ -180,14 +343,28 @@ impl HttpServerState {
             })?;
         eprintln!("[HTTPS] Step 2/7: Address parsed successfully: {}", addr);
 
-        // Step 3: Create self-signed certificate
-        eprintln!("[HTTPS] Step 3/7: Generating self-signed certificate...");
-        let (cert_pem, key_pem) = generate_self_signed_cert()
-            .map_err(|e| {
-                eprintln!("[HTTPS] Failed to generate certificate: {}", e);
-                CoreError::Unknown
-            })?;
-        eprintln!("[HTTPS] Step 3/7: Certificate generated successfully");
+        // Step 3: Load or create the certificate
+        let (cert_pem, key_pem) = match certificate {
+            Some((cert_path, key_path)) => {
+                eprintln!("[HTTPS] Step 3/7: Loading certificate from {} and key from {}...", cert_path, key_path);
+                let read = |path: &str| {
+                    std::fs::read_to_string(path).map_err(|e| {
+                        eprintln!("[HTTPS] Failed to read {}: {}", path, e);
+                        CoreError::TlsError(format!("{}: {}", path, e))
+                    })
+                };
+                (read(cert_path)?, read(key_path)?)
+            }
+            None => {
+                eprintln!("[HTTPS] Step 3/7: Generating self-signed certificate...");
+                generate_self_signed_cert()
+                    .map_err(|e| {
+                        eprintln!("[HTTPS] Failed to generate certificate: {}", e);
+                        CoreError::TlsError(e.to_string())
+                    })?
+            }
+        };
+        eprintln!("[HTTPS] Step 3/7: Certificate ready");
 
         // Step 4: Configure TLS
         eprintln!("[HTTPS] Step 4/7: Configuring TLS...");
@@ -197,30 +374,33 @@ impl HttpServerState {
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| {
                 eprintln!("[HTTPS] Failed to parse certificate: {}", e);
-                CoreError::Unknown
+                CoreError::TlsError(e.to_string())
             })?;
 
+        if certs.is_empty() {
+            eprintln!("[HTTPS] No certificate found");
+            return Err(CoreError::TlsError("no certificate found".to_string()));
+        }
+        let fingerprint = Fingerprint::of_certificate(certs[0].as_ref());
+        eprintln!("[HTTPS]   Certificate fingerprint (SHA-256): {}", fingerprint);
+
         // Parse private key for rustls
-        let keys = pkcs8_private_keys(&mut key_pem.as_bytes())
-            .collect::<Result<Vec<_>, _>>()
+        let key = private_key(&mut key_pem.as_bytes())
             .map_err(|e| {
                 eprintln!("[HTTPS] Failed to parse private key: {}", e);
-                CoreError::Unknown
+                CoreError::TlsError(e.to_string())
+            })?
+            .ok_or_else(|| {
+                eprintln!("[HTTPS] No private key found");
+                CoreError::TlsError("no private key found".to_string())
             })?;
 
-        let key = keys.into_iter().next().ok_or_else(|| {
-            eprintln!("[HTTPS] No private key found");
-            CoreError::Unknown
-        })?;
-
-        let key = rustls::pki_types::PrivateKeyDer::Pkcs8(key);
-
         let tls_config = RustlsServerConfig::builder()
             .with_no_client_auth()
             .with_single_cert(certs, key)
             .map_err(|e| {
                 eprintln!("[HTTPS] Failed to configure TLS: {}", e);
-                CoreError::Unknown
+                CoreError::TlsError(e.to_string())
             })?;
 
         let tls_acceptor = TlsAcceptor::from(Arc::new(tls_config));
@@ -232,7 +412,7 @@ impl HttpServerState {
             let runtime = Runtime::new()
                 .map_err(|e| {
                     eprintln!("[HTTPS] Failed to create Tokio runtime: {}", e);
-                    CoreError::Unknown
+                    CoreError::RuntimeError(e.to_string())
                 })?;
             self.runtime = Some(Arc::new(runtime));
         }
@@ -240,19 +420,26 @@ impl HttpServerState {
 
         let runtime = self.runtime.as_ref().unwrap().clone();
 
+        // Bind synchronously so bind errors are reported to the caller
+        let std_listener = bind_listener(addr)?;
+        let local_addr = std_listener.local_addr().ok();
+        eprintln!("[HTTPS]   TCP listener bound successfully to {:?}", local_addr);
+
         // Step 6: Update server state
         eprintln!("[HTTPS] Step 6/7: Updating server state...");
         {
             let mut state = self.inner.lock();
             state.is_running = true;
+            state.local_addr = local_addr;
             state.config = Some(ServerConfig {
                 address: address.to_string(),
                 static_dir: static_dir.to_string(),
                 use_https: true,
-                cert_path: None,
-                key_path: None,
+                cert_path: certificate.map(|(cert_path, _)| cert_path.to_string()),
+                key_path: certificate.map(|(_, key_path)| key_path.to_string()),
             });
         }
+        self.fingerprint = Some(fingerprint);
         eprintln!("[HTTPS] Step 6/7: Server state updated: running=true");
 
         // Step 7: Spawn async server task
@@ -262,22 +449,29 @@ impl HttpServerState {
         self.shutdown_tx = Some(shutdown_tx);
         eprintln!("[HTTPS]   Shutdown channel created");
 
-        let router = create_router(static_dir);
+        let router = create_router(
+            static_dir,
+            self.routes.clone(),
+            self.hub.clone(),
+            self.header_policy.clone(),
+            Some(fingerprint),
+            self.access.clone(),
+            self.history.clone(),
+        );
         eprintln!("[HTTPS]   Router created with static directory");
 
         let inner = self.inner.clone();
-        let addr_clone = addr.to_string();
+        let events = self.events.clone();
 
         runtime.spawn(async move {
-            eprintln!("[HTTPS] Async task started, binding to {}", addr_clone);
+            eprintln!("[HTTPS] Async task started on {}", addr);
 
-            let listener = match tokio::net::TcpListener::bind(&addr_clone).await {
-                Ok(l) => {
-                    eprintln!("[HTTPS] TCP listener bound successfully to {}", addr_clone);
-                    l
-                }
+            let listener = match tokio::net::TcpListener::from_std(std_listener) {
+                Ok(l) => l,
                 Err(e) => {
-                    eprintln!("[HTTPS] Failed to bind TCP listener: {}", e);
+                    eprintln!("[HTTPS] Failed to register TCP listener: {}", e);
+                    inner.lock().is_running = false;
+                    events.emit(CoreEvent::ServerFailed { reason: e.to_string() });
                     return;
                 }
             };
@@ -292,7 +486,7 @@ impl HttpServerState {
             loop {
                 tokio::select! {
                     result = listener.accept() => {
-                        let (stream, _) = match result {
+                        let (stream, remote_addr) = match result {
                             Ok(s) => s,
                             Err(e) => {
                                 eprintln!("[HTTPS] Failed to accept connection: {}", e);
@@ -316,6 +510,9 @@ impl HttpServerState {
                                     // Create a service from the router
                                     let svc = tower::ServiceBuilder::new()
                                         .layer(tower_http::add_extension::AddExtensionLayer::new(inner))
+                                        .layer(tower_http::add_extension::AddExtensionLayer::new(
+                                            axum::extract::ConnectInfo(remote_addr),
+                                        ))
                                         .service(router_ref);
 
                                     // Use hyper_util's TowerToHyperService to convert tower service to hyper service
@@ -386,14 +583,22 @@ impl HttpServerState {
             eprintln!("[HTTP]   No shutdown sender present");
         }
 
+        // Close WebSocket peers; upgraded connections outlive graceful shutdown.
+        // Sessions go first so no seats are held for a stopped server.
+        self.hub.sessions().clear();
+        self.hub.limiter().reset();
+        self.hub.disconnect_all();
+
         // Step 3: Update state
         eprintln!("[HTTP] Step 3/3: Updating server state...");
         {
             let mut state = self.inner.lock();
             state.is_running = false;
             state.connected_clients = 0;
+            state.local_addr = None;
         }
         eprintln!("[HTTP] Server state updated: running=false, connected_clients=0");
+        self.events.emit(CoreEvent::ServerStopped);
     }
 
     /// Check if the server is running
@@ -404,6 +609,37 @@ impl HttpServerState {
         is_running
     }
 
+    /// Register a host handler for a path prefix (see [`RouteRegistry::register`])
+    ///
+    /// Can be called before or after the server starts.
+    pub fn register_route(&self, prefix: &str, handler: RouteHandler) -> Result<(), CoreError> {
+        eprintln!("[HTTP] Registering host route: {}", prefix);
+        self.routes.register(prefix, handler)
+    }
+
+    /// Remove the host handler for a prefix. Returns true if one was registered.
+    pub fn unregister_route(&self, prefix: &str) -> bool {
+        eprintln!("[HTTP] Unregistering host route: {}", prefix);
+        self.routes.unregister(prefix)
+    }
+
+    /// Run a request through the host handlers without going over the network
+    ///
+    /// Returns 404 if no registered prefix matches the path.
+    pub fn handle_request(&self, request: HttpRequest) -> HttpResponse {
+        let path = request.path.clone();
+        self.routes
+            .dispatch(request)
+            .unwrap_or_else(|| HttpResponse::new(404, format!("No handler for {}", path)))
+    }
+
+    /// Get the address the listener is bound to, if running
+    ///
+    /// Unlike `get_address`, this resolves port 0 to the assigned port.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.inner.lock().local_addr
+    }
+
     /// Get the server address if running
     pub fn get_address(&self) -> String {
         let state = self.inner.lock();
@@ -414,6 +650,19 @@ impl HttpServerState {
     }
 }
 
+/// Bind a non-blocking std TCP listener for handing over to Tokio
+fn bind_listener(addr: SocketAddr) -> Result<std::net::TcpListener, CoreError> {
+    let listener = std::net::TcpListener::bind(addr)
+        .map_err(|e| {
+            eprintln!("[HTTP] Failed to bind TCP listener to {}: {}", addr, e);
+            CoreError::BindFailed(format!("{}: {}", addr, e))
+        })?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| CoreError::IoError(e.to_string()))?;
+    Ok(listener)
+}
+
 /// Generate a self-signed certificate using rcgen
 /// Returns (cert_pem, key_pem) as strings
 fn generate_self_signed_cert() -> Result<(String, String), rcgen::Error> {
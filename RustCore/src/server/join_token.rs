@@ -0,0 +1,361 @@
+//! Compact join tokens for hosts that cannot be found through mDNS.
+//!
+//! A join token packs everything a client needs to reach a host (addresses,
+//! port, scheme and an optional certificate fingerprint) into a short
+//! uppercase code. The alphabet only uses characters from the QR code
+//! alphanumeric set, so the same string can be rendered as a QR code or read
+//! out and typed in by hand.
+//!
+//! Binary layout (before base32 encoding):
+//!
+//! | Bytes | Field |
+//! |-------|-------|
+//! | 1 | Format version |
+//! | 1 | Flags (bit 0: TLS, bit 1: fingerprint present) |
+//! | 2 | Port (big endian) |
+//! | 1 | Address count |
+//! | 5 or 17 each | Address family (4 or 6) followed by the address bytes |
+//! | 32 | SHA-256 certificate fingerprint (only if flagged) |
+//! | 1 | CRC-8 checksum over all preceding bytes |
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use crate::error::CoreError;
+
+/// Prefix of every encoded join token
+pub const JOIN_TOKEN_PREFIX: &str = "FT";
+
+/// Current binary format version
+const FORMAT_VERSION: u8 = 1;
+
+/// Maximum number of addresses a token may carry
+pub const MAX_TOKEN_ADDRESSES: usize = 8;
+
+/// Length of a SHA-256 certificate fingerprint
+const FINGERPRINT_LEN: usize = 32;
+
+const FLAG_TLS: u8 = 0b01;
+const FLAG_FINGERPRINT: u8 = 0b10;
+
+/// RFC 4648 base32 alphabet (QR alphanumeric safe)
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Number of characters between separators in the display form
+const GROUP_SIZE: usize = 4;
+
+/// Connection scheme advertised by a join token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinScheme {
+    /// Plain HTTP / WebSocket
+    Http,
+    /// HTTPS / secure WebSocket
+    Https,
+}
+
+impl JoinScheme {
+    /// Scheme name used in URLs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JoinScheme::Http => "http",
+            JoinScheme::Https => "https",
+        }
+    }
+}
+
+/// Everything a client needs to connect to a host
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinToken {
+    /// Candidate host addresses, in order of preference
+    pub addresses: Vec<IpAddr>,
+    /// Server port
+    pub port: u16,
+    /// Connection scheme
+    pub scheme: JoinScheme,
+    /// SHA-256 fingerprint of the host certificate, if any
+    pub fingerprint: Option<[u8; 32]>,
+}
+
+impl JoinToken {
+    /// Create a token for the given addresses
+    pub fn new(addresses: Vec<IpAddr>, port: u16, scheme: JoinScheme) -> Self {
+        Self {
+            addresses,
+            port,
+            scheme,
+            fingerprint: None,
+        }
+    }
+
+    /// Create a token advertising every non-loopback address of this machine
+    ///
+    /// IPv4 addresses are listed first since they are the most likely to be
+    /// reachable on venue Wi-Fi.
+    pub fn for_local_host(port: u16, scheme: JoinScheme) -> Result<Self, CoreError> {
+        let interfaces = if_addrs::get_if_addrs()
+            .map_err(|e| CoreError::IoError(e.to_string()))?;
+
+        let mut addresses: Vec<IpAddr> = interfaces
+            .into_iter()
+            .filter(|iface| !iface.is_loopback())
+            .map(|iface| iface.ip())
+            .filter(|ip| match ip {
+                // Link-local IPv6 addresses need a scope id, which tokens do not carry
+                IpAddr::V6(v6) => (v6.segments()[0] & 0xffc0) != 0xfe80,
+                IpAddr::V4(_) => true,
+            })
+            .collect();
+        addresses.sort_by_key(|ip| ip.is_ipv6());
+        addresses.dedup();
+        addresses.truncate(MAX_TOKEN_ADDRESSES);
+
+        Ok(Self::new(addresses, port, scheme))
+    }
+
+    /// Attach a certificate fingerprint
+    pub fn with_fingerprint(mut self, fingerprint: [u8; 32]) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Socket addresses to try, in order of preference
+    pub fn socket_addrs(&self) -> Vec<SocketAddr> {
+        self.addresses
+            .iter()
+            .map(|ip| SocketAddr::new(*ip, self.port))
+            .collect()
+    }
+
+    /// Base URL for the first address (e.g. "https://192.168.1.20:8443")
+    pub fn url(&self) -> Option<String> {
+        self.socket_addrs()
+            .first()
+            .map(|addr| format!("{}://{}", self.scheme.as_str(), addr))
+    }
+
+    /// Encode the token without separators (best for QR codes)
+    pub fn encode(&self) -> Result<String, CoreError> {
+        if self.addresses.is_empty() {
+            return Err(CoreError::InvalidJoinToken("token has no addresses".to_string()));
+        }
+        if self.addresses.len() > MAX_TOKEN_ADDRESSES {
+            return Err(CoreError::InvalidJoinToken(format!(
+                "token has {} addresses, at most {} are allowed",
+                self.addresses.len(),
+                MAX_TOKEN_ADDRESSES
+            )));
+        }
+
+        let mut flags = 0;
+        if self.scheme == JoinScheme::Https {
+            flags |= FLAG_TLS;
+        }
+        if self.fingerprint.is_some() {
+            flags |= FLAG_FINGERPRINT;
+        }
+
+        let mut bytes = vec![FORMAT_VERSION, flags];
+        bytes.extend_from_slice(&self.port.to_be_bytes());
+        bytes.push(self.addresses.len() as u8);
+        for address in &self.addresses {
+            match address {
+                IpAddr::V4(v4) => {
+                    bytes.push(4);
+                    bytes.extend_from_slice(&v4.octets());
+                }
+                IpAddr::V6(v6) => {
+                    bytes.push(6);
+                    bytes.extend_from_slice(&v6.octets());
+                }
+            }
+        }
+        if let Some(fingerprint) = &self.fingerprint {
+            bytes.extend_from_slice(fingerprint);
+        }
+        bytes.push(crc8(&bytes));
+
+        Ok(format!("{}{}", JOIN_TOKEN_PREFIX, base32_encode(&bytes)))
+    }
+
+    /// Encode the token grouped with dashes for reading out loud
+    /// (e.g. "FTAE-AB5Q-...")
+    pub fn encode_short_code(&self) -> Result<String, CoreError> {
+        let encoded = self.encode()?;
+        let groups: Vec<String> = encoded
+            .as_bytes()
+            .chunks(GROUP_SIZE)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect();
+        Ok(groups.join("-"))
+    }
+
+    /// Decode a token from either the QR or the short-code form
+    ///
+    /// Separators, whitespace and letter case are ignored.
+    pub fn decode(code: &str) -> Result<Self, CoreError> {
+        let normalized: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+
+        let body = normalized
+            .strip_prefix(JOIN_TOKEN_PREFIX)
+            .ok_or_else(|| CoreError::InvalidJoinToken(format!("missing '{}' prefix", JOIN_TOKEN_PREFIX)))?;
+        let bytes = base32_decode(body)?;
+
+        let (payload, checksum) = match bytes.split_last() {
+            Some((checksum, payload)) => (payload, *checksum),
+            None => return Err(CoreError::InvalidJoinToken("token is empty".to_string())),
+        };
+        if crc8(payload) != checksum {
+            return Err(CoreError::InvalidJoinToken("checksum mismatch".to_string()));
+        }
+
+        let mut reader = ByteReader::new(payload);
+        let version = reader.u8()?;
+        if version != FORMAT_VERSION {
+            return Err(CoreError::InvalidJoinToken(format!("unsupported version {}", version)));
+        }
+        let flags = reader.u8()?;
+        let port = u16::from_be_bytes(reader.array::<2>()?);
+
+        let count = reader.u8()? as usize;
+        if count == 0 || count > MAX_TOKEN_ADDRESSES {
+            return Err(CoreError::InvalidJoinToken(format!("invalid address count {}", count)));
+        }
+        let mut addresses = Vec::with_capacity(count);
+        for _ in 0..count {
+            let address = match reader.u8()? {
+                4 => IpAddr::V4(Ipv4Addr::from(reader.array::<4>()?)),
+                6 => IpAddr::V6(Ipv6Addr::from(reader.array::<16>()?)),
+                family => {
+                    return Err(CoreError::InvalidJoinToken(format!("unknown address family {}", family)));
+                }
+            };
+            addresses.push(address);
+        }
+
+        let fingerprint = if flags & FLAG_FINGERPRINT != 0 {
+            Some(reader.array::<FINGERPRINT_LEN>()?)
+        } else {
+            None
+        };
+
+        if !reader.is_empty() {
+            return Err(CoreError::InvalidJoinToken("trailing data".to_string()));
+        }
+
+        let scheme = if flags & FLAG_TLS != 0 {
+            JoinScheme::Https
+        } else {
+            JoinScheme::Http
+        };
+
+        Ok(Self {
+            addresses,
+            port,
+            scheme,
+            fingerprint,
+        })
+    }
+}
+
+impl fmt::Display for JoinToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.encode() {
+            Ok(encoded) => f.write_str(&encoded),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+impl FromStr for JoinToken {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::decode(s)
+    }
+}
+
+/// Sequential reader over the token payload
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn u8(&mut self) -> Result<u8, CoreError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CoreError> {
+        if self.bytes.len() < N {
+            return Err(CoreError::InvalidJoinToken("token is truncated".to_string()));
+        }
+        let (head, tail) = self.bytes.split_at(N);
+        self.bytes = tail;
+        let mut out = [0u8; N];
+        out.copy_from_slice(head);
+        Ok(out)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// CRC-8 (polynomial 0x07), enough to catch typos in hand-entered codes
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Base32 encode without padding
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// Base32 decode without padding
+fn base32_decode(text: &str) -> Result<Vec<u8>, CoreError> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| CoreError::InvalidJoinToken(format!("invalid character '{}'", c as char)))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,307 @@
+//! Connection, message and request limits.
+//!
+//! Protects the host from misbehaving clients: token-bucket rate limits per
+//! WebSocket connection, per address for WebSocket messages and per address
+//! for HTTP requests, frame, message and request body size caps, and caps
+//! on concurrent connections. Clients that hit a limit get an `error`
+//! message (see [`LimitError::to_message`]); connections that keep
+//! exceeding their rate are closed with [`CLOSE_POLICY_VIOLATION`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Close code for a connection that kept exceeding its rate limit
+pub const CLOSE_POLICY_VIOLATION: u16 = 1008;
+
+/// Close code for a message over the size limit
+pub const CLOSE_MESSAGE_TOO_BIG: u16 = 1009;
+
+/// Close code for a join refused because the server is at capacity
+pub const CLOSE_TRY_AGAIN_LATER: u16 = 1013;
+
+/// Addresses tracked before idle buckets are pruned
+const PRUNE_THRESHOLD: usize = 256;
+
+/// A token bucket rate: `per_second` tokens refill every second, up to `burst`
+///
+/// A `per_second` of 0 disables the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Sustained rate
+    pub per_second: u32,
+    /// Most tokens that can be spent at once
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// A rate of `per_second` with room for bursts of `burst`
+    pub const fn new(per_second: u32, burst: u32) -> Self {
+        Self { per_second, burst }
+    }
+
+    /// No limit
+    pub const fn unlimited() -> Self {
+        Self::new(0, 0)
+    }
+
+    /// Whether this rate limits anything
+    pub fn is_limited(&self) -> bool {
+        self.per_second > 0
+    }
+}
+
+/// Limits applied to connections, messages and requests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Most WebSocket peers at once (0 for no limit)
+    pub max_connections: usize,
+    /// Most WebSocket peers from one address (0 for no limit)
+    pub max_connections_per_address: usize,
+    /// Largest WebSocket frame, in bytes
+    pub max_frame_bytes: usize,
+    /// Largest WebSocket message (after reassembling fragments), in bytes
+    pub max_message_bytes: usize,
+    /// Largest HTTP request body, in bytes
+    pub max_request_body_bytes: usize,
+    /// Messages one connection may send
+    pub connection_message_rate: RateLimit,
+    /// Messages all connections from one address may send together
+    pub address_message_rate: RateLimit,
+    /// HTTP requests (including WebSocket upgrades) one address may make
+    pub address_request_rate: RateLimit,
+    /// Rate-limited messages a connection may send before it is closed (0 never closes)
+    pub max_violations: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_connections: 64,
+            max_connections_per_address: 16,
+            max_frame_bytes: 64 * 1024,
+            max_message_bytes: 64 * 1024,
+            max_request_body_bytes: 1024 * 1024,
+            connection_message_rate: RateLimit::new(20, 40),
+            address_message_rate: RateLimit::new(50, 100),
+            address_request_rate: RateLimit::new(50, 200),
+            max_violations: 20,
+        }
+    }
+}
+
+/// Why a connection, message or request was refused
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    /// Too many messages or requests; wait before sending more
+    RateLimited {
+        /// Time until the next one is allowed
+        retry_after: Duration,
+    },
+    /// A message or body was larger than allowed
+    TooLarge {
+        /// Largest accepted size in bytes
+        max_bytes: usize,
+    },
+    /// The server has as many connections as it accepts
+    ServerFull,
+    /// This address has as many connections as it may open
+    TooManyConnections,
+    /// The host is not accepting new players
+    NotAccepting,
+}
+
+impl LimitError {
+    /// Stable machine-readable code sent to clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitError::RateLimited { .. } => "rate_limited",
+            LimitError::TooLarge { .. } => "too_large",
+            LimitError::ServerFull => "server_full",
+            LimitError::TooManyConnections => "too_many_connections",
+            LimitError::NotAccepting => "not_accepting",
+        }
+    }
+
+    /// JSON error message sent to the client
+    ///
+    /// `{"type": "error", "code": "...", "message": "...", "retry_after_ms": n, "max_bytes": n}`
+    /// (`retry_after_ms` only for `rate_limited`, `max_bytes` only for `too_large`).
+    pub fn to_message(&self) -> String {
+        let mut message = serde_json::json!({
+            "type": "error",
+            "code": self.code(),
+            "message": self.to_string(),
+        });
+        match self {
+            LimitError::RateLimited { retry_after } => {
+                message["retry_after_ms"] = serde_json::json!(retry_after.as_millis() as u64);
+            }
+            LimitError::TooLarge { max_bytes } => message["max_bytes"] = serde_json::json!(max_bytes),
+            _ => {}
+        }
+        message.to_string()
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::RateLimited { retry_after } => {
+                write!(f, "Too many messages, slow down (retry in {} ms)", retry_after.as_millis())
+            }
+            LimitError::TooLarge { max_bytes } => write!(f, "Message larger than {} bytes", max_bytes),
+            LimitError::ServerFull => write!(f, "Server is full"),
+            LimitError::TooManyConnections => write!(f, "Too many connections from this address"),
+            LimitError::NotAccepting => write!(f, "Not accepting new players"),
+        }
+    }
+}
+
+/// A token bucket
+#[derive(Clone, Debug)]
+pub(crate) struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket for `rate`
+    pub fn new(rate: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(rate.burst.max(1)),
+            updated: now,
+        }
+    }
+
+    /// Spend a token if one is available
+    ///
+    /// # Returns
+    /// Time until a token will be available if none is now
+    pub fn take(&mut self, rate: RateLimit, now: Instant) -> Result<(), Duration> {
+        if !rate.is_limited() {
+            return Ok(());
+        }
+        let capacity = f64::from(rate.burst.max(1));
+        let per_second = f64::from(rate.per_second);
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        }
+    }
+
+    /// Whether the bucket has been idle long enough to have refilled completely
+    fn is_full(&self, rate: RateLimit, now: Instant) -> bool {
+        if !rate.is_limited() {
+            return true;
+        }
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * f64::from(rate.per_second) >= f64::from(rate.burst.max(1))
+    }
+}
+
+/// Largest request body a host handler may read, attached to requests by the router
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RequestBodyLimit(pub usize);
+
+struct LimiterInner {
+    limits: Limits,
+    accepting: bool,
+    address_messages: HashMap<IpAddr, TokenBucket>,
+    address_requests: HashMap<IpAddr, TokenBucket>,
+}
+
+/// Limits and per-address buckets, shared between the peer hub and the router
+#[derive(Clone)]
+pub struct Limiter {
+    inner: Arc<Mutex<LimiterInner>>,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Limiter {
+    /// Create a limiter with the default [`Limits`], accepting new players
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LimiterInner {
+                limits: Limits::default(),
+                accepting: true,
+                address_messages: HashMap::new(),
+                address_requests: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Current limits
+    pub fn limits(&self) -> Limits {
+        self.inner.lock().limits
+    }
+
+    /// Replace the limits
+    ///
+    /// Size limits apply to connections opened afterwards; rates and
+    /// connection caps apply at once.
+    pub fn set_limits(&self, limits: Limits) {
+        self.inner.lock().limits = limits;
+    }
+
+    /// Whether new players may join (resuming players always may)
+    pub fn is_accepting(&self) -> bool {
+        self.inner.lock().accepting
+    }
+
+    /// Stop (or resume) accepting new players, e.g. once a game has started
+    pub fn set_accepting(&self, accepting: bool) {
+        self.inner.lock().accepting = accepting;
+    }
+
+    /// Forget every address bucket
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock();
+        inner.address_messages.clear();
+        inner.address_requests.clear();
+    }
+
+    /// Spend a message token for `address`
+    pub(crate) fn allow_message(&self, address: IpAddr) -> Result<(), LimitError> {
+        let mut inner = self.inner.lock();
+        let rate = inner.limits.address_message_rate;
+        take(&mut inner.address_messages, address, rate)
+    }
+
+    /// Spend a request token for `address`
+    pub(crate) fn allow_request(&self, address: IpAddr) -> Result<(), LimitError> {
+        let mut inner = self.inner.lock();
+        let rate = inner.limits.address_request_rate;
+        take(&mut inner.address_requests, address, rate)
+    }
+}
+
+/// Spend a token from the bucket of `address`, pruning idle buckets as the map grows
+fn take(buckets: &mut HashMap<IpAddr, TokenBucket>, address: IpAddr, rate: RateLimit) -> Result<(), LimitError> {
+    if !rate.is_limited() {
+        return Ok(());
+    }
+    let now = Instant::now();
+    if buckets.len() >= PRUNE_THRESHOLD && !buckets.contains_key(&address) {
+        buckets.retain(|_, bucket| !bucket.is_full(rate, now));
+    }
+    buckets
+        .entry(address)
+        .or_insert_with(|| TokenBucket::new(rate, now))
+        .take(rate, now)
+        .map_err(|retry_after| LimitError::RateLimited { retry_after })
+}
@@ -0,0 +1,158 @@
+//! mDNS service browsing.
+//!
+//! Watches the network for instances of a service type and publishes
+//! `MdnsServiceFound` / `MdnsServiceRemoved` events.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use parking_lot::Mutex;
+use tokio::runtime::Runtime;
+
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+
+use crate::error::CoreError;
+
+use super::events::{CoreEvent, EventBus};
+
+/// mDNS browser state for FFI interface
+pub struct MdnsBrowserState {
+    /// mDNS daemon (None when stopped)
+    daemon: Option<ServiceDaemon>,
+
+    /// Tokio runtime for the browse task
+    runtime: Option<Arc<Runtime>>,
+
+    /// Service type being browsed
+    service_type: String,
+
+    /// Full names of the services currently visible
+    services: Arc<Mutex<BTreeSet<String>>>,
+
+    /// Discovery events
+    events: EventBus,
+}
+
+impl Default for MdnsBrowserState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MdnsBrowserState {
+    /// Create a new browser state
+    pub fn new() -> Self {
+        Self {
+            daemon: None,
+            runtime: None,
+            service_type: String::new(),
+            services: Arc::new(Mutex::new(BTreeSet::new())),
+            events: EventBus::new(),
+        }
+    }
+
+    /// Event bus for discovery events
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Check if the browser is running
+    pub fn is_running(&self) -> bool {
+        self.daemon.is_some()
+    }
+
+    /// Full names of the services currently visible
+    pub fn services(&self) -> Vec<String> {
+        self.services.lock().iter().cloned().collect()
+    }
+
+    /// Start browsing for a service type (e.g. "_game._tcp.local.")
+    ///
+    /// # Returns
+    /// Ok(()) on success, Err(CoreError) on failure
+    pub fn start(&mut self, service_type: &str) -> Result<(), CoreError> {
+        if self.is_running() {
+            eprintln!("[mDNS] Browse failed: already browsing {}", self.service_type);
+            return Err(CoreError::AlreadyRunning);
+        }
+
+        eprintln!("[mDNS] Browsing for {}", service_type);
+        if self.runtime.is_none() {
+            let runtime = Runtime::new()
+                .map_err(|e| {
+                    eprintln!("[mDNS] Failed to create Tokio runtime: {}", e);
+                    CoreError::RuntimeError(e.to_string())
+                })?;
+            self.runtime = Some(Arc::new(runtime));
+        }
+        let runtime = self.runtime.as_ref().unwrap().clone();
+
+        let daemon = ServiceDaemon::new()
+            .map_err(|e| {
+                eprintln!("[mDNS] Failed to create mDNS daemon: {}", e);
+                CoreError::MdnsError(e.to_string())
+            })?;
+        let receiver = daemon
+            .browse(service_type)
+            .map_err(|e| {
+                eprintln!("[mDNS] Failed to browse {}: {}", service_type, e);
+                let _ = daemon.shutdown();
+                CoreError::MdnsError(e.to_string())
+            })?;
+
+        let services = self.services.clone();
+        let events = self.events.clone();
+        runtime.spawn(async move {
+            // The channel disconnects once the daemon shuts down
+            while let Ok(event) = receiver.recv_async().await {
+                match event {
+                    ServiceEvent::ServiceFound(service_type, fullname) => {
+                        if services.lock().insert(fullname.clone()) {
+                            eprintln!("[mDNS] Service found: {}", fullname);
+                            events.emit(CoreEvent::MdnsServiceFound { service_type, fullname });
+                        }
+                    }
+                    ServiceEvent::ServiceRemoved(service_type, fullname) => {
+                        if services.lock().remove(&fullname) {
+                            eprintln!("[mDNS] Service removed: {}", fullname);
+                            events.emit(CoreEvent::MdnsServiceRemoved { service_type, fullname });
+                        }
+                    }
+                    other => tracing::debug!("[mDNS] Browse event: {:?}", other),
+                }
+            }
+            eprintln!("[mDNS] Browse task ended");
+        });
+
+        self.daemon = Some(daemon);
+        self.service_type = service_type.to_string();
+        Ok(())
+    }
+
+    /// Stop browsing and forget the visible services
+    pub fn stop(&mut self) {
+        let daemon = match self.daemon.take() {
+            Some(daemon) => daemon,
+            None => {
+                eprintln!("[mDNS] Stop browse called but browser is not running");
+                return;
+            }
+        };
+
+        let _ = daemon.stop_browse(&self.service_type);
+        if let Err(e) = daemon.shutdown() {
+            eprintln!("[mDNS] Failed to shut down browse daemon: {}", e);
+        }
+        self.services.lock().clear();
+        eprintln!("[mDNS] Stopped browsing {}", self.service_type);
+        self.service_type.clear();
+    }
+}
+
+impl Drop for MdnsBrowserState {
+    fn drop(&mut self) {
+        if let Some(daemon) = self.daemon.take() {
+            let _ = daemon.shutdown();
+        }
+        self.runtime.take();
+    }
+}
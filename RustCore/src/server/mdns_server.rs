@@ -3,14 +3,51 @@
 //! Provides asynchronous mDNS service discovery and registration.
 
 use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::Mutex;
 use tokio::runtime::Runtime;
 
-use mdns_sd::{DaemonEvent, ServiceDaemon, ServiceInfo};
+use mdns_sd::{DaemonEvent, DaemonStatus, ServiceDaemon, ServiceInfo, UnregisterStatus};
 
 use crate::error::CoreError;
 
+use super::events::{CoreEvent, EventBus};
+
+/// How long `stop()` waits for the daemon to confirm the goodbye packets
+const UNREGISTER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long `stop()` waits for the daemon thread to shut down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// TXT record key carrying the host certificate's SHA-256 fingerprint
+pub const TXT_FINGERPRINT: &str = "fingerprint";
+
+/// Registration status of the mDNS service
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum MdnsStatus {
+    /// No service is registered
+    #[default]
+    Stopped,
+    /// The service is registered and the daemon is healthy
+    Registered,
+    /// The daemon reported an error; the service is no longer announced
+    Failed(String),
+}
+
+impl std::fmt::Display for MdnsStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MdnsStatus::Stopped => write!(f, "stopped"),
+            MdnsStatus::Registered => write!(f, "running"),
+            MdnsStatus::Failed(reason) => write!(f, "failed ({})", reason),
+        }
+    }
+}
+
 /// mDNS Server state for FFI interface
-#[derive(Clone)]
+///
+/// Not `Clone`: the daemon handle is shared between clones, and dropping the
+/// state shuts the daemon down.
 pub struct MdnsServerState {
     /// mDNS daemon (None when stopped)
     daemon: Option<ServiceDaemon>,
@@ -18,8 +55,14 @@ pub struct MdnsServerState {
     /// Tokio runtime (None when stopped)
     runtime: Option<Arc<Runtime>>,
 
-    /// Whether the service is currently registered
-    is_registered: bool,
+    /// Registration status, shared with the monitor task
+    status: Arc<Mutex<MdnsStatus>>,
+
+    /// Full name of the registered service, used to unregister it
+    registered_fullname: String,
+
+    /// Daemon failure events
+    events: EventBus,
 
     /// Service type (e.g., "_game._tcp.local.")
     pub(super) service_type: String,
@@ -32,6 +75,9 @@ pub struct MdnsServerState {
 
     /// Service port
     pub(super) port: u16,
+
+    /// TXT record properties published with the service
+    txt_properties: Vec<(String, String)>,
 }
 
 impl Default for MdnsServerState {
@@ -47,17 +93,32 @@ impl MdnsServerState {
         Self {
             daemon: None,
             runtime: None,
-            is_registered: false,
+            status: Arc::new(Mutex::new(MdnsStatus::Stopped)),
+            registered_fullname: String::new(),
+            events: EventBus::new(),
             service_type: String::new(),
             instance_name: String::new(),
             hostname: String::new(),
             port: 0,
+            txt_properties: Vec::new(),
         }
     }
 
     /// Check if the mDNS service is running
+    ///
+    /// Returns false once the daemon has reported an error.
     pub fn is_running(&self) -> bool {
-        self.is_registered
+        *self.status.lock() == MdnsStatus::Registered
+    }
+
+    /// Get the current registration status
+    pub fn status(&self) -> MdnsStatus {
+        self.status.lock().clone()
+    }
+
+    /// Event bus for daemon failures (`MdnsFailed`)
+    pub fn events(&self) -> &EventBus {
+        &self.events
     }
 
     /// Get the service full name for logging
@@ -65,8 +126,25 @@ impl MdnsServerState {
         format!("{}.{}.", self.instance_name, self.service_type)
     }
 
+    /// Set a TXT record property, replacing any previous value for `key`
+    ///
+    /// Properties are published the next time the service is started.
+    pub fn set_txt_property(&mut self, key: &str, value: &str) {
+        match self.txt_properties.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.txt_properties.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// TXT record properties published with the service
+    pub fn txt_properties(&self) -> &[(String, String)] {
+        &self.txt_properties
+    }
+
     /// Start the mDNS service registration
     ///
+    /// A daemon left over from a failed registration is shut down first.
+    ///
     /// # Arguments
     /// * `service_type` - Service type (e.g., "_game._tcp.local.")
     /// * `instance_name` - Instance name (e.g., "MyServer")
@@ -83,11 +161,15 @@ impl MdnsServerState {
         port: u16,
     ) -> Result<(), CoreError> {
         // Check if already running
-        if self.is_registered {
+        if self.is_running() {
             eprintln!("[mDNS] Start failed: service already registered (fullname={}.{}.{})",
                 instance_name, service_type, hostname);
             return Err(CoreError::AlreadyRunning);
         }
+        if self.daemon.is_some() {
+            eprintln!("[mDNS] Shutting down the daemon of the failed registration ({})", self.status());
+            self.stop();
+        }
 
         eprintln!("[mDNS] Starting service registration...");
         eprintln!("[mDNS]   service_type={}", service_type);
@@ -100,7 +182,7 @@ impl MdnsServerState {
         let runtime = Runtime::new()
             .map_err(|e| {
                 eprintln!("[mDNS] Failed to create Tokio runtime: {}", e);
-                CoreError::Unknown
+                CoreError::RuntimeError(e.to_string())
             })?;
         eprintln!("[mDNS] Tokio runtime created successfully");
         let runtime = Arc::new(runtime);
@@ -110,7 +192,7 @@ impl MdnsServerState {
         let daemon = ServiceDaemon::new()
             .map_err(|e| {
                 eprintln!("[mDNS] Failed to create mDNS daemon: {}", e);
-                CoreError::Unknown
+                CoreError::MdnsError(e.to_string())
             })?;
         eprintln!("[mDNS] mDNS daemon created successfully");
 
@@ -119,17 +201,26 @@ impl MdnsServerState {
         let service_hostname = format!("{}.local.", hostname);
         eprintln!("[mDNS]   service_hostname={}", service_hostname);
 
-        let service_info = ServiceInfo::new::<_, &[(&str, &str)]>(
+        for (key, value) in &self.txt_properties {
+            eprintln!("[mDNS]   txt {}={}", key, value);
+        }
+        let txt_properties: Vec<(&str, &str)> = self
+            .txt_properties
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+
+        let service_info = ServiceInfo::new(
             service_type,
             instance_name,
             &service_hostname,
             "",
             port,
-            &[],
+            &txt_properties[..],
         )
         .map_err(|e| {
             eprintln!("[mDNS] Failed to create service info: {}", e);
-            CoreError::Unknown
+            CoreError::MdnsError(e.to_string())
         })?
         .enable_addr_auto();
 
@@ -145,32 +236,45 @@ impl MdnsServerState {
             .register(service_info.clone())
             .map_err(|e| {
                 eprintln!("[mDNS] Failed to register service: {}", e);
-                CoreError::Unknown
+                CoreError::MdnsError(e.to_string())
             })?;
         eprintln!("[mDNS] Service registered successfully with mDNS");
 
         // Step 5: Spawn monitor task
         eprintln!("[mDNS] Step 5/5: Spawning monitor task...");
+        *self.status.lock() = MdnsStatus::Registered;
+        let status = self.status.clone();
+        let events = self.events.clone();
         runtime.spawn(async move {
             eprintln!("[mDNS] Monitor task started for {}", service_fullname_clone);
 
-            if let Ok(monitor) = daemon_for_monitor.monitor() {
-                loop {
-                    // Use recv_timeout to avoid blocking indefinitely
-                    match monitor.recv_timeout(tokio::time::Duration::from_secs(1)) {
-                        Ok(event) => {
-                            eprintln!("[mDNS] Daemon event: {:?}", event);
-                            if let DaemonEvent::Error(e) = event {
-                                eprintln!("[mDNS] Daemon error: {}", e);
-                                break;
-                            }
-                        }
-                        Err(_) => {
-                            // Timeout or error - check if daemon is still alive by trying to get service info
-                            tracing::debug!("[mDNS] Monitor timeout or error, continuing...");
-                            continue;
+            let monitor = match daemon_for_monitor.monitor() {
+                Ok(monitor) => monitor,
+                Err(e) => {
+                    eprintln!("[mDNS] Failed to monitor daemon: {}", e);
+                    *status.lock() = MdnsStatus::Failed(e.to_string());
+                    events.emit(CoreEvent::MdnsFailed { reason: e.to_string() });
+                    return;
+                }
+            };
+
+            // The channel disconnects once the daemon shuts down
+            while let Ok(event) = monitor.recv_async().await {
+                eprintln!("[mDNS] Daemon event: {:?}", event);
+                if let DaemonEvent::Error(e) = event {
+                    eprintln!("[mDNS] Daemon error: {}", e);
+                    let failed = {
+                        let mut status = status.lock();
+                        let failed = *status == MdnsStatus::Registered;
+                        if failed {
+                            *status = MdnsStatus::Failed(e.to_string());
                         }
+                        failed
+                    };
+                    if failed {
+                        events.emit(CoreEvent::MdnsFailed { reason: e.to_string() });
                     }
+                    break;
                 }
             }
 
@@ -181,7 +285,7 @@ impl MdnsServerState {
         eprintln!("[mDNS] Updating internal state...");
         self.daemon = Some(daemon);
         self.runtime = Some(runtime);
-        self.is_registered = true;
+        self.registered_fullname = service_fullname.clone();
         self.service_type = service_type.to_string();
         self.instance_name = instance_name.to_string();
         self.hostname = hostname.to_string();
@@ -193,24 +297,58 @@ impl MdnsServerState {
 
     /// Stop the mDNS service and release resources
     ///
-    /// This drops the daemon (which stops the monitor task) and the runtime.
+    /// Unregisters the service so the daemon sends goodbye packets, waits for
+    /// the daemon to confirm, then shuts the daemon down (which stops the
+    /// monitor task). Also clears a failed status.
     /// Subsequent start() calls will create new instances.
     pub fn stop(&mut self) {
-        if !self.is_registered {
-            eprintln!("[mDNS] Stop called but service is not running");
-            return;
-        }
+        let daemon = match self.daemon.take() {
+            Some(daemon) => daemon,
+            None => {
+                eprintln!("[mDNS] Stop called but service is not running");
+                return;
+            }
+        };
 
-        let fullname = self.service_fullname();
+        let fullname = self.registered_fullname.clone();
         eprintln!("[mDNS] Stopping service: {}", fullname);
 
-        // Step 1: Drop daemon (causes monitor task to exit)
-        eprintln!("[mDNS] Step 1/2: Dropping daemon (monitor will exit)...");
-        self.daemon.take();
+        // Step 1: Unregister (sends goodbye packets so peers drop the record)
+        eprintln!("[mDNS] Step 1/3: Unregistering service...");
+        match daemon.unregister(&fullname) {
+            Ok(receiver) => match receiver.recv_timeout(UNREGISTER_TIMEOUT) {
+                Ok(UnregisterStatus::OK) => {
+                    eprintln!("[mDNS]   Goodbye packets sent for {}", fullname);
+                }
+                Ok(UnregisterStatus::NotFound) => {
+                    eprintln!("[mDNS]   Service was not registered with the daemon: {}", fullname);
+                }
+                Err(e) => {
+                    eprintln!("[mDNS]   No unregister confirmation: {}", e);
+                }
+            },
+            Err(e) => {
+                eprintln!("[mDNS]   Failed to unregister service: {}", e);
+            }
+        }
+
+        // Step 2: Shut down the daemon (causes monitor task to exit)
+        eprintln!("[mDNS] Step 2/3: Shutting down daemon (monitor will exit)...");
+        match daemon.shutdown() {
+            Ok(receiver) => match receiver.recv_timeout(SHUTDOWN_TIMEOUT) {
+                Ok(DaemonStatus::Shutdown) => eprintln!("[mDNS]   Daemon shut down"),
+                Ok(status) => eprintln!("[mDNS]   Unexpected daemon status: {:?}", status),
+                Err(e) => eprintln!("[mDNS]   No shutdown confirmation: {}", e),
+            },
+            Err(e) => {
+                eprintln!("[mDNS]   Failed to shut down daemon: {}", e);
+            }
+        }
 
-        // Step 2: Update state
-        eprintln!("[mDNS] Step 2/2: Updating internal state...");
-        self.is_registered = false;
+        // Step 3: Update state
+        eprintln!("[mDNS] Step 3/3: Updating internal state...");
+        *self.status.lock() = MdnsStatus::Stopped;
+        self.registered_fullname.clear();
 
         eprintln!("[mDNS] Service stopped successfully: {}", fullname);
     }
@@ -220,10 +358,13 @@ impl Drop for MdnsServerState {
     fn drop(&mut self) {
         let fullname = self.service_fullname();
 
-        // Don't call stop() here - it can cause panics when dropped inside async context
-        // The daemon and runtime will be dropped naturally, which will cancel tasks
-        if self.is_registered {
+        // Don't call stop() here - it blocks waiting for the daemon, which can
+        // cause panics when dropped inside async context. Request the goodbye
+        // packets and shutdown without waiting for confirmation instead.
+        if let Some(daemon) = self.daemon.take() {
             eprintln!("[mDNS] Dropping MdnsServerState (fullname={}) - resources will be cleaned up", fullname);
+            let _ = daemon.unregister(&self.registered_fullname);
+            let _ = daemon.shutdown();
         }
         eprintln!("[mDNS] Dropping runtime (stops async tasks)...");
         self.runtime.take();
@@ -1,7 +1,9 @@
 //! Server module - HTTP server implementation with axum.
 //!
-//! Provides HTTP server functionality with routing, WebSocket support,
-//! static file serving, and mDNS service discovery.
+//! Provides HTTP server functionality with routing, host route handlers,
+//! static file serving, a WebSocket peer hub with room access control,
+//! resumable sessions, heartbeats and rate limits, typed server events, mDNS service registration and
+//! browsing, and a UDP broadcast discovery fallback.
 //!
 //! This module is only available on native platforms (not wasm32).
 
@@ -10,9 +12,55 @@ pub mod http_server;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod router;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod handlers;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod host_config;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod websocket;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod room_access;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sessions;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod heartbeat;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod limits;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod mdns_server;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mdns_browser;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod join_token;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod discovery;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use http_server::HttpServerState;
 #[cfg(not(target_arch = "wasm32"))]
-pub use mdns_server::MdnsServerState;
+pub use handlers::{RouteHandler, RouteRegistry};
+#[cfg(not(target_arch = "wasm32"))]
+pub use router::HeaderPolicy;
+#[cfg(not(target_arch = "wasm32"))]
+pub use host_config::{FieldError, HostConfig};
+#[cfg(not(target_arch = "wasm32"))]
+pub use events::{CoreEvent, EventBus, EventQueue, PeerId};
+#[cfg(not(target_arch = "wasm32"))]
+pub use websocket::{Payload, PeerHub, PeerInfo};
+#[cfg(not(target_arch = "wasm32"))]
+pub use room_access::{AccessError, RoomAccess};
+#[cfg(not(target_arch = "wasm32"))]
+pub use sessions::{seat_from_index, Seat, SessionRegistry};
+#[cfg(not(target_arch = "wasm32"))]
+pub use heartbeat::{HeartbeatConfig, PeerLatency};
+#[cfg(not(target_arch = "wasm32"))]
+pub use limits::{LimitError, Limiter, Limits, RateLimit};
+#[cfg(not(target_arch = "wasm32"))]
+pub use mdns_server::{MdnsServerState, MdnsStatus, TXT_FINGERPRINT};
+#[cfg(not(target_arch = "wasm32"))]
+pub use mdns_browser::MdnsBrowserState;
+#[cfg(not(target_arch = "wasm32"))]
+pub use join_token::{JoinScheme, JoinToken};
+#[cfg(not(target_arch = "wasm32"))]
+pub use discovery::DiscoveryServerState;
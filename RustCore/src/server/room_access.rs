@@ -0,0 +1,271 @@
+//! Room access control for WebSocket joins and the game history.
+//!
+//! The host shows a short random room code (and optionally sets a
+//! password). Clients pass both as query parameters when opening the
+//! WebSocket (`/ws?code=K7M2QX&password=...`) or fetching `/history`
+//! (refused with `403 Forbidden`). Rejected WebSocket clients receive a
+//! `join_rejected` message followed by a close frame with
+//! [`CLOSE_REJECTED`]. Addresses that fail too often are locked out for a
+//! while, so codes cannot be guessed by brute force.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Number of characters in a room code
+pub const ROOM_CODE_LENGTH: usize = 6;
+
+/// Room code characters (no 0/O or 1/I, which are easy to confuse)
+const ROOM_CODE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Close code sent when a join attempt is rejected (application-defined range)
+pub const CLOSE_REJECTED: u16 = 4001;
+
+/// Failed attempts from one address before it is locked out
+pub const DEFAULT_MAX_FAILURES: u32 = 5;
+
+/// How long a locked-out address must wait before trying again
+pub const DEFAULT_LOCKOUT: Duration = Duration::from_secs(60);
+
+/// Why a join attempt was refused
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessError {
+    /// The room code is missing or wrong
+    InvalidCode,
+    /// The room password is missing or wrong
+    WrongPassword,
+    /// Too many failed attempts from this address
+    LockedOut {
+        /// Time left until the address may try again
+        retry_after: Duration,
+    },
+}
+
+impl AccessError {
+    /// Stable machine-readable reason, sent to clients and used as the close reason
+    pub fn reason(&self) -> &'static str {
+        match self {
+            AccessError::InvalidCode => "invalid_code",
+            AccessError::WrongPassword => "wrong_password",
+            AccessError::LockedOut { .. } => "locked_out",
+        }
+    }
+
+    /// JSON message sent to the rejected client before closing
+    ///
+    /// `{"type": "join_rejected", "reason": "...", "message": "...", "retry_after_ms": n}`
+    /// (`retry_after_ms` only for `locked_out`).
+    pub fn to_message(&self) -> String {
+        let mut message = serde_json::json!({
+            "type": "join_rejected",
+            "reason": self.reason(),
+            "message": self.to_string(),
+        });
+        if let AccessError::LockedOut { retry_after } = self {
+            message["retry_after_ms"] = serde_json::json!(retry_after.as_millis() as u64);
+        }
+        message.to_string()
+    }
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessError::InvalidCode => write!(f, "Invalid room code"),
+            AccessError::WrongPassword => write!(f, "Wrong room password"),
+            AccessError::LockedOut { retry_after } => write!(
+                f,
+                "Too many failed attempts, try again in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+        }
+    }
+}
+
+/// Failed attempts from one address
+#[derive(Default)]
+struct Failures {
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+struct Inner {
+    room_code: String,
+    code_required: bool,
+    password_digest: Option<[u8; 32]>,
+    max_failures: u32,
+    lockout: Duration,
+    failures: HashMap<IpAddr, Failures>,
+}
+
+/// Room code, password and lockout state, shared between the server and its router
+///
+/// A new room is open: codes are only checked once
+/// [`set_code_required`](Self::set_code_required) is enabled, and passwords
+/// once one is set.
+#[derive(Clone)]
+pub struct RoomAccess {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for RoomAccess {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RoomAccess {
+    /// Create an open room with a fresh random code
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                room_code: generate_room_code(),
+                code_required: false,
+                password_digest: None,
+                max_failures: DEFAULT_MAX_FAILURES,
+                lockout: DEFAULT_LOCKOUT,
+                failures: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Code players type to join (e.g. "K7M2QX")
+    pub fn room_code(&self) -> String {
+        self.inner.lock().room_code.clone()
+    }
+
+    /// Replace the room code with a fresh random one
+    ///
+    /// # Returns
+    /// The new code
+    pub fn regenerate_room_code(&self) -> String {
+        let code = generate_room_code();
+        self.inner.lock().room_code = code.clone();
+        code
+    }
+
+    /// Whether joins must present the room code
+    pub fn code_required(&self) -> bool {
+        self.inner.lock().code_required
+    }
+
+    /// Require (or stop requiring) the room code
+    pub fn set_code_required(&self, required: bool) {
+        self.inner.lock().code_required = required;
+    }
+
+    /// Set the room password; `None` or an empty string removes it
+    pub fn set_password(&self, password: Option<&str>) {
+        self.inner.lock().password_digest = password
+            .filter(|password| !password.is_empty())
+            .map(password_digest);
+    }
+
+    /// Whether joins must present a password
+    pub fn has_password(&self) -> bool {
+        self.inner.lock().password_digest.is_some()
+    }
+
+    /// Lock an address out for `lockout` after `max_failures` failed attempts
+    ///
+    /// A `max_failures` of 0 disables lockouts.
+    pub fn set_lockout_policy(&self, max_failures: u32, lockout: Duration) {
+        let mut inner = self.inner.lock();
+        inner.max_failures = max_failures;
+        inner.lockout = lockout;
+    }
+
+    /// Time left before a locked-out address may try again
+    pub fn lockout_remaining(&self, address: IpAddr) -> Option<Duration> {
+        let inner = self.inner.lock();
+        let locked_until = inner.failures.get(&address)?.locked_until?;
+        locked_until.checked_duration_since(Instant::now()).filter(|left| !left.is_zero())
+    }
+
+    /// Forget every failed attempt and lift all lockouts
+    pub fn clear_lockouts(&self) {
+        self.inner.lock().failures.clear();
+    }
+
+    /// Check a join attempt from `address`
+    ///
+    /// Codes are compared case-insensitively, ignoring surrounding spaces.
+    /// A successful attempt resets the address's failure count; a failed
+    /// one counts towards a lockout.
+    pub fn check(&self, address: IpAddr, code: Option<&str>, password: Option<&str>) -> Result<(), AccessError> {
+        let mut inner = self.inner.lock();
+        if !inner.code_required && inner.password_digest.is_none() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        if let Some(locked_until) = inner.failures.get(&address).and_then(|f| f.locked_until) {
+            if locked_until > now {
+                return Err(AccessError::LockedOut {
+                    retry_after: locked_until - now,
+                });
+            }
+            // The lockout is over: start counting afresh
+            inner.failures.remove(&address);
+        }
+
+        let code_ok = !inner.code_required
+            || code.is_some_and(|code| code.trim().eq_ignore_ascii_case(&inner.room_code));
+        let password_ok = match inner.password_digest {
+            Some(expected) => password.is_some_and(|password| digests_equal(&password_digest(password), &expected)),
+            None => true,
+        };
+        let error = match (code_ok, password_ok) {
+            (true, true) => {
+                inner.failures.remove(&address);
+                return Ok(());
+            }
+            (false, _) => AccessError::InvalidCode,
+            (true, false) => AccessError::WrongPassword,
+        };
+
+        let (max_failures, lockout) = (inner.max_failures, inner.lockout);
+        let failures = inner.failures.entry(address).or_default();
+        failures.count += 1;
+        if max_failures > 0 && failures.count >= max_failures {
+            failures.locked_until = Some(now + lockout);
+            eprintln!(
+                "[ROOM] Locked out {} for {}s after {} failed attempts",
+                address,
+                lockout.as_secs(),
+                failures.count
+            );
+        }
+        Err(error)
+    }
+}
+
+/// Random room code from [`ROOM_CODE_ALPHABET`]
+fn generate_room_code() -> String {
+    let mut bytes = [0u8; ROOM_CODE_LENGTH];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("system random number generator failed");
+    // 256 is a multiple of the alphabet size, so every character is equally likely
+    bytes
+        .iter()
+        .map(|byte| ROOM_CODE_ALPHABET[*byte as usize % ROOM_CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+fn password_digest(password: &str) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(digest(&SHA256, password.as_bytes()).as_ref());
+    bytes
+}
+
+/// Compare digests without stopping at the first difference
+fn digests_equal(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -1,35 +1,93 @@
 //! Router configuration for axum.
 //!
-//! Provides HTTP routing with static file serving and path traversal protection.
+//! Provides HTTP routing with static file serving, path traversal protection
+//! and per-address request limits. With a game history configured, finished
+//! games are listed at `/history`, fetched at `/history/{id}` and exported
+//! as JSON lines at `/history/export`. The history holds every secret of
+//! those games, so it is only served to clients that pass the room's code
+//! and password checks, like a WebSocket join.
 
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::{ConnectInfo, Path, Query, Request, State},
+    middleware::{self, Next},
     routing::get,
     response::{IntoResponse, Response},
-    Router,
+    Json, Router,
 };
-use http::StatusCode;
+use http::{HeaderValue, StatusCode};
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use tokio::sync::broadcast;
 use bytes::Bytes;
 
+use crate::game::history::{GameHistory, HISTORY_FILE};
+use crate::game::rules::rules_json;
+use crate::tls::Fingerprint;
+
+use super::handlers::{dispatch_host_routes, RouteRegistry};
+use super::limits::{LimitError, Limiter, RequestBodyLimit};
+use super::room_access::{AccessError, RoomAccess};
+use super::websocket::{websocket_handler, PeerHub, WEBSOCKET_PATH};
+
 /// Application state for the router.
 #[derive(Clone)]
 pub struct AppState {
     /// Directory for serving static files
     pub static_dir: PathBuf,
+    /// Connected WebSocket peers
+    pub hub: PeerHub,
+    /// Fingerprint of the served certificate (HTTPS only)
+    pub fingerprint: Option<Fingerprint>,
+    /// Room code, password and lockout checks for WebSocket joins
+    pub access: RoomAccess,
+    /// Finished games, if the host keeps them
+    pub history: Option<GameHistory>,
+}
+
+/// Extra headers added to every response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderPolicy {
+    /// Send `Cross-Origin-Opener-Policy: same-origin` and
+    /// `Cross-Origin-Embedder-Policy: require-corp` (needed by threaded Godot web exports)
+    pub cross_origin_isolation: bool,
+    /// Value of `Access-Control-Allow-Origin`, or None to omit it
+    pub cors_allow_origin: Option<String>,
+}
+
+impl Default for HeaderPolicy {
+    fn default() -> Self {
+        Self {
+            cross_origin_isolation: true,
+            cors_allow_origin: None,
+        }
+    }
 }
 
 /// Create the main router with all routes.
 ///
 /// # Arguments
 /// * `static_dir` - Directory path for static file serving
+/// * `routes` - Host handlers, checked before the built-in routes
+/// * `hub` - WebSocket peer hub served at `/ws`
+/// * `header_policy` - Headers added to every response
+/// * `fingerprint` - Certificate fingerprint reported by `/health` (HTTPS only)
+/// * `access` - Room access checks applied to WebSocket joins and `/history`
+/// * `history` - Finished games served under `/history`, if kept
 ///
 /// # Returns
 /// Configured Axum Router
 #[allow(dead_code)]
-pub fn create_router(static_dir: &str) -> Router {
+pub fn create_router(
+    static_dir: &str,
+    routes: RouteRegistry,
+    hub: PeerHub,
+    header_policy: HeaderPolicy,
+    fingerprint: Option<Fingerprint>,
+    access: RoomAccess,
+    history: Option<GameHistory>,
+) -> Router {
     // Print directly to stderr for debugging
     eprintln!("[ROUTER] Creating router with static directory: {}", static_dir);
 
@@ -39,19 +97,139 @@ pub fn create_router(static_dir: &str) -> Router {
     let (tx, _) = broadcast::channel::<Bytes>(100);
     let _ = tx; // Suppress unused warning
     let static_dir = PathBuf::from(static_dir);
-    let app_state = AppState { static_dir };
+    let app_state = AppState {
+        static_dir,
+        hub,
+        fingerprint,
+        access,
+        history,
+    };
+
+    let history_routes = Router::new()
+        .route("/history", get(history_list_handler))
+        .route("/history/export", get(history_export_handler))
+        .route("/history/{id}", get(history_game_handler))
+        .route_layer(middleware::from_fn_with_state(app_state.access.clone(), require_room_access));
 
     let router = Router::new()
         .route("/", get(serve_index_html))
         .route("/{*path}", get(serve_static_file))
         .route("/health", get(health_handler))
-        .with_state(app_state.clone());
+        .route("/rules", get(rules_handler))
+        .merge(history_routes)
+        .route(WEBSOCKET_PATH, get(websocket_handler))
+        .with_state(app_state.clone())
+        .layer(middleware::from_fn_with_state(routes, dispatch_host_routes))
+        .layer(middleware::from_fn_with_state(app_state.hub.limiter().clone(), limit_requests))
+        .layer(middleware::from_fn_with_state(header_policy, apply_header_policy));
 
     eprintln!("[ROUTER] Router created successfully with static_dir={}", app_state.static_dir.display());
 
     router
 }
 
+/// Apply the per-address request rate and the request body limit
+///
+/// Refused requests get `429 Too Many Requests` (with `Retry-After`) or
+/// `413 Payload Too Large`, with the JSON `error` message as the body.
+async fn limit_requests(State(limiter): State<Limiter>, mut request: Request, next: Next) -> Response {
+    let limits = limiter.limits();
+    if let Some(ConnectInfo(address)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        if let Err(error) = limiter.allow_request(address.ip()) {
+            eprintln!("[ROUTER] Rate limited {}: {}", address, error);
+            return limit_response(StatusCode::TOO_MANY_REQUESTS, &error);
+        }
+    }
+
+    let declared = request
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limits.max_request_body_bytes as u64) {
+        let error = LimitError::TooLarge {
+            max_bytes: limits.max_request_body_bytes,
+        };
+        return limit_response(StatusCode::PAYLOAD_TOO_LARGE, &error);
+    }
+    // Bodies without a length are cut off while being read
+    request
+        .extensions_mut()
+        .insert(RequestBodyLimit(limits.max_request_body_bytes));
+    next.run(request).await
+}
+
+/// JSON error response for a refused request
+fn limit_response(status: StatusCode, error: &LimitError) -> Response {
+    let mut response = Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(error.to_message()))
+        .unwrap_or_else(|_| status.into_response());
+    if let LimitError::RateLimited { retry_after } = error {
+        // Whole seconds, rounded up
+        let seconds = retry_after.as_millis().div_ceil(1000).max(1);
+        if let Ok(value) = HeaderValue::from_str(&seconds.to_string()) {
+            response.headers_mut().insert(http::header::RETRY_AFTER, value);
+        }
+    }
+    response
+}
+
+/// Check the room code and password of a request, like a WebSocket join
+///
+/// Both are read from the `code` and `password` query parameters, and
+/// failures count towards the address's lockout. Refused requests get
+/// `403 Forbidden` (`429 Too Many Requests` with `Retry-After` while the
+/// address is locked out), with the `join_rejected` message as the body.
+async fn require_room_access(
+    State(access): State<RoomAccess>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Query(params): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let code = params.get("code").map(String::as_str);
+    let password = params.get("password").map(String::as_str);
+    let Err(error) = access.check(address.ip(), code, password) else {
+        return next.run(request).await;
+    };
+    eprintln!("[ROUTER] Refused {} from {}: {}", request.uri().path(), address, error);
+    let status = match error {
+        AccessError::LockedOut { .. } => StatusCode::TOO_MANY_REQUESTS,
+        _ => StatusCode::FORBIDDEN,
+    };
+    let mut response = Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(error.to_message()))
+        .unwrap_or_else(|_| status.into_response());
+    if let AccessError::LockedOut { retry_after } = error {
+        // Whole seconds, rounded up
+        let seconds = retry_after.as_millis().div_ceil(1000).max(1);
+        if let Ok(value) = HeaderValue::from_str(&seconds.to_string()) {
+            response.headers_mut().insert(http::header::RETRY_AFTER, value);
+        }
+    }
+    response
+}
+
+/// Add the configured headers to a response
+async fn apply_header_policy(State(policy): State<HeaderPolicy>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    if policy.cross_origin_isolation {
+        headers.insert("Cross-Origin-Opener-Policy", HeaderValue::from_static("same-origin"));
+        headers.insert("Cross-Origin-Embedder-Policy", HeaderValue::from_static("require-corp"));
+    }
+    if let Some(origin) = policy.cors_allow_origin.as_deref() {
+        if let Ok(value) = HeaderValue::from_str(origin) {
+            headers.insert(http::header::ACCESS_CONTROL_ALLOW_ORIGIN, value);
+        }
+    }
+    response
+}
+
 /// Serve index.html for root path
 async fn serve_index_html(State(state): State<AppState>) -> impl IntoResponse {
     serve_static_file(Path("index.html".to_string()), State(state)).await
@@ -59,10 +237,84 @@ async fn serve_index_html(State(state): State<AppState>) -> impl IntoResponse {
 
 /// Health check endpoint handler.
 ///
-/// Returns "OK" to indicate the server is running.
-async fn health_handler() -> &'static str {
+/// Returns `{"status": "ok", "tls": bool, "fingerprint": "AB:CD:..." | null}`
+/// so clients can confirm which certificate the host is serving.
+async fn health_handler(State(state): State<AppState>) -> Json<serde_json::Value> {
     tracing::debug!("[ROUTER] Health check request received");
-    "OK"
+    Json(serde_json::json!({
+        "status": "ok",
+        "tls": state.fingerprint.is_some(),
+        "fingerprint": state.fingerprint.map(|fp| fp.to_string()),
+    }))
+}
+
+/// Quest rules endpoint handler.
+///
+/// Returns the team sizes and fail thresholds for every player count (see
+/// [`rules_json`]), so clients show the numbers the engine plays by.
+async fn rules_handler() -> Json<serde_json::Value> {
+    Json(rules_json())
+}
+
+/// Game history list handler.
+///
+/// Returns `{"games": [...]}` with a summary of every finished game (see
+/// [`GameHistory::list`]), or 404 when the host keeps no history.
+async fn history_list_handler(State(state): State<AppState>) -> Response {
+    let Some(history) = state.history else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match history.list() {
+        Ok(games) => Json(serde_json::json!({ "games": games })).into_response(),
+        Err(e) => {
+            eprintln!("[ROUTER] Could not list the game history: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Game history record handler.
+///
+/// Returns the stored record of one finished game (see
+/// [`GameRecord::to_json`](crate::game::GameRecord::to_json)), or 404.
+async fn history_game_handler(Path(id): Path<String>, State(state): State<AppState>) -> Response {
+    let Some(history) = state.history else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match history.get(&id) {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("[ROUTER] Could not read game {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Game history export handler.
+///
+/// Returns every finished game as a JSON lines download, or 404 when the
+/// host keeps no history.
+async fn history_export_handler(State(state): State<AppState>) -> Response {
+    let Some(history) = state.history else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match history.export() {
+        Ok(lines) => {
+            let mut response = lines.into_response();
+            let headers = response.headers_mut();
+            headers.insert(http::header::CONTENT_TYPE, HeaderValue::from_static("application/x-ndjson"));
+            let disposition = format!("attachment; filename=\"{}\"", HISTORY_FILE);
+            if let Ok(value) = HeaderValue::from_str(&disposition) {
+                headers.insert(http::header::CONTENT_DISPOSITION, value);
+            }
+            response
+        }
+        Err(e) => {
+            eprintln!("[ROUTER] Could not export the game history: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
 }
 
 /// Serve static files with path traversal protection.
@@ -138,8 +390,6 @@ async fn serve_static_file(Path(path): Path<String>, State(state): State<AppStat
             Response::builder()
                 .status(StatusCode::OK)
                 .header(http::header::CONTENT_TYPE, content_type)
-                .header("Cross-Origin-Opener-Policy", "same-origin")
-                .header("Cross-Origin-Embedder-Policy", "require-corp")
                 .body(Body::from(content))
                 .unwrap()
         }
@@ -0,0 +1,359 @@
+//! Resumable player sessions.
+//!
+//! Every WebSocket peer gets a session token, sent to it in a `session`
+//! message right after it joins. Once the host seats a peer, its seat and
+//! the private messages sent to that seat (such as a hidden role) are held
+//! for a grace period after the connection drops. A client that reconnects
+//! with `/ws?session=<token>` within the grace period takes its seat back
+//! and receives the private messages again.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::error::CoreError;
+
+use super::events::PeerId;
+
+/// How long a seat is held for a disconnected player by default
+pub const DEFAULT_RECONNECT_GRACE: Duration = Duration::from_secs(120);
+
+/// Random bytes in a session token (sent as lowercase hex)
+const SESSION_TOKEN_BYTES: usize = 16;
+
+/// Seat index in the room
+pub type Seat = u32;
+
+/// Convert a seat index from the host, where -1 usually means "no seat"
+///
+/// # Returns
+/// [`CoreError::InvalidSeat`] for a negative index or one past `u32::MAX`
+pub fn seat_from_index(index: i64) -> Result<Seat, CoreError> {
+    Seat::try_from(index).map_err(|_| CoreError::InvalidSeat(index))
+}
+
+/// A player's session
+struct Session {
+    /// Current peer, or the last one while held
+    peer_id: PeerId,
+    connected: bool,
+    seat: Option<Seat>,
+    /// Private messages for the seat, by key, in the order first sent
+    private_messages: Vec<(String, String)>,
+    /// Bumped on every disconnect so stale expiry timers are ignored
+    generation: u64,
+}
+
+struct SessionsInner {
+    sessions: HashMap<String, Session>,
+    tokens: HashMap<PeerId, String>,
+    grace_period: Duration,
+}
+
+/// Result of binding a new connection to a session
+pub(crate) struct Attached {
+    /// Token the client should use to resume
+    pub token: String,
+    /// Set when an existing session was resumed
+    pub resumed: Option<Resumed>,
+}
+
+/// A resumed session
+pub(crate) struct Resumed {
+    /// Peer the session belonged to before
+    pub previous_peer_id: PeerId,
+    /// Still connected previous peer that must be disconnected
+    pub replaced: Option<PeerId>,
+    /// Seat taken back, if any
+    pub seat: Option<Seat>,
+    /// Private messages to send again
+    pub private_messages: Vec<String>,
+}
+
+/// A seat held after its player disconnected
+pub(crate) struct Held {
+    pub token: String,
+    pub seat: Seat,
+    pub generation: u64,
+    pub grace_period: Duration,
+}
+
+/// Session tokens, seats and private messages, shared by the peer hub
+#[derive(Clone)]
+pub struct SessionRegistry {
+    inner: Arc<Mutex<SessionsInner>>,
+}
+
+impl Default for SessionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionRegistry {
+    /// Create an empty registry with [`DEFAULT_RECONNECT_GRACE`]
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SessionsInner {
+                sessions: HashMap::new(),
+                tokens: HashMap::new(),
+                grace_period: DEFAULT_RECONNECT_GRACE,
+            })),
+        }
+    }
+
+    /// How long seats are held after a disconnect
+    pub fn grace_period(&self) -> Duration {
+        self.inner.lock().grace_period
+    }
+
+    /// Set how long seats are held after a disconnect (zero frees them at once)
+    ///
+    /// Applies to later disconnects.
+    pub fn set_grace_period(&self, grace_period: Duration) {
+        self.inner.lock().grace_period = grace_period;
+    }
+
+    /// Check if `token` names a session that can still be resumed
+    pub fn is_live(&self, token: &str) -> bool {
+        self.inner.lock().sessions.contains_key(token)
+    }
+
+    /// Seat of a connected peer
+    pub fn seat_of(&self, peer_id: PeerId) -> Option<Seat> {
+        let inner = self.inner.lock();
+        let token = inner.tokens.get(&peer_id)?;
+        inner.sessions.get(token)?.seat
+    }
+
+    /// Peer in a seat, connected or held
+    ///
+    /// # Returns
+    /// The peer id (the last one if held) and whether it is connected
+    pub fn occupant(&self, seat: Seat) -> Option<(PeerId, bool)> {
+        self.inner
+            .lock()
+            .sessions
+            .values()
+            .find(|session| session.seat == Some(seat))
+            .map(|session| (session.peer_id, session.connected))
+    }
+
+    /// Every occupied seat with its peer and whether it is connected, in order
+    pub fn seats(&self) -> Vec<(Seat, PeerId, bool)> {
+        let mut seats: Vec<(Seat, PeerId, bool)> = self
+            .inner
+            .lock()
+            .sessions
+            .values()
+            .filter_map(|session| Some((session.seat?, session.peer_id, session.connected)))
+            .collect();
+        seats.sort_unstable();
+        seats
+    }
+
+    /// Seats held for disconnected players, in order
+    pub fn held_seats(&self) -> Vec<Seat> {
+        let mut seats: Vec<Seat> = self
+            .inner
+            .lock()
+            .sessions
+            .values()
+            .filter(|session| !session.connected)
+            .filter_map(|session| session.seat)
+            .collect();
+        seats.sort_unstable();
+        seats
+    }
+
+    /// Seat a connected peer, or unseat it with `None`
+    ///
+    /// Private messages stay with the session, so call
+    /// [`release_seat`](Self::release_seat) to clear them when a player leaves.
+    ///
+    /// # Returns
+    /// false if the peer is unknown or the seat belongs to another session
+    pub fn set_seat(&self, peer_id: PeerId, seat: Option<Seat>) -> bool {
+        let mut inner = self.inner.lock();
+        let Some(token) = inner.tokens.get(&peer_id).cloned() else {
+            return false;
+        };
+        let taken = seat.is_some()
+            && inner
+                .sessions
+                .iter()
+                .any(|(other, session)| *other != token && session.seat == seat);
+        if taken {
+            return false;
+        }
+        match inner.sessions.get_mut(&token) {
+            Some(session) => {
+                session.seat = seat;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Free a seat: a held session is dropped, a connected one unseated,
+    /// and the seat's private messages are forgotten
+    ///
+    /// # Returns
+    /// The peer that occupied the seat
+    pub fn release_seat(&self, seat: Seat) -> Option<PeerId> {
+        let mut inner = self.inner.lock();
+        let token = inner
+            .sessions
+            .iter()
+            .find(|(_, session)| session.seat == Some(seat))
+            .map(|(token, _)| token.clone())?;
+        let session = inner.sessions.get_mut(&token)?;
+        let peer_id = session.peer_id;
+        if session.connected {
+            session.seat = None;
+            session.private_messages.clear();
+        } else {
+            inner.sessions.remove(&token);
+        }
+        Some(peer_id)
+    }
+
+    /// Remember a private message for a seat, replacing any with the same key
+    ///
+    /// # Returns
+    /// false if no session holds the seat
+    pub(crate) fn store_private_message(&self, seat: Seat, key: &str, message: &str) -> bool {
+        let mut inner = self.inner.lock();
+        let Some(session) = inner.sessions.values_mut().find(|session| session.seat == Some(seat)) else {
+            return false;
+        };
+        match session.private_messages.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = message.to_string(),
+            None => session.private_messages.push((key.to_string(), message.to_string())),
+        }
+        true
+    }
+
+    /// Bind a new connection to a fresh session, or resume the one `token` names
+    ///
+    /// Unknown or expired tokens get a fresh session.
+    pub(crate) fn attach(&self, peer_id: PeerId, token: Option<&str>) -> Attached {
+        let mut inner = self.inner.lock();
+        if let Some(token) = token.filter(|token| inner.sessions.contains_key(*token)) {
+            let token = token.to_string();
+            let session = inner.sessions.get_mut(&token).expect("checked above");
+            let previous_peer_id = session.peer_id;
+            let replaced = session.connected.then_some(previous_peer_id);
+            session.peer_id = peer_id;
+            session.connected = true;
+            session.generation += 1;
+            let resumed = Resumed {
+                previous_peer_id,
+                replaced,
+                seat: session.seat,
+                private_messages: session.private_messages.iter().map(|(_, m)| m.clone()).collect(),
+            };
+            inner.tokens.remove(&previous_peer_id);
+            inner.tokens.insert(peer_id, token.clone());
+            return Attached {
+                token,
+                resumed: Some(resumed),
+            };
+        }
+
+        let token = generate_token();
+        inner.sessions.insert(
+            token.clone(),
+            Session {
+                peer_id,
+                connected: true,
+                seat: None,
+                private_messages: Vec::new(),
+                generation: 0,
+            },
+        );
+        inner.tokens.insert(peer_id, token.clone());
+        Attached { token, resumed: None }
+    }
+
+    /// Unbind a disconnected peer
+    ///
+    /// Seated sessions are held for the grace period; others are dropped.
+    pub(crate) fn detach(&self, peer_id: PeerId) -> Option<Held> {
+        let mut inner = self.inner.lock();
+        let token = inner.tokens.remove(&peer_id)?;
+        let grace_period = inner.grace_period;
+        let session = inner.sessions.get_mut(&token)?;
+        match session.seat {
+            Some(seat) if !grace_period.is_zero() => {
+                session.connected = false;
+                session.generation += 1;
+                Some(Held {
+                    token,
+                    seat,
+                    generation: session.generation,
+                    grace_period,
+                })
+            }
+            _ => {
+                inner.sessions.remove(&token);
+                None
+            }
+        }
+    }
+
+    /// Drop a session without holding its seat (the peer was kicked)
+    pub(crate) fn forget(&self, peer_id: PeerId) {
+        let mut inner = self.inner.lock();
+        if let Some(token) = inner.tokens.remove(&peer_id) {
+            inner.sessions.remove(&token);
+        }
+    }
+
+    /// Release a held seat whose grace period ran out
+    ///
+    /// Does nothing if the player came back or the seat was released since.
+    ///
+    /// # Returns
+    /// The last peer id and the seat that was freed
+    pub(crate) fn expire(&self, token: &str, generation: u64) -> Option<(PeerId, Seat)> {
+        let mut inner = self.inner.lock();
+        let session = inner.sessions.get(token)?;
+        if session.connected || session.generation != generation {
+            return None;
+        }
+        let released = (session.peer_id, session.seat?);
+        inner.sessions.remove(token);
+        Some(released)
+    }
+
+    /// Drop every session (the server stopped)
+    pub(crate) fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.sessions.clear();
+        inner.tokens.clear();
+    }
+}
+
+/// `session` message telling a client its token and seat
+pub(crate) fn session_message(token: &str, peer_id: PeerId, seat: Option<Seat>, resumed: bool) -> String {
+    serde_json::json!({
+        "type": "session",
+        "token": token,
+        "peer_id": peer_id,
+        "seat": seat,
+        "resumed": resumed,
+    })
+    .to_string()
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; SESSION_TOKEN_BYTES];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("system random number generator failed");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,649 @@
+//! WebSocket peer hub.
+//!
+//! Clients connect to [`WEBSOCKET_PATH`] on the HTTP(S) server. Each
+//! connection gets a [`PeerId`] and its lifecycle and text messages are
+//! published on the server's [`EventBus`]. The host answers through
+//! [`PeerHub::send`], [`PeerHub::broadcast`] and [`PeerHub::kick`].
+//! Connections are checked against the room's
+//! [`RoomAccess`](super::room_access::RoomAccess) before they become peers,
+//! and each peer is bound to a resumable session (see [`super::sessions`]).
+//! Peers are pinged to measure latency and to drop dead connections (see
+//! [`super::heartbeat`]), and their message rates and sizes are capped (see
+//! [`super::limits`]).
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Query, State};
+use axum::response::Response;
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+use crate::error::CoreError;
+use crate::types::SharedServerState;
+
+use super::events::{CoreEvent, EventBus, PeerId};
+use super::heartbeat::{HeartbeatConfig, HeartbeatState, PeerLatency, Tick, CLOSE_TIMED_OUT};
+use super::limits::{
+    LimitError, Limiter, TokenBucket, CLOSE_MESSAGE_TOO_BIG, CLOSE_POLICY_VIOLATION, CLOSE_TRY_AGAIN_LATER,
+};
+use super::room_access::CLOSE_REJECTED;
+use super::router::AppState;
+use super::sessions::{session_message, Seat, SessionRegistry};
+
+/// Path of the WebSocket endpoint
+pub const WEBSOCKET_PATH: &str = "/ws";
+
+/// First id handed out (0 means "everyone" and 1 is the host, as in Godot)
+const FIRST_PEER_ID: PeerId = 2;
+
+/// Close code sent when the server shuts down ("going away")
+const CLOSE_GOING_AWAY: u16 = 1001;
+
+/// Close code sent when the host kicks a peer (application-defined range)
+pub const CLOSE_KICKED: u16 = 4000;
+
+/// How long a closing connection may take to flush its last frames
+const CLOSE_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Data sent to a peer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Payload {
+    /// UTF-8 text frame (the JSON protocol uses these)
+    Text(String),
+    /// Binary frame
+    Binary(Vec<u8>),
+}
+
+impl From<Payload> for Message {
+    fn from(payload: Payload) -> Self {
+        match payload {
+            Payload::Text(text) => Message::Text(text.into()),
+            Payload::Binary(bytes) => Message::Binary(bytes.into()),
+        }
+    }
+}
+
+/// A connected peer as seen by the host
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// Peer id
+    pub peer_id: PeerId,
+    /// Remote address
+    pub address: SocketAddr,
+}
+
+/// A connected client
+struct Peer {
+    /// Remote address
+    address: SocketAddr,
+    /// Frames waiting to be written to the socket
+    outbound: mpsc::UnboundedSender<Message>,
+    /// Outstanding ping and round-trip statistics
+    heartbeat: HeartbeatState,
+}
+
+/// Connection slots admitted but not yet upgraded, by address
+type Reservations = Arc<Mutex<HashMap<IpAddr, usize>>>;
+
+/// A connection slot held from the admission check until the peer joins
+///
+/// Dropping it, e.g. when the upgrade fails, frees the slot.
+struct Reservation {
+    reservations: Reservations,
+    address: IpAddr,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let mut reservations = self.reservations.lock();
+        if let Some(count) = reservations.get_mut(&self.address) {
+            *count -= 1;
+            if *count == 0 {
+                reservations.remove(&self.address);
+            }
+        }
+    }
+}
+
+/// Connected WebSocket peers, shared between the server and its router
+#[derive(Clone)]
+pub struct PeerHub {
+    /// Locked before `reservations` wherever both are held
+    peers: Arc<Mutex<HashMap<PeerId, Peer>>>,
+    reservations: Reservations,
+    next_id: Arc<AtomicU32>,
+    events: EventBus,
+    state: SharedServerState,
+    sessions: SessionRegistry,
+    heartbeat: Arc<Mutex<HeartbeatConfig>>,
+    limiter: Limiter,
+}
+
+impl PeerHub {
+    /// Create a hub publishing on `events` and counting clients in `state`
+    pub fn new(events: EventBus, state: SharedServerState) -> Self {
+        Self {
+            peers: Arc::new(Mutex::new(HashMap::new())),
+            reservations: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU32::new(FIRST_PEER_ID)),
+            events,
+            state,
+            sessions: SessionRegistry::new(),
+            heartbeat: Arc::new(Mutex::new(HeartbeatConfig::default())),
+            limiter: Limiter::new(),
+        }
+    }
+
+    /// Connection caps, size limits and rate limits
+    pub fn limiter(&self) -> &Limiter {
+        &self.limiter
+    }
+
+    /// Ping interval and missed-pong thresholds
+    pub fn heartbeat_config(&self) -> HeartbeatConfig {
+        *self.heartbeat.lock()
+    }
+
+    /// Change the ping settings
+    ///
+    /// The interval applies to connections opened afterwards; the thresholds
+    /// apply at once.
+    pub fn set_heartbeat_config(&self, config: HeartbeatConfig) {
+        *self.heartbeat.lock() = config;
+    }
+
+    /// Round-trip statistics of a connected peer
+    pub fn latency(&self, peer_id: PeerId) -> Option<PeerLatency> {
+        self.peers.lock().get(&peer_id).map(|peer| peer.heartbeat.latency())
+    }
+
+    /// Round-trip statistics of every connected, seated player, ordered by seat
+    pub fn seat_latencies(&self) -> Vec<(Seat, PeerId, PeerLatency)> {
+        self.sessions
+            .seats()
+            .into_iter()
+            .filter(|(_, _, connected)| *connected)
+            .filter_map(|(seat, peer_id, _)| Some((seat, peer_id, self.latency(peer_id)?)))
+            .collect()
+    }
+
+    /// Player sessions: seats, held seats and the reconnect grace period
+    pub fn sessions(&self) -> &SessionRegistry {
+        &self.sessions
+    }
+
+    /// Number of connected peers
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().len()
+    }
+
+    /// Connected peers, ordered by id
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<PeerInfo> = self
+            .peers
+            .lock()
+            .iter()
+            .map(|(peer_id, peer)| PeerInfo {
+                peer_id: *peer_id,
+                address: peer.address,
+            })
+            .collect();
+        peers.sort_by_key(|peer| peer.peer_id);
+        peers
+    }
+
+    /// Check if a peer is connected
+    pub fn contains(&self, peer_id: PeerId) -> bool {
+        self.peers.lock().contains_key(&peer_id)
+    }
+
+    /// Queue a frame for one peer
+    pub fn send(&self, peer_id: PeerId, payload: Payload) -> Result<(), CoreError> {
+        let peers = self.peers.lock();
+        let peer = peers.get(&peer_id).ok_or(CoreError::PeerNotFound(peer_id))?;
+        // The writer only goes away once the peer is being removed
+        peer.outbound
+            .send(payload.into())
+            .map_err(|_| CoreError::PeerNotFound(peer_id))
+    }
+
+    /// Queue a frame for every peer except `exclude`
+    ///
+    /// # Returns
+    /// Number of peers the frame was queued for
+    pub fn broadcast(&self, payload: Payload, exclude: Option<PeerId>) -> usize {
+        let message: Message = payload.into();
+        self.peers
+            .lock()
+            .iter()
+            .filter(|(peer_id, _)| Some(**peer_id) != exclude)
+            .filter(|(_, peer)| peer.outbound.send(message.clone()).is_ok())
+            .count()
+    }
+
+    /// Send a private message to the player in `seat` and keep it for resumes
+    ///
+    /// The message replaces any earlier one stored under `key` and is sent
+    /// again, in order, whenever the player reconnects with their session
+    /// token. If the player is currently disconnected it is only stored.
+    pub fn send_to_seat(&self, seat: Seat, key: &str, message: &str) -> Result<(), CoreError> {
+        if !self.sessions.store_private_message(seat, key, message) {
+            return Err(CoreError::SeatNotFound(seat));
+        }
+        match self.sessions.occupant(seat) {
+            Some((peer_id, true)) => self.send(peer_id, Payload::Text(message.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Disconnect a peer with [`CLOSE_KICKED`] and a reason
+    ///
+    /// The peer is removed (and `ClientDisconnected` emitted) immediately, so
+    /// no further messages are delivered to or from it. Its session ends
+    /// and its seat is not held.
+    pub fn kick(&self, peer_id: PeerId, reason: &str) -> Result<(), CoreError> {
+        self.close(peer_id, CLOSE_KICKED, reason, None)?;
+        eprintln!("[WS] Kicked peer {}: {}", peer_id, reason);
+        Ok(())
+    }
+
+    /// Remove a peer, ending its session, and close its socket after an optional last message
+    fn close(&self, peer_id: PeerId, code: u16, reason: &str, notice: Option<String>) -> Result<(), CoreError> {
+        let peer = self.peers.lock().remove(&peer_id).ok_or(CoreError::PeerNotFound(peer_id))?;
+        self.sessions.forget(peer_id);
+        if let Some(notice) = notice {
+            let _ = peer.outbound.send(Message::Text(notice.into()));
+        }
+        let _ = peer.outbound.send(Message::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        })));
+        self.disconnected(peer_id);
+        Ok(())
+    }
+
+    /// Disconnect a peer that exceeded a limit, telling it why
+    fn close_for_limit(&self, peer_id: PeerId, code: u16, error: &LimitError) {
+        if self.close(peer_id, code, error.code(), Some(error.to_message())).is_ok() {
+            eprintln!("[WS] Disconnected peer {}: {}", peer_id, error);
+            self.events.emit(CoreEvent::PeerLimited {
+                peer_id,
+                reason: error.code().to_string(),
+            });
+        }
+    }
+
+    /// Check the connection caps for a new peer from `address` and hold its slot
+    ///
+    /// Slots held by connections still upgrading count against the caps, so
+    /// concurrent joins cannot exceed them. Resuming players may join while
+    /// new players are refused.
+    fn admit(&self, address: IpAddr, resuming: bool) -> Result<Reservation, LimitError> {
+        if !resuming && !self.limiter.is_accepting() {
+            return Err(LimitError::NotAccepting);
+        }
+        let limits = self.limiter.limits();
+        let peers = self.peers.lock();
+        let mut reservations = self.reservations.lock();
+        let reserved: usize = reservations.values().sum();
+        if limits.max_connections > 0 && peers.len() + reserved >= limits.max_connections {
+            return Err(LimitError::ServerFull);
+        }
+        let from_address = peers.values().filter(|peer| peer.address.ip() == address).count()
+            + reservations.get(&address).copied().unwrap_or(0);
+        if limits.max_connections_per_address > 0 && from_address >= limits.max_connections_per_address {
+            return Err(LimitError::TooManyConnections);
+        }
+        *reservations.entry(address).or_insert(0) += 1;
+        Ok(Reservation {
+            reservations: self.reservations.clone(),
+            address,
+        })
+    }
+
+    /// Spend a message token for a peer
+    fn allow_message(&self, bucket: &mut TokenBucket, address: IpAddr) -> Result<(), LimitError> {
+        let rate = self.limiter.limits().connection_message_rate;
+        bucket
+            .take(rate, Instant::now())
+            .map_err(|retry_after| LimitError::RateLimited { retry_after })?;
+        self.limiter.allow_message(address)
+    }
+
+    /// Ask every peer to close (used when the server stops)
+    pub fn disconnect_all(&self) {
+        let peers = self.peers.lock();
+        for peer in peers.values() {
+            let _ = peer.outbound.send(Message::Close(Some(CloseFrame {
+                code: CLOSE_GOING_AWAY,
+                reason: "Server stopped".into(),
+            })));
+        }
+    }
+
+    fn allocate_id(&self) -> PeerId {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Turn an admitted connection's slot into a peer
+    fn add(&self, peer_id: PeerId, peer: Peer, reservation: Reservation) {
+        let address = peer.address;
+        let count = {
+            let mut peers = self.peers.lock();
+            peers.insert(peer_id, peer);
+            drop(reservation);
+            peers.len()
+        };
+        self.state.lock().connected_clients = count;
+        eprintln!("[WS] Peer {} connected from {} ({} connected)", peer_id, address, count);
+        self.events.emit(CoreEvent::ClientConnected {
+            peer_id,
+            address: address.to_string(),
+        });
+    }
+
+    fn remove(&self, peer_id: PeerId) {
+        let removed = self.peers.lock().remove(&peer_id).is_some();
+        if removed {
+            self.disconnected(peer_id);
+            self.hold_seat(peer_id);
+        }
+    }
+
+    /// Bind a new peer to its session and tell it its token
+    ///
+    /// A resumed session gets its seat back and its private messages again;
+    /// a connection still using the session is closed.
+    fn start_session(&self, peer_id: PeerId, token: Option<&str>) {
+        let attached = self.sessions.attach(peer_id, token);
+        let resumed = match attached.resumed {
+            Some(resumed) => resumed,
+            None => {
+                let _ = self.send(peer_id, Payload::Text(session_message(&attached.token, peer_id, None, false)));
+                return;
+            }
+        };
+
+        if let Some(replaced) = resumed.replaced {
+            let _ = self.kick(replaced, "Session resumed from another connection");
+        }
+        let _ = self.send(
+            peer_id,
+            Payload::Text(session_message(&attached.token, peer_id, resumed.seat, true)),
+        );
+        for message in resumed.private_messages {
+            let _ = self.send(peer_id, Payload::Text(message));
+        }
+        eprintln!(
+            "[WS] Peer {} resumed the session of peer {} (seat {:?})",
+            peer_id, resumed.previous_peer_id, resumed.seat
+        );
+        self.events.emit(CoreEvent::PlayerResumed {
+            peer_id,
+            previous_peer_id: resumed.previous_peer_id,
+            seat: resumed.seat,
+        });
+    }
+
+    /// Hold a departed peer's seat and release it when the grace period ends
+    fn hold_seat(&self, peer_id: PeerId) {
+        let Some(held) = self.sessions.detach(peer_id) else {
+            return;
+        };
+        eprintln!("[WS] Holding seat {} of peer {} for {:?}", held.seat, peer_id, held.grace_period);
+        self.events.emit(CoreEvent::SeatHeld {
+            peer_id,
+            seat: held.seat,
+            grace_ms: held.grace_period.as_millis() as u64,
+        });
+
+        let sessions = self.sessions.clone();
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(held.grace_period).await;
+            if let Some((peer_id, seat)) = sessions.expire(&held.token, held.generation) {
+                eprintln!("[WS] Released seat {} of peer {}", seat, peer_id);
+                events.emit(CoreEvent::SeatReleased { peer_id, seat });
+            }
+        });
+    }
+
+    /// Decide what to do now that a ping is due for a peer
+    ///
+    /// # Returns
+    /// None once the peer has left the hub
+    fn heartbeat_tick(&self, peer_id: PeerId) -> Option<Tick> {
+        let config = self.heartbeat_config();
+        let tick = self.peers.lock().get_mut(&peer_id)?.heartbeat.tick(&config, Instant::now());
+        match &tick {
+            Tick::PingStale(_, missed_pongs) => {
+                eprintln!("[WS] Peer {} is stale ({} pings unanswered)", peer_id, missed_pongs);
+                self.events.emit(CoreEvent::PeerStale {
+                    peer_id,
+                    missed_pongs: *missed_pongs,
+                });
+            }
+            Tick::TimedOut(missed_pongs) => {
+                eprintln!("[WS] Peer {} timed out ({} pings unanswered)", peer_id, missed_pongs);
+            }
+            Tick::Ping(_) => {}
+        }
+        Some(tick)
+    }
+
+    /// Record a pong from a peer
+    fn pong(&self, peer_id: PeerId, payload: &[u8]) {
+        let (recovered, latency) = {
+            let mut peers = self.peers.lock();
+            let Some(peer) = peers.get_mut(&peer_id) else {
+                return;
+            };
+            (peer.heartbeat.pong(payload, Instant::now()), peer.heartbeat.latency())
+        };
+        if recovered {
+            let rtt_ms = latency.rtt.unwrap_or_default().as_millis() as u64;
+            eprintln!("[WS] Peer {} recovered (rtt {} ms)", peer_id, rtt_ms);
+            self.events.emit(CoreEvent::PeerRecovered { peer_id, rtt_ms });
+        }
+    }
+
+    /// Update the client count and report a removed peer
+    fn disconnected(&self, peer_id: PeerId) {
+        let count = self.peer_count();
+        self.state.lock().connected_clients = count;
+        eprintln!("[WS] Peer {} disconnected ({} connected)", peer_id, count);
+        self.events.emit(CoreEvent::ClientDisconnected { peer_id });
+    }
+
+    /// Serve one upgraded connection until either side closes it
+    async fn run_peer(self, socket: WebSocket, address: SocketAddr, session: Option<String>, reservation: Reservation) {
+        let peer_id = self.allocate_id();
+        let (mut sink, mut stream) = socket.split();
+        let (outbound, mut outbound_rx) = mpsc::unbounded_channel();
+        self.add(
+            peer_id,
+            Peer {
+                address,
+                outbound,
+                heartbeat: HeartbeatState::default(),
+            },
+            reservation,
+        );
+        self.start_session(peer_id, session.as_deref());
+
+        let interval = self.heartbeat_config().interval;
+        let mut bucket = TokenBucket::new(self.limiter.limits().connection_message_rate, Instant::now());
+        let mut violations = 0u32;
+        let hub = self.clone();
+        let mut writer_done = false;
+        let mut writer = tokio::spawn(async move {
+            let mut pings = (!interval.is_zero()).then(|| {
+                let mut pings = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+                pings.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                pings
+            });
+            loop {
+                let message = tokio::select! {
+                    message = outbound_rx.recv() => match message {
+                        Some(message) => message,
+                        None => break,
+                    },
+                    _ = next_ping(&mut pings) => match hub.heartbeat_tick(peer_id) {
+                        Some(Tick::Ping(payload)) | Some(Tick::PingStale(payload, _)) => Message::Ping(payload.into()),
+                        Some(Tick::TimedOut(_)) => Message::Close(Some(CloseFrame {
+                            code: CLOSE_TIMED_OUT,
+                            reason: "Heartbeat timeout".into(),
+                        })),
+                        None => break,
+                    },
+                };
+                let closing = matches!(message, Message::Close(_));
+                if sink.send(message).await.is_err() || closing {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            let frame = tokio::select! {
+                frame = stream.next() => match frame {
+                    Some(frame) => frame,
+                    None => break,
+                },
+                // The writer stops after sending a close frame (or failing to
+                // write), which a half-open connection would never answer
+                _ = &mut writer => {
+                    writer_done = true;
+                    break;
+                }
+            };
+            match frame {
+                // Kicked peers are dropped from the hub before their socket closes
+                Ok(_) if !self.contains(peer_id) => break,
+                Ok(message @ (Message::Text(_) | Message::Binary(_))) => {
+                    if let Err(error) = self.allow_message(&mut bucket, address.ip()) {
+                        violations += 1;
+                        let max_violations = self.limiter.limits().max_violations;
+                        if max_violations > 0 && violations >= max_violations {
+                            self.close_for_limit(peer_id, CLOSE_POLICY_VIOLATION, &error);
+                            break;
+                        }
+                        let _ = self.send(peer_id, Payload::Text(error.to_message()));
+                        continue;
+                    }
+                    // Binary frames are not part of the protocol
+                    if let Message::Text(text) = message {
+                        self.events.emit(CoreEvent::MessageReceived {
+                            peer_id,
+                            message: text.to_string(),
+                        });
+                    }
+                }
+                Ok(Message::Pong(payload)) => self.pong(peer_id, &payload),
+                Ok(Message::Close(_)) => break,
+                // Pings are answered by axum
+                Ok(_) => {}
+                Err(e) if is_capacity_error(&e) => {
+                    let max_bytes = self.limiter.limits().max_message_bytes;
+                    self.close_for_limit(peer_id, CLOSE_MESSAGE_TOO_BIG, &LimitError::TooLarge { max_bytes });
+                    break;
+                }
+                Err(e) => {
+                    tracing::debug!("[WS] Peer {} read error: {}", peer_id, e);
+                    break;
+                }
+            }
+        }
+
+        self.remove(peer_id);
+        // Removing the peer closes its queue, so the writer finishes once
+        // any last error and close frames are flushed
+        if !writer_done && tokio::time::timeout(CLOSE_FLUSH_TIMEOUT, &mut writer).await.is_err() {
+            writer.abort();
+        }
+    }
+}
+
+/// Wait for the next ping, or forever if heartbeats are disabled
+async fn next_ping(pings: &mut Option<tokio::time::Interval>) {
+    match pings {
+        Some(pings) => {
+            pings.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Whether a read failed because a frame or message was over the size limit
+fn is_capacity_error(error: &axum::Error) -> bool {
+    // axum does not expose the tungstenite error kind, only its text
+    error.to_string().contains("Space limit exceeded")
+}
+
+/// Tell a refused client why and close the socket
+async fn reject(mut socket: WebSocket, message: String, code: u16, reason: &'static str) {
+    let _ = socket.send(Message::Text(message.into())).await;
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        })))
+        .await;
+}
+
+/// Upgrade handler for [`WEBSOCKET_PATH`]
+///
+/// The room code and password are read from the `code` and `password`
+/// query parameters. A `session` parameter naming a live session resumes
+/// it without checking the code and password again. Joins over the
+/// connection caps are refused with an `error` message.
+pub async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Query(mut params): Query<HashMap<String, String>>,
+    State(state): State<AppState>,
+) -> Response {
+    let session = params
+        .remove("session")
+        .filter(|token| state.hub.sessions.is_live(token));
+    let code = params.get("code").map(String::as_str);
+    let password = params.get("password").map(String::as_str);
+    let checked = match session {
+        Some(_) => Ok(()),
+        None => state.access.check(address.ip(), code, password),
+    };
+    if let Err(error) = checked {
+        eprintln!("[WS] Rejected join from {}: {}", address, error);
+        state.hub.events.emit(CoreEvent::JoinRejected {
+            address: address.to_string(),
+            reason: error.reason().to_string(),
+        });
+        return ws.on_upgrade(move |socket| reject(socket, error.to_message(), CLOSE_REJECTED, error.reason()));
+    }
+
+    let reservation = match state.hub.admit(address.ip(), session.is_some()) {
+        Ok(reservation) => reservation,
+        Err(error) => {
+            eprintln!("[WS] Refused connection from {}: {}", address, error);
+            state.hub.events.emit(CoreEvent::ConnectionRefused {
+                address: address.to_string(),
+                reason: error.code().to_string(),
+            });
+            return ws
+                .on_upgrade(move |socket| reject(socket, error.to_message(), CLOSE_TRY_AGAIN_LATER, error.code()));
+        }
+    };
+
+    let limits = state.hub.limiter.limits();
+    let hub = state.hub.clone();
+    ws.max_frame_size(limits.max_frame_bytes)
+        .max_message_size(limits.max_message_bytes)
+        // A failed upgrade drops the callback and with it the reservation
+        .on_upgrade(move |socket| hub.run_peer(socket, address, session, reservation))
+}
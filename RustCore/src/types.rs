@@ -1,7 +1,9 @@
 //! Shared types for RustCore.
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 
 /// Server state configuration.
 #[derive(Clone, Debug)]
@@ -18,30 +20,45 @@ pub struct ServerConfig {
     pub key_path: Option<String>,
 }
 
-/// HTTP request data.
-#[derive(Clone, Debug)]
+/// HTTP request data passed to host route handlers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HttpRequest {
     /// HTTP method (GET, POST, etc.)
     pub method: String,
     /// Request path
     pub path: String,
+    /// Query string without the leading '?'
+    #[serde(default)]
+    pub query: Option<String>,
     /// Request headers in JSON format
     pub headers: String,
     /// Optional request body
     pub body: Option<String>,
 }
 
-/// HTTP response data.
-#[derive(Clone, Debug)]
+/// HTTP response data returned by host route handlers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HttpResponse {
     /// HTTP status code
     pub status_code: i32,
     /// Response body content
     pub body: String,
     /// Response headers in JSON format
+    #[serde(default)]
     pub headers: String,
 }
 
+impl HttpResponse {
+    /// Create a response with no extra headers
+    pub fn new(status_code: i32, body: impl Into<String>) -> Self {
+        Self {
+            status_code,
+            body: body.into(),
+            headers: "{}".to_string(),
+        }
+    }
+}
+
 /// WebSocket message.
 #[derive(Clone, Debug)]
 pub struct WebSocketMessage {
@@ -63,4 +80,6 @@ pub struct ServerState {
     pub connected_clients: usize,
     /// Server configuration
     pub config: Option<ServerConfig>,
+    /// Address the listener is actually bound to (resolves port 0)
+    pub local_addr: Option<SocketAddr>,
 }
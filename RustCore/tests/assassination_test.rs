@@ -0,0 +1,227 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::game::{
+    spy_channel, AssassinationRules, Audience, Command, Faction, GameError, GameEvent, GameSetup, GameState,
+    GameTable, Phase, Role, SeededRng, WinReason, MAX_SPY_CHAT_CHARS,
+};
+use facingtime_core::server::HttpServerState;
+
+use common::{next_of_type, seat_clients};
+
+/// Commands that win three quests with loyal teams, from the current state on
+fn winning_quest(state: &GameState) -> Vec<Command> {
+    let size = state.quests()[state.quest()].team_size;
+    let team: Vec<u32> = state.roles().faction_seats(Faction::Resistance).into_iter().take(size).collect();
+    let mut commands = vec![Command::ProposeTeam {
+        seat: state.leader(),
+        team: team.clone(),
+        grants: BTreeMap::new(),
+    }];
+    commands.extend(state.seats().iter().map(|&seat| Command::VoteTeam { seat, approve: true }));
+    commands.extend(team.into_iter().map(|seat| Command::PlayQuestCard { seat, success: true }));
+    commands
+}
+
+/// Start a game and win three quests; returns the state and the events of the last command
+fn reach_assassination(setup: GameSetup, seed: u64) -> (GameState, Vec<GameEvent>) {
+    let seats: Vec<u32> = (0..setup.player_count as u32).collect();
+    let (mut state, _) = GameState::new(setup, &seats, &mut SeededRng::new(seed)).unwrap();
+    let mut events = Vec::new();
+    while state.phase() != Phase::Assassination {
+        for command in winning_quest(&state) {
+            events = state.apply(command).unwrap();
+        }
+    }
+    (state, events)
+}
+
+/// A seat of `faction` outside the spies' channel
+fn outsider(state: &GameState, faction: Faction) -> u32 {
+    let channel = spy_channel(state.roles());
+    state
+        .roles()
+        .faction_seats(faction)
+        .into_iter()
+        .find(|seat| !channel.contains(seat))
+        .unwrap()
+}
+
+/// Test: the assassination announces its timeout and tells only the spies who the assassin is
+#[test]
+fn test_assassin_designated() {
+    let setup = GameSetup {
+        oberon: true,
+        ..GameSetup::new(7)
+    };
+    let (state, events) = reach_assassination(setup, 3);
+    let assassin = state.roles().assassin().unwrap();
+    let oberon = state.roles().seats_with(Role::Oberon)[0];
+    let channel = spy_channel(state.roles());
+    assert_eq!(channel.len(), 2, "Oberon stays out of the channel");
+    assert!(channel.contains(&assassin));
+    assert!(!channel.contains(&oberon));
+
+    let started = events.iter().position(|e| matches!(e, GameEvent::AssassinationStarted { .. })).unwrap();
+    assert_eq!(events[started], GameEvent::AssassinationStarted { timeout_ms: None });
+    assert_eq!(events[started].audience(), Audience::Everyone);
+    let designated = &events[started + 1];
+    assert_eq!(
+        designated,
+        &GameEvent::AssassinDesignated {
+            assassin,
+            spies: channel.clone()
+        }
+    );
+    assert_eq!(designated.audience(), Audience::Seats(channel));
+    let message: serde_json::Value = serde_json::from_str(&designated.to_message()).unwrap();
+    assert_eq!(message["type"], "assassin_designated");
+    assert_eq!(message["assassin"], assassin);
+}
+
+/// Test: only the spies talk in their channel, and only during the assassination
+#[test]
+fn test_spy_channel() {
+    let seats = [0, 1, 2, 3, 4];
+    let (mut early, _) = GameState::new(GameSetup::new(5), &seats, &mut SeededRng::new(4)).unwrap();
+    let spy = early.roles().faction_seats(Faction::Spies)[0];
+    let chat = |seat: u32, text: &str| Command::SpyChat {
+        seat,
+        text: text.to_string(),
+    };
+    assert_eq!(early.apply(chat(spy, "hi")), Err(GameError::WrongPhase(Phase::TeamBuilding)));
+
+    let (mut state, _) = reach_assassination(GameSetup::new(5), 4);
+    let channel = spy_channel(state.roles());
+    let loyal = outsider(&state, Faction::Resistance);
+    let error = state.apply(chat(loyal, "Is it me?")).unwrap_err();
+    assert_eq!(error, GameError::NotInSpyChannel);
+    assert_eq!(error.code(), "not_in_spy_channel");
+    assert!(matches!(state.apply(chat(channel[0], "   ")), Err(GameError::InvalidMessage(_))));
+    let long = "x".repeat(MAX_SPY_CHAT_CHARS + 1);
+    assert!(matches!(state.apply(chat(channel[0], &long)), Err(GameError::InvalidMessage(_))));
+
+    let first = state.apply(chat(channel[0], " Merlin is quiet ")).unwrap();
+    let second = state.apply(chat(channel[1], "Agreed")).unwrap();
+    assert_eq!(
+        first,
+        vec![GameEvent::SpyMessage {
+            index: 0,
+            seat: channel[0],
+            text: "Merlin is quiet".to_string(),
+            spies: channel.clone(),
+        }]
+    );
+    assert_eq!(second[0].private_key(), Some("spy_chat_1".to_string()));
+    assert_eq!(second[0].audience(), Audience::Seats(channel));
+    assert_eq!(state.phase(), Phase::Assassination, "Talking does not end the phase");
+}
+
+/// Test: the assassin names exactly one player, who cannot be a known spy
+#[test]
+fn test_single_target() {
+    let (mut state, _) = reach_assassination(GameSetup::new(7), 5);
+    let assassin = state.roles().assassin().unwrap();
+    let merlin = state.roles().seats_with(Role::Merlin)[0];
+    let partner = spy_channel(state.roles()).into_iter().find(|&seat| seat != assassin).unwrap();
+
+    assert_eq!(
+        state.apply(Command::Assassinate { seat: partner, target: merlin }),
+        Err(GameError::NotYourTurn),
+        "The spies confer, but only the assassin names"
+    );
+    assert_eq!(
+        state.apply(Command::Assassinate { seat: assassin, target: partner }),
+        Err(GameError::InvalidTarget(partner))
+    );
+    state.apply(Command::Assassinate { seat: assassin, target: merlin }).unwrap();
+    assert_eq!(state.result(), Some((Faction::Spies, WinReason::MerlinAssassinated)));
+    assert_eq!(
+        state.apply(Command::Assassinate { seat: assassin, target: merlin }),
+        Err(GameError::WrongPhase(Phase::GameOver)),
+        "There is no second guess"
+    );
+}
+
+/// Test: a timeout ends the game with the configured default winner
+#[test]
+fn test_timeout_outcome() {
+    let (mut early, _) = GameState::new(GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(6)).unwrap();
+    assert_eq!(
+        early.apply(Command::AssassinationTimedOut),
+        Err(GameError::WrongPhase(Phase::TeamBuilding))
+    );
+    assert_eq!(Command::AssassinationTimedOut.seat(), None);
+
+    let (mut state, _) = reach_assassination(GameSetup::new(5), 6);
+    let events = state.apply(Command::AssassinationTimedOut).unwrap();
+    assert!(matches!(events[0], GameEvent::GameOver { reason: WinReason::AssassinationTimedOut, .. }));
+    assert_eq!(state.result(), Some((Faction::Resistance, WinReason::AssassinationTimedOut)));
+
+    let strict = GameSetup {
+        assassination: AssassinationRules {
+            timeout: Some(Duration::from_secs(90)),
+            default_winner: Faction::Spies,
+        },
+        ..GameSetup::new(5)
+    };
+    let (mut state, events) = reach_assassination(strict, 6);
+    assert!(events.contains(&GameEvent::AssassinationStarted {
+        timeout_ms: Some(90_000)
+    }));
+    state.apply(Command::AssassinationTimedOut).unwrap();
+    assert_eq!(state.result(), Some((Faction::Spies, WinReason::AssassinationTimedOut)));
+}
+
+/// Test: over WebSocket the channel reaches only the spies and the table's clock ends the phase
+#[test]
+fn test_table_channel_and_clock() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+
+    let (mut clients, peers) = seat_clients(&runtime, &server, 5);
+
+    let setup = GameSetup {
+        assassination: AssassinationRules {
+            timeout: Some(Duration::from_millis(200)),
+            ..AssassinationRules::default()
+        },
+        ..GameSetup::new(5)
+    };
+    let (mut table, _) =
+        GameTable::start(server.hub().clone(), setup, &[0, 1, 2, 3, 4], &mut SeededRng::new(7)).unwrap();
+    while table.state().phase() != Phase::Assassination {
+        for command in winning_quest(table.state()) {
+            table.apply(command).unwrap();
+        }
+    }
+    assert!(table.assassination_deadline().is_some());
+    assert!(table.poll().is_empty(), "The assassin still has time");
+
+    let channel = spy_channel(table.state().roles());
+    let message = r#"{"type":"spy_chat","text":"Seat 0 led every quest"}"#;
+    assert_eq!(table.handle_message(peers[channel[0] as usize], message).map(|e| e.len()), Some(1));
+    for seat in 0..5u32 {
+        let client = &mut clients[seat as usize];
+        if channel.contains(&seat) {
+            let designated = next_of_type(&runtime, client, "assassin_designated").unwrap();
+            assert_eq!(designated["spies"], serde_json::json!(channel));
+            let chat = next_of_type(&runtime, client, "spy_chat").unwrap();
+            assert_eq!(chat["seat"], channel[0]);
+        } else {
+            assert!(next_of_type(&runtime, client, "spy_chat").is_none(), "Seat {} is not a spy", seat);
+        }
+    }
+
+    std::thread::sleep(Duration::from_millis(250));
+    let events = table.poll();
+    assert!(matches!(events[0], GameEvent::GameOver { reason: WinReason::AssassinationTimedOut, .. }));
+    assert_eq!(table.assassination_deadline(), None);
+    assert!(table.poll().is_empty());
+    server.stop();
+}
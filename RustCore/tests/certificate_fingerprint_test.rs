@@ -0,0 +1,176 @@
+mod common;
+
+use std::ffi::{CStr, CString};
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use facingtime_core::ffi::error::FtErrorCode;
+use facingtime_core::ffi::mdns::{ft_mdns_server_create, ft_mdns_server_free, ft_mdns_server_set_txt_property};
+use facingtime_core::ffi::server::{
+    ft_http_server_certificate_fingerprint, ft_http_server_create, ft_http_server_free,
+    ft_http_server_free_response, ft_http_server_start, ft_http_server_stop,
+};
+use facingtime_core::server::{HttpServerState, JoinScheme, JoinToken, MdnsServerState, TXT_FINGERPRINT};
+use facingtime_core::tls::{Fingerprint, PinnedCertVerifier};
+
+use common::{get, write_certificate};
+
+/// JSON body of a raw HTTP response
+fn json_body(response: &str) -> serde_json::Value {
+    let (_, body) = response.split_once("\r\n\r\n").expect("Response should have a body");
+    serde_json::from_str(body).expect("Body should be JSON")
+}
+
+/// GET /health over HTTPS, trusting only the pinned certificate
+fn pinned_health(addr: std::net::SocketAddr, fingerprint: Fingerprint) -> serde_json::Value {
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(PinnedCertVerifier::client_config(fingerprint)));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let response = runtime.block_on(async {
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut tls = connector.connect(server_name, stream).await.expect("Pinned handshake should succeed");
+        tls.write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let _ = tls.read_to_end(&mut response).await;
+        String::from_utf8_lossy(&response).to_string()
+    });
+    json_body(&response)
+}
+
+/// Test: plain HTTP reports no certificate on /health
+#[test]
+fn test_health_over_http() {
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    assert_eq!(server.certificate_fingerprint(), None);
+
+    let response = get(server.local_addr().unwrap(), "/health");
+    assert!(response.starts_with("HTTP/1.1 200"), "Unexpected response: {}", response);
+    assert_eq!(
+        json_body(&response),
+        serde_json::json!({ "status": "ok", "tls": false, "fingerprint": null })
+    );
+    server.stop();
+}
+
+/// Test: HTTPS with certificate files reports the certificate's fingerprint
+#[test]
+fn test_health_reports_configured_certificate() {
+    let (cert_path, key_path, cert_der) = write_certificate("fingerprint_cert");
+    let expected = Fingerprint::of_certificate(&cert_der);
+
+    let mut server = HttpServerState::new();
+    server
+        .start_https_with_certificate("127.0.0.1:0", "/tmp", cert_path.to_str().unwrap(), key_path.to_str().unwrap())
+        .expect("HTTPS server should start");
+    assert_eq!(server.certificate_fingerprint(), Some(expected));
+
+    let health = pinned_health(server.local_addr().unwrap(), expected);
+    assert_eq!(health["tls"], true);
+    assert_eq!(health["fingerprint"], expected.to_string());
+
+    server.stop();
+    let _ = std::fs::remove_dir_all(cert_path.parent().unwrap());
+}
+
+/// Test: each self-signed start publishes the fingerprint of its new certificate
+#[test]
+fn test_self_signed_fingerprint_changes_per_start() {
+    let mut server = HttpServerState::new();
+    server.start_https("127.0.0.1:0", "/tmp").expect("HTTPS server should start");
+    let first = server.certificate_fingerprint().expect("HTTPS should have a fingerprint");
+    let health = pinned_health(server.local_addr().unwrap(), first);
+    assert_eq!(health["fingerprint"], first.to_string());
+    server.stop();
+
+    server.start_https("127.0.0.1:0", "/tmp").expect("HTTPS server should restart");
+    let second = server.certificate_fingerprint().expect("HTTPS should have a fingerprint");
+    assert_ne!(first, second, "A new self-signed certificate should be generated");
+    server.stop();
+
+    server.start("127.0.0.1:0", "/tmp").expect("HTTP server should start");
+    assert_eq!(server.certificate_fingerprint(), None, "Plain HTTP should clear the fingerprint");
+    server.stop();
+}
+
+/// Test: join tokens carry the fingerprint in the displayed format
+#[test]
+fn test_join_token_carries_fingerprint() {
+    let fingerprint = Fingerprint::of_certificate(b"not really a certificate");
+    let token = JoinToken::new(vec!["192.168.1.20".parse().unwrap()], 8089, JoinScheme::Https)
+        .with_fingerprint(*fingerprint.as_bytes());
+
+    let decoded = JoinToken::decode(&token.encode().unwrap()).expect("Token should decode");
+    let carried = Fingerprint::from_bytes(decoded.fingerprint.expect("Fingerprint should survive encoding"));
+    assert_eq!(carried, fingerprint);
+    assert_eq!(carried.to_string().parse::<Fingerprint>().unwrap(), fingerprint);
+}
+
+/// Test: TXT properties are stored and replaced by key
+#[test]
+fn test_mdns_txt_properties() {
+    let mut mdns = MdnsServerState::new();
+    assert!(mdns.txt_properties().is_empty());
+
+    mdns.set_txt_property(TXT_FINGERPRINT, "AA:BB");
+    mdns.set_txt_property("version", "1");
+    mdns.set_txt_property(TXT_FINGERPRINT, "CC:DD");
+    assert_eq!(
+        mdns.txt_properties(),
+        &[
+            ("fingerprint".to_string(), "CC:DD".to_string()),
+            ("version".to_string(), "1".to_string()),
+        ]
+    );
+}
+
+/// Test: the FFI fingerprint is NULL for HTTP and set for HTTPS
+#[test]
+fn test_ffi_certificate_fingerprint() {
+    let server = unsafe { ft_http_server_create() };
+    let address = CString::new("127.0.0.1:0").unwrap();
+    let static_dir = CString::new("/tmp").unwrap();
+
+    unsafe {
+        assert!(ft_http_server_certificate_fingerprint(server).is_null(), "Stopped server has no fingerprint");
+        assert_eq!(ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 0), 0);
+        assert!(ft_http_server_certificate_fingerprint(server).is_null(), "HTTP has no fingerprint");
+        ft_http_server_stop(server);
+
+        assert_eq!(ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 1), 0);
+        let fingerprint = ft_http_server_certificate_fingerprint(server);
+        assert!(!fingerprint.is_null(), "HTTPS should have a fingerprint");
+        let text = CStr::from_ptr(fingerprint).to_str().unwrap().to_string();
+        assert_eq!(Some(text.parse::<Fingerprint>().unwrap()), (*server).certificate_fingerprint());
+        ft_http_server_free_response(fingerprint);
+        ft_http_server_stop(server);
+        ft_http_server_free(server);
+
+        assert!(ft_http_server_certificate_fingerprint(std::ptr::null_mut()).is_null());
+    }
+}
+
+/// Test: ft_mdns_server_set_txt_property validates its arguments
+#[test]
+fn test_ffi_set_txt_property() {
+    let mdns = unsafe { ft_mdns_server_create() };
+    let key = CString::new(TXT_FINGERPRINT).unwrap();
+    let value = CString::new("AA:BB").unwrap();
+
+    unsafe {
+        assert_eq!(ft_mdns_server_set_txt_property(mdns, key.as_ptr(), value.as_ptr()), FtErrorCode::Ok as i32);
+        assert_eq!((*mdns).txt_properties(), &[("fingerprint".to_string(), "AA:BB".to_string())]);
+        assert_eq!(
+            ft_mdns_server_set_txt_property(mdns, std::ptr::null(), value.as_ptr()),
+            FtErrorCode::NullPointer as i32
+        );
+        assert_eq!(
+            ft_mdns_server_set_txt_property(std::ptr::null_mut(), key.as_ptr(), value.as_ptr()),
+            FtErrorCode::NullPointer as i32
+        );
+        ft_mdns_server_free(mdns);
+    }
+}
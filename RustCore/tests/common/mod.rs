@@ -0,0 +1,252 @@
+//! Helpers shared by the integration tests.
+//!
+//! Each test file declares `mod common;` and uses the helpers it needs, so
+//! any one file leaves some of them unused.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use facingtime_core::game::{Command, Faction, GameEvent, GameLog, GameSetup, GameState, Phase, SeededRng};
+use facingtime_core::server::{CoreEvent, EventQueue, HttpServerState, PeerId, Seat};
+
+/// A WebSocket client connected to the test server
+pub type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Start a server on a random port
+///
+/// # Returns
+/// The server, a queue subscribed to its events and its `/ws` URL
+pub fn start_server() -> (HttpServerState, EventQueue, String) {
+    start_server_with(|_| {})
+}
+
+/// Start a server on a random port, set up by `configure` first
+pub fn start_server_with(configure: impl FnOnce(&mut HttpServerState)) -> (HttpServerState, EventQueue, String) {
+    let mut server = HttpServerState::new();
+    let events = server.events().subscribe();
+    configure(&mut server);
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let url = format!("ws://{}/ws", server.local_addr().unwrap());
+    (server, events, url)
+}
+
+/// Wait until the queue yields an event matching `predicate`
+pub fn wait_for(queue: &EventQueue, predicate: impl Fn(&CoreEvent) -> bool) -> CoreEvent {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        while let Some(event) = queue.pop() {
+            if predicate(&event) {
+                return event;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("Timed out waiting for event");
+}
+
+/// Read the next frame, or None if nothing arrives in time
+pub fn next_frame(runtime: &Runtime, client: &mut Client, timeout: Duration) -> Option<Message> {
+    runtime
+        .block_on(async { tokio::time::timeout(timeout, client.next()).await })
+        .ok()
+        .flatten()
+        .and_then(|frame| frame.ok())
+}
+
+/// Read frames until a text frame arrives and parse it as JSON
+///
+/// # Returns
+/// The message, or None if no frame arrives within `wait` or the connection ends
+pub fn next_json_within(runtime: &Runtime, client: &mut Client, wait: Duration) -> Option<serde_json::Value> {
+    loop {
+        match next_frame(runtime, client, wait)? {
+            Message::Text(text) => return Some(serde_json::from_str(&text).expect("Frame should be JSON")),
+            Message::Close(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Read frames until a text frame arrives and parse it as JSON, failing if none arrives in time
+pub fn next_json(runtime: &Runtime, client: &mut Client) -> serde_json::Value {
+    next_json_within(runtime, client, Duration::from_secs(5)).expect("Timed out waiting for a text frame")
+}
+
+/// Read messages until one of type `kind` arrives
+pub fn next_of_type(runtime: &Runtime, client: &mut Client, kind: &str) -> Option<serde_json::Value> {
+    while let Some(message) = next_json_within(runtime, client, Duration::from_millis(500)) {
+        if message["type"] == kind {
+            return Some(message);
+        }
+    }
+    None
+}
+
+/// Read every message that arrives soon
+pub fn drain(runtime: &Runtime, client: &mut Client) -> Vec<serde_json::Value> {
+    std::iter::from_fn(|| next_json_within(runtime, client, Duration::from_millis(300))).collect()
+}
+
+/// Connect a client and return it with its `session` greeting
+pub fn connect(runtime: &Runtime, url: &str) -> (Client, serde_json::Value) {
+    let mut client = runtime
+        .block_on(tokio_tungstenite::connect_async(url))
+        .expect("WebSocket should connect")
+        .0;
+    let session = next_json(runtime, &mut client);
+    assert_eq!(session["type"], "session", "Unexpected greeting: {}", session);
+    (client, session)
+}
+
+/// Connect a client and return it with its peer id, once the server reports it connected
+pub fn connect_peer(runtime: &Runtime, url: &str, events: &EventQueue) -> (Client, PeerId) {
+    let (client, _) = connect(runtime, url);
+    match wait_for(events, |e| matches!(e, CoreEvent::ClientConnected { .. })) {
+        CoreEvent::ClientConnected { peer_id, .. } => (client, peer_id),
+        _ => unreachable!(),
+    }
+}
+
+/// Peer id from a `session` greeting
+pub fn peer_id(session: &serde_json::Value) -> PeerId {
+    session["peer_id"].as_u64().expect("Greeting should carry a peer id") as PeerId
+}
+
+/// Connect `count` clients to `server` and seat them in seats 0..count
+///
+/// # Returns
+/// The clients and their peer ids, by seat
+pub fn seat_clients(runtime: &Runtime, server: &HttpServerState, count: u32) -> (Vec<Client>, Vec<PeerId>) {
+    let url = format!("ws://{}/ws", server.local_addr().unwrap());
+    (0..count)
+        .map(|seat| {
+            let (client, session) = connect(runtime, &url);
+            let peer_id = peer_id(&session);
+            assert!(server.hub().sessions().set_seat(peer_id, Some(seat)));
+            (client, peer_id)
+        })
+        .unzip()
+}
+
+/// Send a raw HTTP/1.1 request and return the full response text
+pub fn raw_request(addr: SocketAddr, request: &str) -> String {
+    let mut stream = std::net::TcpStream::connect(addr).expect("Should connect to server");
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// Send a GET request and return the full response text
+pub fn get(addr: SocketAddr, path: &str) -> String {
+    raw_request(addr, &format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path))
+}
+
+/// Write a fresh self-signed certificate and key to a temporary directory
+///
+/// # Returns
+/// The certificate and key paths and the certificate in DER form
+pub fn write_certificate(name: &str) -> (PathBuf, PathBuf, Vec<u8>) {
+    let dir = std::env::temp_dir().join(format!("ft_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+    std::fs::write(&key_path, certified.signing_key.serialize_pem()).unwrap();
+    (cert_path, key_path, certified.cert.der().to_vec())
+}
+
+/// Start a game on seats 0..player_count
+pub fn start_game(setup: GameSetup, seed: u64) -> (GameState, Vec<GameEvent>) {
+    let seats: Vec<Seat> = (0..setup.player_count as Seat).collect();
+    GameState::new(setup, &seats, &mut SeededRng::new(seed)).unwrap()
+}
+
+/// A proposal without grants
+pub fn propose(seat: Seat, team: Vec<Seat>) -> Command {
+    Command::ProposeTeam {
+        seat,
+        team,
+        grants: BTreeMap::new(),
+    }
+}
+
+/// Apply a command to the game and record it
+fn play(log: &mut GameLog, state: &mut GameState, command: Command) {
+    let events = state.apply(command.clone()).unwrap();
+    log.record(state, command, &events);
+}
+
+/// Play a logged game on seats 0..player_count to the end
+///
+/// The first team of every quest is rejected (only seat 0 approves it) and
+/// the second approved. Leaders pick the seats after 0 that are not
+/// themselves, handing Excalibur (if in play) to the first, who switches
+/// the second's card. Spies fail their quests, the Lady of the Lake looks
+/// at the first seat nobody held, and the assassin names the first loyal
+/// seat. `observe` sees the state after every command.
+///
+/// # Returns
+/// The log and the final state
+pub fn play_logged_game(setup: GameSetup, seed: u64, mut observe: impl FnMut(&GameState)) -> (GameLog, GameState) {
+    let seats: Vec<Seat> = (0..setup.player_count as Seat).collect();
+    let (mut log, mut state, _) = GameLog::start(setup, &seats, seed).unwrap();
+    while state.result().is_none() {
+        match state.phase() {
+            Phase::TeamBuilding => {
+                let leader = state.leader();
+                let size = state.quests()[state.quest()].team_size;
+                let team: Vec<Seat> = seats.iter().copied().filter(|&seat| seat != leader).take(size).collect();
+                let grants = match state.setup().excalibur {
+                    Some(_) => BTreeMap::from([("excalibur".to_string(), team[0])]),
+                    None => BTreeMap::new(),
+                };
+                play(&mut log, &mut state, Command::ProposeTeam { seat: leader, team, grants });
+            }
+            Phase::TeamVote => {
+                let approve = state.rejections() > 0;
+                for &seat in &seats {
+                    play(&mut log, &mut state, Command::VoteTeam { seat, approve: approve || seat == 0 });
+                }
+            }
+            Phase::Quest => {
+                for seat in state.team().to_vec() {
+                    let spy = state.roles().role_of(seat).unwrap().faction() == Faction::Spies;
+                    play(&mut log, &mut state, Command::PlayQuestCard { seat, success: !spy });
+                }
+            }
+            Phase::QuestAction => {
+                let team = state.team().to_vec();
+                let module = "excalibur".to_string();
+                let action = Command::QuestAction { seat: team[0], module, target: Some(team[1]) };
+                play(&mut log, &mut state, action);
+            }
+            Phase::LadyOfTheLake => {
+                let holder = state.lady_holder().unwrap();
+                let held = state.lady().unwrap().holders();
+                let target = seats.iter().copied().find(|seat| !held.contains(seat)).unwrap();
+                play(&mut log, &mut state, Command::InspectLoyalty { seat: holder, target });
+            }
+            Phase::Assassination => {
+                let seat = state.roles().assassin().unwrap();
+                let target = state.roles().faction_seats(Faction::Resistance)[0];
+                play(&mut log, &mut state, Command::Assassinate { seat, target });
+            }
+            Phase::GameOver => unreachable!(),
+        }
+        observe(&state);
+    }
+    (log, state)
+}
@@ -0,0 +1,90 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use facingtime_core::server::discovery::{probe_host, DiscoveryServerState};
+use facingtime_core::server::{JoinScheme, JoinToken};
+
+fn sample_token() -> JoinToken {
+    JoinToken::new(
+        vec![
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20)),
+            IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x20)),
+        ],
+        8443,
+        JoinScheme::Https,
+    )
+    .with_fingerprint([0xab; 32])
+}
+
+/// Test: a token survives an encode/decode round trip
+#[test]
+fn test_join_token_round_trip() {
+    let token = sample_token();
+    let encoded = token.encode().expect("token should encode");
+    assert!(encoded.starts_with("FT"), "Encoded token should carry the FT prefix");
+    assert!(
+        encoded.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()),
+        "Encoded token should only use QR alphanumeric characters"
+    );
+
+    let decoded = JoinToken::decode(&encoded).expect("token should decode");
+    assert_eq!(decoded, token);
+}
+
+/// Test: the short-code form decodes regardless of dashes, spaces and case
+#[test]
+fn test_join_token_short_code_is_forgiving() {
+    let token = JoinToken::new(vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7))], 8080, JoinScheme::Http);
+    let short = token.encode_short_code().expect("token should encode");
+    assert!(short.contains('-'), "Short code should be grouped");
+
+    let typed = short.to_lowercase().replace('-', " ");
+    assert_eq!(JoinToken::decode(&typed).expect("typed code should decode"), token);
+    assert_eq!(token.url().as_deref(), Some("http://10.0.0.7:8080"));
+}
+
+/// Test: typos are caught by the checksum
+#[test]
+fn test_join_token_rejects_typos() {
+    let encoded = sample_token().encode().unwrap();
+    let mut chars: Vec<char> = encoded.chars().collect();
+    let index = chars.len() / 2;
+    chars[index] = if chars[index] == 'A' { 'B' } else { 'A' };
+    let corrupted: String = chars.into_iter().collect();
+
+    assert!(JoinToken::decode(&corrupted).is_err(), "Corrupted token should not decode");
+    assert!(JoinToken::decode("XX1234").is_err(), "Token without prefix should not decode");
+    assert!(JoinToken::decode("FT").is_err(), "Empty token should not decode");
+}
+
+/// Test: tokens without addresses cannot be encoded
+#[test]
+fn test_join_token_requires_address() {
+    let token = JoinToken::new(Vec::new(), 8080, JoinScheme::Http);
+    assert!(token.encode().is_err(), "Token without addresses should not encode");
+}
+
+/// Test: the responder answers a unicast probe with its token
+#[test]
+fn test_discovery_responder_answers_probe() {
+    let port = 38990;
+    let token = JoinToken::new(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], 8443, JoinScheme::Https);
+
+    let mut responder = DiscoveryServerState::new();
+    responder
+        .start(token.clone(), port, None)
+        .expect("responder should start");
+    assert!(responder.is_running(), "Responder should be running after start");
+    assert!(
+        responder.start(token.clone(), port, None).is_err(),
+        "Starting twice should fail"
+    );
+
+    let hosts = probe_host(SocketAddr::from((Ipv4Addr::LOCALHOST, port)), Duration::from_millis(500))
+        .expect("probe should succeed");
+    assert_eq!(hosts.len(), 1, "Exactly one host should answer");
+    assert_eq!(hosts[0].token, token);
+
+    responder.stop();
+    assert!(!responder.is_running(), "Responder should be stopped");
+}
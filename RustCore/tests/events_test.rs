@@ -0,0 +1,191 @@
+mod common;
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+
+use facingtime_core::ffi::events::{ft_core_poll_events, ft_core_set_event_callback};
+use facingtime_core::ffi::server::{ft_http_server_create, ft_http_server_free, ft_http_server_start, ft_http_server_stop};
+use facingtime_core::server::{CoreEvent, EventBus, EventQueue, HttpServerState};
+
+use common::{start_server, wait_for};
+
+/// Test: a full queue drops the oldest events
+#[test]
+fn test_event_queue_drops_oldest() {
+    let queue = EventQueue::new(2);
+    for peer_id in 2..5 {
+        queue.push(CoreEvent::ClientDisconnected { peer_id });
+    }
+
+    assert_eq!(queue.dropped(), 1, "One event should have been dropped");
+    assert_eq!(
+        queue.drain(),
+        vec![
+            CoreEvent::ClientDisconnected { peer_id: 3 },
+            CoreEvent::ClientDisconnected { peer_id: 4 },
+        ]
+    );
+    assert!(queue.is_empty());
+}
+
+/// Test: every attached queue receives events until detached
+#[test]
+fn test_event_bus_fan_out() {
+    let bus = EventBus::new();
+    let first = bus.subscribe();
+    let second = EventQueue::default();
+    bus.attach(second.clone());
+    bus.attach(second.clone());
+
+    bus.emit(CoreEvent::ServerStopped);
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1, "Attaching twice should not duplicate events");
+
+    bus.detach(&second);
+    bus.emit(CoreEvent::ServerStopped);
+    assert_eq!(first.len(), 2);
+    assert_eq!(second.len(), 1, "Detached queue should not receive events");
+}
+
+/// Test: events serialize with a snake_case type tag
+#[test]
+fn test_event_json() {
+    let event = CoreEvent::MessageReceived {
+        peer_id: 2,
+        message: "hi".to_string(),
+    };
+    let json: serde_json::Value = serde_json::from_str(&event.to_json()).unwrap();
+    assert_eq!(json["type"], "message_received");
+    assert_eq!(json["peer_id"], 2);
+    assert_eq!(json["message"], "hi");
+    assert_eq!(event.code(), 6);
+}
+
+/// Test: start, stop and bind failures are reported as events
+#[test]
+fn test_server_lifecycle_events() {
+    let mut server = HttpServerState::new();
+    let events = server.events().subscribe();
+
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let addr = server.local_addr().unwrap();
+    assert_eq!(
+        events.pop(),
+        Some(CoreEvent::ServerStarted { address: addr.to_string() }),
+        "Start should report the bound address"
+    );
+
+    let mut second = HttpServerState::new();
+    let second_events = second.events().subscribe();
+    assert!(second.start(&addr.to_string(), "/tmp").is_err());
+    assert!(
+        matches!(second_events.pop(), Some(CoreEvent::ServerFailed { .. })),
+        "Bind failure should be reported"
+    );
+
+    server.stop();
+    assert_eq!(events.pop(), Some(CoreEvent::ServerStopped));
+}
+
+/// Test: WebSocket clients get peer ids and their messages become events
+#[test]
+fn test_websocket_peer_events() {
+    let (mut server, events, url) = start_server();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut socket = runtime
+        .block_on(tokio_tungstenite::connect_async(url))
+        .expect("WebSocket should connect")
+        .0;
+
+    let peer_id = match wait_for(&events, |e| matches!(e, CoreEvent::ClientConnected { .. })) {
+        CoreEvent::ClientConnected { peer_id, address } => {
+            assert!(address.starts_with("127.0.0.1:"), "Unexpected address {}", address);
+            peer_id
+        }
+        _ => unreachable!(),
+    };
+    assert!(peer_id >= 2, "Peer ids 0 and 1 are reserved");
+    assert_eq!(server.inner.lock().connected_clients, 1);
+
+    runtime.block_on(socket.send(Message::text("{\"type\":0}"))).unwrap();
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::MessageReceived { .. })),
+        CoreEvent::MessageReceived {
+            peer_id,
+            message: "{\"type\":0}".to_string(),
+        }
+    );
+
+    runtime.block_on(socket.close(None)).unwrap();
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::ClientDisconnected { .. })),
+        CoreEvent::ClientDisconnected { peer_id }
+    );
+
+    server.stop();
+}
+
+/// Test: stopping the server closes connected WebSocket clients
+#[test]
+fn test_stop_closes_websocket_peers() {
+    let (mut server, events, url) = start_server();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut socket = runtime.block_on(tokio_tungstenite::connect_async(url)).unwrap().0;
+    wait_for(&events, |e| matches!(e, CoreEvent::ClientConnected { .. }));
+
+    server.stop();
+    let closed = runtime.block_on(async {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match socket.next().await {
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return true,
+                    Some(Ok(_)) => continue,
+                }
+            }
+        })
+        .await
+    });
+    assert_eq!(closed, Ok(true), "Client should be closed when the server stops");
+}
+
+/// Events received by the FFI callback
+static RECEIVED: Mutex<Vec<(i32, String)>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn record_event(user_data: *mut c_void, event_type: i32, event_json: *const c_char) {
+    assert_eq!(*(user_data as *const u32), 42, "User data should be passed through");
+    let json = CStr::from_ptr(event_json).to_str().unwrap().to_string();
+    RECEIVED.lock().push((event_type, json));
+}
+
+/// Test: FFI events are delivered only when polled, on the polling thread
+#[test]
+fn test_ffi_poll_events() {
+    let mut user_data: u32 = 42;
+    unsafe { ft_core_set_event_callback(Some(record_event), &mut user_data as *mut u32 as *mut c_void) };
+
+    let server = unsafe { ft_http_server_create() };
+    let address = CString::new("127.0.0.1:0").unwrap();
+    let static_dir = CString::new("/tmp").unwrap();
+    assert_eq!(unsafe { ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 0) }, 0);
+    unsafe { ft_http_server_stop(server) };
+
+    assert!(RECEIVED.lock().is_empty(), "Nothing should be delivered before polling");
+    let delivered = unsafe { ft_core_poll_events() };
+    assert_eq!(delivered, 2);
+
+    let received = RECEIVED.lock().clone();
+    assert_eq!(received[0].0, 1, "First event should be FT_EVENT_SERVER_STARTED");
+    assert!(received[0].1.contains("\"type\":\"server_started\""));
+    assert_eq!(received[1].0, 2, "Second event should be FT_EVENT_SERVER_STOPPED");
+    assert_eq!(unsafe { ft_core_poll_events() }, 0, "Queue should be empty after polling");
+
+    unsafe { ft_core_set_event_callback(None, std::ptr::null_mut()) };
+    unsafe { ft_http_server_free(server) };
+}
@@ -0,0 +1,316 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use facingtime_core::game::{
+    Audience, Command, ExcaliburRules, ExcaliburTargeting, Faction, GameError, GameEvent, GameSetup, GameState,
+    Phase, QuestContext, QuestModule, SeededRng,
+};
+
+use common::{propose, start_game};
+
+/// A 5-player game with Excalibur under `rules`
+fn start(rules: ExcaliburRules, seed: u64) -> GameState {
+    let setup = GameSetup {
+        excalibur: Some(rules),
+        ..GameSetup::new(5)
+    };
+    start_game(setup, seed).0
+}
+
+/// A proposal handing Excalibur to `holder`
+fn propose_with_excalibur(seat: u32, team: Vec<u32>, holder: u32) -> Command {
+    Command::ProposeTeam {
+        seat,
+        team,
+        grants: BTreeMap::from([("excalibur".to_string(), holder)]),
+    }
+}
+
+/// Use Excalibur on `target`, or decline
+fn excalibur(seat: u32, target: Option<u32>) -> Command {
+    Command::QuestAction {
+        seat,
+        module: "excalibur".to_string(),
+        target,
+    }
+}
+
+/// A team of one spy and one loyal player, neither of them the leader
+fn spy_and_loyal(state: &GameState) -> (u32, u32) {
+    let pick = |faction| {
+        state
+            .roles()
+            .faction_seats(faction)
+            .into_iter()
+            .find(|&seat| seat != state.leader())
+            .unwrap()
+    };
+    (pick(Faction::Spies), pick(Faction::Resistance))
+}
+
+/// Approve the proposed team and have the spy fail while the loyal player succeeds
+fn approve_and_play(state: &mut GameState, spy: u32, loyal: u32) {
+    for seat in 0..5 {
+        state.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+    }
+    state.apply(Command::PlayQuestCard { seat: spy, success: false }).unwrap();
+    state.apply(Command::PlayQuestCard { seat: loyal, success: true }).unwrap();
+}
+
+/// Test: the leader must hand Excalibur to another team member
+#[test]
+fn test_excalibur_grants() {
+    let mut state = start(ExcaliburRules::default(), 1);
+    let leader = state.leader();
+    let (spy, loyal) = spy_and_loyal(&state);
+    let outsider = (0..5).find(|seat| ![leader, spy, loyal].contains(seat)).unwrap();
+
+    assert!(matches!(
+        state.apply(propose(leader, vec![spy, loyal])),
+        Err(GameError::InvalidMessage(_))
+    ));
+    assert_eq!(
+        state.apply(propose_with_excalibur(leader, vec![spy, loyal], outsider)),
+        Err(GameError::InvalidTarget(outsider))
+    );
+    assert_eq!(
+        state.apply(propose_with_excalibur(leader, vec![leader, loyal], leader)),
+        Err(GameError::InvalidTarget(leader)),
+        "The leader keeps Excalibur only under the variant"
+    );
+    let mut grants = BTreeMap::new();
+    grants.insert("excalibur".to_string(), loyal);
+    grants.insert("crown".to_string(), loyal);
+    let error = state
+        .apply(Command::ProposeTeam {
+            seat: leader,
+            team: vec![spy, loyal],
+            grants,
+        })
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Invalid message: `crown` is not in this game");
+    assert_eq!(state.phase(), Phase::TeamBuilding);
+
+    let events = state.apply(propose_with_excalibur(leader, vec![spy, loyal], loyal)).unwrap();
+    let message: serde_json::Value = serde_json::from_str(&events[0].to_message()).unwrap();
+    assert_eq!(message["type"], "team_proposed");
+    assert_eq!(message["grants"]["excalibur"], loyal);
+
+    let variant = ExcaliburRules {
+        leader_may_hold: true,
+        ..ExcaliburRules::default()
+    };
+    let mut state = start(variant, 1);
+    let leader = state.leader();
+    assert!(state.apply(propose_with_excalibur(leader, vec![leader, loyal], leader)).is_ok());
+
+    let (mut plain, events) = start_game(GameSetup::new(5), 1);
+    assert!(matches!(&events[0], GameEvent::GameStarted { modules, .. } if modules.is_empty()));
+    let leader = plain.leader();
+    assert!(matches!(
+        plain.apply(propose_with_excalibur(leader, vec![spy, loyal], loyal)),
+        Err(GameError::InvalidMessage(_))
+    ));
+}
+
+/// Test: the holder switches a card before the count and alone learns what it was
+#[test]
+fn test_excalibur_switches_card() {
+    let mut state = start(ExcaliburRules::default(), 2);
+    let leader = state.leader();
+    let (spy, loyal) = spy_and_loyal(&state);
+    state.apply(propose_with_excalibur(leader, vec![spy, loyal], loyal)).unwrap();
+    for seat in 0..5 {
+        state.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+    }
+    state.apply(Command::PlayQuestCard { seat: spy, success: false }).unwrap();
+    let events = state.apply(Command::PlayQuestCard { seat: loyal, success: true }).unwrap();
+    assert_eq!(
+        events[1],
+        GameEvent::QuestActionRequired {
+            module: "excalibur",
+            seat: loyal
+        }
+    );
+    assert_eq!(state.phase(), Phase::QuestAction);
+    assert!(state.outcomes().is_empty(), "The cards are not counted yet");
+
+    assert_eq!(state.apply(excalibur(spy, Some(loyal))), Err(GameError::NotYourTurn));
+    assert_eq!(state.apply(excalibur(loyal, Some(loyal))), Err(GameError::InvalidTarget(loyal)));
+    assert_eq!(state.apply(excalibur(loyal, Some(leader))), Err(GameError::InvalidTarget(leader)));
+    let wrong_module = Command::QuestAction {
+        seat: loyal,
+        module: "lady".to_string(),
+        target: Some(spy),
+    };
+    assert!(matches!(state.apply(wrong_module), Err(GameError::InvalidMessage(_))));
+
+    let events = state.apply(excalibur(loyal, Some(spy))).unwrap();
+    assert_eq!(
+        events[0],
+        GameEvent::ExcaliburUsed {
+            holder: loyal,
+            target: Some(spy)
+        }
+    );
+    assert_eq!(
+        events[1],
+        GameEvent::ExcaliburRevealed {
+            quest: 0,
+            holder: loyal,
+            target: spy,
+            success: false
+        }
+    );
+    assert_eq!(events[0].audience(), Audience::Everyone);
+    assert_eq!(events[1].audience(), Audience::Seat(loyal));
+    assert_eq!(events[1].private_key(), Some("excalibur_result_0".to_string()));
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { fails: 0, succeeded: true, .. })));
+    assert_eq!(state.phase(), Phase::TeamBuilding);
+}
+
+/// Test: declining Excalibur counts the cards as played
+#[test]
+fn test_excalibur_declined() {
+    let mut state = start(ExcaliburRules::default(), 3);
+    let leader = state.leader();
+    let (spy, loyal) = spy_and_loyal(&state);
+    state.apply(propose_with_excalibur(leader, vec![spy, loyal], spy)).unwrap();
+    approve_and_play(&mut state, spy, loyal);
+
+    let events = state.apply(excalibur(spy, None)).unwrap();
+    assert_eq!(events[0], GameEvent::ExcaliburUsed { holder: spy, target: None });
+    assert!(events.iter().all(|e| e.audience() == Audience::Everyone));
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { fails: 1, succeeded: false, .. })));
+    assert_eq!(state.apply(excalibur(spy, None)), Err(GameError::WrongPhase(Phase::TeamBuilding)));
+}
+
+/// Test: under the any-team-member variant the holder may switch their own card
+#[test]
+fn test_excalibur_any_team_member() {
+    let rules = ExcaliburRules {
+        targeting: ExcaliburTargeting::AnyTeamMember,
+        ..ExcaliburRules::default()
+    };
+    assert_eq!(ExcaliburTargeting::from_name(rules.targeting.name()), Some(rules.targeting));
+    let mut state = start(rules, 4);
+    let leader = state.leader();
+    let (spy, loyal) = spy_and_loyal(&state);
+    state.apply(propose_with_excalibur(leader, vec![spy, loyal], spy)).unwrap();
+    approve_and_play(&mut state, spy, loyal);
+
+    let events = state.apply(excalibur(spy, Some(spy))).unwrap();
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { fails: 0, succeeded: true, .. })));
+}
+
+/// A host module: the leader confirms every quest before its cards are counted
+#[derive(Clone, Debug, Default)]
+struct Confirmation {
+    confirmed: bool,
+}
+
+impl QuestModule for Confirmation {
+    fn name(&self) -> &'static str {
+        "confirmation"
+    }
+
+    fn clone_box(&self) -> Box<dyn QuestModule> {
+        Box::new(self.clone())
+    }
+
+    fn pending_action(&self, context: &QuestContext<'_>) -> Option<u32> {
+        (!self.confirmed).then_some(context.leader)
+    }
+
+    fn act(
+        &mut self,
+        _context: &QuestContext<'_>,
+        _seat: u32,
+        _target: Option<u32>,
+        _cards: &mut BTreeMap<u32, bool>,
+    ) -> Result<Vec<GameEvent>, GameError> {
+        self.confirmed = true;
+        Ok(Vec::new())
+    }
+
+    fn quest_resolved(&mut self) {
+        self.confirmed = false;
+    }
+}
+
+/// Test: modules plugged in by the host run after each other in order
+#[test]
+fn test_host_module_runs_after_excalibur() {
+    let setup = GameSetup {
+        excalibur: Some(ExcaliburRules::default()),
+        ..GameSetup::new(5)
+    };
+    let mut modules = setup.quest_modules();
+    modules.push(Box::new(Confirmation::default()));
+    let (mut state, events) =
+        GameState::with_modules(setup, &[0, 1, 2, 3, 4], &mut SeededRng::new(5), modules).unwrap();
+    assert!(
+        matches!(&events[0], GameEvent::GameStarted { modules, .. } if modules == &vec!["excalibur", "confirmation"])
+    );
+
+    let leader = state.leader();
+    let (spy, loyal) = spy_and_loyal(&state);
+    state.apply(propose_with_excalibur(leader, vec![spy, loyal], loyal)).unwrap();
+    approve_and_play(&mut state, spy, loyal);
+    let events = state.apply(excalibur(loyal, None)).unwrap();
+    assert_eq!(
+        events[1],
+        GameEvent::QuestActionRequired {
+            module: "confirmation",
+            seat: leader
+        }
+    );
+    let confirm = Command::QuestAction {
+        seat: leader,
+        module: "confirmation".to_string(),
+        target: None,
+    };
+    let events = state.apply(confirm).unwrap();
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { quest: 0, .. })));
+    assert_eq!(state.modules().len(), 2);
+}
+
+/// Test: a switched card still shows as played in its owner's view
+#[test]
+fn test_switched_card_shows_as_played() {
+    let setup = GameSetup {
+        excalibur: Some(ExcaliburRules::default()),
+        ..GameSetup::new(5)
+    };
+    let mut modules = setup.quest_modules();
+    modules.push(Box::new(Confirmation::default()));
+    let (mut state, _) = GameState::with_modules(setup, &[0, 1, 2, 3, 4], &mut SeededRng::new(5), modules).unwrap();
+
+    let (spy, loyal) = spy_and_loyal(&state);
+    state.apply(propose_with_excalibur(state.leader(), vec![spy, loyal], loyal)).unwrap();
+    approve_and_play(&mut state, spy, loyal);
+    state.apply(excalibur(loyal, Some(spy))).unwrap();
+    assert_eq!(state.phase(), Phase::QuestAction, "The confirmation is still pending");
+    assert_eq!(state.view_for(spy)["private"]["quest_card"], false);
+    assert_eq!(state.view_for(loyal)["private"]["quest_card"], true);
+}
+
+/// Test: quest actions and grants are parsed from client messages
+#[test]
+fn test_parse_module_messages() {
+    assert_eq!(
+        Command::parse(1, r#"{"type":"propose_team","team":[1,2],"grants":{"excalibur":2}}"#),
+        Ok(Some(propose_with_excalibur(1, vec![1, 2], 2)))
+    );
+    assert_eq!(
+        Command::parse(2, r#"{"type":"quest_action","module":"excalibur","target":null}"#),
+        Ok(Some(excalibur(2, None)))
+    );
+    assert_eq!(
+        Command::parse(2, r#"{"type":"quest_action","module":"excalibur","target":4}"#),
+        Ok(Some(excalibur(2, Some(4))))
+    );
+    let error = Command::parse(1, r#"{"type":"propose_team","team":[1,2],"grants":{"excalibur":"two"}}"#);
+    assert_eq!(error.unwrap_err().to_string(), "Invalid message: `grants` is missing or invalid");
+}
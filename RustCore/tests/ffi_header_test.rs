@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::path::Path;
+
+use facingtime_core::ffi::version::{ft_core_abi_version, ft_core_version, FT_ABI_VERSION};
+
+/// A function signature normalized to C type spelling
+#[derive(Debug, PartialEq, Eq)]
+struct Signature {
+    return_type: String,
+    params: Vec<String>,
+}
+
+/// Normalize C type spelling ("const char *" -> "const char*")
+fn normalize_c_type(ty: &str) -> String {
+    ty.replace('*', " * ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" *", "*")
+}
+
+/// Map a Rust FFI type to its C spelling
+fn rust_type_to_c(ty: &str) -> String {
+    let ty = ty.trim();
+    if let Some(inner) = ty.strip_prefix("*mut ") {
+        return format!("{}*", rust_type_to_c(inner));
+    }
+    if let Some(inner) = ty.strip_prefix("*const ") {
+        return format!("const {}*", rust_type_to_c(inner));
+    }
+    let c = match ty {
+        "" | "()" => "void",
+        "c_char" => "char",
+        "c_void" => "void",
+        "bool" => "bool",
+        "i32" => "int32_t",
+        "u32" => "uint32_t",
+        "i64" => "int64_t",
+        "u64" => "uint64_t",
+        "u16" => "uint16_t",
+        "u8" => "uint8_t",
+        "usize" => "size_t",
+        other if other.starts_with("Ft") => other,
+        other => panic!("No C mapping for Rust FFI type '{}'; extend rust_type_to_c", other),
+    };
+    c.to_string()
+}
+
+/// Remove `//` line comments from Rust source
+fn strip_rust_comments(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| match line.find("//") {
+            Some(index) => &line[..index],
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Remove `/* */` and `//` comments and preprocessor lines from C source
+fn strip_c_comments(source: &str) -> String {
+    let mut out = String::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find("*/").expect("Unterminated comment in header");
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    out.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(|line| match line.find("//") {
+            Some(index) => &line[..index],
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split on top-level commas (ignoring commas nested in () or <>)
+fn split_top_level(params: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in params.chars() {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// Collect exported `ft_*` functions from the Rust FFI sources
+fn rust_exports() -> BTreeMap<String, Signature> {
+    let ffi_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ffi");
+    let mut exports = BTreeMap::new();
+
+    for entry in std::fs::read_dir(&ffi_dir).expect("src/ffi should exist") {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|e| e.to_str()) != Some("rs") {
+            continue;
+        }
+        let source = strip_rust_comments(&std::fs::read_to_string(&path).unwrap());
+
+        let mut rest = source.as_str();
+        while let Some(index) = rest.find("extern \"C\" fn ft_") {
+            let decl = &rest[index + "extern \"C\" fn ".len()..];
+            let open = decl.find('(').unwrap();
+            let name = decl[..open].trim().to_string();
+
+            // Find the matching close paren
+            let mut depth = 0;
+            let mut close = open;
+            for (i, c) in decl[open..].char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            close = open + i;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let params = split_top_level(&decl[open + 1..close])
+                .iter()
+                .map(|param| {
+                    let (_, ty) = param.split_once(':').expect("FFI parameter should be named");
+                    normalize_c_type(&rust_type_to_c(ty))
+                })
+                .collect();
+
+            let after = &decl[close + 1..];
+            let body = after.find('{').unwrap();
+            let return_type = after[..body].trim().trim_start_matches("->").trim();
+
+            exports.insert(
+                name,
+                Signature {
+                    return_type: normalize_c_type(&rust_type_to_c(return_type)),
+                    params,
+                },
+            );
+            rest = &decl[close..];
+        }
+    }
+
+    exports
+}
+
+/// Collect `ft_*` function declarations from the C header
+fn header_declarations(header: &str) -> BTreeMap<String, Signature> {
+    let source = strip_c_comments(header);
+    let mut declarations = BTreeMap::new();
+
+    for statement in source.split(';') {
+        let statement = statement.trim();
+        if statement.starts_with("typedef") || !statement.contains("ft_") {
+            continue;
+        }
+        let statement = statement.trim_start_matches("extern \"C\" {").trim();
+        let open = match statement.find('(') {
+            Some(open) => open,
+            None => continue,
+        };
+        let close = statement.rfind(')').expect("Unbalanced declaration in header");
+
+        let head = statement[..open].trim();
+        let name_start = head
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let name = head[name_start..].to_string();
+        if !name.starts_with("ft_") {
+            continue;
+        }
+        let return_type = normalize_c_type(&head[..name_start]);
+
+        let params_text = statement[open + 1..close].trim();
+        let params = if params_text == "void" || params_text.is_empty() {
+            Vec::new()
+        } else {
+            split_top_level(params_text)
+                .iter()
+                .map(|param| {
+                    let normalized = normalize_c_type(param);
+                    // Drop the parameter name
+                    let type_end = normalized
+                        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .map(|i| i + 1)
+                        .unwrap_or(0);
+                    normalize_c_type(&normalized[..type_end])
+                })
+                .collect()
+        };
+
+        declarations.insert(name, Signature { return_type, params });
+    }
+
+    declarations
+}
+
+fn read_header() -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/facingtime_core.h");
+    std::fs::read_to_string(path).expect("include/facingtime_core.h should exist")
+}
+
+/// Test: the checked-in header is the one cbindgen generates from the FFI sources
+///
+/// Run it alone with `FT_UPDATE_HEADER=1` to replace the checked-in header with the generated one.
+#[test]
+fn test_header_is_generated() {
+    let generated_path = Path::new(env!("OUT_DIR")).join("facingtime_core.h");
+    let generated = std::fs::read_to_string(&generated_path).expect("build.rs should generate the header");
+    if std::env::var_os("FT_UPDATE_HEADER").is_some() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/facingtime_core.h");
+        std::fs::write(path, &generated).unwrap();
+    }
+    assert!(
+        read_header() == generated,
+        "include/facingtime_core.h differs from {}; regenerate it with \
+         `FT_UPDATE_HEADER=1 cargo test --test ffi_header_test test_header_is_generated`",
+        generated_path.display()
+    );
+}
+
+/// Test: every exported function is declared in the header with a matching signature
+#[test]
+fn test_header_matches_exports() {
+    let exports = rust_exports();
+    let declarations = header_declarations(&read_header());
+    assert!(!exports.is_empty(), "Should find exported ft_* functions");
+
+    for (name, signature) in &exports {
+        let declared = declarations
+            .get(name)
+            .unwrap_or_else(|| panic!("{} is exported but not declared in facingtime_core.h", name));
+        assert_eq!(declared, signature, "Header signature of {} does not match Rust", name);
+    }
+
+    for name in declarations.keys() {
+        assert!(
+            exports.contains_key(name),
+            "{} is declared in facingtime_core.h but not exported",
+            name
+        );
+    }
+}
+
+/// Test: the header ABI version matches the library
+#[test]
+fn test_header_abi_version_matches_library() {
+    let header = read_header();
+    let defined = header
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("#define FT_ABI_VERSION"))
+        .expect("Header should define FT_ABI_VERSION")
+        .trim()
+        .parse::<u32>()
+        .expect("FT_ABI_VERSION should be a number");
+
+    assert_eq!(defined, FT_ABI_VERSION, "Header FT_ABI_VERSION is out of date");
+    assert_eq!(ft_core_abi_version(), FT_ABI_VERSION);
+}
+
+/// Test: ft_core_version returns the crate version
+#[test]
+fn test_core_version_matches_crate() {
+    let version = unsafe { CStr::from_ptr(ft_core_version()) };
+    assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+}
@@ -0,0 +1,285 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::ffi::error::{ft_last_error_code, FtErrorCode};
+use facingtime_core::ffi::server::{
+    ft_http_server_create, ft_http_server_export_games, ft_http_server_free, ft_http_server_free_response,
+    ft_http_server_get_game, ft_http_server_list_games, ft_http_server_set_data_dir,
+};
+use facingtime_core::game::{
+    Command, Faction, GameHistory, GameLog, GameRecord, GameSetup, GameTable, Phase, SeededRng, HISTORY_FILE,
+};
+use facingtime_core::server::HttpServerState;
+
+use common::{play_logged_game, seat_clients};
+
+/// A fresh, empty directory under the system temp directory
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ft_history_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Play a 5-player game to the end (see [`play_logged_game`])
+fn play_game(seed: u64) -> GameLog {
+    play_logged_game(GameSetup::new(5), seed, |_| {}).0
+}
+
+/// The first seed whose game reaches the assassination
+fn assassination_game() -> GameLog {
+    (1..)
+        .map(play_game)
+        .find(|log| log.replay().unwrap().observer_view()["result"]["reason"] != "quests_failed")
+        .unwrap()
+}
+
+/// Send a GET request and return the status line and body
+fn get(addr: std::net::SocketAddr, path: &str) -> (String, String) {
+    let response = common::get(addr, path);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (head.lines().next().unwrap().to_string(), body.to_string())
+}
+
+/// Take a string returned by the FFI
+fn take_string(text: *mut std::os::raw::c_char) -> Option<String> {
+    if text.is_null() {
+        return None;
+    }
+    let owned = unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned();
+    unsafe { ft_http_server_free_response(text) };
+    Some(owned)
+}
+
+/// Test: a game is only recorded once it is over
+#[test]
+fn test_record_needs_a_finished_game() {
+    let (log, _, _) = GameLog::start(GameSetup::new(5), &[0, 1, 2, 3, 4], 1).unwrap();
+    assert_eq!(GameRecord::new(log, BTreeMap::new()), None);
+}
+
+/// Test: a record holds the players, roles, every proposal and vote, the quests, the assassination and the winner
+#[test]
+fn test_record_contents() {
+    let log = assassination_game();
+    let state = log.replay().unwrap();
+    let names = BTreeMap::from([(0, "Ann".to_string()), (3, "Dee".to_string())]);
+    let record = GameRecord::new(log.clone(), names).unwrap();
+    assert_eq!(record.result(), state.result().unwrap());
+    assert!(record.id().ends_with(&format!("{:016x}", log.seed())));
+
+    let json = record.to_json();
+    assert_eq!(json["id"], record.id());
+    assert_eq!(json["player_count"], 5);
+    assert_eq!(json["players"][0]["seat"], 0);
+    assert_eq!(json["players"][0]["name"], "Ann");
+    assert_eq!(json["players"][1]["name"], serde_json::Value::Null);
+    for (seat, role) in state.roles().roles() {
+        assert_eq!(json["players"][seat as usize]["role"], role.name());
+    }
+    assert_eq!(json["winner"], state.result().unwrap().0.name());
+    assert_eq!(json["reason"], state.result().unwrap().1.name());
+
+    let proposals = json["proposals"].as_array().unwrap();
+    assert_eq!(proposals.len(), 2 * state.outcomes().len(), "Every quest had a rejected team first");
+    assert_eq!(proposals[0]["attempt"], 1);
+    assert_eq!(proposals[0]["approved"], false);
+    assert_eq!(proposals[0]["votes"].as_array().unwrap().len(), 5);
+    assert_eq!(proposals[1]["votes"][2], serde_json::json!({"seat": 2, "approve": true}));
+
+    let quests = json["quests"].as_array().unwrap();
+    assert_eq!(quests.len(), state.outcomes().len());
+    for (quest, outcome) in quests.iter().zip(state.outcomes()) {
+        assert_eq!(quest["succeeded"], outcome.succeeded);
+        assert_eq!(quest["cards"].as_array().unwrap().len(), outcome.team.len());
+    }
+    let assassin = state.roles().assassin().unwrap();
+    let target = state.roles().faction_seats(Faction::Resistance)[0];
+    assert_eq!(json["assassination"], serde_json::json!({"assassin": assassin, "target": target}));
+    assert_eq!(json["log"], log.to_json());
+}
+
+/// Test: a stored record reads back into the same game, and a tampered one does not
+#[test]
+fn test_record_round_trip() {
+    let record = GameRecord::new(play_game(2), BTreeMap::from([(1, "Bo".to_string())])).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&record.to_json().to_string()).unwrap();
+    assert_eq!(GameRecord::from_json(&json), Ok(record));
+
+    let mut tampered = json.clone();
+    tampered["log"]["seed"] = "99".into();
+    assert!(GameRecord::from_json(&tampered).is_err());
+}
+
+/// Test: saved games are listed, fetched and exported, and unreadable lines are skipped
+#[test]
+fn test_history_file() {
+    let dir = temp_dir("file");
+    let history = GameHistory::open(&dir).unwrap();
+    assert!(dir.is_dir(), "The data directory is created");
+    assert_eq!(history.list().unwrap(), Vec::<serde_json::Value>::new());
+    assert_eq!(history.export().unwrap(), "");
+
+    let first = GameRecord::new(play_game(3), BTreeMap::new()).unwrap();
+    let second = GameRecord::new(play_game(4), BTreeMap::new()).unwrap();
+    history.save(&first).unwrap();
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(dir.join(HISTORY_FILE))
+        .unwrap()
+        .write_all(b"not json\n")
+        .unwrap();
+    history.save(&second).unwrap();
+
+    let list = history.list().unwrap();
+    let ids: Vec<&str> = list.iter().map(|game| game["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec![first.id(), second.id()]);
+    assert_eq!(list[0]["winner"], first.to_json()["winner"]);
+    assert_eq!(list[0].get("log"), None, "Summaries leave the details out");
+    assert_eq!(history.get(second.id()).unwrap(), Some(second.to_json()));
+    assert_eq!(history.get("unknown").unwrap(), None);
+    assert_eq!(history.export().unwrap().lines().count(), 3, "The export is the file as written");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Test: the table saves its game once it is over, with the players' names, and it is served under /history
+#[test]
+fn test_table_saves_and_serves() {
+    let dir = temp_dir("table");
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let addr = server.local_addr().unwrap();
+    assert!(get(addr, "/history").0.contains("404"), "No history without a data directory");
+    server.stop();
+
+    server.set_data_dir(Some(dir.to_str().unwrap())).unwrap();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let addr = server.local_addr().unwrap();
+    let runtime = Runtime::new().unwrap();
+    let (_clients, _) = seat_clients(&runtime, &server, 5);
+    let (mut table, _) =
+        GameTable::start(server.hub().clone(), GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(8)).unwrap();
+    table.set_history(server.history().cloned());
+    table.set_player_name(2, "Cy");
+    while table.state().phase() != Phase::GameOver {
+        let state = table.state();
+        let commands: Vec<Command> = match state.phase() {
+            Phase::TeamBuilding => {
+                let size = state.quests()[state.quest()].team_size;
+                let team = (0..size as u32).collect();
+                vec![Command::ProposeTeam { seat: state.leader(), team, grants: BTreeMap::new() }]
+            }
+            Phase::TeamVote => (0..5).map(|seat| Command::VoteTeam { seat, approve: true }).collect(),
+            Phase::Quest => state.team().iter().map(|&seat| Command::PlayQuestCard { seat, success: true }).collect(),
+            Phase::Assassination => vec![Command::AssassinationTimedOut],
+            phase => unreachable!("{:?}", phase),
+        };
+        assert!(server.history().unwrap().list().unwrap().is_empty(), "Nothing is saved before the end");
+        for command in commands {
+            table.apply(command).unwrap();
+        }
+    }
+
+    let (status, body) = get(addr, "/history");
+    assert!(status.contains("200"));
+    let games: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let id = games["games"][0]["id"].as_str().unwrap().to_string();
+    assert_eq!(games["games"].as_array().unwrap().len(), 1);
+    assert_eq!(games["games"][0]["players"][2]["name"], "Cy");
+
+    let (status, body) = get(addr, &format!("/history/{}", id));
+    assert!(status.contains("200"));
+    let record: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(record["log"], table.log().to_json());
+    assert!(get(addr, "/history/unknown").0.contains("404"));
+
+    let (status, body) = get(addr, "/history/export");
+    assert!(status.contains("200"));
+    assert_eq!(body.lines().count(), 1);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(body.lines().next().unwrap()).unwrap(), record);
+    server.stop();
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Test: the history is only served with the room code and password, and failures count towards a lockout
+#[test]
+fn test_history_needs_room_access() {
+    let dir = temp_dir("access");
+    let record = GameRecord::new(play_game(10), BTreeMap::new()).unwrap();
+    GameHistory::open(&dir).unwrap().save(&record).unwrap();
+    let mut server = HttpServerState::new();
+    server.set_data_dir(Some(dir.to_str().unwrap())).unwrap();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let addr = server.local_addr().unwrap();
+    assert!(get(addr, "/history").0.contains("200"), "An open room serves its history");
+
+    let access = server.access();
+    access.set_code_required(true);
+    access.set_password(Some("secret"));
+    access.set_lockout_policy(5, Duration::from_secs(60));
+    let code = access.room_code();
+    for path in ["/history".to_string(), format!("/history/{}", record.id()), "/history/export".to_string()] {
+        let (status, body) = get(addr, &path);
+        assert!(status.contains("403"), "{} without the code: {}", path, status);
+        let message: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(message["reason"], "invalid_code");
+        assert!(!body.contains(record.id()), "A refused request reveals nothing");
+    }
+    let (status, body) = get(addr, &format!("/history?code={}", code));
+    assert!(status.contains("403"));
+    assert!(body.contains("wrong_password"));
+
+    let (status, body) = get(addr, &format!("/history/{}?code={}&password=secret", record.id(), code));
+    assert!(status.contains("200"), "{}", status);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap(), record.to_json());
+
+    for _ in 0..5 {
+        assert!(get(addr, "/history?code=WRONG9").0.contains("403"));
+    }
+    let response = common::get(addr, &format!("/history?code={}&password=secret", code));
+    assert!(response.starts_with("HTTP/1.1 429"), "Locked out: {}", response);
+    assert!(response.to_lowercase().contains("retry-after:"));
+    assert!(get(addr, "/rules").0.contains("200"), "Other routes need no code");
+    server.stop();
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Test: the FFI lists, fetches and exports past games, and refuses without a data directory
+#[test]
+fn test_ffi_history() {
+    let dir = temp_dir("ffi");
+    let record = GameRecord::new(play_game(9), BTreeMap::new()).unwrap();
+    GameHistory::open(&dir).unwrap().save(&record).unwrap();
+
+    let server = unsafe { ft_http_server_create() };
+    assert_eq!(take_string(unsafe { ft_http_server_list_games(server) }), None);
+    assert_eq!(ft_last_error_code(), FtErrorCode::InvalidConfig as i32);
+
+    let data_dir = CString::new(dir.to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { ft_http_server_set_data_dir(server, data_dir.as_ptr()) }, FtErrorCode::Ok as i32);
+    let list: serde_json::Value = serde_json::from_str(&take_string(unsafe { ft_http_server_list_games(server) })
+        .unwrap())
+    .unwrap();
+    assert_eq!(list[0]["id"], record.id());
+
+    let id = CString::new(record.id()).unwrap();
+    let game = take_string(unsafe { ft_http_server_get_game(server, id.as_ptr()) }).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&game).unwrap(), record.to_json());
+    let unknown = CString::new("unknown").unwrap();
+    assert_eq!(take_string(unsafe { ft_http_server_get_game(server, unknown.as_ptr()) }), None);
+    assert_eq!(ft_last_error_code(), FtErrorCode::Ok as i32, "An unknown game is not an error");
+
+    let export = take_string(unsafe { ft_http_server_export_games(server) }).unwrap();
+    assert_eq!(export, format!("{}\n", record.to_json()));
+
+    assert_eq!(unsafe { ft_http_server_set_data_dir(server, std::ptr::null()) }, FtErrorCode::Ok as i32);
+    assert_eq!(take_string(unsafe { ft_http_server_export_games(server) }), None);
+    unsafe { ft_http_server_free(server) };
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::game::{
+    Command, ExcaliburRules, GameEvent, GameLog, GameSetup, GameState, GameTable, Phase, ReplayError, SeededRng,
+};
+use facingtime_core::server::HttpServerState;
+
+use common::{play_logged_game, seat_clients};
+
+/// Play a whole 7-player game with Excalibur and the Lady of the Lake
+///
+/// # Returns
+/// The log, the final state and a snapshot taken after the second quest with its `seq`
+fn play_game(seed: u64) -> (GameLog, GameState, (u64, serde_json::Value)) {
+    let setup = GameSetup {
+        lady_of_the_lake: true,
+        excalibur: Some(ExcaliburRules::default()),
+        ..GameSetup::new(7)
+    };
+    let mut middle = None;
+    let (log, state) = play_logged_game(setup, seed, |state| {
+        if state.outcomes().len() == 2 && middle.is_none() {
+            middle = Some((state.seq(), state.observer_view()));
+        }
+    });
+    (log, state, middle.unwrap())
+}
+
+/// Test: every accepted command is logged in order with its numbered events
+#[test]
+fn test_log_records_commands() {
+    let (log, state, _) = play_game(1);
+    let entries = log.entries();
+    assert_eq!(entries[0].command, None, "The log opens with the start of the game");
+    assert!(matches!(entries[0].events[0], GameEvent::GameStarted { .. }));
+    assert_eq!(entries.last().unwrap().seq, state.seq());
+    let numbers: Vec<u64> = entries.iter().flat_map(|entry| entry.numbered_events().map(|(seq, _)| seq)).collect();
+    assert_eq!(numbers, (1..=state.seq()).collect::<Vec<u64>>());
+    assert!(entries.windows(2).all(|pair| pair[0].at_ms <= pair[1].at_ms));
+
+    let rejected_first_team: Vec<u32> = entries
+        .iter()
+        .flat_map(|entry| &entry.events)
+        .find_map(|event| match event {
+            GameEvent::TeamVoteResult { quest: 0, attempt: 1, votes, .. } => {
+                Some(votes.iter().filter(|(_, approve)| !approve).map(|(seat, _)| *seat).collect())
+            }
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(rejected_first_team, vec![1, 2, 3, 4, 5, 6], "The log answers who rejected a team");
+}
+
+/// Test: replaying the log rebuilds the same game, at the end and at any point in between
+#[test]
+fn test_replay_matches() {
+    for seed in [2, 3, 4] {
+        let (log, state, (middle_seq, middle)) = play_game(seed);
+        let replayed = log.replay().unwrap();
+        assert_eq!(replayed.seq(), state.seq());
+        assert_eq!(replayed.result(), state.result());
+        assert_eq!(replayed.quest_cards(), state.quest_cards());
+        for seat in 0..7 {
+            assert_eq!(replayed.view_for(seat), state.view_for(seat), "Seat {} with seed {}", seat, seed);
+        }
+        assert_eq!(log.replay_to(middle_seq).unwrap().observer_view(), middle);
+        assert_eq!(log.replay_to(0).unwrap().seq(), log.entries()[0].seq, "At least the start is replayed");
+    }
+}
+
+/// Test: the JSON form reads back into the same log, and a tampered log does not replay
+#[test]
+fn test_json_round_trip() {
+    let (log, _, _) = play_game(5);
+    let text = log.to_json().to_string();
+    let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(json["seed"], "5");
+    assert_eq!(GameLog::from_json(&json), Ok(log.clone()));
+
+    let mut tampered = json.clone();
+    let vote = tampered["entries"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|entry| entry["command"]["type"] == "vote_team")
+        .unwrap();
+    vote["events"][0]["seat"] = 6.into();
+    let seq = vote["seq"].as_u64().unwrap();
+    assert_eq!(GameLog::from_json(&tampered), Err(ReplayError::Diverged { seq }));
+
+    let mut reseeded = json.clone();
+    reseeded["seed"] = "6".into();
+    assert!(GameLog::from_json(&reseeded).is_err(), "Another seed deals another game");
+    assert!(matches!(
+        GameLog::from_json(&serde_json::json!({"seed": "5"})),
+        Err(ReplayError::Malformed(_))
+    ));
+}
+
+/// Test: the table logs what its players do and leaves refused commands out
+#[test]
+fn test_table_log() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+
+    let (_clients, peers) = seat_clients(&runtime, &server, 5);
+
+    let (mut table, _) =
+        GameTable::start(server.hub().clone(), GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(7)).unwrap();
+    let leader = table.state().leader();
+    let message = r#"{"type":"propose_team","team":[0,1]}"#;
+    assert!(table.handle_message(peers[(leader as usize + 1) % 5], message).is_some());
+    assert_eq!(table.log().entries().len(), 1, "A refused command is not logged");
+    assert_eq!(table.handle_message(peers[leader as usize], message).map(|e| e.len()), Some(2));
+    table.apply(Command::VoteTeam { seat: 2, approve: false }).unwrap();
+
+    let entries = table.log().entries();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].command.as_ref().and_then(Command::seat), Some(leader));
+    assert_eq!(entries[2].command, Some(Command::VoteTeam { seat: 2, approve: false }));
+    assert_eq!(table.log().replay().unwrap().view_for(2), table.state().view_for(2));
+    server.stop();
+}
+
+/// Test: the table reports the end of its game, so a game in progress is never replaced
+#[test]
+fn test_table_is_over() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+
+    let (_clients, _) = seat_clients(&runtime, &server, 5);
+
+    let (mut table, _) =
+        GameTable::start(server.hub().clone(), GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(9)).unwrap();
+    assert!(!table.is_over());
+    while table.state().phase() == Phase::TeamBuilding {
+        assert!(!table.is_over(), "Rejected teams do not end the game until the vote track runs out");
+        let leader = table.state().leader();
+        table
+            .apply(Command::ProposeTeam { seat: leader, team: vec![0, 1], grants: BTreeMap::new() })
+            .unwrap();
+        for seat in 0..5 {
+            table.apply(Command::VoteTeam { seat, approve: false }).unwrap();
+        }
+    }
+    assert_eq!(table.state().phase(), Phase::GameOver);
+    assert!(table.is_over());
+    server.stop();
+}
@@ -0,0 +1,193 @@
+use facingtime_core::game::{
+    spy_count, AssassinationRules, Faction, GameSetup, Role, RoleAssignment, RoleError, SeededRng, SetupError,
+    MAX_PLAYERS, MIN_PLAYERS,
+};
+
+/// Count how often `role` appears in a lineup
+fn count(lineup: &[Role], role: Role) -> usize {
+    lineup.iter().filter(|&&r| r == role).count()
+}
+
+/// Every combination of the optional role flags for a player count
+fn all_setups(player_count: usize) -> Vec<GameSetup> {
+    (0..32u8)
+        .map(|bits| GameSetup {
+            player_count,
+            merlin: bits & 1 != 0,
+            percival: bits & 2 != 0,
+            morgana: bits & 4 != 0,
+            mordred: bits & 8 != 0,
+            oberon: bits & 16 != 0,
+            lady_of_the_lake: false,
+            excalibur: None,
+            assassination: AssassinationRules::default(),
+        })
+        .collect()
+}
+
+/// Test: the default setup matches the GameConfig lineup
+#[test]
+fn test_default_setup() {
+    for players in MIN_PLAYERS..=MAX_PLAYERS {
+        let setup = GameSetup::new(players);
+        assert!(setup.validate().is_empty(), "{} players", players);
+        let lineup = setup.lineup().unwrap();
+        assert_eq!(lineup.len(), players);
+        assert_eq!(count(&lineup, Role::Merlin), 1);
+        assert_eq!(count(&lineup, Role::Percival), 1);
+        assert_eq!(count(&lineup, Role::Morgana), 1);
+        assert_eq!(count(&lineup, Role::Mordred), 1);
+        assert_eq!(count(&lineup, Role::Oberon), 0);
+    }
+}
+
+/// Test: plain Resistance deals only members and spies, and has no assassin
+#[test]
+fn test_plain_resistance() {
+    let setup = GameSetup::plain(6);
+    assert_eq!(
+        setup.lineup().unwrap(),
+        vec![
+            Role::ResistanceMember,
+            Role::ResistanceMember,
+            Role::ResistanceMember,
+            Role::ResistanceMember,
+            Role::Spy,
+            Role::Spy
+        ]
+    );
+    let assignment = setup.deal(&[0, 1, 2, 3, 4, 5], &mut SeededRng::new(1)).unwrap();
+    assert_eq!(assignment.assassin(), None);
+
+    let with_oberon = GameSetup { oberon: true, ..GameSetup::plain(7) };
+    assert!(with_oberon.validate().is_empty(), "Oberon does not need Merlin");
+}
+
+/// Test: every valid combination keeps the official faction counts
+#[test]
+fn test_faction_counts_for_every_setup() {
+    for players in MIN_PLAYERS..=MAX_PLAYERS {
+        let spies = spy_count(players).unwrap();
+        for setup in all_setups(players) {
+            match setup.lineup() {
+                Ok(lineup) => {
+                    assert_eq!(lineup.len(), players);
+                    let evil = lineup.iter().filter(|r| r.faction() == Faction::Spies).count();
+                    assert_eq!(evil, spies, "{:?}", setup);
+                    for (role, chosen) in [
+                        (Role::Merlin, setup.merlin),
+                        (Role::Percival, setup.percival),
+                        (Role::Morgana, setup.morgana),
+                        (Role::Mordred, setup.mordred),
+                        (Role::Oberon, setup.oberon),
+                    ] {
+                        assert_eq!(count(&lineup, role), usize::from(chosen), "{:?}", setup);
+                    }
+                }
+                Err(errors) => assert_eq!(errors, setup.validate()),
+            }
+        }
+    }
+}
+
+/// Test: special roles need Merlin, and Morgana needs Percival
+#[test]
+fn test_role_dependencies() {
+    let setup = GameSetup {
+        merlin: false,
+        ..GameSetup::new(7)
+    };
+    assert_eq!(
+        setup.validate(),
+        vec![
+            SetupError::RequiresMerlin(Role::Percival),
+            SetupError::RequiresMerlin(Role::Morgana),
+            SetupError::RequiresMerlin(Role::Mordred),
+        ]
+    );
+    assert_eq!(setup.validate()[0].to_string(), "Percival needs Merlin in the game");
+    assert_eq!(setup.validate()[0].field(), "percival");
+
+    let setup = GameSetup {
+        percival: false,
+        ..GameSetup::new(7)
+    };
+    assert_eq!(setup.validate(), vec![SetupError::MorganaWithoutPercival]);
+    assert_eq!(setup.validate()[0].field(), "morgana");
+
+    let setup = GameSetup {
+        morgana: false,
+        ..GameSetup::new(7)
+    };
+    assert!(setup.validate().is_empty(), "Percival may play without Morgana");
+}
+
+/// Test: more special spies than spy seats are rejected with an explanation
+#[test]
+fn test_too_many_special_spies() {
+    let setup = GameSetup {
+        oberon: true,
+        ..GameSetup::new(5)
+    };
+    let errors = setup.validate();
+    assert_eq!(
+        errors,
+        vec![SetupError::TooManySpies {
+            player_count: 5,
+            spies: 2,
+            chosen: vec![Role::Mordred, Role::Morgana, Role::Oberon],
+        }]
+    );
+    assert_eq!(errors[0].field(), "spies");
+    assert_eq!(
+        errors[0].to_string(),
+        "5 players have 2 spies, but Mordred, Morgana, Oberon need 3"
+    );
+
+    let seven = GameSetup {
+        oberon: true,
+        ..GameSetup::new(7)
+    };
+    assert!(seven.validate().is_empty(), "7 players have room for three special spies");
+    assert_eq!(count(&seven.lineup().unwrap(), Role::Spy), 0);
+}
+
+/// Test: unsupported player counts are rejected before anything else
+#[test]
+fn test_unsupported_player_count() {
+    for players in [0, 4, 11] {
+        let setup = GameSetup {
+            merlin: false,
+            ..GameSetup::new(players)
+        };
+        assert_eq!(setup.validate(), vec![SetupError::UnsupportedPlayerCount(players)]);
+        assert_eq!(setup.validate()[0].field(), "player_count");
+    }
+}
+
+/// Test: dealing an invalid setup reports every explanation
+#[test]
+fn test_deal_invalid_setup() {
+    let setup = GameSetup {
+        merlin: false,
+        percival: false,
+        oberon: true,
+        ..GameSetup::new(5)
+    };
+    let error = setup.deal(&[0, 1, 2, 3, 4], &mut SeededRng::new(0)).unwrap_err();
+    assert_eq!(error, RoleError::InvalidSetup(setup.validate()));
+    assert_eq!(
+        error.to_string(),
+        "Morgana needs Merlin in the game; Mordred needs Merlin in the game; \
+         Morgana only matters to Percival; add Percival or leave Morgana out; \
+         5 players have 2 spies, but Mordred, Morgana, Oberon need 3"
+    );
+
+    let valid = GameSetup::new(5);
+    assert_eq!(
+        valid.deal(&[0, 1, 2, 3], &mut SeededRng::new(0)),
+        Err(RoleError::WrongPlayerCount { roles: 5, seats: 4 })
+    );
+    let assignment: RoleAssignment = valid.deal(&[0, 1, 2, 3, 4], &mut SeededRng::new(0)).unwrap();
+    assert!(assignment.assassin().is_some());
+}
@@ -0,0 +1,390 @@
+mod common;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::game::lady::LadyTargetError;
+use facingtime_core::game::{
+    Audience, Command, Faction, GameError, GameEvent, GameSetup, GameState, GameTable, Phase, Role, SeededRng,
+    WinReason,
+};
+use facingtime_core::server::HttpServerState;
+
+use common::{next_of_type, propose, seat_clients, start_game};
+
+/// The only seat holding `role`
+fn seat_of(state: &GameState, role: Role) -> u32 {
+    state.roles().seats_with(role)[0]
+}
+
+/// A team of the quest's size, with a spy on it unless `loyal` is set
+fn team_for(state: &GameState, loyal: bool) -> Vec<u32> {
+    let size = state.quests()[state.quest()].team_size;
+    let mut seats: Vec<u32> = state.seats().to_vec();
+    seats.sort_by_key(|&seat| {
+        let spy = state.roles().role_of(seat).unwrap().faction() == Faction::Spies;
+        spy == loyal
+    });
+    seats.truncate(size);
+    seats
+}
+
+/// Have every player vote on the proposed team
+fn vote_all(state: &mut GameState, approve: bool) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    for seat in state.seats().to_vec() {
+        events.extend(state.apply(Command::VoteTeam { seat, approve }).unwrap());
+    }
+    events
+}
+
+/// Propose a team, approve it and play the quest; spies on the team fail it unless `succeed` is set
+fn play_quest(state: &mut GameState, succeed: bool) -> Vec<GameEvent> {
+    let team = team_for(state, succeed);
+    let leader = state.leader();
+    state.apply(propose(leader, team.clone())).unwrap();
+    vote_all(state, true);
+    let mut events = Vec::new();
+    for seat in team {
+        let spy = state.roles().role_of(seat).unwrap().faction() == Faction::Spies;
+        let success = succeed || !spy;
+        events.extend(state.apply(Command::PlayQuestCard { seat, success }).unwrap());
+    }
+    events
+}
+
+/// Test: a new game announces its seats, leader and quests and waits for a team
+#[test]
+fn test_game_start() {
+    let (state, events) = start_game(GameSetup::new(7), 3);
+    assert_eq!(state.phase(), Phase::TeamBuilding);
+    assert_eq!(state.quest(), 0);
+    assert_eq!(state.lady_holder(), None);
+    assert_eq!(
+        events[0],
+        GameEvent::GameStarted {
+            seats: (0..7).collect(),
+            leader: state.leader(),
+            lady: None,
+            quests: state.quests().to_vec(),
+            modules: Vec::new(),
+        }
+    );
+    let message: serde_json::Value = serde_json::from_str(&events[1].to_message()).unwrap();
+    assert_eq!(message["type"], "phase");
+    assert_eq!(message["phase"], "team_building");
+
+    let (state, events) = start_game(GameSetup { lady_of_the_lake: true, ..GameSetup::new(7) }, 3);
+    let holder = state.lady_holder().unwrap();
+    assert_eq!((holder + 1) % 7, state.leader(), "The Lady starts to the leader's right");
+    assert!(matches!(events[0], GameEvent::GameStarted { lady: Some(seat), .. } if seat == holder));
+}
+
+/// Test: only the leader proposes, with the quest's size and no repeats
+#[test]
+fn test_team_proposal_errors() {
+    let (mut state, _) = start_game(GameSetup::new(5), 1);
+    let leader = state.leader();
+    let other = (leader + 1) % 5;
+
+    assert_eq!(
+        state.apply(propose(other, vec![0, 1])),
+        Err(GameError::NotYourTurn)
+    );
+    assert_eq!(
+        state.apply(propose(leader, vec![0, 1, 2])),
+        Err(GameError::WrongTeamSize { expected: 2, actual: 3 })
+    );
+    assert_eq!(
+        state.apply(propose(leader, vec![1, 1])),
+        Err(GameError::DuplicateTeamMember(1))
+    );
+    assert_eq!(
+        state.apply(propose(leader, vec![1, 9])),
+        Err(GameError::NotInGame(9))
+    );
+    assert_eq!(
+        state.apply(Command::VoteTeam { seat: 9, approve: true }),
+        Err(GameError::NotInGame(9))
+    );
+    assert_eq!(
+        state.apply(Command::VoteTeam { seat: leader, approve: true }),
+        Err(GameError::WrongPhase(Phase::TeamBuilding))
+    );
+    assert_eq!(state.phase(), Phase::TeamBuilding, "Refused commands change nothing");
+
+    state.apply(propose(leader, vec![0, 1])).unwrap();
+    assert_eq!(state.phase(), Phase::TeamVote);
+    assert_eq!(state.team(), &[0, 1]);
+    state.apply(Command::VoteTeam { seat: 0, approve: true }).unwrap();
+    assert_eq!(
+        state.apply(Command::VoteTeam { seat: 0, approve: false }),
+        Err(GameError::AlreadyActed)
+    );
+    assert_eq!(state.voted(), vec![0]);
+}
+
+/// Test: a tie rejects the team, leadership passes, and five rejections win for the spies
+#[test]
+fn test_vote_track() {
+    let (mut state, _) = start_game(GameSetup::new(6), 2);
+    let first_leader = state.leader();
+    for attempt in 0..5 {
+        let leader = state.leader();
+        assert_eq!(leader, (first_leader + attempt) % 6);
+        let team = team_for(&state, true);
+        state.apply(propose(leader, team)).unwrap();
+        let mut events = Vec::new();
+        for seat in state.seats().to_vec() {
+            events.extend(state.apply(Command::VoteTeam { seat, approve: seat % 2 == 0 }).unwrap());
+        }
+        let result = events
+            .iter()
+            .find(|event| matches!(event, GameEvent::TeamVoteResult { .. }))
+            .unwrap();
+        let GameEvent::TeamVoteResult {
+            approved,
+            attempt: number,
+            votes,
+            ..
+        } = result
+        else {
+            panic!("Expected a vote result");
+        };
+        assert!(!approved, "Three of six approvals is not a majority");
+        assert_eq!((*number, votes.len()), (attempt as usize + 1, 6));
+        assert_eq!(state.rejections(), attempt as usize + 1);
+    }
+    assert_eq!(state.phase(), Phase::GameOver);
+    assert_eq!(state.result(), Some((Faction::Spies, WinReason::VoteTrackExhausted)));
+}
+
+/// Test: quests resolve from fail counts, and the fourth quest of seven players needs two fails
+#[test]
+fn test_quest_resolution() {
+    let setup = GameSetup {
+        oberon: true,
+        ..GameSetup::new(7)
+    };
+    let (mut state, _) = start_game(setup, 4);
+    let events = play_quest(&mut state, false);
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { quest: 0, fails: 2, succeeded: false, .. })));
+    for event in events.iter().filter(|e| matches!(e, GameEvent::QuestCardPlayed { .. })) {
+        assert!(!event.to_message().contains("success"), "Cards stay secret");
+    }
+    play_quest(&mut state, true);
+    play_quest(&mut state, true);
+    assert_eq!(state.quest(), 3);
+    assert_eq!(state.quests()[3].fails_required, 2);
+
+    // One spy fails the fourth quest alone
+    let spy = state.roles().faction_seats(Faction::Spies)[0];
+    let mut team = vec![spy];
+    team.extend(
+        state
+            .seats()
+            .iter()
+            .copied()
+            .filter(|&seat| state.roles().role_of(seat).unwrap().faction() == Faction::Resistance)
+            .take(3),
+    );
+    let leader = state.leader();
+    state.apply(propose(leader, team.clone())).unwrap();
+    vote_all(&mut state, true);
+    let mut events = Vec::new();
+    for seat in team {
+        events.extend(state.apply(Command::PlayQuestCard { seat, success: seat != spy }).unwrap());
+    }
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { quest: 3, fails: 1, succeeded: true, .. })));
+    assert_eq!(state.phase(), Phase::Assassination, "Three quests succeeded");
+    assert_eq!(state.outcomes().iter().filter(|o| o.succeeded).count(), 3);
+}
+
+/// Test: the Lady of the Lake follows quests two to four and passes to each inspected player
+#[test]
+fn test_lady_of_the_lake() {
+    let setup = GameSetup {
+        lady_of_the_lake: true,
+        ..GameSetup::new(5)
+    };
+    let (mut state, _) = start_game(setup, 8);
+    let first = state.lady_holder().unwrap();
+
+    play_quest(&mut state, true);
+    assert_eq!(state.phase(), Phase::TeamBuilding, "No Lady after the first quest");
+    play_quest(&mut state, false);
+    assert_eq!(state.phase(), Phase::LadyOfTheLake);
+    let leader = state.leader();
+    assert_eq!(
+        state.apply(propose(leader, vec![0, 1])),
+        Err(GameError::WrongPhase(Phase::LadyOfTheLake))
+    );
+    let not_holder = (first + 1) % 5;
+    assert_eq!(
+        state.apply(Command::InspectLoyalty { seat: not_holder, target: first }),
+        Err(GameError::NotYourTurn)
+    );
+    assert_eq!(
+        state.apply(Command::InspectLoyalty { seat: first, target: first }),
+        Err(GameError::InvalidLadyTarget(LadyTargetError::SelfInspection))
+    );
+
+    let second = (first + 2) % 5;
+    let events = state.apply(Command::InspectLoyalty { seat: first, target: second }).unwrap();
+    assert_eq!(events[0], GameEvent::LadyInspected { holder: first, target: second });
+    let faction = state.roles().role_of(second).unwrap().faction();
+    assert_eq!(
+        events[1],
+        GameEvent::LoyaltyRevealed {
+            holder: first,
+            target: second,
+            faction
+        }
+    );
+    assert_eq!(events[0].audience(), Audience::Everyone);
+    assert!(!events[0].to_message().contains("faction"), "The public event hides the loyalty");
+    assert_eq!(events[1].audience(), Audience::Seat(first));
+    assert_eq!(events[1].private_key(), Some(format!("lady_result_{}", second)));
+    assert_eq!(state.phase(), Phase::TeamBuilding);
+    assert_eq!(state.lady_holder(), Some(second));
+
+    play_quest(&mut state, true);
+    assert_eq!(state.phase(), Phase::LadyOfTheLake);
+    assert_eq!(
+        state.apply(Command::InspectLoyalty { seat: second, target: first }),
+        Err(GameError::InvalidLadyTarget(LadyTargetError::PreviousHolder))
+    );
+    let third = (first + 3) % 5;
+    state.apply(Command::InspectLoyalty { seat: second, target: third }).unwrap();
+    assert_eq!(state.lady().unwrap().holders(), vec![first, second, third]);
+
+    play_quest(&mut state, false);
+    assert_eq!(state.phase(), Phase::LadyOfTheLake, "The Lady follows the fourth quest");
+}
+
+/// Test: only the assassin names a target; naming Merlin wins for the spies
+#[test]
+fn test_assassination() {
+    for (seed, hit) in [(11, true), (12, false)] {
+        let (mut state, _) = start_game(GameSetup::new(5), seed);
+        for _ in 0..3 {
+            play_quest(&mut state, true);
+        }
+        assert_eq!(state.phase(), Phase::Assassination);
+        let assassin = state.roles().assassin().unwrap();
+        let merlin = seat_of(&state, Role::Merlin);
+        let percival = seat_of(&state, Role::Percival);
+        let other_spy = state
+            .roles()
+            .faction_seats(Faction::Spies)
+            .into_iter()
+            .find(|&seat| seat != assassin)
+            .unwrap();
+        assert_eq!(
+            state.apply(Command::Assassinate { seat: other_spy, target: merlin }),
+            Err(GameError::NotYourTurn)
+        );
+        assert_eq!(
+            state.apply(Command::Assassinate { seat: assassin, target: assassin }),
+            Err(GameError::InvalidTarget(assassin))
+        );
+
+        let target = if hit { merlin } else { percival };
+        let events = state.apply(Command::Assassinate { seat: assassin, target }).unwrap();
+        assert_eq!(events[0], GameEvent::AssassinationAttempted { assassin, target });
+        let expected = if hit {
+            (Faction::Spies, WinReason::MerlinAssassinated)
+        } else {
+            (Faction::Resistance, WinReason::MerlinSurvived)
+        };
+        assert_eq!(state.result(), Some(expected));
+        let message: serde_json::Value = serde_json::from_str(&events[1].to_message()).unwrap();
+        assert_eq!(message["type"], "game_over");
+        assert_eq!(message["roles"].as_array().unwrap().len(), 5, "Every role is revealed");
+    }
+
+    let (mut plain, _) = start_game(GameSetup::plain(5), 1);
+    for _ in 0..3 {
+        play_quest(&mut plain, true);
+    }
+    assert_eq!(plain.result(), Some((Faction::Resistance, WinReason::QuestsSucceeded)));
+}
+
+/// Test: client messages become commands for the sender's seat
+#[test]
+fn test_command_parse() {
+    assert_eq!(
+        Command::parse(2, r#"{"type":"propose_team","team":[0,2]}"#),
+        Ok(Some(propose(2, vec![0, 2])))
+    );
+    assert_eq!(
+        Command::parse(1, r#"{"type":"vote_team","approve":false}"#),
+        Ok(Some(Command::VoteTeam { seat: 1, approve: false }))
+    );
+    assert_eq!(
+        Command::parse(3, r#"{"type":"lady_inspect","target":4,"seat":0}"#),
+        Ok(Some(Command::InspectLoyalty { seat: 3, target: 4 })),
+        "The seat comes from the session, not the message"
+    );
+    assert_eq!(Command::parse(0, r#"{"type":"chat","text":"hi"}"#), Ok(None));
+    assert_eq!(Command::parse(0, "not json"), Ok(None));
+    let error = Command::parse(0, r#"{"type":"play_quest_card"}"#).unwrap_err();
+    assert_eq!(error.code(), "invalid_message");
+    assert_eq!(error.to_string(), "Invalid message: `success` is missing or invalid");
+}
+
+/// Test: over WebSocket, refusals reach only the sender and the Lady's result only her holder
+#[test]
+fn test_table_over_websocket() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+
+    let (mut clients, peers) = seat_clients(&runtime, &server, 5);
+
+    let setup = GameSetup {
+        lady_of_the_lake: true,
+        ..GameSetup::new(5)
+    };
+    let (mut table, _) =
+        GameTable::start(server.hub().clone(), setup, &[0, 1, 2, 3, 4], &mut SeededRng::new(8)).unwrap();
+    for client in clients.iter_mut() {
+        assert!(next_of_type(&runtime, client, "game_started").is_some());
+    }
+
+    // A refused command is answered to its sender alone
+    let leader = table.state().leader();
+    let other = (leader + 1) % 5;
+    let reply = table.handle_message(peers[other as usize], r#"{"type":"propose_team","team":[0,1]}"#);
+    assert_eq!(reply, Some(Vec::new()));
+    let error = next_of_type(&runtime, &mut clients[other as usize], "error").unwrap();
+    assert_eq!(error["code"], "not_your_turn");
+    assert_eq!(next_of_type(&runtime, &mut clients[leader as usize], "error"), None);
+    assert_eq!(table.handle_message(peers[0], r#"{"type":"chat"}"#), None);
+
+    // Reach the Lady of the Lake after two quests
+    while table.state().phase() != Phase::LadyOfTheLake {
+        let state = table.state().clone();
+        let team = team_for(&state, true);
+        table.apply(propose(state.leader(), team.clone())).unwrap();
+        for seat in state.seats().to_vec() {
+            table.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+        }
+        for seat in team {
+            table.apply(Command::PlayQuestCard { seat, success: true }).unwrap();
+        }
+    }
+    let holder = table.state().lady_holder().unwrap();
+    let target = (holder + 1) % 5;
+    let message = format!(r#"{{"type":"lady_inspect","target":{}}}"#, target);
+    let events = table.handle_message(peers[holder as usize], &message).unwrap();
+    assert_eq!(events.len(), 3);
+
+    let result = next_of_type(&runtime, &mut clients[holder as usize], "lady_result").unwrap();
+    assert_eq!(result["target"], target);
+    assert_eq!(result["faction"], table.state().roles().role_of(target).unwrap().faction().name());
+    for seat in (0..5).filter(|&seat| seat != holder) {
+        let client = &mut clients[seat as usize];
+        assert_eq!(next_of_type(&runtime, client, "lady_result"), None, "Seat {} sees no result", seat);
+    }
+    server.stop();
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::game::{
+    spy_channel, Command, ExcaliburRules, Faction, GameSetup, GameState, GameTable, Phase, SeededRng,
+};
+use facingtime_core::server::HttpServerState;
+
+use common::{connect, drain, peer_id, propose, seat_clients, start_game};
+
+/// Propose a loyal team, approve it and win the quest
+fn win_quest(state: &mut GameState) {
+    let size = state.quests()[state.quest()].team_size;
+    let team: Vec<u32> = state.roles().faction_seats(Faction::Resistance).into_iter().take(size).collect();
+    state.apply(propose(state.leader(), team.clone())).unwrap();
+    for seat in state.seats().to_vec() {
+        state.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+    }
+    for seat in team {
+        state.apply(Command::PlayQuestCard { seat, success: true }).unwrap();
+    }
+}
+
+/// Whether a snapshot names anyone's role
+fn names_a_role(view: &serde_json::Value) -> bool {
+    let text = view.to_string();
+    ["merlin", "percival", "resistance_member", "mordred", "morgana", "\"spy\"", "oberon"]
+        .iter()
+        .any(|role| text.contains(role))
+}
+
+/// Test: the observer view shows the board and nothing secret until the game is over
+#[test]
+fn test_observer_view() {
+    let mut state = start_game(GameSetup::new(5), 1).0;
+    let leader = state.leader();
+    state.apply(propose(leader, vec![0, 1])).unwrap();
+    state.apply(Command::VoteTeam { seat: 2, approve: false }).unwrap();
+
+    let view = state.observer_view();
+    assert_eq!(view["type"], "game_state");
+    assert_eq!(view["seq"], state.seq());
+    assert_eq!(view["seat"], serde_json::Value::Null);
+    assert_eq!(view["phase"], "team_vote");
+    assert_eq!(view["leader"], leader);
+    assert_eq!(view["team"], serde_json::json!([0, 1]));
+    assert_eq!(view["voted"], serde_json::json!([2]));
+    assert_eq!(view["quests"].as_array().unwrap().len(), 5);
+    assert_eq!(view["quests"][0]["team_size"], 2);
+    assert_eq!(view["quests"][0]["result"], serde_json::Value::Null);
+    assert_eq!(view["private"], serde_json::Value::Null);
+    assert_eq!(view["result"], serde_json::Value::Null);
+    assert!(!view.to_string().contains("approve"), "Votes stay secret while the vote lasts");
+    assert!(!names_a_role(&view));
+    assert_eq!(state.view_for(9), view, "A seat outside the game gets the observer view");
+
+    for seat in [0, 1, 3, 4] {
+        state.apply(Command::VoteTeam { seat, approve: false }).unwrap();
+    }
+    assert_eq!(state.observer_view()["rejections"], 1);
+    while state.result().is_none() {
+        win_quest(&mut state);
+        if state.phase() == Phase::Assassination {
+            state.apply(Command::AssassinationTimedOut).unwrap();
+        }
+    }
+    let view = state.observer_view();
+    assert_eq!(view["quests"][0]["result"]["succeeded"], true);
+    assert_eq!(view["result"]["winner"], "resistance");
+    assert_eq!(view["result"]["roles"].as_object().unwrap().len(), 5);
+    assert_eq!(view["result"]["quest_cards"].as_array().unwrap().len(), 3);
+}
+
+/// Test: a seat sees its own night information, vote and card, and nobody else's
+#[test]
+fn test_seat_view() {
+    let mut state = start_game(GameSetup::new(5), 2).0;
+    let team: Vec<u32> = state.roles().faction_seats(Faction::Resistance).into_iter().take(2).collect();
+    let other = (0..5).find(|seat| !team.contains(seat)).unwrap();
+    state.apply(propose(state.leader(), team.clone())).unwrap();
+    state.apply(Command::VoteTeam { seat: team[0], approve: false }).unwrap();
+
+    let mine = state.view_for(team[0]);
+    assert_eq!(mine["seat"], team[0]);
+    assert_eq!(mine["private"]["night_info"], state.roles().night_info(team[0]).unwrap().to_json());
+    assert_eq!(mine["private"]["team_vote"], false);
+    let theirs = state.view_for(other);
+    assert_eq!(theirs["private"]["team_vote"], serde_json::Value::Null);
+    assert_eq!(theirs["private"]["night_info"]["seat"], other);
+
+    for seat in 0..5 {
+        if seat != team[0] {
+            state.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+        }
+    }
+    assert_eq!(state.view_for(team[0])["private"]["team_vote"], serde_json::Value::Null);
+    state.apply(Command::PlayQuestCard { seat: team[1], success: true }).unwrap();
+    assert_eq!(state.view_for(team[1])["private"]["quest_card"], true);
+    assert_eq!(state.view_for(team[0])["private"]["quest_card"], serde_json::Value::Null);
+    assert_eq!(state.observer_view()["played"], serde_json::json!([team[1]]));
+}
+
+/// Test: Lady of the Lake results, Excalibur results and the spies' channel reach only the right seats
+#[test]
+fn test_private_knowledge() {
+    let setup = GameSetup {
+        lady_of_the_lake: true,
+        ..GameSetup::new(5)
+    };
+    let mut state = start_game(setup, 3).0;
+    win_quest(&mut state);
+    win_quest(&mut state);
+    assert_eq!(state.phase(), Phase::LadyOfTheLake);
+    let holder = state.lady_holder().unwrap();
+    let target = (holder + 1) % 5;
+    state.apply(Command::InspectLoyalty { seat: holder, target }).unwrap();
+    let faction = state.roles().role_of(target).unwrap().faction().name();
+    assert_eq!(
+        state.view_for(holder)["private"]["lady_results"],
+        serde_json::json!([{"target": target, "faction": faction}])
+    );
+    assert_eq!(state.view_for(target)["private"]["lady_results"], serde_json::json!([]));
+    assert_eq!(state.observer_view()["lady"]["holder"], target);
+
+    let setup = GameSetup {
+        excalibur: Some(ExcaliburRules::default()),
+        ..GameSetup::new(5)
+    };
+    let mut state = start_game(setup, 4).0;
+    let leader = state.leader();
+    let team: Vec<u32> = (0..5).filter(|&seat| seat != leader).take(2).collect();
+    let grants = BTreeMap::from([("excalibur".to_string(), team[0])]);
+    state.apply(Command::ProposeTeam { seat: leader, team: team.clone(), grants }).unwrap();
+    assert_eq!(state.observer_view()["modules"]["excalibur"], serde_json::json!({"holder": team[0], "used": false}));
+    for seat in 0..5 {
+        state.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+    }
+    for &seat in &team {
+        state.apply(Command::PlayQuestCard { seat, success: true }).unwrap();
+    }
+    let module = "excalibur".to_string();
+    state.apply(Command::QuestAction { seat: team[0], module, target: Some(team[1]) }).unwrap();
+    let switched = serde_json::json!({"results": [{"quest": 0, "target": team[1], "success": true}]});
+    assert_eq!(state.view_for(team[0])["private"]["modules"]["excalibur"], switched);
+    assert_eq!(state.view_for(team[1])["private"]["modules"]["excalibur"], serde_json::Value::Null);
+
+    let mut state = start_game(GameSetup::new(5), 5).0;
+    let channel = spy_channel(state.roles());
+    let loyal = state.roles().faction_seats(Faction::Resistance)[0];
+    assert_eq!(state.view_for(channel[0])["private"]["spy_channel"], serde_json::Value::Null);
+    while state.phase() != Phase::Assassination {
+        win_quest(&mut state);
+    }
+    state.apply(Command::SpyChat { seat: channel[0], text: "Seat 1?".to_string() }).unwrap();
+    let spies = &state.view_for(channel[1])["private"]["spy_channel"];
+    assert_eq!(spies["assassin"], state.roles().assassin().unwrap());
+    assert_eq!(spies["messages"], serde_json::json!([{"index": 0, "seat": channel[0], "text": "Seat 1?"}]));
+    assert_eq!(state.view_for(loyal)["private"]["spy_channel"], serde_json::Value::Null);
+    assert!(!state.observer_view().to_string().contains("Seat 1?"));
+}
+
+/// Test: over WebSocket, events are numbered and `get_state` answers the sender with a snapshot in step with them
+#[test]
+fn test_snapshot_in_step_with_events() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let url = format!("ws://{}/ws", server.local_addr().unwrap());
+
+    let (mut clients, mut peers) = seat_clients(&runtime, &server, 5);
+    let (observer, session) = connect(&runtime, &url);
+    clients.push(observer);
+    peers.push(peer_id(&session));
+
+    let (mut table, started) =
+        GameTable::start(server.hub().clone(), GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(6)).unwrap();
+    let leader = table.state().leader();
+    table.apply(propose(leader, vec![0, 1])).unwrap();
+    table.apply(Command::VoteTeam { seat: 3, approve: true }).unwrap();
+
+    let observed = drain(&runtime, &mut clients[5]);
+    let numbers: Vec<u64> = observed.iter().filter_map(|m| m["seq"].as_u64()).collect();
+    assert_eq!(numbers, (1..=table.state().seq()).collect::<Vec<u64>>(), "Public events are numbered in order");
+    let opening: Vec<serde_json::Value> = (1..)
+        .zip(&started)
+        .map(|(seq, event)| serde_json::from_str(&event.to_sequenced_message(seq)).unwrap())
+        .collect();
+    assert_eq!(opening[0]["type"], "game_started");
+    assert_eq!(opening[1]["type"], "phase");
+    assert_eq!(observed[..opening.len()], opening[..], "The start is returned as it was delivered");
+    for client in clients.iter_mut().take(5) {
+        drain(&runtime, client);
+    }
+
+    assert_eq!(table.handle_message(peers[5], r#"{"type":"get_state"}"#), Some(Vec::new()));
+    assert_eq!(table.handle_message(peers[3], r#"{"type":"get_state"}"#), Some(Vec::new()));
+    let snapshot = drain(&runtime, &mut clients[5]);
+    assert_eq!(snapshot, vec![table.state().observer_view()]);
+    assert_eq!(snapshot[0]["seq"], numbers[numbers.len() - 1]);
+    let mine = drain(&runtime, &mut clients[3]);
+    assert_eq!(mine.len(), 1, "Only the sender gets the snapshot");
+    assert_eq!(mine[0]["seat"], 3);
+    assert_eq!(mine[0]["private"]["team_vote"], true);
+    assert!(drain(&runtime, &mut clients[2]).is_empty());
+
+    assert!(table.handle_message(peers[4], r#"{"type":"vote_team","approve":true}"#).is_some());
+    let next = drain(&runtime, &mut clients[5]);
+    assert_eq!(next[0]["type"], "team_vote_cast");
+    assert_eq!(next[0]["seq"].as_u64(), snapshot[0]["seq"].as_u64().map(|seq| seq + 1));
+    server.stop();
+}
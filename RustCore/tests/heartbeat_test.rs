@@ -0,0 +1,223 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use facingtime_core::client::{ClientConfig, WebSocketClientState};
+use facingtime_core::server::heartbeat::CLOSE_TIMED_OUT;
+use facingtime_core::server::{CoreEvent, EventQueue, HeartbeatConfig, HostConfig, HttpServerState};
+
+use common::{connect_peer, start_server_with, wait_for, Client};
+
+/// Start a server on a random port with the given ping settings
+fn start_server(heartbeat: HeartbeatConfig) -> (HttpServerState, EventQueue, String) {
+    start_server_with(|server| server.hub().set_heartbeat_config(heartbeat))
+}
+
+/// Settings that ping every 50 ms
+fn fast_heartbeat(stale_after: u32, disconnect_after: u32) -> HeartbeatConfig {
+    HeartbeatConfig {
+        interval: Duration::from_millis(50),
+        stale_after,
+        disconnect_after,
+    }
+}
+
+/// Keep reading a client in the background so its pings are answered
+///
+/// # Returns
+/// The close frame code, once the server closes the connection
+fn answer_pings(runtime: &Runtime, mut client: Client) -> tokio::task::JoinHandle<Option<CloseCode>> {
+    runtime.spawn(async move {
+        while let Some(frame) = client.next().await {
+            match frame {
+                Ok(Message::Close(close)) => return close.map(|close| close.code),
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        None
+    })
+}
+
+/// Test: answered pings give a round-trip time, also reported by seat
+#[test]
+fn test_ping_measures_latency() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(fast_heartbeat(2, 4));
+    let hub = server.hub().clone();
+
+    let (client, peer_id) = connect_peer(&runtime, &url, &events);
+    let reader = answer_pings(&runtime, client);
+    assert!(hub.sessions().set_seat(peer_id, Some(3)));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while hub.latency(peer_id).unwrap().samples < 3 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let latency = hub.latency(peer_id).unwrap();
+    assert!(latency.samples >= 3, "Pongs should be recorded: {:?}", latency);
+    let rtt = latency.rtt.expect("A round trip should be measured");
+    assert!(rtt < Duration::from_secs(1), "Loopback round trips are short: {:?}", rtt);
+    assert!(latency.smoothed_rtt.is_some());
+    assert_eq!(latency.missed_pongs, 0);
+    assert!(!latency.stale);
+
+    let seats = hub.seat_latencies();
+    assert_eq!(seats.len(), 1);
+    assert_eq!((seats[0].0, seats[0].1), (3, peer_id));
+    assert!(seats[0].2.samples >= 3);
+    assert_eq!(hub.latency(999), None);
+
+    server.stop();
+    runtime.block_on(reader).unwrap();
+}
+
+/// Test: a peer that stops answering is reported stale, then disconnected and its seat held
+#[test]
+fn test_unresponsive_peer_times_out() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(fast_heartbeat(2, 4));
+
+    let (client, peer_id) = connect_peer(&runtime, &url, &events);
+    assert!(server.hub().sessions().set_seat(peer_id, Some(1)));
+
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::PeerStale { .. })),
+        CoreEvent::PeerStale {
+            peer_id,
+            missed_pongs: 2
+        }
+    );
+    assert!(server.hub().latency(peer_id).unwrap().stale);
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::ClientDisconnected { .. })),
+        CoreEvent::ClientDisconnected { peer_id }
+    );
+    assert!(!server.hub().contains(peer_id));
+    wait_for(&events, |e| matches!(e, CoreEvent::SeatHeld { seat: 1, .. }));
+
+    // The socket is closed; the close frame itself may be lost when the
+    // client's late pongs hit the closed connection first
+    let close = runtime
+        .block_on(async { tokio::time::timeout(Duration::from_secs(5), answer_pings(&runtime, client)).await })
+        .expect("Server should close the connection")
+        .unwrap();
+    if let Some(code) = close {
+        assert_eq!(code, CloseCode::from(CLOSE_TIMED_OUT));
+    }
+    server.stop();
+}
+
+/// Test: a stale peer that answers again recovers
+#[test]
+fn test_stale_peer_recovers() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(fast_heartbeat(2, 0));
+
+    let (client, peer_id) = connect_peer(&runtime, &url, &events);
+    wait_for(&events, |e| matches!(e, CoreEvent::PeerStale { .. }));
+
+    let reader = answer_pings(&runtime, client);
+    match wait_for(&events, |e| matches!(e, CoreEvent::PeerRecovered { .. })) {
+        CoreEvent::PeerRecovered { peer_id: recovered, .. } => assert_eq!(recovered, peer_id),
+        _ => unreachable!(),
+    }
+    let latency = server.hub().latency(peer_id).unwrap();
+    assert!(!latency.stale);
+    assert_eq!(latency.missed_pongs, 0);
+    assert!(server.hub().contains(peer_id), "disconnect_after 0 never disconnects");
+
+    server.stop();
+    runtime.block_on(reader).unwrap();
+}
+
+/// Test: a zero interval sends no pings
+#[test]
+fn test_heartbeat_disabled() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(HeartbeatConfig {
+        interval: Duration::ZERO,
+        ..fast_heartbeat(1, 1)
+    });
+
+    let (_client, peer_id) = connect_peer(&runtime, &url, &events);
+    std::thread::sleep(Duration::from_millis(200));
+    assert_eq!(server.hub().latency(peer_id).unwrap(), Default::default());
+    assert!(server.hub().contains(peer_id));
+    assert!(
+        !events.drain().iter().any(|e| matches!(e, CoreEvent::PeerStale { .. })),
+        "No pings means nothing can be missed"
+    );
+    server.stop();
+}
+
+/// Test: the Rust client answers pings on its own
+#[test]
+fn test_client_answers_pings() {
+    let (mut server, events, url) = start_server(fast_heartbeat(1, 2));
+
+    let mut client = WebSocketClientState::new();
+    client.connect(ClientConfig::new(&url)).unwrap();
+    let peer_id = match wait_for(&events, |e| matches!(e, CoreEvent::ClientConnected { .. })) {
+        CoreEvent::ClientConnected { peer_id, .. } => peer_id,
+        _ => unreachable!(),
+    };
+
+    std::thread::sleep(Duration::from_millis(300));
+    let latency = server.hub().latency(peer_id).expect("Client should stay connected");
+    assert!(latency.samples >= 2, "Client should answer pings: {:?}", latency);
+    assert!(!latency.stale);
+
+    client.disconnect();
+    server.stop();
+}
+
+/// Test: heartbeat settings are validated and converted from the host config
+#[test]
+fn test_host_config_heartbeat() {
+    let defaults = HostConfig::default();
+    assert_eq!(defaults.heartbeat_config(), HeartbeatConfig::default());
+
+    let config = HostConfig {
+        static_dir: "/tmp".to_string(),
+        ping_interval_ms: -1,
+        stale_after_missed_pongs: 0,
+        disconnect_after_missed_pongs: -1,
+        ..HostConfig::default()
+    };
+    assert_eq!(
+        config.validate().iter().map(|e| e.field).collect::<Vec<_>>(),
+        vec!["ping_interval_ms", "stale_after_missed_pongs", "disconnect_after_missed_pongs"]
+    );
+
+    let config = HostConfig {
+        static_dir: "/tmp".to_string(),
+        stale_after_missed_pongs: 3,
+        disconnect_after_missed_pongs: 2,
+        ..HostConfig::default()
+    };
+    assert_eq!(
+        config.validate().iter().map(|e| e.field).collect::<Vec<_>>(),
+        vec!["disconnect_after_missed_pongs"]
+    );
+
+    let config = HostConfig {
+        ping_interval_ms: 250,
+        stale_after_missed_pongs: 3,
+        disconnect_after_missed_pongs: 0,
+        ..HostConfig::default()
+    };
+    assert_eq!(
+        config.heartbeat_config(),
+        HeartbeatConfig {
+            interval: Duration::from_millis(250),
+            stale_after: 3,
+            disconnect_after: 0,
+        }
+    );
+}
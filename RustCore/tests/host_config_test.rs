@@ -0,0 +1,227 @@
+mod common;
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use facingtime_core::error::CoreError;
+use facingtime_core::server::{HeaderPolicy, HostConfig, HttpServerState};
+
+use common::{get, write_certificate};
+
+/// Default configuration serving /tmp
+fn valid_config() -> HostConfig {
+    HostConfig {
+        static_dir: "/tmp".to_string(),
+        ..HostConfig::default()
+    }
+}
+
+/// Names of the fields reported invalid
+fn invalid_fields(config: &HostConfig) -> Vec<&'static str> {
+    config.validate().iter().map(|e| e.field).collect()
+}
+
+/// Test: the defaults are valid once a static directory is given
+#[test]
+fn test_default_config_is_valid() {
+    assert_eq!(invalid_fields(&HostConfig::default()), vec!["static_dir"]);
+    assert!(valid_config().validate().is_empty());
+    assert_eq!(valid_config().socket_address(), "0.0.0.0:8089");
+    assert_eq!(valid_config().certificate(), None, "No certificate means self-signed");
+}
+
+/// Test: every invalid field is reported, not just the first
+#[test]
+fn test_validate_reports_every_field() {
+    let config = HostConfig {
+        bind_address: "localhost".to_string(),
+        port: 70000,
+        static_dir: "/definitely/not/here".to_string(),
+        cors_allow_origin: "example.com".to_string(),
+        mdns_service_type: "game".to_string(),
+        mdns_instance_name: " ".to_string(),
+        mdns_hostname: "my host".to_string(),
+        min_players: 4,
+        max_players: 11,
+        ..HostConfig::default()
+    };
+    assert_eq!(
+        invalid_fields(&config),
+        vec![
+            "bind_address",
+            "port",
+            "static_dir",
+            "cors_allow_origin",
+            "mdns_service_type",
+            "mdns_instance_name",
+            "mdns_hostname",
+            "min_players",
+            "max_players",
+        ]
+    );
+
+    let disabled = HostConfig {
+        mdns_enabled: false,
+        ..config
+    };
+    assert!(
+        !invalid_fields(&disabled).contains(&"mdns_service_type"),
+        "mDNS fields should be ignored when mDNS is disabled"
+    );
+}
+
+/// Test: player limits and CORS origins at their boundaries
+#[test]
+fn test_validate_player_limits_and_origin() {
+    let limits = |min_players, max_players| HostConfig {
+        min_players,
+        max_players,
+        ..valid_config()
+    };
+    assert!(limits(5, 10).validate().is_empty());
+    assert!(limits(7, 7).validate().is_empty());
+    assert_eq!(invalid_fields(&limits(8, 6)), vec!["max_players"]);
+
+    for origin in ["", "*", "http://192.168.1.2:8089", "https://example.com"] {
+        let config = HostConfig {
+            cors_allow_origin: origin.to_string(),
+            ..valid_config()
+        };
+        assert!(config.validate().is_empty(), "'{}' should be accepted", origin);
+    }
+}
+
+/// Test: certificate and key must be given together and exist
+#[test]
+fn test_validate_certificate_paths() {
+    let only_cert = HostConfig {
+        cert_path: "/tmp/cert.pem".to_string(),
+        ..valid_config()
+    };
+    assert_eq!(invalid_fields(&only_cert), vec!["key_path"]);
+
+    let missing = HostConfig {
+        cert_path: "/definitely/not/cert.pem".to_string(),
+        key_path: "/definitely/not/key.pem".to_string(),
+        ..valid_config()
+    };
+    assert_eq!(invalid_fields(&missing), vec!["cert_path", "key_path"]);
+
+    let plain_http = HostConfig {
+        use_https: false,
+        ..missing
+    };
+    assert!(plain_http.validate().is_empty(), "Certificate paths only matter for HTTPS");
+}
+
+/// Test: the data directory may be missing but not a file
+#[test]
+fn test_validate_data_dir() {
+    let data_dir = |data_dir: &str| HostConfig {
+        data_dir: data_dir.to_string(),
+        ..valid_config()
+    };
+    assert!(data_dir("").validate().is_empty(), "No data directory keeps no history");
+    assert!(data_dir("/definitely/not/here").validate().is_empty(), "A missing directory is created");
+    let file = std::env::temp_dir().join(format!("ft_data_dir_{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    assert_eq!(invalid_fields(&data_dir(file.to_str().unwrap())), vec!["data_dir"]);
+    std::fs::remove_file(file).unwrap();
+}
+
+/// Test: the header policy controls cross-origin isolation and CORS headers
+#[test]
+fn test_header_policy() {
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let response = get(server.local_addr().unwrap(), "/health").to_lowercase();
+    assert!(response.contains("cross-origin-opener-policy: same-origin"));
+    assert!(response.contains("cross-origin-embedder-policy: require-corp"));
+    assert!(!response.contains("access-control-allow-origin"));
+    server.stop();
+
+    let config = HostConfig {
+        cross_origin_isolation: false,
+        cors_allow_origin: "*".to_string(),
+        ..valid_config()
+    };
+    server.set_header_policy(config.header_policy());
+    assert_eq!(
+        config.header_policy(),
+        HeaderPolicy {
+            cross_origin_isolation: false,
+            cors_allow_origin: Some("*".to_string()),
+        }
+    );
+    server.start("127.0.0.1:0", "/tmp").expect("Server should restart");
+    let response = get(server.local_addr().unwrap(), "/health").to_lowercase();
+    assert!(!response.contains("cross-origin-opener-policy"));
+    assert!(response.contains("access-control-allow-origin: *"));
+    server.stop();
+}
+
+/// Test: HTTPS serves the certificate loaded from PEM files
+#[test]
+fn test_https_with_certificate_files() {
+    let (cert_path, key_path, cert_der) = write_certificate("https_cert");
+    let mut server = HttpServerState::new();
+    server
+        .start_https_with_certificate("127.0.0.1:0", "/tmp", cert_path.to_str().unwrap(), key_path.to_str().unwrap())
+        .expect("HTTPS server should start");
+    assert_eq!(server.inner.lock().config.as_ref().unwrap().cert_path.as_deref(), cert_path.to_str());
+    let addr = server.local_addr().unwrap();
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(rustls::pki_types::CertificateDer::from(cert_der.clone())).unwrap();
+    let client_config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (presented, response) = runtime.block_on(async {
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut tls = connector.connect(server_name, stream).await.expect("TLS handshake should succeed");
+        let presented = tls.get_ref().1.peer_certificates().unwrap()[0].to_vec();
+        tls.write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let _ = tls.read_to_end(&mut response).await;
+        (presented, String::from_utf8_lossy(&response).to_string())
+    });
+    assert_eq!(presented, cert_der, "Server should present the configured certificate");
+    assert!(response.starts_with("HTTP/1.1 200"), "Unexpected response: {}", response);
+
+    server.stop();
+    let _ = std::fs::remove_dir_all(cert_path.parent().unwrap());
+}
+
+/// Test: unreadable or malformed certificate files are TLS errors
+#[test]
+fn test_https_with_bad_certificate_files() {
+    let (cert_path, key_path, _) = write_certificate("bad_cert");
+    let mut server = HttpServerState::new();
+
+    let result = server.start_https_with_certificate(
+        "127.0.0.1:0",
+        "/tmp",
+        "/definitely/not/cert.pem",
+        key_path.to_str().unwrap(),
+    );
+    assert!(matches!(result, Err(CoreError::TlsError(_))), "Missing file should be a TLS error");
+
+    // A key file where the certificate should be contains no certificate
+    let result = server.start_https_with_certificate(
+        "127.0.0.1:0",
+        "/tmp",
+        key_path.to_str().unwrap(),
+        cert_path.to_str().unwrap(),
+    );
+    assert!(matches!(result, Err(CoreError::TlsError(_))), "Swapped files should be a TLS error");
+    assert!(!server.is_running());
+
+    let _ = std::fs::remove_dir_all(cert_path.parent().unwrap());
+}
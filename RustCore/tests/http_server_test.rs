@@ -1,8 +1,9 @@
 // Integration tests for RustCore FFI interface
 // These tests verify the FFI functions are properly exported and functional
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 
+use facingtime_core::ffi::error::{ft_last_error_code, ft_last_error_message, FtErrorCode};
 use facingtime_core::ffi::server::{
     ft_http_server_create,
     ft_http_server_free,
@@ -72,8 +73,8 @@ fn test_server_start() {
         ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr() ,0)
     };
 
-    // Server should start successfully (return 1)
-    assert_eq!(result, 1, "Server should start successfully");
+    // Server should start successfully (return FT_OK)
+    assert_eq!(result, 0, "Server should start successfully");
 
     // Verify server is now running
     let is_running = unsafe { ft_http_server_is_running(server) };
@@ -102,3 +103,77 @@ fn test_server_stop_multiple_times() {
 
     unsafe { ft_http_server_free(server); }
 }
+
+/// Helper to read the last FFI error message as a Rust string
+fn last_error_message() -> String {
+    let message = ft_last_error_message();
+    assert!(!message.is_null(), "An error message should be available");
+    unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+}
+
+/// Test: an unparsable address reports FT_ERR_INVALID_ADDRESS with details
+#[test]
+fn test_server_start_invalid_address_error() {
+    let server = unsafe { ft_http_server_create() };
+    let address = CString::new("not-an-address").unwrap();
+    let static_dir = CString::new("/tmp").unwrap();
+
+    let result = unsafe { ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 0) };
+    assert_eq!(result, FtErrorCode::InvalidAddress as i32, "Invalid address should be reported");
+    assert_eq!(ft_last_error_code(), result, "Last error code should match the return value");
+    assert!(last_error_message().contains("not-an-address"), "Message should name the address");
+
+    unsafe { ft_http_server_free(server); }
+}
+
+/// Test: a port already in use reports FT_ERR_BIND_FAILED
+#[test]
+fn test_server_start_bind_failed_error() {
+    let occupied = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = CString::new(occupied.local_addr().unwrap().to_string()).unwrap();
+    let static_dir = CString::new("/tmp").unwrap();
+
+    let server = unsafe { ft_http_server_create() };
+    let result = unsafe { ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 0) };
+    assert_eq!(result, FtErrorCode::BindFailed as i32, "Bind failure should be reported");
+    assert_eq!(unsafe { ft_http_server_is_running(server) }, 0, "Server should not be running");
+
+    unsafe { ft_http_server_free(server); }
+}
+
+/// Test: starting twice reports FT_ERR_ALREADY_RUNNING and success clears the error
+#[test]
+fn test_server_start_already_running_error() {
+    let server = unsafe { ft_http_server_create() };
+    let address = CString::new("127.0.0.1:0").unwrap();
+    let static_dir = CString::new("/tmp").unwrap();
+
+    let first = unsafe { ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 0) };
+    assert_eq!(first, FtErrorCode::Ok as i32);
+    assert_eq!(ft_last_error_code(), FtErrorCode::Ok as i32, "Success should clear the last error");
+    assert!(ft_last_error_message().is_null(), "No message should be set after success");
+
+    let second = unsafe { ft_http_server_start(server, address.as_ptr(), static_dir.as_ptr(), 0) };
+    assert_eq!(second, FtErrorCode::AlreadyRunning as i32, "Second start should fail");
+
+    unsafe { ft_http_server_stop(server); }
+    unsafe { ft_http_server_free(server); }
+}
+
+/// Test: null handles and invalid UTF-8 are reported distinctly
+#[test]
+fn test_server_start_argument_errors() {
+    let address = CString::new("127.0.0.1:0").unwrap();
+    let result = unsafe { ft_http_server_start(std::ptr::null_mut(), address.as_ptr(), std::ptr::null(), 0) };
+    assert_eq!(result, FtErrorCode::NullPointer as i32, "Null server should be reported");
+
+    let server = unsafe { ft_http_server_create() };
+    let invalid = [0xffu8, 0xfe, 0x00];
+    let result = unsafe {
+        ft_http_server_start(server, invalid.as_ptr() as *const std::os::raw::c_char, std::ptr::null(), 0)
+    };
+    assert_eq!(result, FtErrorCode::InvalidUtf8 as i32, "Invalid UTF-8 should be reported");
+    assert!(last_error_message().contains("address"), "Message should name the argument");
+
+    unsafe { ft_http_server_free(server); }
+}
@@ -0,0 +1,395 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::SinkExt;
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::MaybeTlsStream;
+
+use facingtime_core::server::limits::{CLOSE_MESSAGE_TOO_BIG, CLOSE_POLICY_VIOLATION, CLOSE_TRY_AGAIN_LATER};
+use facingtime_core::server::{
+    CoreEvent, EventQueue, HostConfig, HttpServerState, LimitError, Limits, RateLimit,
+};
+use facingtime_core::types::HttpResponse;
+
+use common::{connect_peer, next_frame, next_json, raw_request, start_server_with, wait_for, Client};
+
+/// Limits that never get in the way unless a test tightens them
+fn loose_limits() -> Limits {
+    Limits {
+        max_connections: 0,
+        max_connections_per_address: 0,
+        connection_message_rate: RateLimit::unlimited(),
+        address_message_rate: RateLimit::unlimited(),
+        address_request_rate: RateLimit::unlimited(),
+        max_violations: 0,
+        ..Limits::default()
+    }
+}
+
+/// Start a server on a random port with the given limits
+fn start_server(limits: Limits) -> (HttpServerState, EventQueue, String) {
+    start_server_with(|server| server.hub().limiter().set_limits(limits))
+}
+
+/// Read frames until the close frame and return its code
+fn close_code(runtime: &Runtime, client: &mut Client) -> CloseCode {
+    loop {
+        match next_frame(runtime, client, Duration::from_secs(5)).expect("Timed out waiting for a frame") {
+            Message::Close(Some(close)) => return close.code,
+            Message::Close(None) => panic!("Close frame should carry a code"),
+            _ => {}
+        }
+    }
+}
+
+/// Send text frames from a client
+fn send_texts(runtime: &Runtime, client: &mut Client, count: usize) {
+    runtime.block_on(async {
+        for i in 0..count {
+            client.send(Message::Text(format!("message {}", i).into())).await.unwrap();
+        }
+    });
+}
+
+/// Count MessageReceived events that arrive within a short wait
+fn count_received(events: &EventQueue) -> usize {
+    std::thread::sleep(Duration::from_millis(200));
+    events
+        .drain()
+        .iter()
+        .filter(|e| matches!(e, CoreEvent::MessageReceived { .. }))
+        .count()
+}
+
+/// Test: limit errors describe themselves as typed error messages
+#[test]
+fn test_limit_error_messages() {
+    let rate_limited: serde_json::Value = serde_json::from_str(
+        &LimitError::RateLimited {
+            retry_after: Duration::from_millis(250),
+        }
+        .to_message(),
+    )
+    .unwrap();
+    assert_eq!(rate_limited["type"], "error");
+    assert_eq!(rate_limited["code"], "rate_limited");
+    assert_eq!(rate_limited["retry_after_ms"], 250);
+
+    let too_large: serde_json::Value =
+        serde_json::from_str(&LimitError::TooLarge { max_bytes: 1024 }.to_message()).unwrap();
+    assert_eq!(too_large["code"], "too_large");
+    assert_eq!(too_large["max_bytes"], 1024);
+
+    for (error, code) in [
+        (LimitError::ServerFull, "server_full"),
+        (LimitError::TooManyConnections, "too_many_connections"),
+        (LimitError::NotAccepting, "not_accepting"),
+    ] {
+        let message: serde_json::Value = serde_json::from_str(&error.to_message()).unwrap();
+        assert_eq!(message["code"], code);
+        assert!(message.get("retry_after_ms").is_none());
+        assert_eq!(message["message"], error.to_string());
+    }
+}
+
+/// Test: messages over a connection's rate are dropped with an error reply
+#[test]
+fn test_connection_message_rate() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(Limits {
+        connection_message_rate: RateLimit::new(1, 3),
+        ..loose_limits()
+    });
+
+    let (mut client, _) = connect_peer(&runtime, &url, &events);
+    send_texts(&runtime, &mut client, 5);
+    for _ in 0..2 {
+        let error = next_json(&runtime, &mut client);
+        assert_eq!(error["code"], "rate_limited");
+        assert!(error["retry_after_ms"].as_u64().unwrap() > 0);
+    }
+    assert_eq!(count_received(&events), 3, "Only the burst should get through");
+
+    // The bucket refills at the configured rate
+    std::thread::sleep(Duration::from_millis(1100));
+    send_texts(&runtime, &mut client, 1);
+    assert_eq!(count_received(&events), 1);
+    server.stop();
+}
+
+/// Test: connections from one address share the address rate
+#[test]
+fn test_address_message_rate() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(Limits {
+        address_message_rate: RateLimit::new(1, 2),
+        ..loose_limits()
+    });
+
+    let (mut first, _) = connect_peer(&runtime, &url, &events);
+    let (mut second, _) = connect_peer(&runtime, &url, &events);
+    send_texts(&runtime, &mut first, 2);
+    assert_eq!(count_received(&events), 2);
+    send_texts(&runtime, &mut second, 1);
+    assert_eq!(next_json(&runtime, &mut second)["code"], "rate_limited");
+    assert_eq!(count_received(&events), 0);
+    server.stop();
+}
+
+/// Test: a connection that keeps flooding is closed and its session ended
+#[test]
+fn test_flooding_peer_disconnected() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(Limits {
+        connection_message_rate: RateLimit::new(1, 1),
+        max_violations: 3,
+        ..loose_limits()
+    });
+
+    let (mut client, peer_id) = connect_peer(&runtime, &url, &events);
+    assert!(server.hub().sessions().set_seat(peer_id, Some(0)));
+    send_texts(&runtime, &mut client, 10);
+
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::PeerLimited { .. })),
+        CoreEvent::PeerLimited {
+            peer_id,
+            reason: "rate_limited".to_string()
+        }
+    );
+    assert_eq!(close_code(&runtime, &mut client), CloseCode::from(CLOSE_POLICY_VIOLATION));
+    assert!(!server.hub().contains(peer_id));
+    assert!(server.hub().sessions().held_seats().is_empty(), "Abusers do not keep their seat");
+    server.stop();
+}
+
+/// Test: an oversized message gets a too_large error and closes the connection
+#[test]
+fn test_message_too_large() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(Limits {
+        max_frame_bytes: 1024,
+        max_message_bytes: 1024,
+        ..loose_limits()
+    });
+
+    let (mut client, peer_id) = connect_peer(&runtime, &url, &events);
+    runtime
+        .block_on(client.send(Message::Text("x".repeat(1000).into())))
+        .unwrap();
+    assert_eq!(count_received(&events), 1, "Messages under the limit are delivered");
+
+    runtime
+        .block_on(client.send(Message::Text("x".repeat(4096).into())))
+        .unwrap();
+    let error = next_json(&runtime, &mut client);
+    assert_eq!(error["code"], "too_large");
+    assert_eq!(error["max_bytes"], 1024);
+    assert_eq!(close_code(&runtime, &mut client), CloseCode::from(CLOSE_MESSAGE_TOO_BIG));
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::PeerLimited { .. })),
+        CoreEvent::PeerLimited {
+            peer_id,
+            reason: "too_large".to_string()
+        }
+    );
+    server.stop();
+}
+
+/// Test: joins over the connection caps are refused with an error
+#[test]
+fn test_connection_caps() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(Limits {
+        max_connections: 2,
+        max_connections_per_address: 1,
+        ..loose_limits()
+    });
+
+    let (_first, _) = connect_peer(&runtime, &url, &events);
+    let mut refused = runtime.block_on(tokio_tungstenite::connect_async(&url)).unwrap().0;
+    assert_eq!(next_json(&runtime, &mut refused)["code"], "too_many_connections");
+    assert_eq!(close_code(&runtime, &mut refused), CloseCode::from(CLOSE_TRY_AGAIN_LATER));
+    match wait_for(&events, |e| matches!(e, CoreEvent::ConnectionRefused { .. })) {
+        CoreEvent::ConnectionRefused { reason, .. } => assert_eq!(reason, "too_many_connections"),
+        _ => unreachable!(),
+    }
+
+    server.hub().limiter().set_limits(Limits {
+        max_connections: 1,
+        ..loose_limits()
+    });
+    let mut refused = runtime.block_on(tokio_tungstenite::connect_async(&url)).unwrap().0;
+    assert_eq!(next_json(&runtime, &mut refused)["code"], "server_full");
+    assert_eq!(server.hub().peer_count(), 1);
+    server.stop();
+}
+
+/// Test: joins racing each other cannot exceed the connection caps
+#[test]
+fn test_concurrent_joins_respect_caps() {
+    let runtime = Runtime::new().unwrap();
+    for (limits, code) in [
+        (Limits { max_connections: 3, ..loose_limits() }, "server_full"),
+        (Limits { max_connections_per_address: 3, ..loose_limits() }, "too_many_connections"),
+    ] {
+        let (mut server, _events, url) = start_server(limits);
+        // Connect every socket first so the upgrade requests arrive together
+        let mut clients: Vec<Client> = runtime.block_on(async {
+            let addr = url.trim_start_matches("ws://").trim_end_matches("/ws");
+            let mut sockets = Vec::new();
+            for _ in 0..20 {
+                sockets.push(MaybeTlsStream::Plain(TcpStream::connect(addr).await.unwrap()));
+            }
+            let joins = sockets.into_iter().map(|socket| tokio_tungstenite::client_async(url.as_str(), socket));
+            futures_util::future::join_all(joins)
+                .await
+                .into_iter()
+                .map(|joined| joined.expect("WebSocket should connect").0)
+                .collect()
+        });
+        let greetings: Vec<serde_json::Value> = clients.iter_mut().map(|c| next_json(&runtime, c)).collect();
+        let admitted = greetings.iter().filter(|greeting| greeting["type"] == "session").count();
+        assert_eq!(admitted, 3, "Only as many joins as the cap are admitted");
+        assert!(greetings.iter().filter(|g| g["type"] != "session").all(|g| g["code"] == code));
+        assert_eq!(server.hub().peer_count(), 3);
+        server.stop();
+    }
+}
+
+/// Test: a host that stops accepting players still lets seated players resume
+#[test]
+fn test_not_accepting_allows_resume() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server(loose_limits());
+
+    let mut client = runtime.block_on(tokio_tungstenite::connect_async(&url)).unwrap().0;
+    let session = next_json(&runtime, &mut client);
+    let peer_id = session["peer_id"].as_u64().unwrap() as u32;
+    assert!(server.hub().sessions().set_seat(peer_id, Some(2)));
+
+    server.hub().limiter().set_accepting(false);
+    assert!(!server.hub().limiter().is_accepting());
+    let mut refused = runtime.block_on(tokio_tungstenite::connect_async(&url)).unwrap().0;
+    assert_eq!(next_json(&runtime, &mut refused)["code"], "not_accepting");
+
+    drop(client);
+    wait_for(&events, |e| matches!(e, CoreEvent::SeatHeld { .. }));
+    let resume_url = format!("{}?session={}", url, session["token"].as_str().unwrap());
+    let mut resumed = runtime.block_on(tokio_tungstenite::connect_async(&resume_url)).unwrap().0;
+    let greeting = next_json(&runtime, &mut resumed);
+    assert_eq!(greeting["type"], "session");
+    assert_eq!(greeting["seat"], 2);
+    server.stop();
+}
+
+/// Test: HTTP requests over the address rate get 429 with Retry-After
+#[test]
+fn test_http_request_rate() {
+    let (mut server, _events, _url) = start_server(Limits {
+        address_request_rate: RateLimit::new(1, 2),
+        ..loose_limits()
+    });
+    let addr = server.local_addr().unwrap();
+    let request = "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+
+    for _ in 0..2 {
+        let response = raw_request(addr, request);
+        assert!(response.starts_with("HTTP/1.1 200"), "Unexpected response: {}", response);
+    }
+    let response = raw_request(addr, request);
+    assert!(response.starts_with("HTTP/1.1 429"), "Unexpected response: {}", response);
+    assert!(response.to_ascii_lowercase().contains("retry-after: 1"), "Retry-After missing: {}", response);
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    server.stop();
+}
+
+/// Test: request bodies over the limit are refused before reaching host handlers
+#[test]
+fn test_request_body_limit() {
+    let mut server = HttpServerState::new();
+    server.hub().limiter().set_limits(Limits {
+        max_request_body_bytes: 1024,
+        ..loose_limits()
+    });
+    server
+        .register_route(
+            "/api/echo",
+            Arc::new(|request| HttpResponse::new(200, request.body.unwrap_or_default().len().to_string())),
+        )
+        .unwrap();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let addr = server.local_addr().unwrap();
+
+    let body = "x".repeat(1024);
+    let response = raw_request(
+        addr,
+        &format!(
+            "POST /api/echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+    );
+    assert!(response.ends_with("\r\n\r\n1024"), "Bodies at the limit are delivered: {}", response);
+
+    let response = raw_request(
+        addr,
+        "POST /api/echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5000\r\nConnection: close\r\n\r\nxx",
+    );
+    assert!(response.starts_with("HTTP/1.1 413"), "Declared length over the limit: {}", response);
+    assert!(response.contains("\"too_large\""), "Typed error body missing: {}", response);
+
+    let chunk = "x".repeat(2000);
+    let response = raw_request(
+        addr,
+        &format!(
+            "POST /api/echo HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            chunk.len(),
+            chunk
+        ),
+    );
+    assert!(response.starts_with("HTTP/1.1 413"), "Chunked body over the limit: {}", response);
+    server.stop();
+}
+
+/// Test: limit settings are validated and converted from the host config
+#[test]
+fn test_host_config_limits() {
+    assert_eq!(HostConfig::default().limits(), Limits::default());
+
+    let config = HostConfig {
+        static_dir: "/tmp".to_string(),
+        max_connections: -1,
+        message_burst: -5,
+        max_frame_bytes: 10,
+        max_request_body_bytes: 4 * 1024 * 1024,
+        ..HostConfig::default()
+    };
+    assert_eq!(
+        config.validate().iter().map(|e| e.field).collect::<Vec<_>>(),
+        vec!["max_connections", "message_burst", "max_frame_bytes", "max_request_body_bytes"]
+    );
+
+    let config = HostConfig {
+        max_connections: 12,
+        max_connections_per_address: 0,
+        messages_per_second: 5,
+        message_burst: 10,
+        requests_per_second: 0,
+        max_rate_violations: 7,
+        ..HostConfig::default()
+    };
+    let limits = config.limits();
+    assert_eq!(limits.max_connections, 12);
+    assert_eq!(limits.max_connections_per_address, 0);
+    assert_eq!(limits.connection_message_rate, RateLimit::new(5, 10));
+    assert!(!limits.address_request_rate.is_limited());
+    assert_eq!(limits.max_violations, 7);
+}
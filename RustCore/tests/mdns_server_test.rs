@@ -65,8 +65,8 @@ fn test_mdns_server_start() {
         )
     };
 
-    // Server should start successfully (return 1)
-    assert_eq!(result, 1, "mDNS server should start successfully");
+    // Server should start successfully (return FT_OK)
+    assert_eq!(result, 0, "mDNS server should start successfully");
 
     // Verify server is now running
     let is_running = unsafe { ft_mdns_server_is_running(server) };
@@ -123,3 +123,62 @@ fn test_mdns_server_handle_consistency() {
 
     unsafe { ft_mdns_server_free(server); }
 }
+
+/// Test: stopping unregisters the service and clears the running state
+#[test]
+fn test_mdns_server_stop_unregisters() {
+    use facingtime_core::server::{MdnsServerState, MdnsStatus};
+
+    let mut server = MdnsServerState::new();
+    assert_eq!(server.status(), MdnsStatus::Stopped, "New mDNS server should be stopped");
+
+    server
+        .start("_game._tcp.local.", "UnregisterTest", "unregistertest", 3457)
+        .expect("mDNS server should start");
+    assert_eq!(server.status(), MdnsStatus::Registered, "mDNS server should be registered after start");
+
+    server.stop();
+    assert_eq!(server.status(), MdnsStatus::Stopped, "mDNS server should be stopped after stop");
+    assert!(!server.is_running(), "mDNS server should not be running after stop");
+
+    // A stopped server can be started again
+    server
+        .start("_game._tcp.local.", "UnregisterTest", "unregistertest", 3457)
+        .expect("mDNS server should restart");
+    server.stop();
+}
+
+/// Test: the browser can be started once, stopped and started again through FFI
+#[test]
+fn test_mdns_browser_start_stop() {
+    use facingtime_core::ffi::error::FtErrorCode;
+    use facingtime_core::ffi::mdns::{
+        ft_mdns_browser_create,
+        ft_mdns_browser_free,
+        ft_mdns_browser_start,
+        ft_mdns_browser_stop,
+    };
+
+    let browser = unsafe { ft_mdns_browser_create() };
+    assert!(is_valid_ptr(browser), "Browser create should return a non-null handle");
+
+    let service_type = CString::new("_game._tcp.local.").unwrap();
+    assert_eq!(unsafe { ft_mdns_browser_start(browser, service_type.as_ptr()) }, 0, "Browse should start");
+    assert_eq!(
+        unsafe { ft_mdns_browser_start(browser, service_type.as_ptr()) },
+        FtErrorCode::AlreadyRunning as i32,
+        "Second browse should fail"
+    );
+
+    unsafe { ft_mdns_browser_stop(browser) };
+    assert_eq!(unsafe { ft_mdns_browser_start(browser, service_type.as_ptr()) }, 0, "Browse should restart");
+    unsafe { ft_mdns_browser_stop(browser) };
+
+    assert_eq!(
+        unsafe { ft_mdns_browser_start(browser, std::ptr::null()) },
+        FtErrorCode::NullPointer as i32,
+        "NULL service type should be rejected"
+    );
+
+    unsafe { ft_mdns_browser_free(browser) };
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::game::{Command, Faction, GameError, GameEvent, GameSetup, GameState, GameTable, SeededRng};
+use facingtime_core::server::HttpServerState;
+
+use common::{drain, seat_clients};
+
+/// One spy and enough loyal players for the current quest
+fn mixed_team(state: &GameState) -> Vec<u32> {
+    let size = state.quests()[state.quest()].team_size;
+    let mut team = vec![state.roles().faction_seats(Faction::Spies)[0]];
+    team.extend(state.roles().faction_seats(Faction::Resistance).into_iter().take(size - 1));
+    team
+}
+
+/// Propose `team` and have everyone approve it
+fn approve(state: &mut GameState, team: &[u32]) {
+    let proposal = Command::ProposeTeam {
+        seat: state.leader(),
+        team: team.to_vec(),
+        grants: BTreeMap::new(),
+    };
+    state.apply(proposal).unwrap();
+    for seat in state.seats().to_vec() {
+        state.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+    }
+}
+
+/// Whether a protocol message names a single player's quest card
+fn reveals_a_card(message: &serde_json::Value) -> bool {
+    message.get("success").is_some() || message.get("cards").is_some() || message.get("quest_cards").is_some()
+}
+
+/// Test: loyal players cannot play Fail, and a refused card leaves no trace
+#[test]
+fn test_loyal_players_must_succeed() {
+    let (mut state, _) = GameState::new(GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(1)).unwrap();
+    let team = mixed_team(&state);
+    approve(&mut state, &team);
+    let (spy, loyal) = (team[0], team[1]);
+
+    let error = state.apply(Command::PlayQuestCard { seat: loyal, success: false }).unwrap_err();
+    assert_eq!(error, GameError::LoyalMustSucceed);
+    assert_eq!(error.code(), "loyal_must_succeed");
+    assert!(state.played().is_empty(), "The refused card was not played");
+
+    state.apply(Command::PlayQuestCard { seat: spy, success: false }).unwrap();
+    let events = state.apply(Command::PlayQuestCard { seat: loyal, success: true }).unwrap();
+    assert!(events.iter().any(|e| matches!(e, GameEvent::QuestResult { fails: 1, succeeded: false, .. })));
+}
+
+/// Test: no event tells who played which card until the game is over, when all cards are revealed
+#[test]
+fn test_cards_revealed_only_at_the_end() {
+    let (mut state, _) = GameState::new(GameSetup::plain(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(2)).unwrap();
+    let mut played = Vec::new();
+    let mut events = Vec::new();
+    while state.result().is_none() {
+        assert_eq!(state.quest_cards(), None);
+        let team = mixed_team(&state);
+        approve(&mut state, &team);
+        let mut cards = Vec::new();
+        for &seat in &team {
+            let success = seat != team[0];
+            cards.push((seat, success));
+            events.extend(state.apply(Command::PlayQuestCard { seat, success }).unwrap());
+        }
+        cards.sort();
+        played.push(cards);
+    }
+
+    let (last, during) = events.split_last().unwrap();
+    for event in during {
+        let message: serde_json::Value = serde_json::from_str(&event.to_message()).unwrap();
+        assert!(!reveals_a_card(&message), "{} reveals a card", message);
+    }
+    assert_eq!(state.quest_cards(), Some(played.as_slice()));
+    let GameEvent::GameOver { quest_cards, .. } = last else {
+        panic!("Expected the end of the game");
+    };
+    assert_eq!(quest_cards, &played);
+    let message: serde_json::Value = serde_json::from_str(&last.to_message()).unwrap();
+    assert_eq!(message["quest_cards"][0].as_array().unwrap().len(), 2);
+    assert_eq!(message["quest_cards"][0][0]["seat"], played[0][0].0);
+    assert_eq!(message["quest_cards"][0][0]["success"], played[0][0].1);
+}
+
+/// Test: over WebSocket, other players see that a card was played, never which
+#[test]
+fn test_cards_stay_off_the_wire() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+
+    let (mut clients, peers) = seat_clients(&runtime, &server, 5);
+
+    let (mut table, _) =
+        GameTable::start(server.hub().clone(), GameSetup::new(5), &[0, 1, 2, 3, 4], &mut SeededRng::new(3)).unwrap();
+    let state = table.state().clone();
+    let team = mixed_team(&state);
+    table
+        .apply(Command::ProposeTeam {
+            seat: state.leader(),
+            team: team.clone(),
+            grants: BTreeMap::new(),
+        })
+        .unwrap();
+    for seat in 0..5 {
+        table.apply(Command::VoteTeam { seat, approve: true }).unwrap();
+    }
+    for client in clients.iter_mut() {
+        drain(&runtime, client);
+    }
+
+    let (spy, loyal) = (team[0], team[1]);
+    let fail = r#"{"type":"play_quest_card","success":false}"#;
+    assert_eq!(table.handle_message(peers[loyal as usize], fail), Some(Vec::new()));
+    assert_eq!(table.handle_message(peers[spy as usize], fail).map(|e| e.len()), Some(1));
+    let succeed = r#"{"type":"play_quest_card","success":true}"#;
+    assert!(table.handle_message(peers[loyal as usize], succeed).is_some());
+
+    for (seat, client) in clients.iter_mut().enumerate() {
+        let messages = drain(&runtime, client);
+        assert!(messages.iter().all(|m| !reveals_a_card(m)), "Seat {} saw a card", seat);
+        let refused = messages.iter().any(|m| m["code"] == "loyal_must_succeed");
+        assert_eq!(refused, seat as u32 == loyal, "Only the sender hears of the refusal");
+        assert_eq!(messages.iter().filter(|m| m["type"] == "quest_card_played").count(), 2);
+    }
+    server.stop();
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+
+use facingtime_core::error::CoreError;
+use facingtime_core::game::rng::shuffle;
+use facingtime_core::game::{
+    default_lineup, send_night_info, spy_count, Faction, GameRng, Role, RoleAssignment, RoleError, SeededRng,
+    Sighting,
+};
+use facingtime_core::server::HttpServerState;
+
+use common::{next_json_within, seat_clients};
+
+/// Seats 0..count
+fn seats(count: u32) -> Vec<u32> {
+    (0..count).collect()
+}
+
+/// The only seat holding `role`
+fn seat_of(assignment: &RoleAssignment, role: Role) -> u32 {
+    let seats = assignment.seats_with(role);
+    assert_eq!(seats.len(), 1, "Expected one {:?}", role);
+    seats[0]
+}
+
+/// Test: default lineups have one role per player and the official faction counts
+#[test]
+fn test_default_lineups() {
+    for (players, spies) in [(5, 2), (6, 2), (7, 3), (8, 3), (9, 3), (10, 4)] {
+        assert_eq!(spy_count(players), Some(spies));
+        let lineup = default_lineup(players).unwrap();
+        assert_eq!(lineup.len(), players);
+        assert_eq!(lineup.iter().filter(|r| r.faction() == Faction::Spies).count(), spies);
+        for role in [Role::Merlin, Role::Percival, Role::Morgana, Role::Mordred] {
+            assert_eq!(lineup.iter().filter(|&&r| r == role).count(), 1, "{} players", players);
+        }
+    }
+    assert_eq!(default_lineup(4), None);
+    assert_eq!(default_lineup(11), None);
+    assert_eq!(spy_count(0), None);
+}
+
+/// Test: role names round-trip and factions match GameEnums
+#[test]
+fn test_role_names() {
+    for role in Role::ALL {
+        assert_eq!(Role::from_name(role.name()), Some(role));
+    }
+    assert_eq!(Role::from_name("assassin"), None);
+    assert_eq!(Role::Oberon.faction(), Faction::Spies);
+    assert_eq!(Role::ResistanceMember.faction(), Faction::Resistance);
+    assert!(!Role::Mordred.known_to_merlin());
+    assert!(!Role::Oberon.known_to_spies());
+}
+
+/// Test: the same seed deals the same roles and every role is dealt once
+#[test]
+fn test_seeded_deal_is_reproducible() {
+    let first = RoleAssignment::deal_default(&seats(7), &mut SeededRng::new(42)).unwrap();
+    let again = RoleAssignment::deal_default(&seats(7), &mut SeededRng::new(42)).unwrap();
+    assert_eq!(first, again);
+
+    let mut dealt: Vec<Role> = first.roles().map(|(_, role)| role).collect();
+    let mut expected = default_lineup(7).unwrap();
+    dealt.sort();
+    expected.sort();
+    assert_eq!(dealt, expected);
+    assert_eq!(first.seats(), seats(7));
+
+    let deals: HashSet<Vec<(u32, Role)>> = (0..20)
+        .map(|seed| {
+            RoleAssignment::deal_default(&seats(7), &mut SeededRng::new(seed))
+                .unwrap()
+                .roles()
+                .collect()
+        })
+        .collect();
+    assert!(deals.len() > 1, "Different seeds should deal differently");
+}
+
+/// Test: every seat can be dealt Merlin
+#[test]
+fn test_deal_covers_every_seat() {
+    let mut rng = SeededRng::new(7);
+    let mut merlins = HashSet::new();
+    for _ in 0..200 {
+        let assignment = RoleAssignment::deal_default(&[3, 5, 8, 13, 21], &mut rng).unwrap();
+        merlins.insert(seat_of(&assignment, Role::Merlin));
+    }
+    assert_eq!(merlins, HashSet::from([3, 5, 8, 13, 21]));
+}
+
+/// Test: the seeded generator stays in bounds and shuffles into permutations
+#[test]
+fn test_rng_helpers() {
+    let mut rng = SeededRng::new(1);
+    let mut seen = HashSet::new();
+    for _ in 0..500 {
+        let value = rng.below(6);
+        assert!(value < 6);
+        seen.insert(value);
+    }
+    assert_eq!(seen.len(), 6);
+
+    let mut items: Vec<u32> = (0..10).collect();
+    shuffle(&mut rng, &mut items);
+    let mut sorted = items.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+}
+
+/// Test: deals are refused when seats and lineup do not match
+#[test]
+fn test_deal_errors() {
+    let mut rng = SeededRng::new(0);
+    assert_eq!(
+        RoleAssignment::deal(&seats(5), &default_lineup(6).unwrap(), &mut rng),
+        Err(RoleError::WrongPlayerCount { roles: 6, seats: 5 })
+    );
+    assert_eq!(
+        RoleAssignment::deal(&[0, 1, 2, 2, 4], &default_lineup(5).unwrap(), &mut rng),
+        Err(RoleError::DuplicateSeat(2))
+    );
+    let error = RoleAssignment::deal_default(&seats(4), &mut rng).unwrap_err();
+    assert_eq!(error, RoleError::UnsupportedPlayerCount(4));
+    assert_eq!(error.to_string(), "Games need 5 to 10 players, not 4");
+}
+
+/// Test: each role sees exactly what the rules allow
+#[test]
+fn test_night_info_views() {
+    let lineup = [
+        Role::Merlin,
+        Role::Percival,
+        Role::ResistanceMember,
+        Role::ResistanceMember,
+        Role::Mordred,
+        Role::Morgana,
+        Role::Spy,
+        Role::Oberon,
+    ];
+    for seed in 0..10 {
+        let assignment = RoleAssignment::deal(&seats(8), &lineup, &mut SeededRng::new(seed)).unwrap();
+        let merlin = seat_of(&assignment, Role::Merlin);
+        let mordred = seat_of(&assignment, Role::Mordred);
+        let morgana = seat_of(&assignment, Role::Morgana);
+        let spy = seat_of(&assignment, Role::Spy);
+        let oberon = seat_of(&assignment, Role::Oberon);
+        let sees = |seat: u32| assignment.night_info(seat).unwrap().sees;
+        let sorted = |mut seats: Vec<u32>| {
+            seats.sort();
+            seats
+        };
+
+        let merlin_sees = sees(merlin);
+        assert!(merlin_sees.iter().all(|&(_, sighting)| sighting == Sighting::Spy));
+        assert_eq!(
+            merlin_sees.iter().map(|&(seat, _)| seat).collect::<Vec<_>>(),
+            sorted(vec![morgana, spy, oberon]),
+            "Merlin sees every spy but Mordred"
+        );
+
+        let percival_sees = sees(seat_of(&assignment, Role::Percival));
+        assert_eq!(
+            percival_sees,
+            sorted(vec![merlin, morgana])
+                .into_iter()
+                .map(|seat| (seat, Sighting::MerlinOrMorgana))
+                .collect::<Vec<_>>(),
+            "Percival cannot tell Merlin from Morgana"
+        );
+
+        for (viewer, others) in [
+            (mordred, vec![morgana, spy]),
+            (morgana, vec![mordred, spy]),
+            (spy, vec![mordred, morgana]),
+        ] {
+            assert_eq!(
+                sees(viewer),
+                sorted(others).into_iter().map(|seat| (seat, Sighting::Spy)).collect::<Vec<_>>()
+            );
+        }
+        assert!(sees(oberon).is_empty(), "Oberon knows nobody");
+        for member in assignment.seats_with(Role::ResistanceMember) {
+            assert!(sees(member).is_empty());
+        }
+    }
+    let assignment = RoleAssignment::deal_default(&seats(5), &mut SeededRng::new(0)).unwrap();
+    assert_eq!(assignment.night_info(9), None);
+}
+
+/// Test: the assassin is a Morgana or plain spy, or Mordred when there is neither
+#[test]
+fn test_assassin_choice() {
+    let mut assassins = HashSet::new();
+    for seed in 0..50 {
+        let assignment = RoleAssignment::deal_default(&seats(7), &mut SeededRng::new(seed)).unwrap();
+        let assassin = assignment.assassin().unwrap();
+        let role = assignment.role_of(assassin).unwrap();
+        assert!(matches!(role, Role::Morgana | Role::Spy), "Assassin was {:?}", role);
+        assert!(assignment.night_info(assassin).unwrap().assassin);
+        assassins.insert(role);
+    }
+    assert_eq!(assassins, HashSet::from([Role::Morgana, Role::Spy]));
+
+    let lineup = [
+        Role::Merlin,
+        Role::ResistanceMember,
+        Role::ResistanceMember,
+        Role::Mordred,
+        Role::Oberon,
+    ];
+    let assignment = RoleAssignment::deal(&seats(5), &lineup, &mut SeededRng::new(3)).unwrap();
+    assert_eq!(assignment.assassin(), Some(seat_of(&assignment, Role::Mordred)));
+    assert_eq!(
+        assignment.faction_seats(Faction::Spies).len(),
+        2,
+        "Oberon and Mordred are both spies"
+    );
+}
+
+/// Test: night information is described as a typed message
+#[test]
+fn test_night_info_message() {
+    let assignment = RoleAssignment::deal_default(&seats(5), &mut SeededRng::new(11)).unwrap();
+    let percival = seat_of(&assignment, Role::Percival);
+    let message: serde_json::Value =
+        serde_json::from_str(&assignment.night_info(percival).unwrap().to_message()).unwrap();
+    assert_eq!(message["type"], "night_info");
+    assert_eq!(message["seat"], percival);
+    assert_eq!(message["role"], "percival");
+    assert_eq!(message["faction"], "resistance");
+    assert_eq!(message["assassin"], false);
+    let sees = message["sees"].as_array().unwrap();
+    assert_eq!(sees.len(), 2);
+    assert!(sees.iter().all(|entry| entry["as"] == "merlin_or_morgana"));
+}
+
+/// Test: every seated player receives only their own night information
+#[test]
+fn test_night_info_sent_to_owners() {
+    let runtime = Runtime::new().unwrap();
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+
+    let (mut clients, _) = seat_clients(&runtime, &server, 5);
+
+    let assignment = RoleAssignment::deal_default(&seats(5), &mut SeededRng::new(5)).unwrap();
+    send_night_info(server.hub(), &assignment).unwrap();
+    for (seat, client) in clients.iter_mut().enumerate() {
+        let seat = seat as u32;
+        let info = next_json_within(&runtime, client, Duration::from_secs(5)).expect("Night info should arrive");
+        assert_eq!(info["type"], "night_info");
+        assert_eq!(info["seat"], seat);
+        assert_eq!(info["role"], assignment.role_of(seat).unwrap().name());
+        assert_eq!(
+            next_json_within(&runtime, client, Duration::from_millis(100)),
+            None,
+            "No other player's view is sent"
+        );
+    }
+
+    let larger = RoleAssignment::deal_default(&seats(6), &mut SeededRng::new(5)).unwrap();
+    assert!(matches!(send_night_info(server.hub(), &larger), Err(CoreError::SeatNotFound(5))));
+    server.stop();
+}
@@ -0,0 +1,302 @@
+mod common;
+
+use std::ffi::{CStr, CString};
+use std::net::IpAddr;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::Message;
+
+use facingtime_core::client::{ClientConfig, ClientStatus, WebSocketClientState};
+use facingtime_core::ffi::error::FtErrorCode;
+use facingtime_core::ffi::server::{
+    ft_http_server_create, ft_http_server_free, ft_http_server_free_response, ft_http_server_room_code,
+    ft_http_server_set_room_access,
+};
+use facingtime_core::server::room_access::{CLOSE_REJECTED, ROOM_CODE_LENGTH};
+use facingtime_core::server::{AccessError, CoreEvent, EventQueue, HostConfig, HttpServerState, RoomAccess};
+
+use common::{start_server_with, wait_for};
+
+const PLAYER: &str = "192.168.1.20";
+const OTHER_PLAYER: &str = "192.168.1.21";
+
+/// Parse an IP address
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+/// Start a server that requires its room code
+fn start_guarded_server() -> (HttpServerState, EventQueue, String) {
+    start_server_with(|server| server.access().set_code_required(true))
+}
+
+/// Test: room codes are short and use unambiguous characters
+#[test]
+fn test_room_code_format() {
+    let access = RoomAccess::new();
+    let code = access.room_code();
+    assert_eq!(code.len(), ROOM_CODE_LENGTH);
+    assert!(
+        code.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()),
+        "Unexpected code: {}",
+        code
+    );
+    assert!(!code.contains(['0', '1', 'O', 'I']), "Code should avoid ambiguous characters: {}", code);
+
+    let fresh = access.regenerate_room_code();
+    assert_eq!(access.room_code(), fresh);
+    assert_ne!(fresh, code, "Regenerating should produce a new code");
+}
+
+/// Test: a new room is open until a code or password is required
+#[test]
+fn test_open_room_accepts_everyone() {
+    let access = RoomAccess::new();
+    assert!(!access.code_required());
+    assert!(!access.has_password());
+    assert_eq!(access.check(ip(PLAYER), None, None), Ok(()));
+    assert_eq!(access.check(ip(PLAYER), Some("WRONG1"), Some("nope")), Ok(()));
+}
+
+/// Test: codes are checked case-insensitively and passwords exactly
+#[test]
+fn test_code_and_password_checks() {
+    let access = RoomAccess::new();
+    access.set_code_required(true);
+    let code = access.room_code();
+
+    assert_eq!(access.check(ip(PLAYER), None, None), Err(AccessError::InvalidCode));
+    assert_eq!(access.check(ip(PLAYER), Some("ZZZZZZ9"), None), Err(AccessError::InvalidCode));
+    let typed = format!(" {} ", code.to_lowercase());
+    assert_eq!(access.check(ip(PLAYER), Some(&typed), None), Ok(()));
+
+    access.set_password(Some("swordfish"));
+    assert!(access.has_password());
+    assert_eq!(access.check(ip(PLAYER), Some(&code), None), Err(AccessError::WrongPassword));
+    assert_eq!(access.check(ip(PLAYER), Some(&code), Some("Swordfish")), Err(AccessError::WrongPassword));
+    assert_eq!(
+        access.check(ip(PLAYER), Some("ZZZZZZ9"), Some("swordfish")),
+        Err(AccessError::InvalidCode),
+        "A wrong code is reported before the password"
+    );
+    assert_eq!(access.check(ip(PLAYER), Some(&code), Some("swordfish")), Ok(()));
+
+    access.set_password(Some(""));
+    assert!(!access.has_password(), "An empty password removes it");
+    assert_eq!(access.check(ip(PLAYER), Some(&code), None), Ok(()));
+}
+
+/// Test: repeated failures lock out only the offending address, until the lockout expires
+#[test]
+fn test_lockout_after_repeated_failures() {
+    let access = RoomAccess::new();
+    access.set_code_required(true);
+    access.set_lockout_policy(3, Duration::from_millis(300));
+    let code = access.room_code();
+
+    for _ in 0..3 {
+        assert_eq!(access.check(ip(PLAYER), Some("BAD"), None), Err(AccessError::InvalidCode));
+    }
+    assert!(access.lockout_remaining(ip(PLAYER)).is_some());
+    match access.check(ip(PLAYER), Some(&code), None) {
+        Err(AccessError::LockedOut { retry_after }) => assert!(retry_after <= Duration::from_millis(300)),
+        other => panic!("Correct code should still be locked out, got {:?}", other),
+    }
+    assert_eq!(access.check(ip(OTHER_PLAYER), Some(&code), None), Ok(()), "Other addresses are unaffected");
+
+    std::thread::sleep(Duration::from_millis(350));
+    assert_eq!(access.lockout_remaining(ip(PLAYER)), None);
+    assert_eq!(access.check(ip(PLAYER), Some(&code), None), Ok(()));
+
+    // A success resets the count
+    access.check(ip(PLAYER), Some("BAD"), None).unwrap_err();
+    access.check(ip(PLAYER), Some("BAD"), None).unwrap_err();
+    access.check(ip(PLAYER), Some(&code), None).unwrap();
+    access.check(ip(PLAYER), Some("BAD"), None).unwrap_err();
+    assert_eq!(access.lockout_remaining(ip(PLAYER)), None);
+
+    access.check(ip(PLAYER), Some("BAD"), None).unwrap_err();
+    access.check(ip(PLAYER), Some("BAD"), None).unwrap_err();
+    assert!(access.lockout_remaining(ip(PLAYER)).is_some());
+    access.clear_lockouts();
+    assert_eq!(access.check(ip(PLAYER), Some(&code), None), Ok(()));
+}
+
+/// Test: rejection messages carry a typed reason
+#[test]
+fn test_rejection_message() {
+    let message: serde_json::Value = serde_json::from_str(&AccessError::WrongPassword.to_message()).unwrap();
+    assert_eq!(message["type"], "join_rejected");
+    assert_eq!(message["reason"], "wrong_password");
+    assert_eq!(message["message"], "Wrong room password");
+    assert!(message.get("retry_after_ms").is_none());
+
+    let locked = AccessError::LockedOut {
+        retry_after: Duration::from_millis(1500),
+    };
+    let message: serde_json::Value = serde_json::from_str(&locked.to_message()).unwrap();
+    assert_eq!(message["reason"], "locked_out");
+    assert_eq!(message["retry_after_ms"], 1500);
+}
+
+/// Test: a WebSocket join with a wrong code is told why and closed with 4001
+#[test]
+fn test_websocket_join_rejected() {
+    let (mut server, events, url) = start_guarded_server();
+    let runtime = Runtime::new().unwrap();
+
+    let frames = runtime.block_on(async {
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("{}?code=WRONG9", url))
+            .await
+            .expect("Upgrade should complete so the reason can be delivered");
+        let mut frames = Vec::new();
+        while let Ok(Some(Ok(frame))) = tokio::time::timeout(Duration::from_secs(5), client.next()).await {
+            frames.push(frame);
+        }
+        frames
+    });
+
+    let rejection: serde_json::Value = match &frames[0] {
+        Message::Text(text) => serde_json::from_str(text.as_str()).unwrap(),
+        other => panic!("Expected a rejection message, got {:?}", other),
+    };
+    assert_eq!(rejection["reason"], "invalid_code");
+    match &frames[1] {
+        Message::Close(Some(frame)) => {
+            assert_eq!(u16::from(frame.code), CLOSE_REJECTED);
+            assert_eq!(frame.reason.as_str(), "invalid_code");
+        }
+        other => panic!("Expected a close frame, got {:?}", other),
+    }
+
+    match wait_for(&events, |e| matches!(e, CoreEvent::JoinRejected { .. })) {
+        CoreEvent::JoinRejected { reason, .. } => assert_eq!(reason, "invalid_code"),
+        _ => unreachable!(),
+    }
+    assert!(
+        !events.drain().iter().any(|e| matches!(e, CoreEvent::ClientConnected { .. })),
+        "Rejected clients never become peers"
+    );
+    assert_eq!(server.hub().peer_count(), 0);
+    assert_eq!(server.inner.lock().connected_clients, 0);
+
+    server.stop();
+}
+
+/// Test: the room code and password are accepted from the query string
+#[test]
+fn test_websocket_join_accepted() {
+    let (mut server, events, url) = start_guarded_server();
+    server.access().set_password(Some("p@ss word"));
+    let runtime = Runtime::new().unwrap();
+
+    let join_url = format!("{}?code={}&password=p%40ss%20word", url, server.access().room_code().to_lowercase());
+    let _client = runtime
+        .block_on(tokio_tungstenite::connect_async(join_url))
+        .expect("WebSocket should connect");
+    wait_for(&events, |e| matches!(e, CoreEvent::ClientConnected { .. }));
+    assert_eq!(server.hub().peer_count(), 1);
+
+    server.stop();
+}
+
+/// Test: the client does not retry a rejected join
+#[test]
+fn test_client_stops_after_rejection() {
+    let (mut server, _server_events, url) = start_guarded_server();
+
+    let mut client = WebSocketClientState::new();
+    let events = client.events().subscribe();
+    client
+        .connect(ClientConfig {
+            initial_backoff: Duration::from_millis(20),
+            ..ClientConfig::new(&format!("{}?code=WRONG9", url))
+        })
+        .unwrap();
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::HostDisconnected { .. })),
+        CoreEvent::HostDisconnected {
+            code: CLOSE_REJECTED,
+            reason: "invalid_code".to_string()
+        }
+    );
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(
+        !events.drain().iter().any(|e| matches!(e, CoreEvent::HostReconnecting { .. })),
+        "Rejected clients should not reconnect"
+    );
+    assert_eq!(client.status(), ClientStatus::Disconnected);
+    server.stop();
+}
+
+/// Test: host configuration validates and applies the access settings
+#[test]
+fn test_host_config_access_settings() {
+    let config = HostConfig {
+        static_dir: "/tmp".to_string(),
+        room_password: "x".repeat(65),
+        max_join_failures: -1,
+        lockout_seconds: -5,
+        ..HostConfig::default()
+    };
+    let fields: Vec<&str> = config.validate().iter().map(|e| e.field).collect();
+    assert_eq!(fields, vec!["room_password", "max_join_failures", "lockout_seconds"]);
+
+    let config = HostConfig {
+        static_dir: "/tmp".to_string(),
+        room_password: "secret".to_string(),
+        max_join_failures: 1,
+        ..HostConfig::default()
+    };
+    assert!(config.require_room_code, "Configured hosts require the room code by default");
+    let access = RoomAccess::new();
+    config.configure_access(&access);
+    let code = access.room_code();
+    assert!(access.code_required());
+    assert_eq!(access.check(ip(PLAYER), Some(&code), Some("secret")), Ok(()));
+    assert_eq!(access.check(ip(PLAYER), Some(&code), None), Err(AccessError::WrongPassword));
+    assert!(access.lockout_remaining(ip(PLAYER)).is_some(), "One failure should lock out");
+}
+
+/// Test: the FFI exposes the room code and access settings
+#[test]
+fn test_ffi_room_access() {
+    let server = unsafe { ft_http_server_create() };
+    let password = CString::new("secret").unwrap();
+
+    unsafe {
+        let code = ft_http_server_room_code(server, 0);
+        let first = CStr::from_ptr(code).to_str().unwrap().to_string();
+        ft_http_server_free_response(code);
+        assert_eq!(first, (*server).access().room_code());
+
+        let code = ft_http_server_room_code(server, 1);
+        let second = CStr::from_ptr(code).to_str().unwrap().to_string();
+        ft_http_server_free_response(code);
+        assert_ne!(first, second, "Regenerating should produce a new code");
+
+        assert_eq!(
+            ft_http_server_set_room_access(server, 1, password.as_ptr(), 2, 60_000),
+            FtErrorCode::Ok as i32
+        );
+        let access = (*server).access();
+        assert!(access.code_required());
+        assert_eq!(access.check(ip(PLAYER), Some(&second), Some("secret")), Ok(()));
+
+        assert_eq!(
+            ft_http_server_set_room_access(server, 0, std::ptr::null(), 0, 0),
+            FtErrorCode::Ok as i32
+        );
+        assert!(!(*server).access().code_required());
+        assert!(!(*server).access().has_password(), "NULL password removes it");
+
+        assert_eq!(
+            ft_http_server_set_room_access(std::ptr::null_mut(), 1, std::ptr::null(), 0, 0),
+            FtErrorCode::NullPointer as i32
+        );
+        assert!(ft_http_server_room_code(std::ptr::null_mut(), 0).is_null());
+        ft_http_server_free(server);
+    }
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::sync::Arc;
+
+use facingtime_core::ffi::error::{ft_last_error_code, FtErrorCode};
+use facingtime_core::ffi::server::{
+    ft_http_response_set,
+    ft_http_server_create,
+    ft_http_server_free,
+    ft_http_server_free_response,
+    ft_http_server_handle_request,
+    ft_http_server_register_handler,
+    ft_http_server_unregister_handler,
+    FtHttpResponse,
+};
+use facingtime_core::server::{HttpServerState, RouteRegistry};
+use facingtime_core::types::{HttpRequest, HttpResponse};
+
+use common::raw_request;
+
+fn get(path: &str) -> HttpRequest {
+    HttpRequest {
+        method: "GET".to_string(),
+        path: path.to_string(),
+        query: None,
+        headers: "{}".to_string(),
+        body: None,
+    }
+}
+
+/// Test: prefixes match whole path segments and the longest prefix wins
+#[test]
+fn test_registry_prefix_matching() {
+    let registry = RouteRegistry::new();
+    registry
+        .register("/api", Arc::new(|_| HttpResponse::new(200, "api")))
+        .unwrap();
+    registry
+        .register("/api/room/", Arc::new(|_| HttpResponse::new(200, "room")))
+        .unwrap();
+
+    let body = |path: &str| registry.dispatch(get(path)).map(|r| r.body);
+    assert_eq!(body("/api/room"), Some("room".to_string()));
+    assert_eq!(body("/api/room/3"), Some("room".to_string()));
+    assert_eq!(body("/api/rooms"), Some("api".to_string()), "Prefix should not match a partial segment");
+    assert_eq!(body("/apix"), None);
+    assert_eq!(body("/index.html"), None);
+
+    assert!(registry.unregister("/api/room"), "Trailing slash should not matter");
+    assert_eq!(body("/api/room/3"), Some("api".to_string()));
+    assert!(!registry.unregister("/api/room"), "Second unregister should report nothing removed");
+}
+
+/// Test: prefixes must be absolute
+#[test]
+fn test_registry_rejects_relative_prefix() {
+    let registry = RouteRegistry::new();
+    let result = registry.register("api", Arc::new(|_| HttpResponse::new(200, "")));
+    assert!(result.is_err(), "Prefix without leading '/' should be rejected");
+    assert!(registry.prefixes().is_empty());
+}
+
+/// Test: a running server sends matching requests to the host handler
+#[test]
+fn test_registered_route_served_over_http() {
+    let mut server = HttpServerState::new();
+    server
+        .register_route(
+            "/api/echo",
+            Arc::new(|request| HttpResponse {
+                status_code: 201,
+                headers: r#"{"x-handler":"echo"}"#.to_string(),
+                body: format!(
+                    "{} {} {}",
+                    request.method,
+                    request.query.unwrap_or_default(),
+                    request.body.unwrap_or_default()
+                ),
+            }),
+        )
+        .unwrap();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let addr = server.local_addr().expect("Running server should have a local address");
+
+    let response = raw_request(
+        addr,
+        "POST /api/echo/1?seat=2 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+    );
+    assert!(response.starts_with("HTTP/1.1 201"), "Unexpected response: {}", response);
+    assert!(response.to_ascii_lowercase().contains("x-handler: echo"), "Handler header missing: {}", response);
+    assert!(response.ends_with("POST seat=2 hello"), "Unexpected body: {}", response);
+
+    // Removing the handler falls back to the built-in routes
+    assert!(server.unregister_route("/api/echo"));
+    let response = raw_request(
+        addr,
+        "GET /api/echo HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 404"), "Unexpected response: {}", response);
+
+    server.stop();
+}
+
+/// C callback that echoes the request JSON back as the body
+unsafe extern "C" fn echo_handler(user_data: *mut c_void, request_json: *const c_char, response: *mut FtHttpResponse) {
+    let status = *(user_data as *const i32);
+    let headers = CString::new(r#"{"content-type":"application/json"}"#).unwrap();
+    ft_http_response_set(response, status, headers.as_ptr(), request_json);
+}
+
+/// Test: FFI handlers receive the request as JSON and fill in the response
+#[test]
+fn test_ffi_handler_dispatch() {
+    let server = unsafe { ft_http_server_create() };
+    let mut status: i32 = 202;
+    let prefix = CString::new("/api/room").unwrap();
+
+    let result = unsafe {
+        ft_http_server_register_handler(
+            server,
+            prefix.as_ptr(),
+            Some(echo_handler),
+            &mut status as *mut i32 as *mut c_void,
+        )
+    };
+    assert_eq!(result, 0, "Handler registration should succeed");
+
+    let method = CString::new("PUT").unwrap();
+    let path = CString::new("/api/room/7?code=ABCD").unwrap();
+    let body = CString::new("{\"seat\":3}").unwrap();
+    let raw = unsafe {
+        ft_http_server_handle_request(server, method.as_ptr(), path.as_ptr(), std::ptr::null(), body.as_ptr())
+    };
+    assert!(!raw.is_null(), "handle_request should return a response");
+
+    let response: HttpResponse =
+        serde_json::from_str(unsafe { CStr::from_ptr(raw) }.to_str().unwrap()).unwrap();
+    unsafe { ft_http_server_free_response(raw) };
+
+    assert_eq!(response.status_code, 202);
+    assert!(response.headers.contains("application/json"));
+    let echoed: HttpRequest = serde_json::from_str(&response.body).expect("Body should be the request JSON");
+    assert_eq!(echoed.method, "PUT");
+    assert_eq!(echoed.path, "/api/room/7");
+    assert_eq!(echoed.query.as_deref(), Some("code=ABCD"));
+    assert_eq!(echoed.body.as_deref(), Some("{\"seat\":3}"));
+
+    let result = unsafe { ft_http_server_unregister_handler(server, prefix.as_ptr()) };
+    assert_eq!(result, 0, "Unregister should succeed");
+    let result = unsafe { ft_http_server_unregister_handler(server, prefix.as_ptr()) };
+    assert_eq!(result, FtErrorCode::InvalidRoute as i32, "Second unregister should fail");
+
+    unsafe { ft_http_server_free(server) };
+}
+
+/// Test: unmatched paths get a 404 and invalid arguments are reported
+#[test]
+fn test_ffi_handle_request_errors() {
+    let server = unsafe { ft_http_server_create() };
+
+    let path = CString::new("/api/missing").unwrap();
+    let raw = unsafe {
+        ft_http_server_handle_request(server, std::ptr::null(), path.as_ptr(), std::ptr::null(), std::ptr::null())
+    };
+    assert!(!raw.is_null());
+    let response: HttpResponse =
+        serde_json::from_str(unsafe { CStr::from_ptr(raw) }.to_str().unwrap()).unwrap();
+    unsafe { ft_http_server_free_response(raw) };
+    assert_eq!(response.status_code, 404, "Unmatched path should return 404");
+
+    let raw = unsafe {
+        ft_http_server_handle_request(
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            path.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+        )
+    };
+    assert!(raw.is_null(), "NULL server should return NULL");
+    assert_eq!(ft_last_error_code(), FtErrorCode::NullPointer as i32);
+
+    let relative = CString::new("api").unwrap();
+    let result = unsafe {
+        ft_http_server_register_handler(server, relative.as_ptr(), Some(echo_handler), std::ptr::null_mut())
+    };
+    assert_eq!(result, FtErrorCode::InvalidRoute as i32, "Relative prefix should be rejected");
+
+    let result = unsafe { ft_http_server_register_handler(server, path.as_ptr(), None, std::ptr::null_mut()) };
+    assert_eq!(result, FtErrorCode::NullPointer as i32, "NULL handler should be rejected");
+
+    unsafe { ft_http_server_free(server) };
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use facingtime_core::game::{quest_rules, GameSetup, QuestRule, MAX_PLAYERS, MIN_PLAYERS, QUEST_COUNT};
+use facingtime_core::server::HttpServerState;
+
+use common::get;
+
+/// Official team sizes and fail thresholds, written out per player count
+const OFFICIAL: [(usize, [(usize, usize); QUEST_COUNT]); 6] = [
+    (5, [(2, 1), (3, 1), (2, 1), (3, 1), (3, 1)]),
+    (6, [(2, 1), (3, 1), (4, 1), (3, 1), (4, 1)]),
+    (7, [(2, 1), (3, 1), (3, 1), (4, 2), (4, 1)]),
+    (8, [(3, 1), (4, 1), (4, 1), (5, 2), (5, 1)]),
+    (9, [(3, 1), (4, 1), (4, 1), (5, 2), (5, 1)]),
+    (10, [(3, 1), (4, 1), (4, 1), (5, 2), (5, 1)]),
+];
+
+/// Test: every player count gets the official sizes and thresholds
+#[test]
+fn test_official_table() {
+    for (players, quests) in OFFICIAL {
+        let rules = quest_rules(players).unwrap();
+        for (quest, (team_size, fails_required)) in quests.into_iter().enumerate() {
+            assert_eq!(
+                rules[quest],
+                QuestRule {
+                    team_size,
+                    fails_required
+                },
+                "{} players, quest {}",
+                players,
+                quest + 1
+            );
+        }
+    }
+}
+
+/// Test: only the fourth quest of games with seven or more players needs two fails
+#[test]
+fn test_two_fail_rule() {
+    for players in MIN_PLAYERS..=MAX_PLAYERS {
+        for (quest, rule) in quest_rules(players).unwrap().iter().enumerate() {
+            let expected = if quest == 3 && players >= 7 { 2 } else { 1 };
+            assert_eq!(rule.fails_required, expected, "{} players, quest {}", players, quest + 1);
+            assert!(rule.team_size < players, "Teams never include everyone");
+        }
+    }
+
+    let fourth = quest_rules(7).unwrap()[3];
+    assert!(!fourth.fails_with(0));
+    assert!(!fourth.fails_with(1));
+    assert!(fourth.fails_with(2));
+    assert!(quest_rules(6).unwrap()[3].fails_with(1), "6 players fail the fourth quest with one card");
+}
+
+/// Test: player counts outside the table have no rules, rather than falling back
+#[test]
+fn test_unsupported_counts() {
+    for players in [0, 1, 4, 11, 100] {
+        assert_eq!(quest_rules(players), None);
+        assert_eq!(GameSetup::new(players).quests(), None);
+    }
+    assert_eq!(GameSetup::plain(8).quests(), quest_rules(8));
+}
+
+/// Test: the host serves the same table at /rules
+#[test]
+fn test_rules_endpoint() {
+    let mut server = HttpServerState::new();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let response = get(server.local_addr().unwrap(), "/rules");
+    server.stop();
+
+    assert!(response.starts_with("HTTP/1.1 200"), "Unexpected response: {}", response);
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    let rules: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(rules["min_players"], 5);
+    assert_eq!(rules["max_players"], 10);
+    let games = rules["games"].as_array().unwrap();
+    assert_eq!(games.len(), OFFICIAL.len());
+    for (game, (players, quests)) in games.iter().zip(OFFICIAL) {
+        assert_eq!(game["players"], players);
+        let served: Vec<(u64, u64)> = game["quests"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|quest| {
+                (
+                    quest["team_size"].as_u64().unwrap(),
+                    quest["fails_required"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        let expected: Vec<(u64, u64)> = quests.iter().map(|&(size, fails)| (size as u64, fails as u64)).collect();
+        assert_eq!(served, expected);
+    }
+    assert_eq!(games[0]["spies"], 2);
+    assert_eq!(games[5]["spies"], 4);
+}
@@ -0,0 +1,326 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use facingtime_core::client::{ClientConfig, WebSocketClientState};
+use facingtime_core::error::CoreError;
+use facingtime_core::ffi::error::FtErrorCode;
+use facingtime_core::server::websocket::CLOSE_KICKED;
+use facingtime_core::server::{seat_from_index, CoreEvent, HostConfig, SessionRegistry};
+
+use common::{connect, next_json, peer_id, start_server, wait_for};
+
+/// Resume URL for a `session` greeting
+fn resume_url(url: &str, session: &serde_json::Value) -> String {
+    format!("{}?session={}", url, session["token"].as_str().unwrap())
+}
+
+/// Test: every peer gets its own unseated session token on join
+#[test]
+fn test_session_greeting() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, _events, url) = start_server();
+
+    let (_first, first) = connect(&runtime, &url);
+    let (_second, second) = connect(&runtime, &url);
+    for session in [&first, &second] {
+        let token = session["token"].as_str().unwrap();
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(session["seat"], serde_json::Value::Null);
+        assert_eq!(session["resumed"], false);
+        assert!(server.hub().sessions().is_live(token));
+    }
+    assert_ne!(first["token"], second["token"]);
+    assert_ne!(peer_id(&first), peer_id(&second));
+
+    server.stop();
+    assert!(!server.hub().sessions().is_live(first["token"].as_str().unwrap()));
+}
+
+/// Test: seats are exclusive and unseated players are not held
+#[test]
+fn test_set_seat() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+    let sessions = server.hub().sessions().clone();
+
+    let (_first, first) = connect(&runtime, &url);
+    let (second_client, second) = connect(&runtime, &url);
+    assert!(sessions.set_seat(peer_id(&first), Some(0)));
+    assert!(!sessions.set_seat(peer_id(&second), Some(0)), "Seat 0 is taken");
+    assert!(!sessions.set_seat(999, Some(1)), "Unknown peers cannot be seated");
+    assert_eq!(sessions.seat_of(peer_id(&first)), Some(0));
+    assert_eq!(sessions.occupant(0), Some((peer_id(&first), true)));
+
+    drop(second_client);
+    wait_for(&events, |e| matches!(e, CoreEvent::ClientDisconnected { .. }));
+    assert!(!sessions.is_live(second["token"].as_str().unwrap()), "Unseated sessions end on disconnect");
+    assert!(sessions.held_seats().is_empty());
+
+    assert!(sessions.set_seat(peer_id(&first), None));
+    assert_eq!(sessions.occupant(0), None);
+    server.stop();
+}
+
+/// Test: a seated player's seat is held and released once the grace period runs out
+#[test]
+fn test_seat_held_then_released() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+    let sessions = server.hub().sessions().clone();
+    sessions.set_grace_period(Duration::from_millis(200));
+
+    let (client, session) = connect(&runtime, &url);
+    assert!(sessions.set_seat(peer_id(&session), Some(3)));
+    drop(client);
+
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::SeatHeld { .. })),
+        CoreEvent::SeatHeld {
+            peer_id: peer_id(&session),
+            seat: 3,
+            grace_ms: 200
+        }
+    );
+    assert_eq!(sessions.held_seats(), vec![3]);
+    assert_eq!(sessions.occupant(3), Some((peer_id(&session), false)));
+
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::SeatReleased { .. })),
+        CoreEvent::SeatReleased {
+            peer_id: peer_id(&session),
+            seat: 3
+        }
+    );
+    assert!(sessions.held_seats().is_empty());
+    assert!(!sessions.is_live(session["token"].as_str().unwrap()));
+    server.stop();
+}
+
+/// Test: a zero grace period frees the seat as soon as the player leaves
+#[test]
+fn test_zero_grace_period() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+    let sessions = server.hub().sessions().clone();
+    sessions.set_grace_period(Duration::ZERO);
+
+    let (client, session) = connect(&runtime, &url);
+    assert!(sessions.set_seat(peer_id(&session), Some(1)));
+    drop(client);
+
+    wait_for(&events, |e| matches!(e, CoreEvent::ClientDisconnected { .. }));
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(
+        !events.drain().iter().any(|e| matches!(e, CoreEvent::SeatHeld { .. })),
+        "Nothing should be held"
+    );
+    assert_eq!(sessions.occupant(1), None);
+    server.stop();
+}
+
+/// Test: reconnecting with the token restores the seat and replays private messages
+#[test]
+fn test_resume_restores_seat() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+    let hub = server.hub().clone();
+
+    let (mut client, session) = connect(&runtime, &url);
+    let old_peer = peer_id(&session);
+    assert!(hub.sessions().set_seat(old_peer, Some(2)));
+    hub.send_to_seat(2, "role", r#"{"type":"role","role":"merlin"}"#).unwrap();
+    hub.send_to_seat(2, "info", r#"{"type":"info","seen":[4]}"#).unwrap();
+    hub.send_to_seat(2, "info", r#"{"type":"info","seen":[4,5]}"#).unwrap();
+    assert_eq!(next_json(&runtime, &mut client)["role"], "merlin");
+    assert_eq!(next_json(&runtime, &mut client)["seen"], serde_json::json!([4]));
+    assert_eq!(next_json(&runtime, &mut client)["seen"], serde_json::json!([4, 5]));
+
+    drop(client);
+    wait_for(&events, |e| matches!(e, CoreEvent::SeatHeld { .. }));
+
+    let (mut resumed_client, resumed) = connect(&runtime, &resume_url(&url, &session));
+    assert_eq!(resumed["token"], session["token"]);
+    assert_eq!(resumed["resumed"], true);
+    assert_eq!(resumed["seat"], 2);
+    let new_peer = peer_id(&resumed);
+    assert_ne!(new_peer, old_peer);
+
+    // Replayed in the order first sent, with replaced keys holding the latest message
+    assert_eq!(next_json(&runtime, &mut resumed_client)["role"], "merlin");
+    assert_eq!(next_json(&runtime, &mut resumed_client)["seen"], serde_json::json!([4, 5]));
+
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::PlayerResumed { .. })),
+        CoreEvent::PlayerResumed {
+            peer_id: new_peer,
+            previous_peer_id: old_peer,
+            seat: Some(2)
+        }
+    );
+    assert_eq!(hub.sessions().seat_of(new_peer), Some(2));
+    assert!(hub.sessions().held_seats().is_empty());
+    server.stop();
+}
+
+/// Test: resuming a session that is still connected disconnects the old connection
+#[test]
+fn test_resume_takes_over_connection() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+
+    let (mut old_client, session) = connect(&runtime, &url);
+    assert!(server.hub().sessions().set_seat(peer_id(&session), Some(0)));
+
+    let (_new_client, resumed) = connect(&runtime, &resume_url(&url, &session));
+    assert_eq!(resumed["resumed"], true);
+    assert_eq!(resumed["seat"], 0);
+
+    let frame = runtime
+        .block_on(async { tokio::time::timeout(Duration::from_secs(5), old_client.next()).await })
+        .expect("Old connection should be closed");
+    match frame {
+        Some(Ok(Message::Close(Some(close)))) => assert_eq!(close.code, CloseCode::from(CLOSE_KICKED)),
+        other => panic!("Expected a close frame, got {:?}", other),
+    }
+
+    wait_for(&events, |e| matches!(e, CoreEvent::PlayerResumed { .. }));
+    assert_eq!(server.hub().sessions().seat_of(peer_id(&resumed)), Some(0));
+    assert!(server.hub().sessions().held_seats().is_empty(), "The takeover should not hold the seat");
+    server.stop();
+}
+
+/// Test: unknown tokens start a fresh session and kicked players cannot resume
+#[test]
+fn test_resume_rejects_dead_tokens() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+
+    let (_client, fresh) = connect(&runtime, &format!("{}?session=0123456789abcdef", url));
+    assert_eq!(fresh["resumed"], false);
+    assert_ne!(fresh["token"], "0123456789abcdef");
+
+    let (_kicked_client, session) = connect(&runtime, &url);
+    assert!(server.hub().sessions().set_seat(peer_id(&session), Some(4)));
+    server.hub().kick(peer_id(&session), "Bye").unwrap();
+    wait_for(&events, |e| {
+        matches!(e, CoreEvent::ClientDisconnected { peer_id: id } if *id == peer_id(&session))
+    });
+    assert!(!server.hub().sessions().is_live(session["token"].as_str().unwrap()));
+    assert_eq!(server.hub().sessions().occupant(4), None);
+
+    let (_returned, returned) = connect(&runtime, &resume_url(&url, &session));
+    assert_eq!(returned["resumed"], false);
+    assert_eq!(returned["seat"], serde_json::Value::Null);
+    server.stop();
+}
+
+/// Test: a live session resumes without the room code
+#[test]
+fn test_resume_skips_room_code() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, events, url) = start_server();
+
+    let (client, session) = connect(&runtime, &url);
+    assert!(server.hub().sessions().set_seat(peer_id(&session), Some(0)));
+    server.access().set_code_required(true);
+    drop(client);
+    wait_for(&events, |e| matches!(e, CoreEvent::SeatHeld { .. }));
+
+    let (_client, resumed) = connect(&runtime, &resume_url(&url, &session));
+    assert_eq!(resumed["resumed"], true);
+    server.stop();
+}
+
+/// Test: private messages need an occupied seat and are forgotten when it is released
+#[test]
+fn test_send_to_seat_and_release() {
+    let runtime = Runtime::new().unwrap();
+    let (mut server, _events, url) = start_server();
+    let hub = server.hub().clone();
+
+    assert!(matches!(hub.send_to_seat(5, "role", "{}"), Err(CoreError::SeatNotFound(5))));
+
+    let (mut client, session) = connect(&runtime, &url);
+    assert!(hub.sessions().set_seat(peer_id(&session), Some(5)));
+    hub.send_to_seat(5, "role", r#"{"role":"percival"}"#).unwrap();
+    assert_eq!(next_json(&runtime, &mut client)["role"], "percival");
+
+    assert_eq!(hub.sessions().release_seat(5), Some(peer_id(&session)));
+    assert_eq!(hub.sessions().release_seat(5), None);
+    assert!(matches!(hub.send_to_seat(5, "role", "{}"), Err(CoreError::SeatNotFound(5))));
+    assert!(hub.sessions().is_live(session["token"].as_str().unwrap()), "Releasing keeps a connected session");
+    server.stop();
+}
+
+/// Test: seat indexes from the host are refused when negative or too large, never clamped to seat 0
+#[test]
+fn test_seat_from_index() {
+    assert_eq!(seat_from_index(0).unwrap(), 0);
+    assert_eq!(seat_from_index(u32::MAX as i64).unwrap(), u32::MAX);
+    for index in [-1, i64::MIN, u32::MAX as i64 + 1] {
+        let error = seat_from_index(index).unwrap_err();
+        assert!(matches!(error, CoreError::InvalidSeat(i) if i == index), "{} should be refused", index);
+        assert_eq!(FtErrorCode::from(&error), FtErrorCode::SeatNotFound);
+    }
+}
+
+/// Test: the Rust client keeps the token and does not surface the greeting as a message
+#[test]
+fn test_client_session_token() {
+    let (mut server, server_events, url) = start_server();
+
+    let mut client = WebSocketClientState::new();
+    let client_events = client.events().subscribe();
+    assert_eq!(client.session_token(), None);
+    client.connect(ClientConfig::new(&url)).unwrap();
+    wait_for(&client_events, |e| matches!(e, CoreEvent::HostConnected { .. }));
+    let peer_id = match wait_for(&server_events, |e| matches!(e, CoreEvent::ClientConnected { .. })) {
+        CoreEvent::ClientConnected { peer_id, .. } => peer_id,
+        _ => unreachable!(),
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while client.session_token().is_none() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let token = client.session_token().expect("Client should store its session token");
+    assert!(server.hub().sessions().is_live(&token));
+    assert!(
+        !client_events.drain().iter().any(|e| matches!(e, CoreEvent::HostMessage { .. })),
+        "The session greeting is not an application message"
+    );
+    assert!(server.hub().sessions().set_seat(peer_id, Some(0)));
+
+    client.disconnect();
+    server.stop();
+}
+
+/// Test: the reconnect grace period is validated and applied from the host config
+#[test]
+fn test_host_config_grace_period() {
+    let config = HostConfig {
+        static_dir: "/tmp".to_string(),
+        reconnect_grace_seconds: -1,
+        ..HostConfig::default()
+    };
+    assert_eq!(
+        config.validate().iter().map(|e| e.field).collect::<Vec<_>>(),
+        vec!["reconnect_grace_seconds"]
+    );
+
+    let sessions = SessionRegistry::new();
+    assert_eq!(sessions.grace_period(), Duration::from_secs(120));
+    HostConfig {
+        reconnect_grace_seconds: 30,
+        ..HostConfig::default()
+    }
+    .configure_sessions(&sessions);
+    assert_eq!(sessions.grace_period(), Duration::from_secs(30));
+}
@@ -0,0 +1,354 @@
+mod common;
+
+use std::ffi::CString;
+use std::time::Duration;
+
+use facingtime_core::client::{ClientConfig, ClientStatus, WebSocketClientState};
+use facingtime_core::error::CoreError;
+use facingtime_core::ffi::client::{
+    ft_ws_client_connect, ft_ws_client_create, ft_ws_client_free, ft_ws_client_send, ft_ws_client_status,
+};
+use facingtime_core::ffi::error::FtErrorCode;
+use facingtime_core::server::{CoreEvent, HttpServerState, Payload};
+use facingtime_core::tls::Fingerprint;
+
+use common::{wait_for, write_certificate};
+
+/// A local address nothing is listening on
+fn unused_address() -> std::net::SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+/// Settings that retry quickly
+fn fast_config(url: &str) -> ClientConfig {
+    ClientConfig {
+        initial_backoff: Duration::from_millis(20),
+        max_backoff: Duration::from_millis(100),
+        ..ClientConfig::new(url)
+    }
+}
+
+/// Test: fingerprints print as colon-separated hex and parse leniently
+#[test]
+fn test_fingerprint_format() {
+    let fingerprint = Fingerprint::of_certificate(b"certificate");
+    let text = fingerprint.to_string();
+    assert_eq!(text.len(), 32 * 3 - 1);
+    assert_eq!(text.matches(':').count(), 31);
+    assert_eq!(text, text.to_uppercase());
+
+    assert_eq!(text.parse::<Fingerprint>().unwrap(), fingerprint);
+    let compact = text.replace(':', "").to_lowercase();
+    assert_eq!(compact.parse::<Fingerprint>().unwrap(), fingerprint, "Colons and case should not matter");
+
+    for invalid in ["", "AB:CD", &compact[..62], &format!("{}zz", &compact[..62])] {
+        assert!(
+            matches!(invalid.parse::<Fingerprint>(), Err(CoreError::InvalidConfig(_))),
+            "'{}' should be rejected",
+            invalid
+        );
+    }
+}
+
+/// Test: the reconnect delay doubles up to the maximum
+#[test]
+fn test_backoff_delay() {
+    let config = ClientConfig {
+        initial_backoff: Duration::from_millis(500),
+        max_backoff: Duration::from_secs(3),
+        ..ClientConfig::default()
+    };
+    let delays: Vec<u128> = (1..=5).map(|attempt| config.backoff_delay(attempt).as_millis()).collect();
+    assert_eq!(delays, vec![500, 1000, 2000, 3000, 3000]);
+    assert_eq!(config.backoff_delay(u32::MAX), Duration::from_secs(3), "Large attempts should not overflow");
+}
+
+/// Test: invalid URLs and fingerprint combinations are rejected before connecting
+#[test]
+fn test_connect_validates_config() {
+    let mut client = WebSocketClientState::new();
+    let fingerprint = Fingerprint::of_certificate(b"certificate");
+
+    let result = client.connect(ClientConfig::new("http://127.0.0.1:8089/ws"));
+    assert!(matches!(result, Err(CoreError::InvalidAddress(_))), "Only ws:// and wss:// are supported");
+
+    let result = client.connect(ClientConfig::new("wss://127.0.0.1:8089/ws"));
+    assert!(matches!(result, Err(CoreError::InvalidConfig(_))), "wss:// needs a fingerprint");
+
+    let result = client.connect(ClientConfig {
+        fingerprint: Some(fingerprint),
+        ..ClientConfig::new("ws://127.0.0.1:8089/ws")
+    });
+    assert!(matches!(result, Err(CoreError::InvalidConfig(_))), "A fingerprint needs wss://");
+
+    assert!(!client.is_running());
+    assert_eq!(client.status(), ClientStatus::Disconnected);
+    assert!(matches!(client.send(Payload::Text("hi".to_string())), Err(CoreError::NotRunning)));
+}
+
+/// Test: messages flow both ways and messages sent before connecting are queued
+#[test]
+fn test_client_round_trip() {
+    let mut server = HttpServerState::new();
+    let server_events = server.events().subscribe();
+    server.start("127.0.0.1:0", "/tmp").expect("Server should start");
+    let url = format!("ws://{}/ws", server.local_addr().unwrap());
+
+    let mut client = WebSocketClientState::new();
+    let client_events = client.events().subscribe();
+    client.connect(ClientConfig::new(&url)).expect("Client should start");
+    client.send(Payload::Text("queued".to_string())).expect("Send should queue before connecting");
+    assert!(matches!(client.connect(ClientConfig::new(&url)), Err(CoreError::AlreadyRunning)));
+
+    assert_eq!(
+        wait_for(&client_events, |e| matches!(e, CoreEvent::HostConnected { .. })),
+        CoreEvent::HostConnected { url: url.clone() }
+    );
+    assert_eq!(client.status(), ClientStatus::Connected);
+
+    let peer_id = match wait_for(&server_events, |e| matches!(e, CoreEvent::MessageReceived { .. })) {
+        CoreEvent::MessageReceived { peer_id, message } => {
+            assert_eq!(message, "queued");
+            peer_id
+        }
+        _ => unreachable!(),
+    };
+
+    server.hub().send(peer_id, Payload::Text("welcome".to_string())).unwrap();
+    assert_eq!(
+        wait_for(&client_events, |e| matches!(e, CoreEvent::HostMessage { .. })),
+        CoreEvent::HostMessage {
+            message: "welcome".to_string()
+        }
+    );
+
+    client.disconnect();
+    assert_eq!(
+        wait_for(&client_events, |e| matches!(e, CoreEvent::HostDisconnected { .. })),
+        CoreEvent::HostDisconnected {
+            code: 1000,
+            reason: "Client disconnected".to_string()
+        }
+    );
+    wait_for(&server_events, |e| matches!(e, CoreEvent::ClientDisconnected { .. }));
+    server.stop();
+}
+
+/// Test: the client keeps retrying until the host comes up
+#[test]
+fn test_reconnect_until_host_starts() {
+    let address = unused_address();
+    let url = format!("ws://{}/ws", address);
+
+    let mut client = WebSocketClientState::new();
+    let events = client.events().subscribe();
+    client.connect(fast_config(&url)).unwrap();
+
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::HostReconnecting { .. })),
+        CoreEvent::HostReconnecting {
+            attempt: 1,
+            delay_ms: 20
+        }
+    );
+    wait_for(&events, |e| matches!(e, CoreEvent::HostReconnecting { attempt: 2, .. }));
+    assert!(client.is_running());
+
+    let mut server = HttpServerState::new();
+    server.start(&address.to_string(), "/tmp").expect("Server should start");
+    wait_for(&events, |e| matches!(e, CoreEvent::HostConnected { .. }));
+
+    client.disconnect();
+    server.stop();
+}
+
+/// Test: the client gives up after the configured number of attempts
+#[test]
+fn test_reconnect_gives_up() {
+    let url = format!("ws://{}/ws", unused_address());
+    let mut client = WebSocketClientState::new();
+    let events = client.events().subscribe();
+    client
+        .connect(ClientConfig {
+            max_reconnect_attempts: 2,
+            ..fast_config(&url)
+        })
+        .unwrap();
+
+    match wait_for(&events, |e| matches!(e, CoreEvent::HostConnectFailed { .. })) {
+        CoreEvent::HostConnectFailed { reason } => {
+            assert!(reason.starts_with("gave up after 2 reconnect attempts"), "Unexpected reason {}", reason)
+        }
+        _ => unreachable!(),
+    }
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!client.is_running());
+    assert_eq!(client.status(), ClientStatus::Disconnected);
+    assert!(matches!(client.send(Payload::Text("late".to_string())), Err(CoreError::NotRunning)));
+}
+
+/// Test: a full send queue is reported instead of growing without bound
+#[test]
+fn test_send_queue_full() {
+    let url = format!("ws://{}/ws", unused_address());
+    let mut client = WebSocketClientState::new();
+    client
+        .connect(ClientConfig {
+            send_queue_capacity: 2,
+            initial_backoff: Duration::from_secs(60),
+            ..ClientConfig::new(&url)
+        })
+        .unwrap();
+
+    client.send(Payload::Text("one".to_string())).unwrap();
+    client.send(Payload::Text("two".to_string())).unwrap();
+    assert!(matches!(client.send(Payload::Text("three".to_string())), Err(CoreError::QueueFull)));
+    client.disconnect();
+}
+
+/// Test: being kicked by the host does not trigger a reconnect
+#[test]
+fn test_kick_stops_reconnecting() {
+    let mut server = HttpServerState::new();
+    let server_events = server.events().subscribe();
+    server.start("127.0.0.1:0", "/tmp").unwrap();
+    let url = format!("ws://{}/ws", server.local_addr().unwrap());
+
+    let mut client = WebSocketClientState::new();
+    let events = client.events().subscribe();
+    client.connect(fast_config(&url)).unwrap();
+    let peer_id = match wait_for(&server_events, |e| matches!(e, CoreEvent::ClientConnected { .. })) {
+        CoreEvent::ClientConnected { peer_id, .. } => peer_id,
+        _ => unreachable!(),
+    };
+
+    server.hub().kick(peer_id, "Room is full").unwrap();
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::HostDisconnected { .. })),
+        CoreEvent::HostDisconnected {
+            code: 4000,
+            reason: "Room is full".to_string()
+        }
+    );
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(
+        !events.drain().iter().any(|e| matches!(e, CoreEvent::HostReconnecting { .. })),
+        "Kicked clients should not reconnect"
+    );
+    assert_eq!(client.status(), ClientStatus::Disconnected);
+    server.stop();
+}
+
+/// Test: a host that stops answering pings is detected
+#[test]
+fn test_heartbeat_timeout() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let listener = runtime.block_on(tokio::net::TcpListener::bind("127.0.0.1:0")).unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    // Complete the handshake, then never read again so pings go unanswered
+    runtime.spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        drop(socket);
+    });
+
+    let mut client = WebSocketClientState::new();
+    let events = client.events().subscribe();
+    client
+        .connect(ClientConfig {
+            reconnect: false,
+            heartbeat_interval: Duration::from_millis(50),
+            heartbeat_timeout: Duration::from_millis(200),
+            ..ClientConfig::new(&url)
+        })
+        .unwrap();
+
+    wait_for(&events, |e| matches!(e, CoreEvent::HostConnected { .. }));
+    assert_eq!(
+        wait_for(&events, |e| matches!(e, CoreEvent::HostDisconnected { .. })),
+        CoreEvent::HostDisconnected {
+            code: 1006,
+            reason: "Heartbeat timed out".to_string()
+        }
+    );
+}
+
+/// Test: wss:// connects only when the certificate matches the pin
+#[test]
+fn test_pinned_certificate() {
+    let (cert_path, key_path, cert_der) = write_certificate("client_pin");
+    let fingerprint = Fingerprint::of_certificate(&cert_der);
+    let mut server = HttpServerState::new();
+    server
+        .start_https_with_certificate("127.0.0.1:0", "/tmp", cert_path.to_str().unwrap(), key_path.to_str().unwrap())
+        .expect("HTTPS server should start");
+    let url = format!("wss://{}/ws", server.local_addr().unwrap());
+
+    let mut client = WebSocketClientState::new();
+    let events = client.events().subscribe();
+    client
+        .connect(ClientConfig {
+            fingerprint: Some(fingerprint),
+            ..ClientConfig::new(&url)
+        })
+        .unwrap();
+    wait_for(&events, |e| matches!(e, CoreEvent::HostConnected { .. }));
+    client.disconnect();
+    wait_for(&events, |e| matches!(e, CoreEvent::HostDisconnected { .. }));
+
+    let mut impostor = WebSocketClientState::new();
+    let events = impostor.events().subscribe();
+    impostor
+        .connect(ClientConfig {
+            fingerprint: Some(Fingerprint::of_certificate(b"some other certificate")),
+            ..fast_config(&url)
+        })
+        .unwrap();
+    match wait_for(&events, |e| !matches!(e, CoreEvent::HostReconnecting { .. })) {
+        CoreEvent::HostConnectFailed { reason } => {
+            assert!(reason.contains("does not match fingerprint"), "Unexpected reason {}", reason)
+        }
+        other => panic!("Expected HostConnectFailed, got {:?}", other),
+    }
+
+    server.stop();
+    let _ = std::fs::remove_dir_all(cert_path.parent().unwrap());
+}
+
+/// Test: FFI argument errors and status
+#[test]
+fn test_ffi_client_arguments() {
+    let client = unsafe { ft_ws_client_create() };
+    assert_eq!(unsafe { ft_ws_client_status(client) }, 0, "New client should be FT_CLIENT_DISCONNECTED");
+
+    let url = CString::new("wss://127.0.0.1:1/ws").unwrap();
+    let bad_fingerprint = CString::new("not a fingerprint").unwrap();
+    assert_eq!(
+        unsafe { ft_ws_client_connect(client, url.as_ptr(), bad_fingerprint.as_ptr(), 1) },
+        FtErrorCode::InvalidConfig as i32
+    );
+    assert_eq!(
+        unsafe { ft_ws_client_connect(client, url.as_ptr(), std::ptr::null(), 1) },
+        FtErrorCode::InvalidConfig as i32,
+        "wss:// without a fingerprint should be rejected"
+    );
+    assert_eq!(
+        unsafe { ft_ws_client_connect(client, std::ptr::null(), std::ptr::null(), 1) },
+        FtErrorCode::NullPointer as i32
+    );
+
+    let message = CString::new("hello").unwrap();
+    assert_eq!(
+        unsafe { ft_ws_client_send(client, message.as_ptr()) },
+        FtErrorCode::NotRunning as i32
+    );
+    assert_eq!(
+        unsafe { ft_ws_client_send(std::ptr::null_mut(), message.as_ptr()) },
+        FtErrorCode::NullPointer as i32
+    );
+    assert_eq!(unsafe { ft_ws_client_status(std::ptr::null_mut()) }, 0);
+
+    unsafe { ft_ws_client_free(client) };
+}
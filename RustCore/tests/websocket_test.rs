@@ -0,0 +1,94 @@
+mod common;
+
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use facingtime_core::error::CoreError;
+use facingtime_core::server::websocket::CLOSE_KICKED;
+use facingtime_core::server::{CoreEvent, Payload, PeerId};
+
+use common::{connect_peer, next_frame, start_server};
+
+/// Test: send delivers text and binary frames to one peer
+#[test]
+fn test_send_to_peer() {
+    let (mut server, events, url) = start_server();
+    let runtime = Runtime::new().unwrap();
+    let (mut client, peer_id) = connect_peer(&runtime, &url, &events);
+
+    server.hub().send(peer_id, Payload::Text("hello".to_string())).unwrap();
+    server.hub().send(peer_id, Payload::Binary(vec![1, 2, 3])).unwrap();
+
+    assert_eq!(next_frame(&runtime, &mut client, Duration::from_secs(5)), Some(Message::text("hello")));
+    assert_eq!(
+        next_frame(&runtime, &mut client, Duration::from_secs(5)),
+        Some(Message::binary(vec![1, 2, 3]))
+    );
+
+    let result = server.hub().send(9999, Payload::Text("nobody".to_string()));
+    assert!(matches!(result, Err(CoreError::PeerNotFound(9999))), "Unknown peer should be an error");
+
+    server.stop();
+}
+
+/// Test: broadcast reaches every peer except the excluded one
+#[test]
+fn test_broadcast_with_exclude() {
+    let (mut server, events, url) = start_server();
+    let runtime = Runtime::new().unwrap();
+    let (mut first, first_id) = connect_peer(&runtime, &url, &events);
+    let (mut second, second_id) = connect_peer(&runtime, &url, &events);
+
+    let peers: Vec<PeerId> = server.hub().peers().iter().map(|p| p.peer_id).collect();
+    assert_eq!(peers, vec![first_id, second_id], "Peers should be listed in id order");
+
+    assert_eq!(server.hub().broadcast(Payload::Text("all".to_string()), None), 2);
+    assert_eq!(server.hub().broadcast(Payload::Text("not first".to_string()), Some(first_id)), 1);
+
+    assert_eq!(next_frame(&runtime, &mut first, Duration::from_secs(5)), Some(Message::text("all")));
+    assert_eq!(next_frame(&runtime, &mut second, Duration::from_secs(5)), Some(Message::text("all")));
+    assert_eq!(next_frame(&runtime, &mut second, Duration::from_secs(5)), Some(Message::text("not first")));
+    assert_eq!(
+        next_frame(&runtime, &mut first, Duration::from_millis(200)),
+        None,
+        "Excluded peer should not receive the broadcast"
+    );
+
+    server.stop();
+}
+
+/// Test: kick closes the socket with the kick code and removes the peer at once
+#[test]
+fn test_kick_peer() {
+    let (mut server, events, url) = start_server();
+    let runtime = Runtime::new().unwrap();
+    let (mut client, peer_id) = connect_peer(&runtime, &url, &events);
+
+    server.hub().kick(peer_id, "Room is full").unwrap();
+    assert_eq!(events.pop(), Some(CoreEvent::ClientDisconnected { peer_id }));
+    assert!(server.hub().peers().is_empty(), "Kicked peer should be removed immediately");
+    assert_eq!(server.inner.lock().connected_clients, 0);
+
+    match next_frame(&runtime, &mut client, Duration::from_secs(5)) {
+        Some(Message::Close(Some(frame))) => {
+            assert_eq!(frame.code, CloseCode::from(CLOSE_KICKED));
+            assert_eq!(frame.reason.as_str(), "Room is full");
+        }
+        other => panic!("Expected a close frame, got {:?}", other),
+    }
+
+    assert!(
+        matches!(server.hub().kick(peer_id, "again"), Err(CoreError::PeerNotFound(_))),
+        "Kicking twice should fail"
+    );
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(
+        !events.drain().iter().any(|e| matches!(e, CoreEvent::ClientDisconnected { .. })),
+        "Disconnect should only be reported once"
+    );
+
+    server.stop();
+}